@@ -51,4 +51,55 @@ pub enum Commands {
         /// out to transfer middle data file
         out: String,
     },
+    /// restore transfer middle data to a fresh single node raft data dir
+    #[command(arg_required_else_help = true)]
+    DataToRaft {
+        /// raft node id,default is env RNACOS_RAFT_NODE_ID
+        #[arg(long)]
+        node_id: Option<u64>,
+        /// raft node addr,default is env RNACOS_RAFT_NODE_ADDR
+        #[arg(long)]
+        node_addr: Option<String>,
+        /// rnacos data dir,default is env RNACOS_DATA_DIR
+        #[arg(short, long)]
+        dir: Option<String>,
+        /// the transfer middle data file
+        file: String,
+    },
+    /// verify raft log, index and snapshot files
+    #[command(arg_required_else_help = true)]
+    VerifyData {
+        /// the rnacos data dir; example: nacos_db
+        dir: String,
+    },
+    /// print raft index: members, node addrs, hard state and log segments
+    #[command(arg_required_else_help = true)]
+    RaftIndex {
+        /// the rnacos data dir of a stopped node
+        dir: String,
+    },
+    /// print raft log entries as json lines
+    #[command(arg_required_else_help = true)]
+    RaftLogs {
+        /// start log index
+        #[arg(short, long, default_value_t = 0)]
+        start: u64,
+        /// max entries to print
+        #[arg(short, long, default_value_t = 100)]
+        limit: u64,
+        /// the rnacos data dir of a stopped node
+        dir: String,
+    },
+    /// print raft snapshot header and records as json lines
+    #[command(arg_required_else_help = true)]
+    RaftSnapshot {
+        /// snapshot id,default is the last snapshot
+        #[arg(short, long)]
+        id: Option<u64>,
+        /// max records to print
+        #[arg(short, long, default_value_t = 100)]
+        limit: u64,
+        /// the rnacos data dir of a stopped node
+        dir: String,
+    },
 }
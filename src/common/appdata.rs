@@ -7,14 +7,18 @@ use crate::namespace::NamespaceActor;
 use crate::naming::cluster::node_manage::{InnerNodeManage, NodeManage};
 use crate::naming::cluster::route::NamingRoute;
 use crate::naming::core::NamingActor;
+use crate::naming::naming_delay_nofity::DelayNotifyActor;
 use crate::raft::cache::route::CacheRoute;
 use crate::raft::cache::CacheManager;
+use crate::raft::cluster::leader_transfer::LeaderTransferGuard;
+use crate::raft::cluster::membership::RemovedLearners;
 use crate::raft::cluster::route::{ConfigRoute, RaftRequestRoute};
 use crate::raft::db::route::TableRoute;
 use crate::raft::db::table::TableManager;
 use crate::raft::filestore::core::FileStore;
 use crate::raft::network::factory::RaftClusterRequestSender;
 use crate::raft::NacosRaft;
+use crate::transfer::backup_scheduler::BackupScheduler;
 use crate::transfer::reader::TransferImportManager;
 use crate::transfer::writer::TransferWriterManager;
 use crate::user::UserManager;
@@ -26,6 +30,7 @@ use std::sync::Arc;
 pub struct AppShareData {
     pub config_addr: Addr<ConfigActor>,
     pub naming_addr: Addr<NamingActor>,
+    pub naming_delay_notify: Addr<DelayNotifyActor>,
     pub bi_stream_manage: Addr<BiStreamManage>,
     pub raft: Arc<NacosRaft>,
     pub raft_store: Arc<FileStore>,
@@ -48,4 +53,7 @@ pub struct AppShareData {
     pub transfer_writer_manager: Addr<TransferWriterManager>,
     pub transfer_import_manager: Addr<TransferImportManager>,
     pub health_manager: Addr<HealthManager>,
+    pub backup_scheduler: Addr<BackupScheduler>,
+    pub leader_transfer: Arc<LeaderTransferGuard>,
+    pub removed_learners: Arc<RemovedLearners>,
 }
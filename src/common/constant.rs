@@ -12,6 +12,7 @@ pub const SEQ_KEY_CONFIG: &str = "SEQ_CONFIG";
 
 pub const AUTHORIZATION_HEADER: &str = "Authorization";
 pub const ACCESS_TOKEN_HEADER: &str = "accessToken";
+pub const CONSUL_TOKEN_HEADER: &str = "X-Consul-Token";
 
 pub const GRPC_HEAD_KEY_CLUSTER_ID: &str = "cluster_id";
 pub const GRPC_HEAD_KEY_TRACE_ID: &str = "trace_id";
@@ -63,3 +63,34 @@ macro_rules! user_no_namespace_permission {
         ));
     }};
 }
+
+#[macro_export]
+macro_rules! api_namespace_privilege {
+    ($req:expr) => {{
+        if let Some(session) = $req
+            .extensions()
+            .get::<std::sync::Arc<$crate::common::model::TokenSession>>()
+        {
+            session
+                .namespace_privilege
+                .clone()
+                .map($crate::common::model::privilege::NamespacePrivilegeGroup::new)
+                .unwrap_or_default()
+        } else {
+            $crate::common::model::privilege::NamespacePrivilegeGroup::default()
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! api_v2_no_namespace_permission {
+    ($param:expr) => {{
+        return actix_web::HttpResponse::Forbidden().json(
+            $crate::openapi::v2::model::ApiResult::<()>::error(
+                $crate::openapi::v2::model::ERROR_CODE_ACCESS_DENIED,
+                format!("user no such namespace permission: {:?}", $param),
+                (),
+            ),
+        );
+    }};
+}
@@ -1,4 +1,5 @@
 use crate::common::string_utils::StringUtils;
+use crate::raft::filestore::model::{SNAPSHOT_COMPRESS_GZIP, SNAPSHOT_COMPRESS_NONE};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -72,7 +73,12 @@ pub struct AppSysConfig {
     pub raft_node_addr: String,
     pub raft_auto_init: bool,
     pub raft_join_addr: String,
+    pub raft_learner: bool,
+    pub naming_learner_distro: bool,
     pub raft_snapshot_log_size: u64,
+    pub raft_snapshot_compress_type: u32,
+    pub raft_snapshot_chunk_size: u64,
+    pub raft_snapshot_pb_encoding: bool,
     pub console_login_timeout: i32,
     pub console_login_one_hour_limit: u32,
     pub gmt_fixed_offset_hours: Option<i32>,
@@ -91,6 +97,49 @@ pub struct AppSysConfig {
     pub run_in_docker: bool,
     pub naming_health_timeout: u64,
     pub naming_instance_timeout: u64,
+    pub naming_event_history_size: usize,
+    pub dns_enable: bool,
+    pub dns_port: u16,
+    pub dns_domain: String,
+    pub dns_ttl: u32,
+    pub dns_srv_ttl: u32,
+    pub consul_enable: bool,
+    pub eureka_enable: bool,
+    pub eureka_namespace: String,
+    pub eureka_group: String,
+    pub xds_enable: bool,
+    pub xds_port: u16,
+    pub tls_enable: bool,
+    pub tls_cert_file: String,
+    pub tls_key_file: String,
+    pub tls_client_ca_file: String,
+    pub tls_client_auth_required: bool,
+    pub tls_cluster_server_name: String,
+    pub tls_reload_interval_second: u64,
+    pub grpc_rebalance_enable: bool,
+    pub grpc_rebalance_interval_second: u64,
+    pub grpc_rebalance_threshold_percent: usize,
+    pub grpc_rebalance_batch_size: usize,
+    /// sdk重连到本节点时使用的地址(ip:port)
+    pub grpc_rebalance_sdk_addr: String,
+    pub grpc_max_conn: usize,
+    pub grpc_max_conn_per_ip: usize,
+    pub grpc_config_query_qps: i32,
+    pub grpc_config_publish_qps: i32,
+    pub grpc_naming_register_qps: i32,
+    pub raft_transfer_leader_on_shutdown: bool,
+    pub shutdown_timeout_second: u64,
+    pub backup_dir: String,
+    pub backup_interval_second: u64,
+    pub backup_cron: String,
+    pub backup_keep_files: usize,
+    pub backup_keep_days: u64,
+    pub backup_s3_endpoint: String,
+    pub backup_s3_bucket: String,
+    pub backup_s3_region: String,
+    pub backup_s3_access_key: String,
+    pub backup_s3_secret_key: String,
+    pub backup_s3_prefix: String,
 }
 
 impl AppSysConfig {
@@ -128,11 +177,21 @@ impl AppSysConfig {
             .unwrap_or(1);
         let raft_node_addr =
             std::env::var("RNACOS_RAFT_NODE_ADDR").unwrap_or(format!("127.0.0.1:{}", &grpc_port));
-        let raft_auto_init = std::env::var("RNACOS_RAFT_AUTO_INIT")
-            .unwrap_or("".to_owned())
+        let raft_learner = std::env::var("RNACOS_RAFT_LEARNER")
+            .unwrap_or("false".to_owned())
             .parse()
-            .unwrap_or(raft_node_id == 1);
+            .unwrap_or(false);
+        //learner节点只能加入已有集群
+        let raft_auto_init = !raft_learner
+            && std::env::var("RNACOS_RAFT_AUTO_INIT")
+                .unwrap_or("".to_owned())
+                .parse()
+                .unwrap_or(raft_node_id == 1);
         let raft_join_addr = std::env::var("RNACOS_RAFT_JOIN_ADDR").unwrap_or_default();
+        let naming_learner_distro = std::env::var("RNACOS_NAMING_LEARNER_DISTRO")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
         let console_login_timeout = std::env::var("RNACOS_CONSOLE_LOGIN_TIMEOUT")
             .unwrap_or("86400".to_owned())
             .parse()
@@ -153,6 +212,18 @@ impl AppSysConfig {
             .unwrap_or("10000".to_owned())
             .parse()
             .unwrap_or(10000);
+        let raft_snapshot_compress_type = Self::parse_snapshot_compress_type(
+            &std::env::var("RNACOS_RAFT_SNAPSHOT_COMPRESS").unwrap_or_default(),
+        );
+        let raft_snapshot_chunk_size = std::env::var("RNACOS_RAFT_SNAPSHOT_CHUNK_SIZE")
+            .unwrap_or("1048576".to_owned())
+            .parse::<u64>()
+            .unwrap_or(1048576)
+            .max(1024);
+        let raft_snapshot_pb_encoding = std::env::var("RNACOS_RAFT_SNAPSHOT_PB_ENCODING")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
         let enable_no_auth_console = std::env::var("RNACOS_ENABLE_NO_AUTH_CONSOLE")
             .unwrap_or("false".to_owned())
             .parse()
@@ -224,6 +295,141 @@ impl AppSysConfig {
             //如果配置不合理，则默认使过期时间大于心跳时间15秒
             naming_instance_timeout = naming_health_timeout + 15 * 1000;
         }
+        let naming_event_history_size = std::env::var("RNACOS_NAMING_EVENT_HISTORY_SIZE")
+            .unwrap_or("256".to_owned())
+            .parse()
+            .unwrap_or(256);
+        let dns_enable = std::env::var("RNACOS_DNS_ENABLE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let dns_port = std::env::var("RNACOS_DNS_PORT")
+            .unwrap_or("8853".to_owned())
+            .parse()
+            .unwrap_or(8853);
+        let dns_domain = StringUtils::map_not_empty(std::env::var("RNACOS_DNS_DOMAIN").ok())
+            .unwrap_or("rnacos.local".to_owned());
+        let dns_ttl = std::env::var("RNACOS_DNS_TTL")
+            .unwrap_or("5".to_owned())
+            .parse()
+            .unwrap_or(5);
+        let dns_srv_ttl = std::env::var("RNACOS_DNS_SRV_TTL")
+            .unwrap_or("".to_owned())
+            .parse()
+            .unwrap_or(dns_ttl);
+        let consul_enable = std::env::var("RNACOS_CONSUL_ENABLE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let eureka_enable = std::env::var("RNACOS_EUREKA_ENABLE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let eureka_namespace =
+            StringUtils::map_not_empty(std::env::var("RNACOS_EUREKA_NAMESPACE").ok())
+                .unwrap_or("public".to_owned());
+        let eureka_group = StringUtils::map_not_empty(std::env::var("RNACOS_EUREKA_GROUP").ok())
+            .unwrap_or("DEFAULT_GROUP".to_owned());
+        let xds_enable = std::env::var("RNACOS_XDS_ENABLE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let xds_port = std::env::var("RNACOS_XDS_PORT")
+            .unwrap_or("18000".to_owned())
+            .parse()
+            .unwrap_or(18000);
+        let tls_enable = std::env::var("RNACOS_TLS_ENABLE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let tls_cert_file = std::env::var("RNACOS_TLS_CERT_FILE").unwrap_or_default();
+        let tls_key_file = std::env::var("RNACOS_TLS_KEY_FILE").unwrap_or_default();
+        let tls_client_ca_file = std::env::var("RNACOS_TLS_CLIENT_CA_FILE").unwrap_or_default();
+        let tls_client_auth_required = std::env::var("RNACOS_TLS_CLIENT_AUTH_REQUIRED")
+            .unwrap_or("true".to_owned())
+            .parse()
+            .unwrap_or(true);
+        let tls_cluster_server_name =
+            std::env::var("RNACOS_TLS_CLUSTER_SERVER_NAME").unwrap_or_default();
+        let tls_reload_interval_second = std::env::var("RNACOS_TLS_RELOAD_INTERVAL_SECOND")
+            .unwrap_or("10".to_owned())
+            .parse()
+            .unwrap_or(10);
+        let grpc_rebalance_enable = std::env::var("RNACOS_GRPC_REBALANCE_ENABLE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let grpc_rebalance_interval_second = std::env::var("RNACOS_GRPC_REBALANCE_INTERVAL_SECOND")
+            .unwrap_or("30".to_owned())
+            .parse()
+            .unwrap_or(30);
+        let grpc_rebalance_threshold_percent =
+            std::env::var("RNACOS_GRPC_REBALANCE_THRESHOLD_PERCENT")
+                .unwrap_or("20".to_owned())
+                .parse()
+                .unwrap_or(20);
+        let grpc_rebalance_batch_size = std::env::var("RNACOS_GRPC_REBALANCE_BATCH_SIZE")
+            .unwrap_or("20".to_owned())
+            .parse()
+            .unwrap_or(20);
+        let grpc_rebalance_sdk_addr = std::env::var("RNACOS_GRPC_REBALANCE_SDK_ADDR")
+            .unwrap_or_else(|_| {
+                let ip = raft_node_addr
+                    .rsplit_once(':')
+                    .map(|(ip, _)| ip)
+                    .unwrap_or(raft_node_addr.as_str());
+                format!("{}:{}", ip, &http_port)
+            });
+        let grpc_max_conn = std::env::var("RNACOS_GRPC_MAX_CONN")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let grpc_max_conn_per_ip = std::env::var("RNACOS_GRPC_MAX_CONN_PER_IP")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let grpc_config_query_qps = std::env::var("RNACOS_GRPC_CONFIG_QUERY_QPS")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let grpc_config_publish_qps = std::env::var("RNACOS_GRPC_CONFIG_PUBLISH_QPS")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let grpc_naming_register_qps = std::env::var("RNACOS_GRPC_NAMING_REGISTER_QPS")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let raft_transfer_leader_on_shutdown =
+            std::env::var("RNACOS_RAFT_TRANSFER_LEADER_ON_SHUTDOWN")
+                .unwrap_or("true".to_owned())
+                .parse()
+                .unwrap_or(true);
+        let shutdown_timeout_second = std::env::var("RNACOS_SHUTDOWN_TIMEOUT_SECOND")
+            .unwrap_or("15".to_owned())
+            .parse()
+            .unwrap_or(15);
+        let backup_dir = std::env::var("RNACOS_BACKUP_DIR").unwrap_or_default();
+        let backup_interval_second = std::env::var("RNACOS_BACKUP_INTERVAL_SECOND")
+            .unwrap_or("86400".to_owned())
+            .parse()
+            .unwrap_or(86400);
+        let backup_cron = std::env::var("RNACOS_BACKUP_CRON").unwrap_or_default();
+        let backup_keep_files = std::env::var("RNACOS_BACKUP_KEEP_FILES")
+            .unwrap_or("7".to_owned())
+            .parse()
+            .unwrap_or(7);
+        let backup_keep_days = std::env::var("RNACOS_BACKUP_KEEP_DAYS")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let backup_s3_endpoint = std::env::var("RNACOS_BACKUP_S3_ENDPOINT").unwrap_or_default();
+        let backup_s3_bucket = std::env::var("RNACOS_BACKUP_S3_BUCKET").unwrap_or_default();
+        let backup_s3_region =
+            std::env::var("RNACOS_BACKUP_S3_REGION").unwrap_or("us-east-1".to_owned());
+        let backup_s3_access_key = std::env::var("RNACOS_BACKUP_S3_ACCESS_KEY").unwrap_or_default();
+        let backup_s3_secret_key = std::env::var("RNACOS_BACKUP_S3_SECRET_KEY").unwrap_or_default();
+        let backup_s3_prefix = std::env::var("RNACOS_BACKUP_S3_PREFIX").unwrap_or_default();
         Self {
             local_db_dir,
             config_db_file,
@@ -237,7 +443,12 @@ impl AppSysConfig {
             raft_node_addr,
             raft_auto_init,
             raft_join_addr,
+            raft_learner,
+            naming_learner_distro,
             raft_snapshot_log_size,
+            raft_snapshot_compress_type,
+            raft_snapshot_chunk_size,
+            raft_snapshot_pb_encoding,
             console_login_timeout,
             console_login_one_hour_limit,
             openapi_login_timeout,
@@ -256,10 +467,63 @@ impl AppSysConfig {
             run_in_docker,
             naming_health_timeout,
             naming_instance_timeout,
+            naming_event_history_size,
+            dns_enable,
+            dns_port,
+            dns_domain,
+            dns_ttl,
+            dns_srv_ttl,
+            consul_enable,
+            eureka_enable,
+            eureka_namespace,
+            eureka_group,
+            xds_enable,
+            xds_port,
+            tls_enable,
+            tls_cert_file,
+            tls_key_file,
+            tls_client_ca_file,
+            tls_client_auth_required,
+            tls_cluster_server_name,
+            tls_reload_interval_second,
+            grpc_rebalance_enable,
+            grpc_rebalance_interval_second,
+            grpc_rebalance_threshold_percent,
+            grpc_rebalance_batch_size,
+            grpc_rebalance_sdk_addr,
+            grpc_max_conn,
+            grpc_max_conn_per_ip,
+            grpc_config_query_qps,
+            grpc_config_publish_qps,
+            grpc_naming_register_qps,
+            raft_transfer_leader_on_shutdown,
+            shutdown_timeout_second,
+            backup_dir,
+            backup_interval_second,
+            backup_cron,
+            backup_keep_files,
+            backup_keep_days,
+            backup_s3_endpoint,
+            backup_s3_bucket,
+            backup_s3_region,
+            backup_s3_access_key,
+            backup_s3_secret_key,
+            backup_s3_prefix,
         }
     }
 
     /// 获取数据目录
+    fn parse_snapshot_compress_type(value: &str) -> u32 {
+        match value.to_lowercase().as_str() {
+            "gzip" => SNAPSHOT_COMPRESS_GZIP,
+            "" | "none" => SNAPSHOT_COMPRESS_NONE,
+            _ => {
+                log::warn!("unsupported raft snapshot compress type:{}", value);
+                SNAPSHOT_COMPRESS_NONE
+            }
+        }
+    }
+
     fn get_data_dir(run_in_docker: bool) -> String {
         if let Ok(v) = std::env::var("RNACOS_DATA_DIR") {
             v
@@ -292,6 +556,14 @@ impl AppSysConfig {
     pub fn get_http_console_addr(&self) -> String {
         format!("0.0.0.0:{}", &self.http_console_port)
     }
+
+    pub fn get_dns_addr(&self) -> String {
+        format!("0.0.0.0:{}", &self.dns_port)
+    }
+
+    pub fn get_xds_addr(&self) -> String {
+        format!("0.0.0.0:{}", &self.xds_port)
+    }
 }
 
 /**
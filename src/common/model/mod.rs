@@ -102,4 +102,6 @@ pub struct TokenSession {
     pub username: Arc<String>,
     pub roles: Vec<Arc<String>>,
     pub extend_infos: HashMap<String, String>,
+    #[serde(default)]
+    pub namespace_privilege: Option<PrivilegeGroup<Arc<String>>>,
 }
@@ -204,6 +204,14 @@ impl MessageBufReader {
         self.buf.append(&mut v);
     }
 
+    ///
+    /// 取出未读取的数据,并清空缓存
+    pub fn take_remaining(&mut self) -> Vec<u8> {
+        let v = self.buf[self.start..self.end].to_vec();
+        *self = Self::new();
+        v
+    }
+
     pub fn next_message_vec(&mut self) -> Option<&[u8]> {
         let mut i = self.start;
         let mut can_read_len = false;
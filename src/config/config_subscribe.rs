@@ -101,6 +101,21 @@ impl Subscriber {
         }
     }
 
+    ///
+    /// 查询客户端订阅的配置列表
+    pub fn get_client_subscribes(
+        &self,
+        client_ids: &[Arc<String>],
+    ) -> HashMap<Arc<String>, Vec<ConfigKey>> {
+        let mut map = HashMap::with_capacity(client_ids.len());
+        for client_id in client_ids {
+            if let Some(set) = self.client_keys.get(client_id) {
+                map.insert(client_id.clone(), set.iter().cloned().collect());
+            }
+        }
+        map
+    }
+
     pub fn remove_config_key(&mut self, key: ConfigKey) {
         if let Some(set) = self.listener.remove(&key) {
             let mut remove_keys = vec![];
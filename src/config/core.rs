@@ -25,6 +25,7 @@ use crate::config::config_index::{ConfigQueryParam, TenantIndex};
 use crate::config::config_type::ConfigType;
 use crate::config::model::{
     ConfigRaftCmd, ConfigRaftResult, ConfigValueDO, HistoryItem, SetConfigParam,
+    HISTORY_OP_TYPE_INSERT, HISTORY_OP_TYPE_UPDATE,
 };
 use crate::config::utils::param_utils;
 use crate::namespace::NamespaceActor;
@@ -154,6 +155,7 @@ impl ConfigValue {
                 content,
                 modified_time: op_time,
                 op_user,
+                op_type: Some(Arc::new(HISTORY_OP_TYPE_INSERT.to_owned())),
             }],
             config_type: None,
             desc: None,
@@ -177,11 +179,18 @@ impl ConfigValue {
         self.md5 = md5;
         self.content = content.clone();
         self.tmp = false;
+        //首条历史记录为新增,之后为更新
+        let op_type = if self.histories.is_empty() {
+            HISTORY_OP_TYPE_INSERT
+        } else {
+            HISTORY_OP_TYPE_UPDATE
+        };
         let item = HistoryItem {
             id: history_id,
             content,
             modified_time: op_time,
             op_user,
+            op_type: Some(Arc::new(op_type.to_owned())),
         };
         if self.histories.len() >= 100 {
             self.histories.remove(0);
@@ -200,6 +209,8 @@ pub struct ConfigInfoDto {
     pub content: Option<Arc<String>>,
     pub md5: Option<Arc<String>>,
     pub desc: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -211,6 +222,8 @@ pub struct ConfigHistoryInfoDto {
     pub data_id: Option<String>,
     pub content: Option<String>,
     pub modified_time: Option<i64>, //给历史记录使用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub op_type: Option<String>,
 }
 
 #[derive(Debug)]
@@ -401,6 +414,10 @@ pub struct ConfigActor {
     raft: Option<Weak<NacosRaft>>,
     namespace_actor: Option<Addr<NamespaceActor>>,
     sequence: SimpleSequence,
+    //(tenant,group)最近一次配置新增、修改或删除的时间,删除配置时也会变化
+    group_change_index: HashMap<(Arc<String>, Arc<String>), i64>,
+    //配置变更序号,用于唤醒阻塞查询
+    change_sender: tokio::sync::watch::Sender<u64>,
 }
 
 impl Inject for ConfigActor {
@@ -439,9 +456,22 @@ impl ConfigActor {
             raft: None,
             namespace_actor: None,
             sequence: SimpleSequence::new(0, 100),
+            group_change_index: HashMap::new(),
+            change_sender: tokio::sync::watch::channel(0).0,
         }
     }
 
+    ///
+    /// 记录配置分组变更,保持单调递增
+    fn mark_group_changed(&mut self, key: &ConfigKey) {
+        let index = self
+            .group_change_index
+            .entry((key.tenant.clone(), key.group.clone()))
+            .or_default();
+        *index = (*index + 1).max(now_millis_i64());
+        self.change_sender.send_modify(|v| *v += 1);
+    }
+
     fn set_tmp_config(&mut self, key: ConfigKey, val: Arc<String>) {
         if let Some(v) = self.cache.get_mut(&key) {
             v.tmp = true;
@@ -497,13 +527,16 @@ impl ConfigActor {
             self.cache.insert(param.key.clone(), v);
             self.tenant_index.insert_config(param.key.clone());
         }
+        self.mark_group_changed(&param.key);
         self.listener.notify(param.key.clone());
         self.subscriber.notify(param.key);
         Ok(ConfigResult::NULL)
     }
 
     fn del_config(&mut self, key: ConfigKey) -> anyhow::Result<()> {
-        self.cache.remove(&key);
+        if self.cache.remove(&key).is_some() {
+            self.mark_group_changed(&key);
+        }
         //self.config_db.del_config(&key).ok();
         self.tenant_index.remove_config(&key);
         self.listener.notify(key.clone());
@@ -563,6 +596,7 @@ impl ConfigActor {
                 if param.query_context {
                     info.content = Some(value.content.clone());
                     info.md5 = Some(value.md5.clone());
+                    info.last_modified = Some(value.last_modified);
                 }
                 info_list.push(info);
             }
@@ -621,6 +655,24 @@ impl ConfigActor {
         (0, vec![])
     }
 
+    ///
+    /// 按id查询历史记录,历史记录按id递增保存,使用二分查找
+    pub(crate) fn get_history_info(
+        &self,
+        key: &ConfigKey,
+        id: u64,
+        previous: bool,
+    ) -> Option<ConfigHistoryInfoDto> {
+        let histories = &self.cache.get(key)?.histories;
+        let index = histories.partition_point(|item| item.id < id);
+        let item = if previous {
+            histories.get(index.checked_sub(1)?)?
+        } else {
+            histories.get(index).filter(|item| item.id == id)?
+        };
+        Some(item.to_dto(key))
+    }
+
     ///
     /// 将配置中心数据写入 raft snapshot文件中
     ///
@@ -687,12 +739,21 @@ pub enum ConfigCmd {
     SetFullValue(ConfigKey, ConfigValue),
     InnerSetLastId(u64),
     GET(ConfigKey),
+    QueryGroupChangeIndex(ConfigKey),
+    SubscribeChange,
     QueryPageInfo(Box<ConfigQueryParam>),
     QueryHistoryPageInfo(Box<ConfigHistoryParam>),
+    //按id查询历史记录,previous为true时查询该id的上一个版本
+    QueryHistoryInfo {
+        key: ConfigKey,
+        id: u64,
+        previous: bool,
+    },
     LISTENER(Vec<ListenerItem>, ListenerSenderType, i64),
     Subscribe(Vec<ListenerItem>, Arc<String>),
     RemoveSubscribe(Vec<ListenerItem>, Arc<String>),
     RemoveSubscribeClient(Arc<String>),
+    QueryClientSubscribes(Vec<Arc<String>>),
     BuildSnapshot(Addr<SnapshotWriterActor>),
     GetSequenceSection(u64),
 }
@@ -722,6 +783,10 @@ pub enum ConfigResult {
     ChangeKey(Vec<ConfigKey>),
     ConfigInfoPage(usize, Vec<ConfigInfoDto>),
     ConfigHistoryInfoPage(usize, Vec<ConfigHistoryInfoDto>),
+    ConfigHistoryInfo(Option<ConfigHistoryInfoDto>),
+    GroupChangeIndex(i64),
+    ChangeReceiver(tokio::sync::watch::Receiver<u64>),
+    ClientSubscribes(HashMap<Arc<String>, Vec<ConfigKey>>),
     SequenceSection {
         //id包含start值
         start: u64,
@@ -770,6 +835,17 @@ impl Handler<ConfigCmd> for ConfigActor {
                     });
                 }
             }
+            ConfigCmd::QueryGroupChangeIndex(key) => {
+                let index = self
+                    .group_change_index
+                    .get(&(key.tenant, key.group))
+                    .copied()
+                    .unwrap_or_default();
+                return Ok(ConfigResult::GroupChangeIndex(index));
+            }
+            ConfigCmd::SubscribeChange => {
+                return Ok(ConfigResult::ChangeReceiver(self.change_sender.subscribe()));
+            }
             ConfigCmd::LISTENER(items, sender, time) => {
                 let mut changes = vec![];
                 for item in &items {
@@ -811,6 +887,10 @@ impl Handler<ConfigCmd> for ConfigActor {
             ConfigCmd::RemoveSubscribeClient(client_id) => {
                 self.subscriber.remove_client_subscribe(client_id);
             }
+            ConfigCmd::QueryClientSubscribes(client_ids) => {
+                let map = self.subscriber.get_client_subscribes(&client_ids);
+                return Ok(ConfigResult::ClientSubscribes(map));
+            }
             ConfigCmd::QueryPageInfo(config_query_param) => {
                 let (size, list) = self.get_config_info_page(config_query_param.as_ref());
                 return Ok(ConfigResult::ConfigInfoPage(size, list));
@@ -819,6 +899,10 @@ impl Handler<ConfigCmd> for ConfigActor {
                 let (size, list) = self.get_history_info_page(query_param.as_ref());
                 return Ok(ConfigResult::ConfigHistoryInfoPage(size, list));
             }
+            ConfigCmd::QueryHistoryInfo { key, id, previous } => {
+                let info = self.get_history_info(&key, id, previous);
+                return Ok(ConfigResult::ConfigHistoryInfo(info));
+            }
             ConfigCmd::BuildSnapshot(writer) => {
                 self.build_snapshot(writer).ok();
             }
@@ -5,6 +5,11 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+/// 配置历史记录操作类型:新增
+pub const HISTORY_OP_TYPE_INSERT: &str = "I";
+/// 配置历史记录操作类型:更新
+pub const HISTORY_OP_TYPE_UPDATE: &str = "U";
+
 #[derive(Message)]
 #[rtype(result = "anyhow::Result<ConfigRaftResult>")]
 pub enum ConfigRaftCmd {
@@ -54,6 +59,7 @@ pub struct HistoryItem {
     pub content: Arc<String>,
     pub modified_time: i64,
     pub op_user: Option<Arc<String>>,
+    pub op_type: Option<Arc<String>>,
 }
 
 impl HistoryItem {
@@ -65,6 +71,7 @@ impl HistoryItem {
             data_id: Some(key.data_id.to_string()),
             content: Some(self.content.to_string()),
             modified_time: Some(self.modified_time),
+            op_type: self.op_type.as_ref().map(|e| e.as_ref().to_string()),
         }
     }
 }
@@ -79,6 +86,8 @@ pub struct ConfigHistoryItemDO {
     pub last_time: Option<i64>,
     #[prost(string, optional, tag = "4")]
     pub op_user: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub op_type: Option<String>,
 }
 
 impl From<HistoryItem> for ConfigHistoryItemDO {
@@ -88,6 +97,7 @@ impl From<HistoryItem> for ConfigHistoryItemDO {
             content: Some(value.content.as_ref().to_string()),
             last_time: Some(value.modified_time),
             op_user: value.op_user.map(|e| e.as_ref().to_string()),
+            op_type: value.op_type.map(|e| e.as_ref().to_string()),
         }
     }
 }
@@ -99,6 +109,7 @@ impl From<ConfigHistoryItemDO> for HistoryItem {
             content: Arc::new(value.content.unwrap_or_default()),
             modified_time: value.last_time.unwrap_or_default(),
             op_user: value.op_user.map(Arc::new),
+            op_type: value.op_type.map(Arc::new),
         }
     }
 }
@@ -255,6 +255,46 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/cluster/cluster_node_list")
                     .route(web::get().to(v2::cluster_api::query_cluster_info)),
             )
+            .service(
+                web::resource("/connections/list")
+                    .route(web::get().to(v2::connection_api::query_connection_list)),
+            )
+            .service(
+                web::resource("/connections/close")
+                    .route(web::post().to(v2::connection_api::close_connection)),
+            )
+            .service(
+                web::resource("/cluster/grpc_conn_balance")
+                    .route(web::get().to(v2::cluster_api::query_grpc_conn_balance)),
+            )
+            .service(
+                web::resource("/cluster/grpc_conn_balance/rebalance")
+                    .route(web::post().to(v2::cluster_api::rebalance_grpc_conn)),
+            )
+            .service(
+                web::resource("/cluster/raft_membership")
+                    .route(web::get().to(v2::cluster_api::query_raft_membership)),
+            )
+            .service(
+                web::resource("/cluster/raft_status")
+                    .route(web::get().to(v2::cluster_api::query_raft_status)),
+            )
+            .service(
+                web::resource("/cluster/raft_membership/add_learner")
+                    .route(web::post().to(v2::cluster_api::add_raft_learner)),
+            )
+            .service(
+                web::resource("/cluster/raft_membership/promote")
+                    .route(web::post().to(v2::cluster_api::promote_raft_learner)),
+            )
+            .service(
+                web::resource("/cluster/raft_membership/remove")
+                    .route(web::post().to(v2::cluster_api::remove_raft_node)),
+            )
+            .service(
+                web::resource("/cluster/raft_leader/transfer")
+                    .route(web::post().to(v2::cluster_api::transfer_raft_leader)),
+            )
             .service(
                 web::resource("/config/import")
                     .route(web::post().to(v2::config_api::import_config)),
@@ -294,6 +334,10 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/service/remove")
                     .route(web::post().to(v2::naming_api::remove_service)),
             )
+            .service(
+                web::resource("/service/push_audit")
+                    .route(web::get().to(v2::naming_api::query_push_audit)),
+            )
             .service(
                 web::resource("/instance/list")
                     .route(web::get().to(v2::naming_api::query_instances_list)),
@@ -301,6 +345,10 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
             .service(
                 web::resource("/instance/info").route(web::get().to(v2::naming_api::get_instance)),
             )
+            .service(
+                web::resource("/instance/events")
+                    .route(web::get().to(v2::naming_api::query_instance_events)),
+            )
             .service(
                 web::resource("/instance/add").route(web::post().to(v2::naming_api::add_instance)),
             )
@@ -312,6 +360,14 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/instance/remove")
                     .route(web::post().to(v2::naming_api::remove_instance)),
             )
+            .service(
+                web::resource("/instance/drain")
+                    .route(web::post().to(v2::naming_api::drain_instance)),
+            )
+            .service(
+                web::resource("/instance/drain/cancel")
+                    .route(web::post().to(v2::naming_api::cancel_drain_instance)),
+            )
             .service(
                 web::resource("/transfer/export")
                     .route(web::get().to(transfer_api::download_transfer_file)),
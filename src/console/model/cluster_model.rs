@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+use crate::grpc::conn_rebalance::ConnRebalanceRecord;
 use crate::naming::cluster::node_manage::{ClusterNode, NodeStatus};
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -11,6 +12,7 @@ pub struct ClusterNodeInfo {
     pub current_node: bool,
     pub raft_leader: bool,
     pub distro_valid: bool,
+    pub learner: bool,
 }
 
 impl From<ClusterNode> for ClusterNodeInfo {
@@ -21,6 +23,38 @@ impl From<ClusterNode> for ClusterNodeInfo {
             raft_leader: false,
             current_node: false,
             distro_valid: value.is_local || value.status == NodeStatus::Valid,
+            learner: value.learner,
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcConnNodeInfo {
+    pub node_id: u64,
+    pub addr: Arc<String>,
+    pub current_node: bool,
+    pub conn_count: usize,
+}
+
+impl From<ClusterNode> for GrpcConnNodeInfo {
+    fn from(value: ClusterNode) -> Self {
+        Self {
+            node_id: value.id,
+            addr: value.addr,
+            current_node: value.is_local,
+            conn_count: value.conn_count,
+        }
+    }
+}
+
+///
+/// 集群grpc连接分布及本节点的连接迁移记录
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcConnBalanceInfo {
+    pub rebalance_enable: bool,
+    pub avg_count: usize,
+    pub nodes: Vec<GrpcConnNodeInfo>,
+    pub records: Vec<ConnRebalanceRecord>,
+}
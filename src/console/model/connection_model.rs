@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::core::ConfigKey;
+use crate::grpc::conn_info::{ConnDetailInfo, ConnQueryParam};
+use crate::naming::model::ServiceKey;
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionQueryRequest {
+    pub page_no: Option<usize>,
+    pub page_size: Option<usize>,
+    pub ip: Option<String>,
+    pub app_name: Option<String>,
+    pub client_version: Option<String>,
+}
+
+impl ConnectionQueryRequest {
+    pub fn to_param(self) -> ConnQueryParam {
+        let limit = self.page_size.unwrap_or(20);
+        let offset = (self.page_no.unwrap_or(1).max(1) - 1) * limit;
+        ConnQueryParam {
+            ip: self.ip.filter(|e| !e.is_empty()),
+            app_name: self.app_name.filter(|e| !e.is_empty()),
+            client_version: self.client_version.filter(|e| !e.is_empty()),
+            offset,
+            limit,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionCloseParam {
+    pub client_id: Arc<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSubscribeInfo {
+    pub tenant: Arc<String>,
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+}
+
+impl From<ConfigKey> for ConfigSubscribeInfo {
+    fn from(value: ConfigKey) -> Self {
+        Self {
+            tenant: value.tenant,
+            group: value.group,
+            data_id: value.data_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceSubscribeInfo {
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    pub service_name: Arc<String>,
+}
+
+impl From<ServiceKey> for ServiceSubscribeInfo {
+    fn from(value: ServiceKey) -> Self {
+        Self {
+            namespace_id: value.namespace_id,
+            group_name: value.group_name,
+            service_name: value.service_name,
+        }
+    }
+}
+
+///
+/// 连接详情,包含连接订阅的配置与服务
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionDetailDto {
+    #[serde(flatten)]
+    pub conn: ConnDetailInfo,
+    pub config_subscribes: Vec<ConfigSubscribeInfo>,
+    pub service_subscribes: Vec<ServiceSubscribeInfo>,
+}
@@ -1,5 +1,6 @@
 pub mod cluster_model;
 pub mod config_model;
+pub mod connection_model;
 pub mod login_model;
 pub mod metrics_model;
 pub mod naming_model;
@@ -4,10 +4,15 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::naming::instance_event::{InstanceEventQueryParam, InstanceEventType};
+use crate::naming::naming_delay_nofity::{PushAuditQueryParam, PushResult};
 use crate::naming::service::ServiceInfoDto;
 use crate::naming::service_index::ServiceQueryParam;
 use crate::naming::{
-    model::{Instance, ServiceKey},
+    model::{
+        Instance, InstanceDrainMode, InstanceDrainParam, InstanceMetadataFilter, ServiceKey,
+        DEFAULT_DRAIN_SECONDS,
+    },
     NamingUtils,
 };
 use crate::user_namespace_privilege;
@@ -218,3 +223,123 @@ impl InstanceParams {
         Ok(instance)
     }
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceDrainParams {
+    pub ip: Option<String>,
+    pub port: Option<u32>,
+    pub cluster_name: Option<String>,
+    pub namespace_id: Option<String>,
+    pub service_name: Arc<String>,
+    pub group_name: Option<String>,
+    /// weight 或 offline,默认为weight
+    pub mode: Option<String>,
+    pub drain_seconds: Option<u64>,
+}
+
+impl InstanceDrainParams {
+    pub fn to_param(self, cancel: bool) -> Result<InstanceDrainParam, String> {
+        let ip = match self.ip {
+            Some(v) if !v.is_empty() => v,
+            _ => return Err("the instance ip is empty".to_owned()),
+        };
+        let port = match self.port {
+            Some(v) if v > 0 => v,
+            _ => return Err("the instance port is invalid".to_owned()),
+        };
+        if self.service_name.is_empty() {
+            return Err("the service name is empty".to_owned());
+        }
+        Ok(InstanceDrainParam {
+            namespace_id: Arc::new(NamingUtils::default_namespace(
+                self.namespace_id.unwrap_or_default(),
+            )),
+            group_name: Arc::new(NamingUtils::default_group(
+                self.group_name.unwrap_or_default(),
+            )),
+            service_name: self.service_name,
+            instance: InstanceMetadataFilter {
+                ip: Arc::new(ip),
+                port,
+                cluster_name: self.cluster_name.filter(|e| !e.is_empty()),
+            },
+            mode: InstanceDrainMode::from_name(&self.mode.unwrap_or_default()),
+            drain_seconds: self.drain_seconds.unwrap_or(DEFAULT_DRAIN_SECONDS),
+            cancel,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceEventQueryRequest {
+    pub service_name: Arc<String>,
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub ip: Option<String>,
+    pub port: Option<u32>,
+    /// 起止时间,毫秒
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub event_type: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl InstanceEventQueryRequest {
+    pub fn to_param(self) -> InstanceEventQueryParam {
+        let group_name = Arc::new(NamingUtils::default_group(
+            self.group_name.unwrap_or_default(),
+        ));
+        let namespace_id = Arc::new(NamingUtils::default_namespace(
+            self.namespace_id.unwrap_or_default(),
+        ));
+        InstanceEventQueryParam {
+            service_key: ServiceKey::new_by_arc(namespace_id, group_name, self.service_name),
+            start_time: self.start_time,
+            end_time: self.end_time,
+            ip: self.ip.filter(|e| !e.is_empty()),
+            port: self.port.filter(|e| *e > 0),
+            event_type: self
+                .event_type
+                .as_ref()
+                .and_then(|e| InstanceEventType::from_name(e)),
+            limit: self.limit,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PushAuditQueryRequest {
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub service_name: Option<String>,
+    pub client_id: Option<String>,
+    /// Pushed | Protected | QueryError
+    pub result: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl PushAuditQueryRequest {
+    pub fn to_param(self) -> PushAuditQueryParam {
+        let result = match self.result.as_deref() {
+            Some("Pushed") => Some(PushResult::Pushed),
+            Some("Protected") => Some(PushResult::Protected),
+            Some("QueryError") => Some(PushResult::QueryError),
+            Some("NotConnected") => Some(PushResult::NotConnected),
+            Some("SendError") => Some(PushResult::SendError),
+            _ => None,
+        };
+        PushAuditQueryParam {
+            namespace_id: Some(NamingUtils::default_namespace(
+                self.namespace_id.unwrap_or_default(),
+            )),
+            group_name: self.group_name.filter(|e| !e.is_empty()),
+            service_name: self.service_name.filter(|e| !e.is_empty()),
+            client_id: self.client_id.filter(|e| !e.is_empty()),
+            result,
+            limit: self.limit,
+        }
+    }
+}
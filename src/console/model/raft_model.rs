@@ -30,3 +30,16 @@ impl NodeMember {
         set
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftMemberParam {
+    pub node_id: u64,
+    pub node_addr: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftTransferLeaderParam {
+    pub node_id: Option<u64>,
+}
@@ -1,6 +1,14 @@
 use crate::common::appdata::AppShareData;
 use crate::common::model::ApiResult;
-use crate::console::model::cluster_model::ClusterNodeInfo;
+use crate::console::model::cluster_model::{ClusterNodeInfo, GrpcConnBalanceInfo};
+use crate::console::model::raft_model::{RaftMemberParam, RaftTransferLeaderParam};
+use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
+use crate::grpc::bistream_manage::{BiStreamManageCmd, BiStreamManageResult};
+use crate::naming::cluster::model::NamingRouteRequest;
+use crate::naming::cluster::node_manage::NodeManageRequest;
+use crate::raft::cluster::leader_transfer::transfer_leader;
+use crate::raft::cluster::membership::{query_membership, request_membership, MembershipReq};
+use crate::raft::cluster::node_status::query_cluster_status;
 use actix_web::{web, HttpResponse, Responder};
 use std::sync::Arc;
 
@@ -22,3 +30,128 @@ pub async fn query_cluster_info(app: web::Data<Arc<AppShareData>>) -> impl Respo
     }
     HttpResponse::Ok().json(ApiResult::success(Some(list)))
 }
+
+pub async fn query_grpc_conn_balance(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let nodes = match app.naming_node_manage.get_all_valid_nodes().await {
+        Ok(nodes) => nodes,
+        Err(err) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            ))
+        }
+    };
+    let records = match app
+        .bi_stream_manage
+        .send(BiStreamManageCmd::QueryRebalanceRecords)
+        .await
+    {
+        Ok(Ok(BiStreamManageResult::RebalanceRecords(records))) => records,
+        _ => vec![],
+    };
+    let total: usize = nodes.iter().map(|e| e.conn_count).sum();
+    let avg_count = if nodes.is_empty() {
+        0
+    } else {
+        total.div_ceil(nodes.len())
+    };
+    let info = GrpcConnBalanceInfo {
+        rebalance_enable: app.sys_config.grpc_rebalance_enable,
+        avg_count,
+        nodes: nodes.into_iter().map(|e| e.into()).collect(),
+        records,
+    };
+    HttpResponse::Ok().json(ApiResult::success(Some(info)))
+}
+
+///
+/// 立即触发集群所有节点的连接迁移
+pub async fn rebalance_grpc_conn(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    app.bi_stream_manage
+        .do_send(BiStreamManageCmd::Rebalance { manual: true });
+    app.naming_inner_node_manage
+        .do_send(NodeManageRequest::SendToOtherNodes(
+            NamingRouteRequest::GrpcConnRebalance,
+        ));
+    HttpResponse::Ok().json(ApiResult::success(Some(true)))
+}
+
+pub async fn query_raft_membership(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    match query_membership(app.get_ref()).await {
+        Ok(info) => HttpResponse::Ok().json(ApiResult::success(Some(info))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+///
+/// 集群各节点的raft状态与复制延迟
+pub async fn query_raft_status(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    match query_cluster_status(app.get_ref()).await {
+        Ok(status) => HttpResponse::Ok().json(ApiResult::success(Some(status))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+async fn do_membership_req(app: &Arc<AppShareData>, req: MembershipReq) -> HttpResponse {
+    match request_membership(app, req).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+pub async fn add_raft_learner(
+    app: web::Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<RaftMemberParam>,
+) -> impl Responder {
+    let req = MembershipReq::AddLearner {
+        node_id: param.node_id,
+        addr: Arc::new(param.node_addr.unwrap_or_default()),
+    };
+    do_membership_req(app.get_ref(), req).await
+}
+
+pub async fn promote_raft_learner(
+    app: web::Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<RaftMemberParam>,
+) -> impl Responder {
+    let req = MembershipReq::Promote {
+        node_id: param.node_id,
+    };
+    do_membership_req(app.get_ref(), req).await
+}
+
+///
+/// 移除投票节点或learner,同时删除节点地址
+pub async fn remove_raft_node(
+    app: web::Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<RaftMemberParam>,
+) -> impl Responder {
+    let req = MembershipReq::Remove {
+        node_id: param.node_id,
+    };
+    do_membership_req(app.get_ref(), req).await
+}
+
+///
+/// 转移leader到指定节点,未指定时选择日志最新的follower
+pub async fn transfer_raft_leader(
+    app: web::Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<RaftTransferLeaderParam>,
+) -> impl Responder {
+    match transfer_leader(app.get_ref(), param.node_id).await {
+        Ok(result) => HttpResponse::Ok().json(ApiResult::success(Some(result))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::common::appdata::AppShareData;
+use crate::common::model::{ApiResult, PageResult};
+use crate::config::core::{ConfigCmd, ConfigKey, ConfigResult};
+use crate::console::model::connection_model::{
+    ConnectionCloseParam, ConnectionDetailDto, ConnectionQueryRequest,
+};
+use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
+use crate::grpc::bistream_manage::{BiStreamManageCmd, BiStreamManageResult};
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::naming::model::ServiceKey;
+
+///
+/// 查询本节点的sdk连接详情,合并连接信息与配置、服务订阅关系
+pub async fn query_connection_list(
+    param: web::Query<ConnectionQueryRequest>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (total_count, conn_list) = match app
+        .bi_stream_manage
+        .send(BiStreamManageCmd::QueryConnDetailPage(param.0.to_param()))
+        .await
+    {
+        Ok(Ok(BiStreamManageResult::ConnDetailPage(total_count, list))) => (total_count, list),
+        Ok(Err(err)) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            ))
+        }
+        Err(err) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            ))
+        }
+        _ => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                None,
+            ))
+        }
+    };
+    let client_ids: Vec<Arc<String>> = conn_list.iter().map(|e| e.client_id.clone()).collect();
+    let mut config_subscribes: HashMap<Arc<String>, Vec<ConfigKey>> = match app
+        .config_addr
+        .send(ConfigCmd::QueryClientSubscribes(client_ids.clone()))
+        .await
+    {
+        Ok(Ok(ConfigResult::ClientSubscribes(map))) => map,
+        _ => HashMap::new(),
+    };
+    let mut service_subscribes: HashMap<Arc<String>, Vec<ServiceKey>> = match app
+        .naming_addr
+        .send(NamingCmd::QueryClientSubscribes(client_ids))
+        .await
+    {
+        Ok(Ok(NamingResult::ClientSubscribes(map))) => map,
+        _ => HashMap::new(),
+    };
+    let list: Vec<ConnectionDetailDto> = conn_list
+        .into_iter()
+        .map(|conn| ConnectionDetailDto {
+            config_subscribes: config_subscribes
+                .remove(&conn.client_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| e.into())
+                .collect(),
+            service_subscribes: service_subscribes
+                .remove(&conn.client_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| e.into())
+                .collect(),
+            conn,
+        })
+        .collect();
+    HttpResponse::Ok().json(ApiResult::success(Some(PageResult { total_count, list })))
+}
+
+///
+/// 强制断开本节点的sdk连接
+pub async fn close_connection(
+    param: web::Json<ConnectionCloseParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match app
+        .bi_stream_manage
+        .send(BiStreamManageCmd::CloseConn(param.0.client_id))
+        .await
+    {
+        Ok(Ok(BiStreamManageResult::CloseResult(v))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(v)))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
@@ -1,12 +1,14 @@
 use crate::common::appdata::AppShareData;
 use crate::common::model::{ApiResult, PageResult};
 use crate::console::model::naming_model::{
-    InstanceParams, ServiceDto, ServiceParam, ServiceQueryListRequest,
+    InstanceDrainParams, InstanceEventQueryRequest, InstanceParams, PushAuditQueryRequest,
+    ServiceDto, ServiceParam, ServiceQueryListRequest,
 };
 use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
 use crate::naming::api_model::InstanceVO;
 use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
 use crate::naming::model::{InstanceUpdateTag, ServiceDetailDto};
+use crate::naming::naming_delay_nofity::{DelayNotifyCmd, DelayNotifyResult};
 use crate::naming::NamingUtils;
 use crate::{user_namespace_privilege, user_no_namespace_permission};
 use actix::Addr;
@@ -170,6 +172,79 @@ pub async fn query_instances_list(
     }
 }
 
+pub async fn query_instance_events(
+    req: HttpRequest,
+    param: web::Query<InstanceEventQueryRequest>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = param.0.to_param();
+    let namespace_privilege = user_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&param.service_key.namespace_id) {
+        user_no_namespace_permission!(&param.service_key.namespace_id);
+    }
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryInstanceEvents(param))
+        .await
+    {
+        Ok(Ok(NamingResult::InstanceEvents(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count: list.len(),
+                list,
+            })))
+        }
+        Ok(Ok(_)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+pub async fn query_push_audit(
+    req: HttpRequest,
+    param: web::Query<PushAuditQueryRequest>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = param.0.to_param();
+    let namespace_id = Arc::new(param.namespace_id.clone().unwrap_or_default());
+    let namespace_privilege = user_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&namespace_id) {
+        user_no_namespace_permission!(&namespace_id);
+    }
+    match appdata
+        .naming_delay_notify
+        .send(DelayNotifyCmd::QueryPushAudit(param))
+        .await
+    {
+        Ok(Ok(DelayNotifyResult::PushAudit(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count: list.len(),
+                list,
+            })))
+        }
+        Ok(Ok(_)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
 pub async fn get_instance(
     req: HttpRequest,
     appdata: Data<Arc<AppShareData>>,
@@ -295,3 +370,51 @@ pub async fn remove_instance(
         )),
     }
 }
+
+pub async fn drain_instance(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<InstanceDrainParams>,
+) -> impl Responder {
+    do_drain_instance(req, appdata, param, false).await
+}
+
+pub async fn cancel_drain_instance(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<InstanceDrainParams>,
+) -> impl Responder {
+    do_drain_instance(req, appdata, param, true).await
+}
+
+async fn do_drain_instance(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    param: InstanceDrainParams,
+    cancel: bool,
+) -> HttpResponse {
+    let param = match param.to_param(cancel) {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err),
+            ))
+        }
+    };
+    let namespace_privilege = user_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&param.namespace_id) {
+        user_no_namespace_permission!(&param.namespace_id);
+    }
+    match appdata.naming_route.drain_instance(param).await {
+        Ok(updated) if updated.is_empty() => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some("instance not found".to_string()),
+        )),
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
+
+use crate::api_namespace_privilege;
+use crate::common::appdata::AppShareData;
+use crate::consul::model::{
+    build_catalog_services, get_instance_consul_id, get_instance_tags, AgentServiceRegistration,
+    ConsulQueryParams, ServiceEntry, CONSUL_INDEX_HEADER,
+};
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::naming::model::{Instance, InstanceUpdateTag};
+use crate::naming::service_index::ServiceQueryParam;
+use crate::naming::DEFAULT_GROUP;
+
+pub(crate) fn permission_denied() -> HttpResponse {
+    HttpResponse::Forbidden().body("Permission denied")
+}
+
+fn build_query_param(namespace_id: Arc<String>, service: Option<String>) -> ServiceQueryParam {
+    ServiceQueryParam {
+        namespace_id: Some(namespace_id),
+        group: Some(Arc::new(DEFAULT_GROUP.to_owned())),
+        service: service.map(Arc::new),
+        limit: 0xffff_ffff,
+        ..Default::default()
+    }
+}
+
+async fn query_instances(
+    appdata: &web::Data<Arc<AppShareData>>,
+    param: &ServiceQueryParam,
+) -> anyhow::Result<(i64, Vec<Arc<Instance>>)> {
+    let cmd = NamingCmd::QueryInstanceListWithIndex(param.clone());
+    match appdata.naming_addr.send(cmd).await?? {
+        //consul要求index大于0
+        NamingResult::InstanceListWithIndex(index, list) => Ok((index.max(1), list)),
+        _ => Err(anyhow::anyhow!("naming result error")),
+    }
+}
+
+///
+/// 查询实例列表;请求带index时按consul阻塞查询处理,收到服务变更通知后重新查询,直到数据变更或等待超时
+async fn query_instances_with_index(
+    appdata: &web::Data<Arc<AppShareData>>,
+    param: ServiceQueryParam,
+    query: &ConsulQueryParams,
+) -> anyhow::Result<(i64, Vec<Arc<Instance>>)> {
+    let wait_index = match query.index {
+        Some(v) => v,
+        None => return query_instances(appdata, &param).await,
+    };
+    let mut change_receiver = match appdata
+        .naming_addr
+        .send(NamingCmd::SubscribeServiceChange)
+        .await??
+    {
+        NamingResult::ServiceChangeReceiver(receiver) => receiver,
+        _ => return Err(anyhow::anyhow!("naming result error")),
+    };
+    let deadline = tokio::time::Instant::now() + query.get_wait();
+    loop {
+        change_receiver.borrow_and_update();
+        let (index, list) = query_instances(appdata, &param).await?;
+        if wait_index != index as u64 {
+            return Ok((index, list));
+        }
+        match tokio::time::timeout_at(deadline, change_receiver.changed()).await {
+            Ok(Ok(_)) => {}
+            _ => return Ok((index, list)),
+        }
+    }
+}
+
+async fn find_instance_by_id(
+    appdata: &web::Data<Arc<AppShareData>>,
+    namespace_id: Arc<String>,
+    id: &str,
+) -> anyhow::Result<Option<Arc<Instance>>> {
+    let cmd = NamingCmd::QueryInstanceListWithIndex(build_query_param(namespace_id, None));
+    match appdata.naming_addr.send(cmd).await?? {
+        NamingResult::InstanceListWithIndex(_, list) => {
+            Ok(list.into_iter().find(|e| get_instance_consul_id(e) == id))
+        }
+        _ => Err(anyhow::anyhow!("naming result error")),
+    }
+}
+
+pub async fn register_service(
+    req: HttpRequest,
+    param: web::Query<ConsulQueryParams>,
+    body: web::Bytes,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let namespace_id = param.get_namespace_id();
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&namespace_id) {
+        return permission_denied();
+    }
+    let registration: AgentServiceRegistration = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Request decode failed: {}", e)),
+    };
+    let peer_ip = req.peer_addr().map(|addr| addr.ip().to_string());
+    let instance = match registration.to_instance(namespace_id, peer_ip) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    match appdata.naming_route.update_instance(instance, None).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+pub async fn deregister_service(
+    req: HttpRequest,
+    path: web::Path<String>,
+    param: web::Query<ConsulQueryParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let namespace_id = param.get_namespace_id();
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&namespace_id) {
+        return permission_denied();
+    }
+    let service_id = path.into_inner();
+    match find_instance_by_id(&appdata, namespace_id, &service_id).await {
+        Ok(Some(instance)) => {
+            match appdata
+                .naming_route
+                .delete_instance(instance.as_ref().clone())
+                .await
+            {
+                Ok(_) => HttpResponse::Ok().finish(),
+                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            }
+        }
+        Ok(None) => HttpResponse::NotFound().body(format!("Unknown service ID {:?}", &service_id)),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+///
+/// TTL检查更新,pass/warn作为心跳,fail把实例置为不健康
+async fn update_check(
+    req: HttpRequest,
+    check_id: String,
+    param: web::Query<ConsulQueryParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+    passing: bool,
+) -> HttpResponse {
+    let namespace_id = param.get_namespace_id();
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&namespace_id) {
+        return permission_denied();
+    }
+    let service_id = check_id.strip_prefix("service:").unwrap_or(&check_id);
+    let old_instance = match find_instance_by_id(&appdata, namespace_id, service_id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return HttpResponse::NotFound().body(format!("Unknown check ID {:?}", &check_id))
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut instance = old_instance.as_ref().clone();
+    instance.healthy = passing;
+    instance.init();
+    //健康状态不变时只更新心跳时间
+    let tag = if old_instance.healthy == passing {
+        Some(InstanceUpdateTag {
+            weight: false,
+            enabled: false,
+            ephemeral: false,
+            metadata: false,
+            from_update: false,
+        })
+    } else {
+        None
+    };
+    match appdata.naming_route.update_instance(instance, tag).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+pub async fn pass_check(
+    req: HttpRequest,
+    path: web::Path<String>,
+    param: web::Query<ConsulQueryParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    update_check(req, path.into_inner(), param, appdata, true).await
+}
+
+pub async fn fail_check(
+    req: HttpRequest,
+    path: web::Path<String>,
+    param: web::Query<ConsulQueryParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    update_check(req, path.into_inner(), param, appdata, false).await
+}
+
+pub async fn catalog_services(
+    req: HttpRequest,
+    param: web::Query<ConsulQueryParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let namespace_id = param.get_namespace_id();
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&namespace_id) {
+        return permission_denied();
+    }
+    let query_param = build_query_param(namespace_id, None);
+    match query_instances_with_index(&appdata, query_param, &param).await {
+        Ok((index, list)) => HttpResponse::Ok()
+            .insert_header((CONSUL_INDEX_HEADER, index.to_string()))
+            .json(build_catalog_services(&list)),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+pub async fn health_service(
+    req: HttpRequest,
+    path: web::Path<String>,
+    param: web::Query<ConsulQueryParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let namespace_id = param.get_namespace_id();
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&namespace_id) {
+        return permission_denied();
+    }
+    let query_param = build_query_param(namespace_id, Some(path.into_inner()));
+    match query_instances_with_index(&appdata, query_param, &param).await {
+        Ok((index, list)) => {
+            let only_passing = param.is_passing();
+            let entries: Vec<ServiceEntry> = list
+                .iter()
+                .filter(|e| !only_passing || ServiceEntry::is_passing(e))
+                .filter(|e| match &param.tag {
+                    Some(tag) if !tag.is_empty() => get_instance_tags(e).contains(tag),
+                    _ => true,
+                })
+                .map(|e| ServiceEntry::from_instance(e))
+                .collect();
+            HttpResponse::Ok()
+                .insert_header((CONSUL_INDEX_HEADER, index.to_string()))
+                .json(entries)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+///
+/// 返回raft leader地址,没有leader时返回空字符串
+pub async fn status_leader(appdata: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let addr = match appdata.raft.current_leader().await {
+        Some(leader) => appdata
+            .raft_store
+            .get_target_addr(leader)
+            .await
+            .map(|v| v.as_ref().to_owned())
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+    HttpResponse::Ok().json(addr)
+}
@@ -0,0 +1,249 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
+
+use crate::api_namespace_privilege;
+use crate::common::appdata::AppShareData;
+use crate::common::model::TokenSession;
+use crate::config::config_index::ConfigQueryParam;
+use crate::config::core::{ConfigCmd, ConfigKey, ConfigResult};
+use crate::consul::api::permission_denied;
+use crate::consul::model::{ConsulQueryParams, KVPair, CONSUL_INDEX_HEADER, CONSUL_KV_GROUP};
+use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
+
+///
+/// 按前缀查询kv,返回(key,value,修改时间)
+async fn query_kv_list(
+    appdata: &web::Data<Arc<AppShareData>>,
+    tenant: Arc<String>,
+    prefix: &str,
+) -> anyhow::Result<Vec<(Arc<String>, Arc<String>, i64)>> {
+    let query_param = ConfigQueryParam {
+        tenant: Some(tenant),
+        group: Some(Arc::new(CONSUL_KV_GROUP.to_owned())),
+        query_context: true,
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let cmd = ConfigCmd::QueryPageInfo(Box::new(query_param));
+    match appdata.config_addr.send(cmd).await?? {
+        ConfigResult::ConfigInfoPage(_, list) => {
+            let mut rlist: Vec<_> = list
+                .into_iter()
+                .filter(|e| e.data_id.starts_with(prefix))
+                .map(|e| {
+                    (
+                        e.data_id,
+                        e.content.unwrap_or_default(),
+                        e.last_modified.unwrap_or_default(),
+                    )
+                })
+                .collect();
+            rlist.sort_by(|a, b| a.0.cmp(&b.0));
+            Ok(rlist)
+        }
+        _ => Err(anyhow::anyhow!("config result error")),
+    }
+}
+
+fn check_key_privilege(req: &HttpRequest, key: &ConfigKey) -> bool {
+    let namespace_privilege = api_namespace_privilege!(req);
+    namespace_privilege.check_permission(&key.tenant)
+}
+
+enum KvQueryResult {
+    List(Vec<(Arc<String>, Arc<String>, i64)>),
+    //配置内容及修改时间
+    Value(Arc<String>, i64),
+    NotFound,
+}
+
+///
+/// 查询kv及consul index;index取kv所在分组的变更时间,删除key时也会变化
+async fn query_kv(
+    appdata: &web::Data<Arc<AppShareData>>,
+    key: &ConfigKey,
+    param: &ConsulQueryParams,
+) -> anyhow::Result<(i64, KvQueryResult)> {
+    let group_index = match appdata
+        .config_addr
+        .send(ConfigCmd::QueryGroupChangeIndex(key.clone()))
+        .await??
+    {
+        ConfigResult::GroupChangeIndex(index) => index,
+        _ => 0,
+    };
+    if param.is_recurse() || param.is_keys() {
+        let list = query_kv_list(appdata, key.tenant.clone(), &key.data_id).await?;
+        let index = list
+            .iter()
+            .map(|e| e.2)
+            .max()
+            .unwrap_or_default()
+            .max(group_index)
+            .max(1);
+        if list.is_empty() {
+            return Ok((index, KvQueryResult::NotFound));
+        }
+        return Ok((index, KvQueryResult::List(list)));
+    }
+    match appdata
+        .config_addr
+        .send(ConfigCmd::GET(key.clone()))
+        .await??
+    {
+        ConfigResult::Data {
+            value,
+            last_modified,
+            ..
+        } => Ok((
+            last_modified.max(group_index).max(1),
+            KvQueryResult::Value(value, last_modified),
+        )),
+        _ => Ok((group_index.max(1), KvQueryResult::NotFound)),
+    }
+}
+
+///
+/// 请求带index时按consul阻塞查询处理,收到配置变更通知后重新查询,直到index变化或等待超时
+async fn query_kv_with_index(
+    appdata: &web::Data<Arc<AppShareData>>,
+    key: &ConfigKey,
+    param: &ConsulQueryParams,
+) -> anyhow::Result<(i64, KvQueryResult)> {
+    let wait_index = match param.index {
+        Some(v) => v,
+        None => return query_kv(appdata, key, param).await,
+    };
+    let mut change_receiver = match appdata
+        .config_addr
+        .send(ConfigCmd::SubscribeChange)
+        .await??
+    {
+        ConfigResult::ChangeReceiver(receiver) => receiver,
+        _ => return Err(anyhow::anyhow!("config result error")),
+    };
+    let deadline = tokio::time::Instant::now() + param.get_wait();
+    loop {
+        change_receiver.borrow_and_update();
+        let (index, result) = query_kv(appdata, key, param).await?;
+        if wait_index != index as u64 {
+            return Ok((index, result));
+        }
+        match tokio::time::timeout_at(deadline, change_receiver.changed()).await {
+            Ok(Ok(_)) => {}
+            _ => return Ok((index, result)),
+        }
+    }
+}
+
+pub async fn get_kv(
+    req: HttpRequest,
+    path: web::Path<String>,
+    param: web::Query<ConsulQueryParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = param.to_kv_key(&path.into_inner());
+    if !check_key_privilege(&req, &key) {
+        return permission_denied();
+    }
+    let (index, result) = match query_kv_with_index(&appdata, &key, &param).await {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    match result {
+        KvQueryResult::NotFound => HttpResponse::NotFound()
+            .insert_header((CONSUL_INDEX_HEADER, index.to_string()))
+            .finish(),
+        KvQueryResult::List(list) => {
+            let mut response = HttpResponse::Ok();
+            response.insert_header((CONSUL_INDEX_HEADER, index.to_string()));
+            if param.is_keys() {
+                let keys: Vec<Arc<String>> = list.into_iter().map(|e| e.0).collect();
+                return response.json(keys);
+            }
+            let pairs: Vec<KVPair> = list
+                .into_iter()
+                .map(|(k, v, i)| KVPair::new(k.as_ref().to_owned(), &v, i))
+                .collect();
+            response.json(pairs)
+        }
+        KvQueryResult::Value(value, last_modified) => {
+            let mut response = HttpResponse::Ok();
+            response.insert_header((CONSUL_INDEX_HEADER, index.to_string()));
+            if param.is_raw() {
+                response.body(value.as_ref().to_owned())
+            } else {
+                response.json(vec![KVPair::new(
+                    key.data_id.as_ref().to_owned(),
+                    &value,
+                    last_modified.max(1),
+                )])
+            }
+        }
+    }
+}
+
+pub async fn put_kv(
+    req: HttpRequest,
+    path: web::Path<String>,
+    param: web::Query<ConsulQueryParams>,
+    body: web::Bytes,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = param.to_kv_key(&path.into_inner());
+    if key.data_id.is_empty() {
+        return HttpResponse::BadRequest().body("Missing key name");
+    }
+    if !check_key_privilege(&req, &key) {
+        return permission_denied();
+    }
+    let value = match String::from_utf8(body.to_vec()) {
+        Ok(v) => v,
+        Err(_) => return HttpResponse::BadRequest().body("Value must be utf-8 encoded"),
+    };
+    let mut set_req = SetConfigReq::new(key, Arc::new(value));
+    set_req.op_user = req
+        .extensions()
+        .get::<Arc<TokenSession>>()
+        .map(|session| session.username.clone());
+    match appdata.config_route.set_config(set_req).await {
+        Ok(_) => HttpResponse::Ok().json(true),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+pub async fn delete_kv(
+    req: HttpRequest,
+    path: web::Path<String>,
+    param: web::Query<ConsulQueryParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = param.to_kv_key(&path.into_inner());
+    if !check_key_privilege(&req, &key) {
+        return permission_denied();
+    }
+    let keys = if param.is_recurse() {
+        match query_kv_list(&appdata, key.tenant.clone(), &key.data_id).await {
+            Ok(list) => list
+                .into_iter()
+                .map(|e| ConfigKey::new_by_arc(e.0, key.group.clone(), key.tenant.clone()))
+                .collect(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        }
+    } else if key.data_id.is_empty() {
+        return HttpResponse::BadRequest().body("Missing key name");
+    } else {
+        vec![key]
+    };
+    for key in keys {
+        if let Err(e) = appdata
+            .config_route
+            .del_config(DelConfigReq::new(key))
+            .await
+        {
+            return HttpResponse::InternalServerError().body(e.to_string());
+        }
+    }
+    HttpResponse::Ok().json(true)
+}
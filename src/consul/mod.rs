@@ -0,0 +1,43 @@
+//consul http api compatibility layer
+use actix_web::web;
+
+pub mod api;
+pub mod kv;
+pub mod model;
+
+///
+/// consul兼容接口,服务映射到DEFAULT_GROUP,kv映射到consul_kv分组的配置
+pub fn consul_config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/v1")
+            .service(
+                web::resource("/agent/service/register")
+                    .route(web::put().to(api::register_service)),
+            )
+            .service(
+                web::resource("/agent/service/deregister/{service_id}")
+                    .route(web::put().to(api::deregister_service)),
+            )
+            .service(
+                web::resource("/agent/check/pass/{check_id}").route(web::put().to(api::pass_check)),
+            )
+            .service(
+                web::resource("/agent/check/warn/{check_id}").route(web::put().to(api::pass_check)),
+            )
+            .service(
+                web::resource("/agent/check/fail/{check_id}").route(web::put().to(api::fail_check)),
+            )
+            .service(web::resource("/catalog/services").route(web::get().to(api::catalog_services)))
+            .service(
+                web::resource("/health/service/{service}")
+                    .route(web::get().to(api::health_service)),
+            )
+            .service(web::resource("/status/leader").route(web::get().to(api::status_leader)))
+            .service(
+                web::resource("/kv/{key:.*}")
+                    .route(web::get().to(kv::get_kv))
+                    .route(web::put().to(kv::put_kv))
+                    .route(web::delete().to(kv::delete_kv)),
+            ),
+    );
+}
@@ -0,0 +1,397 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::crypto_utils;
+use crate::config::core::ConfigKey;
+use crate::config::ConfigUtils;
+use crate::naming::model::Instance;
+use crate::naming::{NamingUtils, DEFAULT_GROUP, PRESERVED_HEART_BEAT_DISABLE};
+
+pub const CONSUL_ID_KEY: &str = "consul.id";
+pub const CONSUL_TAGS_KEY: &str = "consul.tags";
+pub const CONSUL_KV_GROUP: &str = "consul_kv";
+pub const CONSUL_INDEX_HEADER: &str = "X-Consul-Index";
+pub const CONSUL_DATACENTER: &str = "dc1";
+pub const CHECK_PASSING: &str = "passing";
+pub const CHECK_CRITICAL: &str = "critical";
+
+const DEFAULT_WAIT: Duration = Duration::from_secs(300);
+const MAX_WAIT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct AgentWeights {
+    pub passing: i32,
+    pub warning: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct AgentServiceCheck {
+    #[serde(rename = "CheckID")]
+    pub check_id: Option<String>,
+    pub name: Option<String>,
+    #[serde(rename = "TTL")]
+    pub ttl: Option<String>,
+    #[serde(rename = "HTTP")]
+    pub http: Option<String>,
+    #[serde(rename = "TCP")]
+    pub tcp: Option<String>,
+    pub interval: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct AgentServiceRegistration {
+    #[serde(rename = "ID")]
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub address: Option<String>,
+    pub port: Option<u32>,
+    pub meta: Option<HashMap<String, String>>,
+    pub weights: Option<AgentWeights>,
+    pub check: Option<AgentServiceCheck>,
+    pub checks: Option<Vec<AgentServiceCheck>>,
+}
+
+impl AgentServiceRegistration {
+    ///
+    /// 带TTL检查的服务注册为临时实例,由check/pass维持心跳;否则注册为持久化实例,不做心跳过期检查
+    pub fn has_ttl_check(&self) -> bool {
+        self.check
+            .iter()
+            .chain(self.checks.iter().flatten())
+            .any(|e| matches!(&e.ttl, Some(v) if !v.is_empty()))
+    }
+
+    pub fn to_instance(
+        self,
+        namespace_id: Arc<String>,
+        peer_ip: Option<String>,
+    ) -> anyhow::Result<Instance> {
+        let ephemeral = self.has_ttl_check();
+        let service_name = match self.name {
+            Some(v) if !v.is_empty() => v,
+            _ => return Err(anyhow::anyhow!("Missing service name")),
+        };
+        let ip = match self.address {
+            Some(v) if !v.is_empty() => v,
+            _ => peer_ip.ok_or_else(|| anyhow::anyhow!("Missing service address"))?,
+        };
+        let mut metadata = self.meta.unwrap_or_default();
+        metadata.insert(
+            CONSUL_ID_KEY.to_owned(),
+            self.id
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| service_name.clone()),
+        );
+        if let Some(tags) = self.tags.filter(|v| !v.is_empty()) {
+            metadata.insert(CONSUL_TAGS_KEY.to_owned(), serde_json::to_string(&tags)?);
+        }
+        if !ephemeral {
+            metadata.insert(PRESERVED_HEART_BEAT_DISABLE.to_owned(), "true".to_owned());
+        }
+        let mut instance = Instance::new(ip, self.port.unwrap_or_default());
+        instance.namespace_id = namespace_id;
+        instance.group_name = Arc::new(DEFAULT_GROUP.to_owned());
+        instance.service_name = Arc::new(service_name);
+        instance.weight = self.weights.map(|v| v.passing).unwrap_or(1).max(0) as f32;
+        instance.ephemeral = ephemeral;
+        instance.metadata = Arc::new(metadata);
+        instance.init();
+        Ok(instance)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ConsulQueryParams {
+    pub ns: Option<String>,
+    pub index: Option<u64>,
+    pub wait: Option<String>,
+    pub passing: Option<String>,
+    pub tag: Option<String>,
+    pub raw: Option<String>,
+    pub keys: Option<String>,
+    pub recurse: Option<String>,
+}
+
+impl ConsulQueryParams {
+    pub fn get_namespace_id(&self) -> Arc<String> {
+        Arc::new(NamingUtils::default_namespace(
+            self.ns.clone().unwrap_or_default(),
+        ))
+    }
+
+    ///
+    /// consul中 ?passing 这类无值参数也表示开启
+    fn is_flag(v: &Option<String>) -> bool {
+        match v {
+            Some(v) => v != "false",
+            None => false,
+        }
+    }
+
+    pub fn is_passing(&self) -> bool {
+        Self::is_flag(&self.passing)
+    }
+
+    pub fn is_raw(&self) -> bool {
+        Self::is_flag(&self.raw)
+    }
+
+    pub fn is_keys(&self) -> bool {
+        Self::is_flag(&self.keys)
+    }
+
+    pub fn is_recurse(&self) -> bool {
+        Self::is_flag(&self.recurse)
+    }
+
+    pub fn get_wait(&self) -> Duration {
+        self.wait
+            .as_ref()
+            .and_then(|v| parse_duration(v))
+            .unwrap_or(DEFAULT_WAIT)
+            .min(MAX_WAIT)
+    }
+
+    pub fn to_kv_key(&self, key: &str) -> ConfigKey {
+        ConfigKey::new(
+            key,
+            CONSUL_KV_GROUP,
+            &ConfigUtils::default_tenant(self.ns.clone().unwrap_or_default()),
+        )
+    }
+}
+
+///
+/// 解析consul的时长参数,如 10s,5m,500ms
+pub fn parse_duration(v: &str) -> Option<Duration> {
+    let v = v.trim();
+    let split = v.find(|c: char| !c.is_ascii_digit()).unwrap_or(v.len());
+    let (num, unit) = v.split_at(split);
+    let num: u64 = num.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(num)),
+        "" | "s" => Some(Duration::from_secs(num)),
+        "m" => Some(Duration::from_secs(num * 60)),
+        "h" => Some(Duration::from_secs(num * 3600)),
+        _ => None,
+    }
+}
+
+pub fn get_instance_tags(instance: &Instance) -> Vec<String> {
+    instance
+        .metadata
+        .get(CONSUL_TAGS_KEY)
+        .and_then(|v| serde_json::from_str(v).ok())
+        .unwrap_or_default()
+}
+
+pub fn get_instance_consul_id(instance: &Instance) -> String {
+    match instance.metadata.get(CONSUL_ID_KEY) {
+        Some(v) => v.to_owned(),
+        None => format!(
+            "{}-{}-{}",
+            &instance.service_name, &instance.ip, instance.port
+        ),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct ConsulNode {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub node: String,
+    pub address: String,
+    pub datacenter: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct AgentService {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub service: String,
+    pub tags: Vec<String>,
+    pub address: String,
+    pub port: u32,
+    pub meta: HashMap<String, String>,
+    pub weights: AgentWeights,
+    pub enable_tag_override: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct HealthCheck {
+    pub node: String,
+    #[serde(rename = "CheckID")]
+    pub check_id: String,
+    pub name: String,
+    pub status: String,
+    #[serde(rename = "ServiceID")]
+    pub service_id: String,
+    pub service_name: String,
+    pub service_tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct ServiceEntry {
+    pub node: ConsulNode,
+    pub service: AgentService,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl ServiceEntry {
+    pub fn is_passing(instance: &Instance) -> bool {
+        instance.healthy && instance.enabled
+    }
+
+    pub fn from_instance(instance: &Instance) -> Self {
+        let id = get_instance_consul_id(instance);
+        let tags = get_instance_tags(instance);
+        let mut meta = instance.metadata.as_ref().clone();
+        meta.remove(CONSUL_ID_KEY);
+        meta.remove(CONSUL_TAGS_KEY);
+        meta.remove(PRESERVED_HEART_BEAT_DISABLE);
+        let status = if Self::is_passing(instance) {
+            CHECK_PASSING
+        } else {
+            CHECK_CRITICAL
+        };
+        let node = instance.ip.as_ref().to_owned();
+        Self {
+            node: ConsulNode {
+                id: node.clone(),
+                node: node.clone(),
+                address: node.clone(),
+                datacenter: CONSUL_DATACENTER.to_owned(),
+            },
+            checks: vec![HealthCheck {
+                node,
+                check_id: format!("service:{}", &id),
+                name: format!("Service '{}' check", &instance.service_name),
+                status: status.to_owned(),
+                service_id: id.clone(),
+                service_name: instance.service_name.as_ref().to_owned(),
+                service_tags: tags.clone(),
+            }],
+            service: AgentService {
+                id,
+                service: instance.service_name.as_ref().to_owned(),
+                tags,
+                address: instance.ip.as_ref().to_owned(),
+                port: instance.port,
+                meta,
+                weights: AgentWeights {
+                    passing: instance.weight as i32,
+                    warning: 1,
+                },
+                enable_tag_override: false,
+            },
+        }
+    }
+}
+
+///
+/// 按服务名汇总tag,对应 /v1/catalog/services 返回值
+pub fn build_catalog_services(instances: &[Arc<Instance>]) -> HashMap<String, Vec<String>> {
+    let mut services: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for instance in instances {
+        services
+            .entry(instance.service_name.as_ref().to_owned())
+            .or_default()
+            .extend(get_instance_tags(instance));
+    }
+    services
+        .into_iter()
+        .map(|(k, v)| (k, v.into_iter().collect()))
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct KVPair {
+    pub key: String,
+    pub create_index: i64,
+    pub modify_index: i64,
+    pub lock_index: i64,
+    pub flags: u64,
+    pub value: Option<String>,
+}
+
+impl KVPair {
+    pub fn new(key: String, value: &str, modify_index: i64) -> Self {
+        Self {
+            key,
+            create_index: modify_index,
+            modify_index,
+            lock_index: 0,
+            flags: 0,
+            value: Some(crypto_utils::encode_base64(value.as_bytes())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration_to_instance() {
+        let body = r#"{"ID":"foo-8080","Name":"foo","Tags":["v1","primary"],"Address":"10.0.0.1","Port":8080,"Meta":{"zone":"a"},"Check":{"TTL":"10s"}}"#;
+        let registration: AgentServiceRegistration = serde_json::from_str(body).unwrap();
+        let instance = registration
+            .to_instance(Arc::new("public".to_owned()), None)
+            .unwrap();
+        assert!(instance.ephemeral);
+        assert!(instance.is_enable_timeout());
+        assert_eq!(instance.ip.as_str(), "10.0.0.1");
+        assert_eq!(instance.group_name.as_str(), DEFAULT_GROUP);
+        assert_eq!(get_instance_consul_id(&instance), "foo-8080");
+        assert_eq!(get_instance_tags(&instance), vec!["v1", "primary"]);
+
+        let entry = ServiceEntry::from_instance(&instance);
+        assert_eq!(entry.service.id, "foo-8080");
+        assert_eq!(entry.service.meta.len(), 1);
+        assert_eq!(entry.checks[0].status, CHECK_PASSING);
+        assert_eq!(entry.checks[0].check_id, "service:foo-8080");
+    }
+
+    #[test]
+    fn test_registration_without_ttl() {
+        let body = r#"{"ID":"bar-8080","Name":"bar","Address":"10.0.0.2","Port":8080}"#;
+        let registration: AgentServiceRegistration = serde_json::from_str(body).unwrap();
+        let instance = registration
+            .to_instance(Arc::new("public".to_owned()), None)
+            .unwrap();
+        assert!(!instance.ephemeral);
+        assert!(!instance.is_enable_timeout());
+        let entry = ServiceEntry::from_instance(&instance);
+        assert!(entry.service.meta.is_empty());
+
+        //http注册的持久化实例仍做过期检查
+        let mut instance = Instance::new("10.0.0.3".to_owned(), 8080);
+        instance.ephemeral = false;
+        assert!(instance.is_enable_timeout());
+    }
+
+    #[test]
+    fn test_query_params() {
+        let param: ConsulQueryParams =
+            serde_urlencoded::from_str("passing&index=12&wait=30s").unwrap();
+        assert!(param.is_passing());
+        assert!(!param.is_recurse());
+        assert_eq!(param.index, Some(12));
+        assert_eq!(param.get_wait(), Duration::from_secs(30));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("5x"), None);
+    }
+}
@@ -0,0 +1,4 @@
+//dns server for service discovery
+
+pub mod model;
+pub mod server;
@@ -0,0 +1,621 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+
+use crate::naming::model::ServiceKey;
+use crate::naming::NamingUtils;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+pub const TYPE_ANY: u16 = 255;
+pub const CLASS_IN: u16 = 1;
+
+pub const RCODE_NO_ERROR: u8 = 0;
+pub const RCODE_FORMAT_ERROR: u8 = 1;
+pub const RCODE_SERVER_FAILURE: u8 = 2;
+pub const RCODE_NAME_ERROR: u8 = 3;
+pub const RCODE_NOT_IMPLEMENTED: u8 = 4;
+pub const RCODE_REFUSED: u8 = 5;
+
+/// 地址记录的二级域名,SRV记录的target指向 `<ip>.addr.<domain>`
+pub const ADDR_LABEL: &str = "addr";
+
+const HEADER_LEN: usize = 12;
+const MAX_LABEL_LEN: usize = 63;
+const MAX_POINTER_JUMPS: usize = 16;
+/// 不支持EDNS时udp响应的最大长度
+pub const MAX_UDP_PAYLOAD: usize = 512;
+//tcp响应使用2字节长度前缀
+pub const MAX_TCP_PAYLOAD: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DnsHeader {
+    pub id: u16,
+    pub flags: u16,
+    pub qd_count: u16,
+    pub an_count: u16,
+    pub ns_count: u16,
+    pub ar_count: u16,
+}
+
+impl DnsHeader {
+    pub fn is_response(&self) -> bool {
+        self.flags & 0x8000 > 0
+    }
+
+    pub fn opcode(&self) -> u8 {
+        ((self.flags >> 11) & 0x0f) as u8
+    }
+
+    pub fn rcode(&self) -> u8 {
+        (self.flags & 0x0f) as u8
+    }
+
+    fn read(buf: &[u8]) -> anyhow::Result<Self> {
+        if buf.len() < HEADER_LEN {
+            return Err(anyhow::anyhow!("dns message header is too short"));
+        }
+        Ok(Self {
+            id: read_u16(buf, 0),
+            flags: read_u16(buf, 2),
+            qd_count: read_u16(buf, 4),
+            an_count: read_u16(buf, 6),
+            ns_count: read_u16(buf, 8),
+            ar_count: read_u16(buf, 10),
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.id.to_be_bytes());
+        out.extend_from_slice(&self.flags.to_be_bytes());
+        out.extend_from_slice(&self.qd_count.to_be_bytes());
+        out.extend_from_slice(&self.an_count.to_be_bytes());
+        out.extend_from_slice(&self.ns_count.to_be_bytes());
+        out.extend_from_slice(&self.ar_count.to_be_bytes());
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DnsQuestion {
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsRecordData {
+    A(Ipv4Addr),
+    AAAA(Ipv6Addr),
+    SRV {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+}
+
+impl DnsRecordData {
+    pub fn record_type(&self) -> u16 {
+        match self {
+            DnsRecordData::A(_) => TYPE_A,
+            DnsRecordData::AAAA(_) => TYPE_AAAA,
+            DnsRecordData::SRV { .. } => TYPE_SRV,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsRecord {
+    pub name: String,
+    pub ttl: u32,
+    pub data: DnsRecordData,
+}
+
+impl DnsRecord {
+    fn write(&self, out: &mut Vec<u8>) -> anyhow::Result<()> {
+        write_name(&self.name, out)?;
+        out.extend_from_slice(&self.data.record_type().to_be_bytes());
+        out.extend_from_slice(&CLASS_IN.to_be_bytes());
+        out.extend_from_slice(&self.ttl.to_be_bytes());
+        let mut rdata = Vec::new();
+        match &self.data {
+            DnsRecordData::A(ip) => rdata.extend_from_slice(&ip.octets()),
+            DnsRecordData::AAAA(ip) => rdata.extend_from_slice(&ip.octets()),
+            DnsRecordData::SRV {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                rdata.extend_from_slice(&priority.to_be_bytes());
+                rdata.extend_from_slice(&weight.to_be_bytes());
+                rdata.extend_from_slice(&port.to_be_bytes());
+                write_name(target, &mut rdata)?;
+            }
+        }
+        out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        out.extend_from_slice(&rdata);
+        Ok(())
+    }
+
+    fn read(buf: &[u8], offset: &mut usize) -> anyhow::Result<Option<Self>> {
+        let name = read_name(buf, offset)?;
+        if *offset + 10 > buf.len() {
+            return Err(anyhow::anyhow!("dns record is too short"));
+        }
+        let rtype = read_u16(buf, *offset);
+        let ttl = u32::from_be_bytes([
+            buf[*offset + 4],
+            buf[*offset + 5],
+            buf[*offset + 6],
+            buf[*offset + 7],
+        ]);
+        let rd_len = read_u16(buf, *offset + 8) as usize;
+        *offset += 10;
+        let rd_start = *offset;
+        if rd_start + rd_len > buf.len() {
+            return Err(anyhow::anyhow!("dns record data is too short"));
+        }
+        *offset += rd_len;
+        let rdata = &buf[rd_start..rd_start + rd_len];
+        let data = match rtype {
+            TYPE_A if rd_len == 4 => {
+                DnsRecordData::A(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]))
+            }
+            TYPE_AAAA if rd_len == 16 => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(rdata);
+                DnsRecordData::AAAA(Ipv6Addr::from(octets))
+            }
+            TYPE_SRV if rd_len > 6 => {
+                let mut target_offset = rd_start + 6;
+                DnsRecordData::SRV {
+                    priority: read_u16(buf, rd_start),
+                    weight: read_u16(buf, rd_start + 2),
+                    port: read_u16(buf, rd_start + 4),
+                    target: read_name(buf, &mut target_offset)?,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Self { name, ttl, data }))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DnsMessage {
+    pub header: DnsHeader,
+    pub questions: Vec<DnsQuestion>,
+    pub answers: Vec<DnsRecord>,
+    pub additionals: Vec<DnsRecord>,
+}
+
+impl DnsMessage {
+    pub fn new_query(id: u16, name: &str, qtype: u16) -> Self {
+        Self {
+            header: DnsHeader {
+                id,
+                //RD
+                flags: 0x0100,
+                ..Default::default()
+            },
+            questions: vec![DnsQuestion {
+                name: name.to_owned(),
+                qtype,
+                qclass: CLASS_IN,
+            }],
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// 根据请求构建响应,保留请求id、问题与RD标记
+    pub fn new_response(request: &DnsMessage, rcode: u8) -> Self {
+        // QR=1, AA=1, 保留opcode与RD
+        let flags = 0x8000 | 0x0400 | (request.header.flags & 0x7900) | (rcode as u16 & 0x0f);
+        Self {
+            header: DnsHeader {
+                id: request.header.id,
+                flags,
+                ..Default::default()
+            },
+            questions: request.questions.clone(),
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// 只解析头、问题与可识别的应答记录
+    pub fn from_bytes(buf: &[u8]) -> anyhow::Result<Self> {
+        let header = DnsHeader::read(buf)?;
+        let mut offset = HEADER_LEN;
+        let mut questions = Vec::with_capacity(header.qd_count as usize);
+        for _ in 0..header.qd_count {
+            let name = read_name(buf, &mut offset)?;
+            if offset + 4 > buf.len() {
+                return Err(anyhow::anyhow!("dns question is too short"));
+            }
+            questions.push(DnsQuestion {
+                name,
+                qtype: read_u16(buf, offset),
+                qclass: read_u16(buf, offset + 2),
+            });
+            offset += 4;
+        }
+        let mut answers = Vec::new();
+        for _ in 0..header.an_count {
+            if let Some(record) = DnsRecord::read(buf, &mut offset)? {
+                answers.push(record);
+            }
+        }
+        let mut additionals = Vec::new();
+        for _ in 0..(header.ns_count as usize + header.ar_count as usize) {
+            if offset >= buf.len() {
+                break;
+            }
+            match DnsRecord::read(buf, &mut offset) {
+                Ok(Some(record)) => additionals.push(record),
+                Ok(None) => {}
+                //忽略无法解析的附加记录(如EDNS OPT)
+                Err(_) => break,
+            }
+        }
+        Ok(Self {
+            header,
+            questions,
+            answers,
+            additionals,
+        })
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(MAX_UDP_PAYLOAD);
+        let mut header = self.header.clone();
+        header.qd_count = self.questions.len() as u16;
+        header.an_count = self.answers.len() as u16;
+        header.ns_count = 0;
+        header.ar_count = self.additionals.len() as u16;
+        header.write(&mut out);
+        for question in &self.questions {
+            write_name(&question.name, &mut out)?;
+            out.extend_from_slice(&question.qtype.to_be_bytes());
+            out.extend_from_slice(&question.qclass.to_be_bytes());
+        }
+        for record in self.answers.iter().chain(self.additionals.iter()) {
+            record.write(&mut out)?;
+        }
+        Ok(out)
+    }
+
+    ///
+    /// udp响应超过512字节时丢弃附加记录,仍超长则设置TC标记让客户端改用tcp
+    pub fn to_udp_bytes(&mut self) -> anyhow::Result<Vec<u8>> {
+        self.encode_with_limit(MAX_UDP_PAYLOAD)
+    }
+
+    ///
+    /// tcp响应长度不能超过2字节长度前缀的上限,超长时同样丢弃记录并设置TC标记
+    pub fn to_tcp_bytes(&mut self) -> anyhow::Result<Vec<u8>> {
+        self.encode_with_limit(MAX_TCP_PAYLOAD)
+    }
+
+    fn encode_with_limit(&mut self, limit: usize) -> anyhow::Result<Vec<u8>> {
+        let bytes = self.to_bytes()?;
+        if bytes.len() <= limit {
+            return Ok(bytes);
+        }
+        self.additionals.clear();
+        let bytes = self.to_bytes()?;
+        if bytes.len() <= limit {
+            return Ok(bytes);
+        }
+        self.answers.clear();
+        self.header.flags |= 0x0200;
+        self.to_bytes()
+    }
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn read_name(buf: &[u8], offset: &mut usize) -> anyhow::Result<String> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut pos = *offset;
+    let mut jumped = false;
+    let mut jumps = 0;
+    loop {
+        if pos >= buf.len() {
+            return Err(anyhow::anyhow!("dns name out of range"));
+        }
+        let len = buf[pos] as usize;
+        if len & 0xc0 == 0xc0 {
+            if pos + 1 >= buf.len() {
+                return Err(anyhow::anyhow!("dns name pointer out of range"));
+            }
+            jumps += 1;
+            if jumps > MAX_POINTER_JUMPS {
+                return Err(anyhow::anyhow!("dns name pointer loop"));
+            }
+            if !jumped {
+                *offset = pos + 2;
+                jumped = true;
+            }
+            pos = ((len & 0x3f) << 8) | buf[pos + 1] as usize;
+            continue;
+        }
+        if len == 0 {
+            if !jumped {
+                *offset = pos + 1;
+            }
+            break;
+        }
+        if pos + 1 + len > buf.len() {
+            return Err(anyhow::anyhow!("dns label out of range"));
+        }
+        labels.push(String::from_utf8_lossy(&buf[pos + 1..pos + 1 + len]).to_string());
+        pos += 1 + len;
+    }
+    Ok(labels.join("."))
+}
+
+fn write_name(name: &str, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() {
+            continue;
+        }
+        if label.len() > MAX_LABEL_LEN {
+            return Err(anyhow::anyhow!("dns label is too long: {}", label));
+        }
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+    Ok(())
+}
+
+///
+/// 域名与服务的映射: `service.group.namespace.<domain>`
+/// 服务名中可以包含`.`,从右向左依次解析namespace与group;
+/// 域名后缀不区分大小写,服务名、分组与命名空间保持大小写;
+/// 以`_`开头的前缀标签(如`_http._tcp`)会被忽略
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsQueryTarget {
+    Service(ServiceKey),
+    Address(DnsAddress),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsAddress {
+    V4(Ipv4Addr),
+    V6(Ipv6Addr),
+}
+
+impl DnsQueryTarget {
+    pub fn parse(name: &str, domain: &str) -> Option<Self> {
+        let name = name.trim_end_matches('.');
+        let domain = domain.trim_matches('.');
+        if name.len() <= domain.len() + 1 {
+            return None;
+        }
+        let split_index = name.len() - domain.len();
+        if !name.is_char_boundary(split_index) {
+            return None;
+        }
+        let (prefix, suffix) = name.split_at(split_index);
+        if !suffix.eq_ignore_ascii_case(domain) {
+            return None;
+        }
+        let mut prefix = prefix.strip_suffix('.')?;
+        //兼容RFC 2782格式的SRV查询: _http._tcp.service.group.namespace.<domain>
+        while prefix.starts_with('_') {
+            prefix = prefix.split_once('.')?.1;
+        }
+        if let Some((addr, label)) = prefix.rsplit_once('.') {
+            if label.eq_ignore_ascii_case(ADDR_LABEL) {
+                return DnsAddress::decode(addr).map(DnsQueryTarget::Address);
+            }
+        }
+        let mut labels = prefix.rsplitn(3, '.');
+        let namespace = labels.next()?;
+        let group = labels.next()?;
+        let service = labels.next()?;
+        if namespace.is_empty() || group.is_empty() || service.is_empty() {
+            return None;
+        }
+        let namespace = NamingUtils::default_namespace(namespace.to_owned());
+        Some(DnsQueryTarget::Service(ServiceKey::new(
+            &namespace, group, service,
+        )))
+    }
+}
+
+impl DnsAddress {
+    pub fn from_ip(ip: &str) -> Option<Self> {
+        if let Ok(v) = ip.parse::<Ipv4Addr>() {
+            Some(DnsAddress::V4(v))
+        } else if let Ok(v) = ip.parse::<Ipv6Addr>() {
+            Some(DnsAddress::V6(v))
+        } else {
+            None
+        }
+    }
+
+    /// ipv4编码为`a-b-c-d`, ipv6编码为32位十六进制
+    pub fn encode(&self) -> String {
+        match self {
+            DnsAddress::V4(ip) => {
+                let o = ip.octets();
+                format!("{}-{}-{}-{}", o[0], o[1], o[2], o[3])
+            }
+            DnsAddress::V6(ip) => ip.octets().iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+
+    pub fn decode(label: &str) -> Option<Self> {
+        if label.len() == 32 && label.chars().all(|c| c.is_ascii_hexdigit()) {
+            let mut octets = [0u8; 16];
+            for (i, item) in octets.iter_mut().enumerate() {
+                *item = u8::from_str_radix(&label[i * 2..i * 2 + 2], 16).ok()?;
+            }
+            return Some(DnsAddress::V6(Ipv6Addr::from(octets)));
+        }
+        let ip = label.replace('-', ".").parse::<Ipv4Addr>().ok()?;
+        Some(DnsAddress::V4(ip))
+    }
+
+    pub fn to_target_name(&self, domain: &str) -> String {
+        format!(
+            "{}.{}.{}",
+            self.encode(),
+            ADDR_LABEL,
+            domain.trim_matches('.')
+        )
+    }
+
+    pub fn to_record(&self, name: String, ttl: u32) -> DnsRecord {
+        let data = match self {
+            DnsAddress::V4(ip) => DnsRecordData::A(*ip),
+            DnsAddress::V6(ip) => DnsRecordData::AAAA(*ip),
+        };
+        DnsRecord { name, ttl, data }
+    }
+
+    pub fn match_type(&self, qtype: u16) -> bool {
+        match self {
+            DnsAddress::V4(_) => qtype == TYPE_A || qtype == TYPE_ANY,
+            DnsAddress::V6(_) => qtype == TYPE_AAAA || qtype == TYPE_ANY,
+        }
+    }
+}
+
+///
+/// nacos实例权重转换为SRV权重,放大100倍保留两位小数精度
+pub fn srv_weight(weight: f32) -> u16 {
+    if !weight.is_finite() || weight <= 0f32 {
+        return 0;
+    }
+    (weight * 100f32).round().min(u16::MAX as f32) as u16
+}
+
+pub fn dns_domain(domain: &str) -> Arc<String> {
+    Arc::new(domain.trim_matches('.').to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_codec() {
+        let query = DnsMessage::new_query(7, "foo.DEFAULT_GROUP.public.rnacos.local", TYPE_SRV);
+        let bytes = query.to_bytes().unwrap();
+        let msg = DnsMessage::from_bytes(&bytes).unwrap();
+        assert_eq!(msg.header.id, 7);
+        assert!(!msg.header.is_response());
+        assert_eq!(msg.questions.len(), 1);
+        assert_eq!(
+            msg.questions[0].name,
+            "foo.DEFAULT_GROUP.public.rnacos.local"
+        );
+        assert_eq!(msg.questions[0].qtype, TYPE_SRV);
+    }
+
+    #[test]
+    fn test_response_codec() {
+        let query = DnsMessage::new_query(9, "foo.g.public.rnacos.local", TYPE_SRV);
+        let mut resp = DnsMessage::new_response(&query, RCODE_NO_ERROR);
+        let addr = DnsAddress::from_ip("10.0.0.1").unwrap();
+        let target = addr.to_target_name("rnacos.local");
+        resp.answers.push(DnsRecord {
+            name: "foo.g.public.rnacos.local".to_owned(),
+            ttl: 5,
+            data: DnsRecordData::SRV {
+                priority: 0,
+                weight: srv_weight(1.5),
+                port: 8080,
+                target: target.clone(),
+            },
+        });
+        resp.additionals.push(addr.to_record(target.clone(), 5));
+        let msg = DnsMessage::from_bytes(&resp.to_bytes().unwrap()).unwrap();
+        assert!(msg.header.is_response());
+        assert_eq!(msg.header.rcode(), RCODE_NO_ERROR);
+        assert_eq!(
+            msg.answers[0].data,
+            DnsRecordData::SRV {
+                priority: 0,
+                weight: 150,
+                port: 8080,
+                target: "10-0-0-1.addr.rnacos.local".to_owned(),
+            }
+        );
+        assert_eq!(
+            msg.additionals[0].data,
+            DnsRecordData::A(Ipv4Addr::new(10, 0, 0, 1))
+        );
+    }
+
+    #[test]
+    fn test_tcp_response_truncated() {
+        let query = DnsMessage::new_query(11, "foo.g.public.rnacos.local", TYPE_A);
+        let mut resp = DnsMessage::new_response(&query, RCODE_NO_ERROR);
+        for i in 0..3000u32 {
+            resp.answers.push(DnsRecord {
+                name: "foo.g.public.rnacos.local".to_owned(),
+                ttl: 5,
+                data: DnsRecordData::A(Ipv4Addr::from(0x0a00_0000 + i)),
+            });
+        }
+        assert!(resp.to_bytes().unwrap().len() > MAX_TCP_PAYLOAD);
+        let bytes = resp.to_tcp_bytes().unwrap();
+        assert!(bytes.len() <= MAX_TCP_PAYLOAD);
+        let msg = DnsMessage::from_bytes(&bytes).unwrap();
+        assert!(msg.answers.is_empty());
+        assert_eq!(msg.header.flags & 0x0200, 0x0200);
+    }
+
+    #[test]
+    fn test_parse_query_target() {
+        let target = DnsQueryTarget::parse(
+            "com.foo.api.DEFAULT_GROUP.dev.RNACOS.local.",
+            "rnacos.local",
+        );
+        assert_eq!(
+            target,
+            Some(DnsQueryTarget::Service(ServiceKey::new(
+                "dev",
+                "DEFAULT_GROUP",
+                "com.foo.api"
+            )))
+        );
+        let target = DnsQueryTarget::parse("foo.g.public.rnacos.local", "rnacos.local");
+        assert_eq!(
+            target,
+            Some(DnsQueryTarget::Service(ServiceKey::new(
+                "public", "g", "foo"
+            )))
+        );
+        let target = DnsQueryTarget::parse("_http._tcp.foo.g.public.rnacos.local", "rnacos.local");
+        assert_eq!(
+            target,
+            Some(DnsQueryTarget::Service(ServiceKey::new(
+                "public", "g", "foo"
+            )))
+        );
+        assert_eq!(
+            DnsQueryTarget::parse("foo.g.rnacos.local", "rnacos.local"),
+            None
+        );
+        assert_eq!(
+            DnsQueryTarget::parse("foo.g.public.example.com", "rnacos.local"),
+            None
+        );
+        let v6 = DnsAddress::from_ip("fe80::1").unwrap();
+        let name = v6.to_target_name("rnacos.local");
+        assert_eq!(
+            DnsQueryTarget::parse(&name, "rnacos.local"),
+            Some(DnsQueryTarget::Address(v6))
+        );
+    }
+}
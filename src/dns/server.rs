@@ -0,0 +1,338 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::Addr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+use crate::common::AppSysConfig;
+use crate::dns::model::{
+    dns_domain, srv_weight, DnsAddress, DnsMessage, DnsQueryTarget, DnsRecord, DnsRecordData,
+    RCODE_FORMAT_ERROR, RCODE_NAME_ERROR, RCODE_NOT_IMPLEMENTED, RCODE_NO_ERROR,
+    RCODE_SERVER_FAILURE, TYPE_A, TYPE_AAAA, TYPE_ANY, TYPE_SRV,
+};
+use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
+use crate::naming::model::{Instance, ServiceKey};
+
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+///
+/// 根据注册中心健康实例应答dns查询
+pub struct DnsResolver {
+    naming_addr: Addr<NamingActor>,
+    domain: Arc<String>,
+    ttl: u32,
+    srv_ttl: u32,
+}
+
+impl DnsResolver {
+    pub fn new(naming_addr: Addr<NamingActor>, sys_config: &AppSysConfig) -> Self {
+        Self {
+            naming_addr,
+            domain: dns_domain(&sys_config.dns_domain),
+            ttl: sys_config.dns_ttl,
+            srv_ttl: sys_config.dns_srv_ttl,
+        }
+    }
+
+    ///
+    /// 处理一个dns请求,返回None表示不需要应答
+    pub async fn handle(&self, buf: &[u8]) -> Option<DnsMessage> {
+        let request = match DnsMessage::from_bytes(buf) {
+            Ok(v) => v,
+            Err(err) => {
+                log::warn!("dns request parse error,{}", err);
+                return None;
+            }
+        };
+        if request.header.is_response() {
+            return None;
+        }
+        if request.header.opcode() != 0 {
+            return Some(DnsMessage::new_response(&request, RCODE_NOT_IMPLEMENTED));
+        }
+        if request.questions.len() != 1 {
+            return Some(DnsMessage::new_response(&request, RCODE_FORMAT_ERROR));
+        }
+        match self.resolve(&request).await {
+            Ok(v) => Some(v),
+            Err(err) => {
+                log::warn!("dns resolve error,{}", err);
+                Some(DnsMessage::new_response(&request, RCODE_SERVER_FAILURE))
+            }
+        }
+    }
+
+    async fn resolve(&self, request: &DnsMessage) -> anyhow::Result<DnsMessage> {
+        let question = &request.questions[0];
+        let target = match DnsQueryTarget::parse(&question.name, &self.domain) {
+            Some(v) => v,
+            None => return Ok(DnsMessage::new_response(request, RCODE_NAME_ERROR)),
+        };
+        let mut response = DnsMessage::new_response(request, RCODE_NO_ERROR);
+        match target {
+            DnsQueryTarget::Address(addr) => {
+                if addr.match_type(question.qtype) {
+                    response
+                        .answers
+                        .push(addr.to_record(question.name.clone(), self.ttl));
+                }
+            }
+            DnsQueryTarget::Service(key) => {
+                let instances = self.query_healthy_instances(key).await?;
+                if instances.is_empty() {
+                    return Ok(DnsMessage::new_response(request, RCODE_NAME_ERROR));
+                }
+                if question.qtype == TYPE_A
+                    || question.qtype == TYPE_AAAA
+                    || question.qtype == TYPE_ANY
+                {
+                    for instance in &instances {
+                        if let Some(addr) = DnsAddress::from_ip(&instance.ip) {
+                            if addr.match_type(question.qtype) {
+                                response
+                                    .answers
+                                    .push(addr.to_record(question.name.clone(), self.ttl));
+                            }
+                        }
+                    }
+                }
+                if question.qtype == TYPE_SRV || question.qtype == TYPE_ANY {
+                    self.fill_srv_records(&question.name, &instances, &mut response);
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    fn fill_srv_records(&self, name: &str, instances: &[Arc<Instance>], response: &mut DnsMessage) {
+        for instance in instances {
+            if instance.port == 0 || instance.port > u16::MAX as u32 {
+                continue;
+            }
+            //ip为主机名时直接作为target
+            let target = match DnsAddress::from_ip(&instance.ip) {
+                Some(addr) => {
+                    let target = addr.to_target_name(&self.domain);
+                    response
+                        .additionals
+                        .push(addr.to_record(target.clone(), self.ttl));
+                    target
+                }
+                None => instance.ip.as_ref().to_owned(),
+            };
+            response.answers.push(DnsRecord {
+                name: name.to_owned(),
+                ttl: self.srv_ttl,
+                data: DnsRecordData::SRV {
+                    priority: 0,
+                    weight: srv_weight(instance.weight),
+                    port: instance.port as u16,
+                    target,
+                },
+            });
+        }
+    }
+
+    async fn query_healthy_instances(&self, key: ServiceKey) -> anyhow::Result<Vec<Arc<Instance>>> {
+        let cmd = NamingCmd::QueryList(key, "".to_owned(), true, None);
+        match self.naming_addr.send(cmd).await?? {
+            NamingResult::InstanceList(list) => Ok(list
+                .into_iter()
+                .filter(|e| e.healthy && e.enabled && e.weight > 0f32)
+                .collect()),
+            _ => Ok(vec![]),
+        }
+    }
+}
+
+pub async fn start_dns_server(
+    naming_addr: Addr<NamingActor>,
+    sys_config: Arc<AppSysConfig>,
+) -> anyhow::Result<()> {
+    let addr = sys_config.get_dns_addr();
+    let resolver = Arc::new(DnsResolver::new(naming_addr, &sys_config));
+    let udp_socket = Arc::new(UdpSocket::bind(&addr).await?);
+    let tcp_listener = TcpListener::bind(&addr).await?;
+    log::info!(
+        "dns server addr:{},domain:{}",
+        &addr,
+        &sys_config.dns_domain
+    );
+    tokio::spawn(run_udp_server(udp_socket, resolver.clone()));
+    tokio::spawn(run_tcp_server(tcp_listener, resolver));
+    Ok(())
+}
+
+pub async fn run_udp_server(socket: Arc<UdpSocket>, resolver: Arc<DnsResolver>) {
+    let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+    loop {
+        let (len, addr) = match socket.recv_from(&mut buf).await {
+            Ok(v) => v,
+            Err(err) => {
+                log::warn!("dns udp recv error,{}", err);
+                continue;
+            }
+        };
+        let data = buf[..len].to_vec();
+        let socket = socket.clone();
+        let resolver = resolver.clone();
+        tokio::spawn(async move {
+            if let Some(mut response) = resolver.handle(&data).await {
+                match response.to_udp_bytes() {
+                    Ok(bytes) => {
+                        socket.send_to(&bytes, addr).await.ok();
+                    }
+                    Err(err) => log::warn!("dns response encode error,{}", err),
+                }
+            }
+        });
+    }
+}
+
+pub async fn run_tcp_server(listener: TcpListener, resolver: Arc<DnsResolver>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                let resolver = resolver.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = handle_tcp_stream(stream, resolver).await {
+                        log::debug!("dns tcp connection {} closed,{}", addr, err);
+                    }
+                });
+            }
+            Err(err) => log::warn!("dns tcp accept error,{}", err),
+        }
+    }
+}
+
+///
+/// tcp报文前两个字节为报文长度
+async fn handle_tcp_stream(
+    mut stream: TcpStream,
+    resolver: Arc<DnsResolver>,
+) -> anyhow::Result<()> {
+    loop {
+        let len = match tokio::time::timeout(TCP_IDLE_TIMEOUT, stream.read_u16()).await {
+            Ok(v) => v? as usize,
+            Err(_) => return Ok(()),
+        };
+        let mut data = vec![0u8; len];
+        stream.read_exact(&mut data).await?;
+        if let Some(mut response) = resolver.handle(&data).await {
+            let bytes = response.to_tcp_bytes()?;
+            stream.write_u16(bytes.len() as u16).await?;
+            stream.write_all(&bytes).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix::Actor;
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    fn build_instance(ip: &str, port: u32, weight: f32, healthy: bool) -> Instance {
+        let mut instance = Instance::new(ip.to_owned(), port);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.cluster_name = "DEFAULT".to_owned();
+        instance.weight = weight;
+        instance.healthy = healthy;
+        instance.init();
+        instance
+    }
+
+    async fn query(socket: &UdpSocket, server: SocketAddr, name: &str, qtype: u16) -> DnsMessage {
+        let request = DnsMessage::new_query(1, name, qtype);
+        socket
+            .send_to(&request.to_bytes().unwrap(), server)
+            .await
+            .unwrap();
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+        let (len, _) = socket.recv_from(&mut buf).await.unwrap();
+        DnsMessage::from_bytes(&buf[..len]).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_dns_query_by_local_resolver() {
+        let mut naming = NamingActor::new();
+        for instance in [
+            build_instance("127.0.0.1", 8080, 1f32, true),
+            build_instance("127.0.0.2", 8081, 2.5f32, true),
+            build_instance("127.0.0.3", 8082, 1f32, false),
+        ] {
+            let key = instance.get_service_key();
+            naming.update_instance(&key, instance, None, false);
+        }
+        let naming_addr = naming.start();
+        let sys_config = AppSysConfig {
+            dns_domain: "rnacos.local".to_owned(),
+            dns_ttl: 3,
+            dns_srv_ttl: 6,
+            ..Default::default()
+        };
+        let resolver = Arc::new(DnsResolver::new(naming_addr, &sys_config));
+        let server_socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let server_addr = server_socket.local_addr().unwrap();
+        tokio::spawn(run_udp_server(server_socket, resolver));
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        let name = "foo.DEFAULT_GROUP.public.rnacos.local";
+        let resp = query(&client, server_addr, name, TYPE_A).await;
+        assert_eq!(resp.header.rcode(), RCODE_NO_ERROR);
+        let mut ips: Vec<_> = resp
+            .answers
+            .iter()
+            .map(|e| {
+                assert_eq!(e.ttl, 3);
+                e.data.clone()
+            })
+            .collect();
+        ips.sort_by_key(|e| format!("{:?}", e));
+        assert_eq!(
+            ips,
+            vec![
+                DnsRecordData::A(Ipv4Addr::new(127, 0, 0, 1)),
+                DnsRecordData::A(Ipv4Addr::new(127, 0, 0, 2)),
+            ]
+        );
+
+        let resp = query(&client, server_addr, name, TYPE_SRV).await;
+        assert_eq!(resp.answers.len(), 2);
+        assert_eq!(resp.additionals.len(), 2);
+        let srv = resp
+            .answers
+            .iter()
+            .find(|e| matches!(&e.data, DnsRecordData::SRV { port, .. } if *port == 8081))
+            .unwrap();
+        assert_eq!(srv.ttl, 6);
+        assert_eq!(
+            srv.data,
+            DnsRecordData::SRV {
+                priority: 0,
+                weight: 250,
+                port: 8081,
+                target: "127-0-0-2.addr.rnacos.local".to_owned(),
+            }
+        );
+
+        let resp = query(&client, server_addr, "127-0-0-2.addr.rnacos.local", TYPE_A).await;
+        assert_eq!(
+            resp.answers[0].data,
+            DnsRecordData::A(Ipv4Addr::new(127, 0, 0, 2))
+        );
+
+        let resp = query(
+            &client,
+            server_addr,
+            "bar.DEFAULT_GROUP.public.rnacos.local",
+            TYPE_A,
+        )
+        .await;
+        assert_eq!(resp.header.rcode(), RCODE_NAME_ERROR);
+    }
+}
@@ -0,0 +1,280 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::eureka::model::{
+    get_eureka_instance_id, EurekaApplication, EurekaApplicationWrap, EurekaApplications,
+    EurekaApplicationsWrap, EurekaInstance, EurekaInstanceWrap, DELTA_RETENTION_MILLIS,
+    STATUS_OUT_OF_SERVICE,
+};
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::naming::instance_event::InstanceEvent;
+use crate::naming::model::{Instance, InstanceUpdateTag};
+use crate::naming::service_index::ServiceQueryParam;
+use crate::now_millis_i64;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct StatusParams {
+    pub value: Option<String>,
+}
+
+fn get_namespace_and_group(appdata: &AppShareData) -> (Arc<String>, Arc<String>) {
+    (
+        Arc::new(appdata.sys_config.eureka_namespace.clone()),
+        Arc::new(appdata.sys_config.eureka_group.clone()),
+    )
+}
+
+///
+/// 查询eureka命名空间与分组下的实例,app_id为空时查询全部服务
+async fn query_instances(
+    appdata: &web::Data<Arc<AppShareData>>,
+    app_id: Option<&str>,
+) -> anyhow::Result<(i64, Vec<Arc<Instance>>)> {
+    let (namespace_id, group_name) = get_namespace_and_group(appdata);
+    let param = ServiceQueryParam {
+        namespace_id: Some(namespace_id),
+        group: Some(group_name),
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let cmd = NamingCmd::QueryInstanceListWithIndex(param);
+    match appdata.naming_addr.send(cmd).await?? {
+        NamingResult::InstanceListWithIndex(index, list) => {
+            let list = match app_id {
+                //eureka应用名不区分大小写
+                Some(app_id) => list
+                    .into_iter()
+                    .filter(|e| e.service_name.eq_ignore_ascii_case(app_id))
+                    .collect(),
+                None => list,
+            };
+            Ok((index.max(1), list))
+        }
+        _ => Err(anyhow::anyhow!("naming result error")),
+    }
+}
+
+///
+/// 查询eureka命名空间与分组下最近变更的实例事件
+async fn query_recent_events(
+    appdata: &web::Data<Arc<AppShareData>>,
+) -> anyhow::Result<Vec<(Arc<String>, InstanceEvent)>> {
+    let (namespace_id, group_name) = get_namespace_and_group(appdata);
+    let cmd = NamingCmd::QueryGroupInstanceEvents {
+        namespace_id,
+        group_name,
+        start_time: now_millis_i64() - DELTA_RETENTION_MILLIS,
+    };
+    match appdata.naming_addr.send(cmd).await?? {
+        NamingResult::GroupInstanceEvents(list) => Ok(list),
+        _ => Err(anyhow::anyhow!("naming result error")),
+    }
+}
+
+async fn find_instance(
+    appdata: &web::Data<Arc<AppShareData>>,
+    app_id: &str,
+    instance_id: &str,
+) -> anyhow::Result<Option<Arc<Instance>>> {
+    let (_, list) = query_instances(appdata, Some(app_id)).await?;
+    Ok(list
+        .into_iter()
+        .find(|e| get_eureka_instance_id(e) == instance_id))
+}
+
+pub async fn register_instance(
+    path: web::Path<String>,
+    body: web::Bytes,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let app_id = path.into_inner();
+    let wrap: EurekaInstanceWrap = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    let (namespace_id, group_name) = get_namespace_and_group(&appdata);
+    let instance = match wrap.instance.to_instance(&app_id, namespace_id, group_name) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    //保留控制台或状态接口设置的下线状态
+    let tag = InstanceUpdateTag {
+        weight: false,
+        enabled: false,
+        ephemeral: true,
+        metadata: true,
+        from_update: false,
+    };
+    match appdata
+        .naming_route
+        .update_instance(instance, Some(tag))
+        .await
+    {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+///
+/// 续约即心跳,实例不存在时返回404让客户端重新注册
+pub async fn renew_instance(
+    path: web::Path<(String, String)>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (app_id, instance_id) = path.into_inner();
+    let old_instance = match find_instance(&appdata, &app_id, &instance_id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut instance = old_instance.as_ref().clone();
+    instance.healthy = true;
+    instance.init();
+    let tag = if old_instance.healthy {
+        Some(InstanceUpdateTag {
+            weight: false,
+            enabled: false,
+            ephemeral: false,
+            metadata: false,
+            from_update: false,
+        })
+    } else {
+        None
+    };
+    match appdata.naming_route.update_instance(instance, tag).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+pub async fn cancel_instance(
+    path: web::Path<(String, String)>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (app_id, instance_id) = path.into_inner();
+    match find_instance(&appdata, &app_id, &instance_id).await {
+        Ok(Some(instance)) => {
+            match appdata
+                .naming_route
+                .delete_instance(instance.as_ref().clone())
+                .await
+            {
+                Ok(_) => HttpResponse::Ok().finish(),
+                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            }
+        }
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+async fn update_enabled(
+    appdata: web::Data<Arc<AppShareData>>,
+    app_id: String,
+    instance_id: String,
+    enabled: bool,
+) -> HttpResponse {
+    let mut instance = match find_instance(&appdata, &app_id, &instance_id).await {
+        Ok(Some(v)) => v.as_ref().clone(),
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    instance.enabled = enabled;
+    let tag = InstanceUpdateTag {
+        weight: false,
+        enabled: true,
+        ephemeral: false,
+        metadata: false,
+        from_update: true,
+    };
+    match appdata
+        .naming_route
+        .update_instance(instance, Some(tag))
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+///
+/// 状态覆盖,OUT_OF_SERVICE对应实例下线,其它状态对应上线
+pub async fn override_status(
+    path: web::Path<(String, String)>,
+    param: web::Query<StatusParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (app_id, instance_id) = path.into_inner();
+    let enabled = match &param.value {
+        Some(v) if !v.is_empty() => !v.eq_ignore_ascii_case(STATUS_OUT_OF_SERVICE),
+        _ => return HttpResponse::BadRequest().body("status value is empty"),
+    };
+    update_enabled(appdata, app_id, instance_id, enabled).await
+}
+
+pub async fn remove_status_override(
+    path: web::Path<(String, String)>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (app_id, instance_id) = path.into_inner();
+    update_enabled(appdata, app_id, instance_id, true).await
+}
+
+pub async fn query_apps(appdata: web::Data<Arc<AppShareData>>) -> impl Responder {
+    match query_instances(&appdata, None).await {
+        Ok((index, list)) => HttpResponse::Ok().json(EurekaApplicationsWrap {
+            applications: EurekaApplications::new(index, &list),
+        }),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+pub async fn query_apps_delta(appdata: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let events = match query_recent_events(&appdata).await {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    match query_instances(&appdata, None).await {
+        Ok((index, list)) => HttpResponse::Ok().json(EurekaApplicationsWrap {
+            applications: EurekaApplications::new_delta(index, &list, &events),
+        }),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+pub async fn query_app(
+    path: web::Path<String>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let app_id = path.into_inner();
+    match query_instances(&appdata, Some(&app_id)).await {
+        Ok((_, list)) if !list.is_empty() => HttpResponse::Ok().json(EurekaApplicationWrap {
+            application: EurekaApplication {
+                name: app_id.to_uppercase(),
+                instance: list
+                    .iter()
+                    .map(|e| EurekaInstance::from_instance(e))
+                    .collect(),
+            },
+        }),
+        Ok(_) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+pub async fn query_instance(
+    path: web::Path<(String, String)>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (app_id, instance_id) = path.into_inner();
+    match find_instance(&appdata, &app_id, &instance_id).await {
+        Ok(Some(instance)) => HttpResponse::Ok().json(EurekaInstanceWrap {
+            instance: EurekaInstance::from_instance(&instance),
+        }),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -0,0 +1,35 @@
+//eureka rest api compatibility layer
+use actix_web::web;
+
+pub mod api;
+pub mod model;
+
+fn eureka_scope(path: &str) -> actix_web::Scope {
+    web::scope(path)
+        .service(web::resource("/apps").route(web::get().to(api::query_apps)))
+        .service(web::resource("/apps/delta").route(web::get().to(api::query_apps_delta)))
+        .service(
+            web::resource("/apps/{app_id}")
+                .route(web::get().to(api::query_app))
+                .route(web::post().to(api::register_instance)),
+        )
+        .service(
+            web::resource("/apps/{app_id}/{instance_id}")
+                .route(web::get().to(api::query_instance))
+                .route(web::put().to(api::renew_instance))
+                .route(web::delete().to(api::cancel_instance)),
+        )
+        .service(
+            web::resource("/apps/{app_id}/{instance_id}/status")
+                .route(web::put().to(api::override_status))
+                .route(web::delete().to(api::remove_status_override)),
+        )
+}
+
+///
+/// eureka兼容接口,实例注册到配置的命名空间与分组;兼容/eureka与/eureka/v2两种前缀
+pub fn eureka_config(config: &mut web::ServiceConfig) {
+    config
+        .service(eureka_scope("/eureka/v2"))
+        .service(eureka_scope("/eureka"));
+}
@@ -0,0 +1,559 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::naming::instance_event::{InstanceEvent, InstanceEventType};
+use crate::naming::model::Instance;
+use crate::naming::{PRESERVED_HEART_BEAT_TIMEOUT, PRESERVED_IP_DELETE_TIMEOUT};
+
+pub const EUREKA_INSTANCE_ID_KEY: &str = "eureka.instanceId";
+pub const EUREKA_HOST_NAME_KEY: &str = "eureka.hostName";
+pub const EUREKA_STATUS_KEY: &str = "eureka.status";
+pub const EUREKA_VIP_ADDRESS_KEY: &str = "eureka.vipAddress";
+pub const EUREKA_SECURE_VIP_ADDRESS_KEY: &str = "eureka.secureVipAddress";
+pub const EUREKA_SECURE_PORT_KEY: &str = "eureka.securePort";
+pub const EUREKA_HOME_PAGE_URL_KEY: &str = "eureka.homePageUrl";
+pub const EUREKA_STATUS_PAGE_URL_KEY: &str = "eureka.statusPageUrl";
+pub const EUREKA_HEALTH_CHECK_URL_KEY: &str = "eureka.healthCheckUrl";
+pub const EUREKA_LEASE_DURATION_KEY: &str = "eureka.leaseDuration";
+pub const EUREKA_RENEWAL_INTERVAL_KEY: &str = "eureka.renewalInterval";
+
+pub const STATUS_UP: &str = "UP";
+pub const STATUS_DOWN: &str = "DOWN";
+pub const STATUS_OUT_OF_SERVICE: &str = "OUT_OF_SERVICE";
+pub const STATUS_UNKNOWN: &str = "UNKNOWN";
+
+pub const ACTION_ADDED: &str = "ADDED";
+pub const ACTION_MODIFIED: &str = "MODIFIED";
+pub const ACTION_DELETED: &str = "DELETED";
+
+/// 增量查询返回最近3分钟内变更的实例,与eureka服务端保持一致
+pub const DELTA_RETENTION_MILLIS: i64 = 180_000;
+
+const DEFAULT_LEASE_DURATION_SECS: i64 = 90;
+const DEFAULT_RENEWAL_INTERVAL_SECS: i64 = 30;
+const DEFAULT_DATA_CENTER_CLASS: &str = "com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo";
+
+///
+/// eureka的数值字段可能是字符串也可能是数字
+fn value_to_i64(v: &Value) -> Option<i64> {
+    match v {
+        Value::Number(v) => v.as_i64(),
+        Value::String(v) => v.parse().ok(),
+        _ => None,
+    }
+}
+
+fn value_to_bool(v: &Value) -> bool {
+    match v {
+        Value::Bool(v) => *v,
+        Value::String(v) => v == "true",
+        _ => false,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct EurekaPort {
+    #[serde(rename = "$", default)]
+    pub port: Value,
+    #[serde(rename = "@enabled", default)]
+    pub enabled: Value,
+}
+
+impl EurekaPort {
+    pub fn new(port: i64, enabled: bool) -> Self {
+        Self {
+            port: Value::from(port),
+            enabled: Value::from(enabled.to_string()),
+        }
+    }
+
+    pub fn get_port(&self) -> Option<i64> {
+        value_to_i64(&self.port)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        value_to_bool(&self.enabled)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DataCenterInfo {
+    #[serde(rename = "@class")]
+    pub class: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaseInfo {
+    #[serde(default)]
+    pub renewal_interval_in_secs: Value,
+    #[serde(default)]
+    pub duration_in_secs: Value,
+    #[serde(default)]
+    pub registration_timestamp: Value,
+    #[serde(default)]
+    pub last_renewal_timestamp: Value,
+    #[serde(default)]
+    pub eviction_timestamp: Value,
+    #[serde(default)]
+    pub service_up_timestamp: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EurekaInstance {
+    pub instance_id: Option<String>,
+    pub host_name: Option<String>,
+    pub app: Option<String>,
+    pub ip_addr: Option<String>,
+    pub status: Option<String>,
+    pub overridden_status: Option<String>,
+    pub port: Option<EurekaPort>,
+    pub secure_port: Option<EurekaPort>,
+    pub country_id: Option<Value>,
+    pub data_center_info: Option<DataCenterInfo>,
+    pub lease_info: Option<LeaseInfo>,
+    pub metadata: Option<HashMap<String, Value>>,
+    pub home_page_url: Option<String>,
+    pub status_page_url: Option<String>,
+    pub health_check_url: Option<String>,
+    pub vip_address: Option<String>,
+    pub secure_vip_address: Option<String>,
+    pub is_coordinating_discovery_server: Option<Value>,
+    pub last_updated_timestamp: Option<Value>,
+    pub last_dirty_timestamp: Option<Value>,
+    pub action_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct EurekaInstanceWrap {
+    pub instance: EurekaInstance,
+}
+
+fn insert_not_empty(metadata: &mut HashMap<String, String>, key: &str, value: Option<String>) {
+    if let Some(v) = value {
+        if !v.is_empty() {
+            metadata.insert(key.to_owned(), v);
+        }
+    }
+}
+
+impl EurekaInstance {
+    ///
+    /// eureka应用名不区分大小写,注册到注册中心时服务名统一转为小写
+    pub fn to_instance(
+        self,
+        app_id: &str,
+        namespace_id: Arc<String>,
+        group_name: Arc<String>,
+    ) -> anyhow::Result<Instance> {
+        let ip = match self.ip_addr {
+            Some(v) if !v.is_empty() => v,
+            _ => return Err(anyhow::anyhow!("ipAddr is empty")),
+        };
+        let port = match self.port.as_ref() {
+            Some(port) if port.is_enabled() || self.secure_port.is_none() => port.get_port(),
+            _ => self.secure_port.as_ref().and_then(|v| v.get_port()),
+        }
+        .filter(|v| *v > 0)
+        .ok_or_else(|| anyhow::anyhow!("port is empty"))?;
+        let mut metadata: HashMap<String, String> = self
+            .metadata
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(k, _)| !k.starts_with('@'))
+            .map(|(k, v)| match v {
+                Value::String(v) => (k, v),
+                v => (k, v.to_string()),
+            })
+            .collect();
+        let lease_duration = self
+            .lease_info
+            .as_ref()
+            .and_then(|v| value_to_i64(&v.duration_in_secs))
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_LEASE_DURATION_SECS);
+        let renewal_interval = self
+            .lease_info
+            .as_ref()
+            .and_then(|v| value_to_i64(&v.renewal_interval_in_secs))
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_RENEWAL_INTERVAL_SECS);
+        //租约到期后标记为不健康,再过一个租约周期后删除
+        metadata.insert(
+            PRESERVED_HEART_BEAT_TIMEOUT.to_owned(),
+            (lease_duration * 1000).to_string(),
+        );
+        metadata.insert(
+            PRESERVED_IP_DELETE_TIMEOUT.to_owned(),
+            (lease_duration * 2000).to_string(),
+        );
+        metadata.insert(
+            EUREKA_LEASE_DURATION_KEY.to_owned(),
+            lease_duration.to_string(),
+        );
+        metadata.insert(
+            EUREKA_RENEWAL_INTERVAL_KEY.to_owned(),
+            renewal_interval.to_string(),
+        );
+        if let Some(secure_port) = self.secure_port.as_ref().filter(|v| v.is_enabled()) {
+            insert_not_empty(
+                &mut metadata,
+                EUREKA_SECURE_PORT_KEY,
+                secure_port.get_port().map(|v| v.to_string()),
+            );
+        }
+        insert_not_empty(&mut metadata, EUREKA_INSTANCE_ID_KEY, self.instance_id);
+        insert_not_empty(&mut metadata, EUREKA_HOST_NAME_KEY, self.host_name);
+        insert_not_empty(&mut metadata, EUREKA_STATUS_KEY, self.status);
+        insert_not_empty(&mut metadata, EUREKA_VIP_ADDRESS_KEY, self.vip_address);
+        insert_not_empty(
+            &mut metadata,
+            EUREKA_SECURE_VIP_ADDRESS_KEY,
+            self.secure_vip_address,
+        );
+        insert_not_empty(&mut metadata, EUREKA_HOME_PAGE_URL_KEY, self.home_page_url);
+        insert_not_empty(
+            &mut metadata,
+            EUREKA_STATUS_PAGE_URL_KEY,
+            self.status_page_url,
+        );
+        insert_not_empty(
+            &mut metadata,
+            EUREKA_HEALTH_CHECK_URL_KEY,
+            self.health_check_url,
+        );
+        let mut instance = Instance::new(ip, port as u32);
+        instance.namespace_id = namespace_id;
+        instance.group_name = group_name;
+        instance.service_name = Arc::new(app_id.to_lowercase());
+        instance.metadata = Arc::new(metadata);
+        instance.init();
+        Ok(instance)
+    }
+
+    pub fn from_instance(instance: &Instance) -> Self {
+        let metadata_value = |key: &str| instance.metadata.get(key).cloned();
+        let metadata_i64 = |key: &str, default_value: i64| {
+            instance
+                .metadata
+                .get(key)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default_value)
+        };
+        let host_name =
+            metadata_value(EUREKA_HOST_NAME_KEY).unwrap_or_else(|| instance.ip.as_ref().to_owned());
+        let vip_address = metadata_value(EUREKA_VIP_ADDRESS_KEY)
+            .unwrap_or_else(|| instance.service_name.as_ref().to_owned());
+        let secure_port = metadata_value(EUREKA_SECURE_PORT_KEY).and_then(|v| v.parse().ok());
+        let user_metadata: HashMap<String, Value> = instance
+            .metadata
+            .iter()
+            .filter(|(k, _)| !k.starts_with("eureka.") && !k.starts_with("preserved."))
+            .map(|(k, v)| (k.to_owned(), Value::from(v.as_str())))
+            .collect();
+        let timestamp = Value::from(instance.last_modified_millis.to_string());
+        Self {
+            instance_id: Some(get_eureka_instance_id(instance)),
+            host_name: Some(host_name.clone()),
+            app: Some(instance.service_name.to_uppercase()),
+            ip_addr: Some(instance.ip.as_ref().to_owned()),
+            status: Some(get_eureka_status(instance).to_owned()),
+            overridden_status: Some(
+                if instance.enabled {
+                    STATUS_UNKNOWN
+                } else {
+                    STATUS_OUT_OF_SERVICE
+                }
+                .to_owned(),
+            ),
+            port: Some(EurekaPort::new(instance.port as i64, true)),
+            secure_port: Some(EurekaPort::new(
+                secure_port.unwrap_or(443),
+                secure_port.is_some(),
+            )),
+            country_id: Some(Value::from(1)),
+            data_center_info: Some(DataCenterInfo {
+                class: DEFAULT_DATA_CENTER_CLASS.to_owned(),
+                name: "MyOwn".to_owned(),
+            }),
+            lease_info: Some(LeaseInfo {
+                renewal_interval_in_secs: Value::from(metadata_i64(
+                    EUREKA_RENEWAL_INTERVAL_KEY,
+                    DEFAULT_RENEWAL_INTERVAL_SECS,
+                )),
+                duration_in_secs: Value::from(metadata_i64(
+                    EUREKA_LEASE_DURATION_KEY,
+                    DEFAULT_LEASE_DURATION_SECS,
+                )),
+                registration_timestamp: Value::from(instance.register_time),
+                last_renewal_timestamp: Value::from(instance.last_modified_millis),
+                eviction_timestamp: Value::from(0),
+                service_up_timestamp: Value::from(instance.register_time),
+            }),
+            metadata: Some(user_metadata),
+            home_page_url: Some(
+                metadata_value(EUREKA_HOME_PAGE_URL_KEY)
+                    .unwrap_or_else(|| format!("http://{}:{}/", &host_name, instance.port)),
+            ),
+            status_page_url: metadata_value(EUREKA_STATUS_PAGE_URL_KEY),
+            health_check_url: metadata_value(EUREKA_HEALTH_CHECK_URL_KEY),
+            secure_vip_address: Some(
+                metadata_value(EUREKA_SECURE_VIP_ADDRESS_KEY)
+                    .unwrap_or_else(|| vip_address.clone()),
+            ),
+            vip_address: Some(vip_address),
+            is_coordinating_discovery_server: Some(Value::from("false")),
+            last_updated_timestamp: Some(timestamp.clone()),
+            last_dirty_timestamp: Some(timestamp),
+            action_type: Some(ACTION_ADDED.to_owned()),
+        }
+    }
+}
+
+pub fn get_eureka_instance_id(instance: &Instance) -> String {
+    match instance.metadata.get(EUREKA_INSTANCE_ID_KEY) {
+        Some(v) => v.to_owned(),
+        None => get_default_instance_id(&instance.ip, &instance.service_name, instance.port),
+    }
+}
+
+fn get_default_instance_id(ip: &str, service_name: &str, port: u32) -> String {
+    format!("{}:{}:{}", ip, service_name, port)
+}
+
+///
+/// 下线优先,其次是健康状态,最后使用客户端上报的状态
+pub fn get_eureka_status(instance: &Instance) -> &str {
+    if !instance.enabled {
+        STATUS_OUT_OF_SERVICE
+    } else if !instance.healthy {
+        STATUS_DOWN
+    } else {
+        instance
+            .metadata
+            .get(EUREKA_STATUS_KEY)
+            .map(|v| v.as_str())
+            .unwrap_or(STATUS_UP)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct EurekaApplication {
+    pub name: String,
+    pub instance: Vec<EurekaInstance>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct EurekaApplications {
+    #[serde(rename = "versions__delta")]
+    pub versions_delta: String,
+    #[serde(rename = "apps__hashcode")]
+    pub apps_hashcode: String,
+    pub application: Vec<EurekaApplication>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct EurekaApplicationsWrap {
+    pub applications: EurekaApplications,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct EurekaApplicationWrap {
+    pub application: EurekaApplication,
+}
+
+impl EurekaApplications {
+    pub fn new(index: i64, instances: &[Arc<Instance>]) -> Self {
+        let mut app_map: BTreeMap<String, Vec<EurekaInstance>> = BTreeMap::new();
+        for instance in instances {
+            app_map
+                .entry(instance.service_name.to_uppercase())
+                .or_default()
+                .push(EurekaInstance::from_instance(instance));
+        }
+        let application = app_map
+            .into_iter()
+            .map(|(name, instance)| EurekaApplication { name, instance })
+            .collect();
+        Self {
+            versions_delta: index.to_string(),
+            apps_hashcode: Self::reconcile_hash_code(instances),
+            application,
+        }
+    }
+
+    ///
+    /// 根据最近的实例事件生成增量数据,hashcode仍按全量数据计算;
+    /// 已删除实例只能按默认规则生成实例id,客户端删除失败时对比hashcode不一致会自动改为全量拉取
+    pub fn new_delta(
+        index: i64,
+        instances: &[Arc<Instance>],
+        events: &[(Arc<String>, InstanceEvent)],
+    ) -> Self {
+        let instance_map: HashMap<(String, &str, u32), &Arc<Instance>> = instances
+            .iter()
+            .map(|e| ((e.service_name.to_uppercase(), e.ip.as_str(), e.port), e))
+            .collect();
+        let mut changed: BTreeMap<(String, Arc<String>, u32), &Arc<String>> = BTreeMap::new();
+        let mut added: HashSet<(String, Arc<String>, u32)> = HashSet::new();
+        for (service_name, event) in events {
+            let key = (service_name.to_uppercase(), event.ip.clone(), event.port);
+            if event.event_type == InstanceEventType::Register {
+                added.insert(key.clone());
+            }
+            changed.insert(key, service_name);
+        }
+        let mut app_map: BTreeMap<String, Vec<EurekaInstance>> = BTreeMap::new();
+        for (key, service_name) in changed {
+            let (app, ip, port) = &key;
+            let instance = match instance_map.get(&(app.clone(), ip.as_str(), *port)) {
+                Some(instance) => {
+                    let mut eureka_instance = EurekaInstance::from_instance(instance);
+                    let action_type = if added.contains(&key) {
+                        ACTION_ADDED
+                    } else {
+                        ACTION_MODIFIED
+                    };
+                    eureka_instance.action_type = Some(action_type.to_owned());
+                    eureka_instance
+                }
+                None => EurekaInstance {
+                    instance_id: Some(get_default_instance_id(ip, service_name, *port)),
+                    host_name: Some(ip.as_ref().to_owned()),
+                    app: Some(app.clone()),
+                    ip_addr: Some(ip.as_ref().to_owned()),
+                    status: Some(STATUS_DOWN.to_owned()),
+                    port: Some(EurekaPort::new(*port as i64, true)),
+                    action_type: Some(ACTION_DELETED.to_owned()),
+                    ..Default::default()
+                },
+            };
+            app_map.entry(app.clone()).or_default().push(instance);
+        }
+        let application = app_map
+            .into_iter()
+            .map(|(name, instance)| EurekaApplication { name, instance })
+            .collect();
+        Self {
+            versions_delta: index.to_string(),
+            apps_hashcode: Self::reconcile_hash_code(instances),
+            application,
+        }
+    }
+
+    ///
+    /// 与eureka客户端一致的hashcode算法,按状态排序拼接 `状态_数量_`
+    pub fn reconcile_hash_code(instances: &[Arc<Instance>]) -> String {
+        let mut status_count: BTreeMap<&str, usize> = BTreeMap::new();
+        for instance in instances {
+            *status_count.entry(get_eureka_status(instance)).or_default() += 1;
+        }
+        let mut hash_code = String::new();
+        for (status, count) in status_count {
+            hash_code.push_str(&format!("{}_{}_", status, count));
+        }
+        hash_code
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eureka_instance_convert() {
+        let body = r#"{"instance":{"instanceId":"host1:foo:8080","hostName":"host1","app":"FOO","ipAddr":"10.0.0.1","status":"UP","overriddenStatus":"UNKNOWN","port":{"$":8080,"@enabled":"true"},"securePort":{"$":443,"@enabled":"false"},"countryId":1,"dataCenterInfo":{"@class":"com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo","name":"MyOwn"},"leaseInfo":{"renewalIntervalInSecs":30,"durationInSecs":90},"metadata":{"zone":"a","@class":"java.util.Collections$EmptyMap"},"vipAddress":"foo","secureVipAddress":"foo","isCoordinatingDiscoveryServer":"false","lastUpdatedTimestamp":"1700000000000","lastDirtyTimestamp":"1700000000000"}}"#;
+        let wrap: EurekaInstanceWrap = serde_json::from_str(body).unwrap();
+        let instance = wrap
+            .instance
+            .to_instance(
+                "FOO",
+                Arc::new("public".to_owned()),
+                Arc::new("DEFAULT_GROUP".to_owned()),
+            )
+            .unwrap();
+        assert_eq!(instance.service_name.as_str(), "foo");
+        assert_eq!(instance.port, 8080);
+        assert_eq!(instance.get_heart_beat_timeout(), Some(90_000));
+        assert_eq!(instance.get_ip_delete_timeout(), Some(180_000));
+        assert!(instance.metadata.get("@class").is_none());
+
+        let output = EurekaInstance::from_instance(&instance);
+        assert_eq!(output.instance_id.unwrap(), "host1:foo:8080");
+        assert_eq!(output.app.unwrap(), "FOO");
+        assert_eq!(output.status.unwrap(), STATUS_UP);
+        assert_eq!(output.metadata.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reconcile_hash_code() {
+        let mut list = vec![];
+        for (port, healthy) in [(8080, true), (8081, true), (8082, false)] {
+            let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+            instance.healthy = healthy;
+            list.push(Arc::new(instance));
+        }
+        assert_eq!(
+            EurekaApplications::reconcile_hash_code(&list),
+            "DOWN_1_UP_2_"
+        );
+    }
+
+    #[test]
+    fn test_apps_delta() {
+        let service_name = Arc::new("foo".to_owned());
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.service_name = service_name.clone();
+        let mut modified = Instance::new("127.0.0.2".to_owned(), 8080);
+        modified.service_name = service_name.clone();
+        let mut deleted = Instance::new("127.0.0.3".to_owned(), 8080);
+        deleted.service_name = service_name.clone();
+        let events = vec![
+            (
+                service_name.clone(),
+                InstanceEvent::new(InstanceEventType::Register, &instance, 1, 1),
+            ),
+            (
+                service_name.clone(),
+                InstanceEvent::new(InstanceEventType::MetadataChange, &instance, 2, 1),
+            ),
+            (
+                service_name.clone(),
+                InstanceEvent::new(InstanceEventType::Unhealthy, &modified, 3, 1),
+            ),
+            (
+                service_name.clone(),
+                InstanceEvent::new(InstanceEventType::Deregister, &deleted, 4, 1),
+            ),
+        ];
+        let list = vec![Arc::new(instance), Arc::new(modified)];
+        let apps = EurekaApplications::new_delta(10, &list, &events);
+        assert_eq!(apps.versions_delta, "10");
+        assert_eq!(apps.apps_hashcode, "UP_2_");
+        assert_eq!(apps.application.len(), 1);
+        let actions: Vec<(String, String)> = apps.application[0]
+            .instance
+            .iter()
+            .map(|e| {
+                (
+                    e.instance_id.clone().unwrap(),
+                    e.action_type.clone().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("127.0.0.1:foo:8080".to_owned(), ACTION_ADDED.to_owned()),
+                ("127.0.0.2:foo:8080".to_owned(), ACTION_MODIFIED.to_owned()),
+                ("127.0.0.3:foo:8080".to_owned(), ACTION_DELETED.to_owned()),
+            ]
+        );
+    }
+}
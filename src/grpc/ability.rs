@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use super::api_model::{SetupAckRequest, INTERNAL_MODEL};
+use super::nacos_proto::Payload;
+use super::PayloadUtils;
+
+/// 与nacos AbilityKey保持一致
+pub const SUPPORT_PERSISTENT_INSTANCE_BY_GRPC: &str = "supportPersistentInstanceByGrpc";
+pub const FUZZY_WATCH: &str = "fuzzyWatch";
+pub const DISTRIBUTED_LOCK: &str = "lock";
+
+/// sdk客户端能力,与nacos AbilityKey中SDK_CLIENT_*保持一致
+pub const SDK_CLIENT_TEST_1: &str = "test_1";
+pub const SDK_CLIENT_FUZZY_WATCH: &str = "fuzzyWatch";
+pub const SDK_CLIENT_DISTRIBUTED_LOCK: &str = "lock";
+pub const SDK_CLIENT_ABILITY_KEYS: [&str; 3] = [
+    SDK_CLIENT_TEST_1,
+    SDK_CLIENT_FUZZY_WATCH,
+    SDK_CLIENT_DISTRIBUTED_LOCK,
+];
+
+pub const SETUP_ACK_REQUEST: &str = "SetupAckRequest";
+
+///
+/// 服务端能力表,未支持的能力显式声明为false
+pub fn server_ability_table() -> HashMap<String, bool> {
+    let mut table = HashMap::new();
+    //grpc注册的实例与连接绑定,不支持持久化实例
+    table.insert(SUPPORT_PERSISTENT_INSTANCE_BY_GRPC.to_owned(), false);
+    table.insert(FUZZY_WATCH.to_owned(), false);
+    table.insert(DISTRIBUTED_LOCK.to_owned(), false);
+    table
+}
+
+pub fn build_setup_ack_payload(request_id: String) -> Payload {
+    let request = SetupAckRequest {
+        module: Some(INTERNAL_MODEL.to_owned()),
+        request_id: Some(request_id),
+        ability_table: Some(server_ability_table()),
+        ..Default::default()
+    };
+    PayloadUtils::build_payload(SETUP_ACK_REQUEST, serde_json::to_string(&request).unwrap())
+}
+
+///
+/// 查询能力表中的能力,未声明时返回None
+pub fn get_ability(table: Option<&HashMap<String, bool>>, key: &str) -> Option<bool> {
+    table.and_then(|e| e.get(key).cloned())
+}
+
+///
+/// 是否为支持能力协商的sdk客户端;这类客户端会按服务端能力表选择持久化实例的注册方式
+pub fn is_sdk_ability_client(table: Option<&HashMap<String, bool>>) -> bool {
+    table
+        .map(|e| {
+            SDK_CLIENT_ABILITY_KEYS
+                .iter()
+                .any(|key| e.contains_key(*key))
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup_ack_payload() {
+        let payload = build_setup_ack_payload("1".to_owned());
+        assert_eq!(
+            PayloadUtils::get_payload_type(&payload).map(|e| e.as_str()),
+            Some(SETUP_ACK_REQUEST)
+        );
+        let request: SetupAckRequest =
+            serde_json::from_slice(&payload.body.unwrap().value).unwrap();
+        let table = request.ability_table.unwrap();
+        assert_eq!(get_ability(Some(&table), FUZZY_WATCH), Some(false));
+        assert_eq!(get_ability(Some(&table), "unknown"), None);
+        assert_eq!(get_ability(None, FUZZY_WATCH), None);
+    }
+
+    #[test]
+    fn test_is_sdk_ability_client() {
+        let mut table = HashMap::new();
+        table.insert(SDK_CLIENT_TEST_1.to_owned(), true);
+        assert!(is_sdk_ability_client(Some(&table)));
+        //服务端能力不能用于判断客户端
+        let mut table = HashMap::new();
+        table.insert(SUPPORT_PERSISTENT_INSTANCE_BY_GRPC.to_owned(), true);
+        assert!(!is_sdk_ability_client(Some(&table)));
+        assert!(!is_sdk_ability_client(Some(&HashMap::new())));
+        assert!(!is_sdk_ability_client(None));
+    }
+}
@@ -53,6 +53,7 @@ pub struct ServerCheckResponse {
     pub message: Option<String>,
     pub request_id: Option<String>,
     pub connection_id: Option<String>,
+    pub support_ability_negotiation: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -74,6 +75,30 @@ pub struct ConnectResetRequest {
     pub server_port: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionSetupRequest {
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+
+    pub client_version: Option<String>,
+    pub tenant: Option<String>,
+    pub labels: Option<HashMap<String, String>>,
+    pub abilities: Option<serde_json::Value>,
+    pub ability_table: Option<HashMap<String, bool>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupAckRequest {
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+
+    pub ability_table: Option<HashMap<String, bool>>,
+}
+
 // --- config ---
 
 #[derive(Debug, Serialize, Deserialize, Default)]
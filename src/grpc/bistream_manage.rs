@@ -1,39 +1,66 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
     time::Duration,
 };
 
 use crate::{
+    common::AppSysConfig,
     config::core::{ConfigActor, ConfigCmd, ConfigKey},
     naming::{
+        cluster::node_manage::{
+            ClusterNode, InnerNodeManage, NodeManageRequest, NodeManageResponse, NodeStatus,
+        },
         core::{NamingActor, NamingCmd},
         model::{ServiceInfo, ServiceKey},
     },
-    now_millis,
+    now_millis, now_millis_i64,
 };
 
 use super::{
-    api_model::{ConfigChangeNotifyRequest, NotifySubscriberRequest, CONFIG_MODEL, NAMING_MODEL},
+    ability::build_setup_ack_payload,
+    api_model::{
+        ConfigChangeNotifyRequest, ConnectionSetupRequest, NotifySubscriberRequest, CONFIG_MODEL,
+        NAMING_MODEL,
+    },
     bistream_conn::{BiStreamConn, BiStreamSenderCmd},
+    conn_info::{ConnClientInfo, ConnDetailInfo, ConnQueryParam},
+    conn_rebalance::{
+        split_sdk_server_addr, ConnRebalanceConfig, ConnRebalancePlan, ConnRebalanceRecord,
+        NodeConnCount,
+    },
     handler::converter::ModelConverter,
     nacos_proto::Payload,
+    quota::{get_conn_client_ip, ConnQuota, GrpcQuotaConfig, OVER_THRESHOLD_CODE},
     PayloadUtils,
 };
 use actix::prelude::*;
 use bean_factory::{bean, Inject};
 use inner_mem_cache::TimeoutSet;
 
+/// 保留最近的连接迁移记录条数
+const REBALANCE_RECORD_SIZE: usize = 50;
+
 pub(crate) struct ConnCacheItem {
     last_active_time: u64,
     conn: Addr<BiStreamConn>,
+    connect_time: u64,
+    //已通知客户端重连,不再重复通知
+    reset: bool,
+    ip: Arc<String>,
+    //客户端建连时上报的版本、标签等信息
+    client_info: Option<Arc<ConnClientInfo>>,
 }
 
 impl ConnCacheItem {
-    fn new(last_active_time: u64, conn: Addr<BiStreamConn>) -> Self {
+    fn new(last_active_time: u64, conn: Addr<BiStreamConn>, ip: Arc<String>) -> Self {
         Self {
             last_active_time,
             conn,
+            connect_time: last_active_time,
+            reset: false,
+            ip,
+            client_info: None,
         }
     }
 }
@@ -49,6 +76,11 @@ pub struct BiStreamManage {
     request_id: u64,
     config_addr: Option<Addr<ConfigActor>>,
     naming_addr: Option<Addr<NamingActor>>,
+    inner_node_manage: Option<Addr<InnerNodeManage>>,
+    rebalance_config: ConnRebalanceConfig,
+    rebalance_records: VecDeque<ConnRebalanceRecord>,
+    conn_quota: ConnQuota,
+    pub(crate) conn_limit_reject_count: u64,
 }
 
 impl BiStreamManage {
@@ -63,21 +95,111 @@ impl BiStreamManage {
     pub fn add_conn(&mut self, client_id: Arc<String>, sender: Addr<BiStreamConn>) {
         log::info!("add_conn client_id:{}", &client_id);
         let now = now_millis();
-        let item = ConnCacheItem::new(now, sender);
-        if let Some(old_conn) = self.conn_cache.insert(client_id.clone(), item) {
+        let ip = get_conn_client_ip(&client_id);
+        if let Some(old_conn) = self.remove_conn(&client_id) {
             log::info!("add_conn remove old conn:{}", &client_id);
             old_conn.conn.do_send(BiStreamSenderCmd::Close);
         }
+        if let Err(msg) = self.conn_quota.check(&ip, self.conn_cache.len()) {
+            //超过连接数限制,先回复限流错误,再通知客户端切换到其它节点
+            log::warn!("add_conn reject client_id:{},{}", &client_id, msg);
+            self.conn_limit_reject_count += 1;
+            sender.do_send(BiStreamSenderCmd::Send(Arc::new(
+                PayloadUtils::build_error_payload(OVER_THRESHOLD_CODE, msg),
+            )));
+            sender.do_send(BiStreamSenderCmd::Reset(self.next_request_id(), None, None));
+            sender.do_send(BiStreamSenderCmd::Close);
+            return;
+        }
+        self.conn_quota.add(ip.clone());
+        let item = ConnCacheItem::new(now, sender, ip);
+        self.conn_cache.insert(client_id.clone(), item);
         self.active_time_set
             .add(now + self.detection_time_out, client_id);
     }
 
-    fn active_client(&mut self, client_id: Arc<String>) -> anyhow::Result<()> {
+    fn remove_conn(&mut self, client_id: &Arc<String>) -> Option<ConnCacheItem> {
+        let item = self.conn_cache.remove(client_id);
+        if let Some(item) = &item {
+            self.conn_quota.remove(&item.ip);
+        }
+        item
+    }
+
+    ///
+    /// 记录客户端建连信息,并回复服务端能力表;未声明能力表的旧版本客户端会忽略该请求
+    fn set_client_info(&mut self, client_id: &Arc<String>, payload: &Payload) {
+        let request_id = self.next_request_id();
+        if let Some(item) = self.conn_cache.get_mut(client_id) {
+            if let Some(body) = &payload.body {
+                match serde_json::from_slice::<ConnectionSetupRequest>(&body.value) {
+                    Ok(request) => {
+                        let client_info: ConnClientInfo = request.into();
+                        item.conn.do_send(BiStreamSenderCmd::Send(Arc::new(
+                            build_setup_ack_payload(request_id),
+                        )));
+                        item.client_info = Some(Arc::new(client_info));
+                    }
+                    Err(err) => {
+                        log::warn!("parse ConnectionSetupRequest error,{}", err);
+                    }
+                }
+            }
+        }
+    }
+
+    fn query_conn_detail_page(&self, param: &ConnQueryParam) -> (usize, Vec<ConnDetailInfo>) {
+        let mut list: Vec<(&Arc<String>, &ConnCacheItem)> = self
+            .conn_cache
+            .iter()
+            .filter(|(_, item)| param.is_match(&item.ip, item.client_info.as_deref()))
+            .collect();
+        let size = list.len();
+        list.sort_by(|a, b| a.0.cmp(b.0));
+        let list = list
+            .into_iter()
+            .skip(param.offset)
+            .take(param.limit)
+            .map(|(client_id, item)| {
+                ConnDetailInfo::new(
+                    client_id.clone(),
+                    item.ip.clone(),
+                    item.client_info.as_deref(),
+                    item.connect_time,
+                    item.last_active_time,
+                )
+            })
+            .collect();
+        (size, list)
+    }
+
+    ///
+    /// 主动断开连接,并清理连接的订阅关系
+    fn close_conn(&mut self, client_id: Arc<String>) -> bool {
+        if let Some(item) = self.remove_conn(&client_id) {
+            log::info!("close conn by console,client_id:{}", &client_id);
+            item.conn.do_send(BiStreamSenderCmd::Close);
+            if let Some(config_addr) = &self.config_addr {
+                config_addr.do_send(ConfigCmd::RemoveSubscribeClient(client_id.clone()));
+            }
+            if let Some(naming_addr) = &self.naming_addr {
+                naming_addr.do_send(NamingCmd::RemoveClient(client_id));
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn active_client(
+        &mut self,
+        client_id: Arc<String>,
+    ) -> anyhow::Result<Option<Arc<ConnClientInfo>>> {
         let now = now_millis();
         if let Some(item) = self.conn_cache.get_mut(&client_id) {
             //log::info!("active_client success client_id:{}",&client_id);
             item.last_active_time = now;
-            Ok(())
+            Ok(item.client_info.clone())
         } else {
             //log::info!("active_client empty client_id:{}",&client_id);
             Err(anyhow::anyhow!("Connection is unregistered."))
@@ -135,7 +257,7 @@ impl BiStreamManage {
             log::info!("check timeout close client, size:{}", del_keys.len());
         }
         for key in &del_keys {
-            if let Some(item) = self.conn_cache.remove(key) {
+            if let Some(item) = self.remove_conn(key) {
                 //item.conn.do_send(BiStreamSenderCmd::Reset(self.next_request_id(),None,None));
                 item.conn.do_send(BiStreamSenderCmd::Close);
             }
@@ -157,9 +279,134 @@ impl BiStreamManage {
             let now = now_millis();
             act.check_active_time_set(now);
             act.check_response_time_set(now);
+            act.report_conn_count();
             act.time_out_heartbeat(ctx);
         });
     }
+
+    fn report_conn_count(&self) {
+        if let Some(inner_node_manage) = &self.inner_node_manage {
+            inner_node_manage.do_send(NodeManageRequest::UpdateLocalConnCount(
+                self.conn_cache.len(),
+            ));
+        }
+    }
+
+    fn rebalance_heartbeat(&self, ctx: &mut actix::Context<Self>) {
+        ctx.run_later(
+            Duration::from_secs(self.rebalance_config.interval_second),
+            |act, ctx| {
+                act.rebalance(false, ctx);
+                act.rebalance_heartbeat(ctx);
+            },
+        );
+    }
+
+    fn rebalance(&mut self, manual: bool, ctx: &mut actix::Context<Self>) {
+        let inner_node_manage = if let Some(v) = &self.inner_node_manage {
+            v.clone()
+        } else {
+            return;
+        };
+        async move { inner_node_manage.send(NodeManageRequest::GetAllNodes).await }
+            .into_actor(self)
+            .map(move |res, act, _ctx| {
+                if let Ok(Ok(NodeManageResponse::AllNodes(nodes))) = res {
+                    act.do_rebalance(nodes, manual, now_millis());
+                }
+            })
+            .spawn(ctx);
+    }
+
+    ///
+    /// 连接数超过集群平均值的节点,通知部分客户端重连到连接数少的节点
+    fn do_rebalance(&mut self, nodes: Vec<ClusterNode>, manual: bool, now: u64) {
+        //learner节点一般部署在其它区域,只在相同角色的节点间迁移连接
+        let local_learner = nodes
+            .iter()
+            .find(|e| e.is_local)
+            .map(|e| e.learner)
+            .unwrap_or(false);
+        let others: Vec<NodeConnCount> = nodes
+            .into_iter()
+            .filter(|e| !e.is_local && e.status == NodeStatus::Valid && e.learner == local_learner)
+            .map(|e| NodeConnCount {
+                sdk_addr: e.sdk_addr,
+                conn_count: e.conn_count,
+            })
+            .collect();
+        //手动触发时不考虑阈值
+        let threshold_percent = if manual {
+            0
+        } else {
+            self.rebalance_config.threshold_percent
+        };
+        let conn_count = self.conn_cache.len();
+        let plan = ConnRebalancePlan::build(
+            conn_count,
+            &others,
+            threshold_percent,
+            self.rebalance_config.batch_size,
+        );
+        if plan.targets.is_empty() && !manual {
+            return;
+        }
+        //刚建立的连接可能是从其它节点迁移过来的,自动迁移时跳过
+        let min_conn_time = if manual {
+            0
+        } else {
+            self.rebalance_config.interval_second * 1000
+        };
+        let mut client_ids: Vec<Arc<String>> = self
+            .conn_cache
+            .iter()
+            .filter(|(_, item)| !item.reset && item.connect_time + min_conn_time <= now)
+            .map(|(key, _)| key.clone())
+            .take(plan.reset_count())
+            .collect();
+        let mut reset_count = 0;
+        for (sdk_addr, count) in &plan.targets {
+            //旧版本节点没有同步sdk地址时由sdk自行选择重连节点
+            let (ip, port) = match sdk_addr.as_ref().and_then(|e| split_sdk_server_addr(e)) {
+                Some((ip, port)) => (Some(ip), Some(port)),
+                None => (None, None),
+            };
+            for _ in 0..*count {
+                let client_id = if let Some(v) = client_ids.pop() {
+                    v
+                } else {
+                    break;
+                };
+                let request_id = self.next_request_id();
+                if let Some(item) = self.conn_cache.get_mut(&client_id) {
+                    item.reset = true;
+                    item.conn.do_send(BiStreamSenderCmd::Reset(
+                        request_id,
+                        ip.clone(),
+                        port.clone(),
+                    ));
+                    reset_count += 1;
+                }
+            }
+        }
+        log::info!(
+            "grpc conn rebalance,manual:{},conn_count:{},avg_count:{},reset_count:{}",
+            manual,
+            conn_count,
+            plan.avg_count,
+            reset_count
+        );
+        if self.rebalance_records.len() >= REBALANCE_RECORD_SIZE {
+            self.rebalance_records.pop_front();
+        }
+        self.rebalance_records.push_back(ConnRebalanceRecord {
+            time: now_millis_i64(),
+            manual,
+            conn_count,
+            avg_count: plan.avg_count,
+            reset_count,
+        });
+    }
 }
 
 impl Actor for BiStreamManage {
@@ -178,10 +425,20 @@ impl Inject for BiStreamManage {
         &mut self,
         factory_data: bean_factory::FactoryData,
         _factory: bean_factory::BeanFactory,
-        _ctx: &mut Self::Context,
+        ctx: &mut Self::Context,
     ) {
         self.config_addr = factory_data.get_actor();
         self.naming_addr = factory_data.get_actor();
+        self.inner_node_manage = factory_data.get_actor();
+        let sys_config: Option<Arc<AppSysConfig>> = factory_data.get_bean();
+        if let Some(sys_config) = sys_config {
+            self.rebalance_config = ConnRebalanceConfig::new(&sys_config);
+            let quota_config = GrpcQuotaConfig::new(&sys_config);
+            self.conn_quota = ConnQuota::new(quota_config.max_conn, quota_config.max_conn_per_ip);
+        }
+        if self.rebalance_config.enable {
+            self.rebalance_heartbeat(ctx);
+        }
         log::info!("BiStreamManage inject complete");
     }
 }
@@ -202,28 +459,41 @@ pub enum BiStreamManageCmd {
     NotifyConfig(ConfigKey, HashSet<Arc<String>>),
     NotifyNaming(ServiceKey, HashSet<Arc<String>>, ServiceInfo),
     QueryConnList,
+    QueryConnDetailPage(ConnQueryParam),
+    CloseConn(Arc<String>),
+    Rebalance { manual: bool },
+    QueryRebalanceRecords,
 }
 
 pub enum BiStreamManageResult {
     ConnList(Vec<Arc<String>>),
+    ConnDetailPage(usize, Vec<ConnDetailInfo>),
+    CloseResult(bool),
+    ClientInfo(Option<Arc<ConnClientInfo>>),
+    RebalanceRecords(Vec<ConnRebalanceRecord>),
+    /// 已发送通知的客户端
+    NotifyResult(HashSet<Arc<String>>),
     None,
 }
 
 impl Handler<BiStreamManageCmd> for BiStreamManage {
     type Result = anyhow::Result<BiStreamManageResult>;
 
-    fn handle(&mut self, msg: BiStreamManageCmd, _ctx: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: BiStreamManageCmd, ctx: &mut Context<Self>) -> Self::Result {
         match msg {
             BiStreamManageCmd::Response(client_id, payload) => {
                 //println!("BiStreamManageCmd payload:{},client_id:{}",PayloadUtils::get_payload_string(&payload),&client_id);
-                if let Some(_t) = PayloadUtils::get_payload_type(&payload) {
+                if let Some(t) = PayloadUtils::get_payload_type(&payload) {
+                    if "ConnectionSetupRequest" == t {
+                        self.set_client_info(&client_id, &payload);
+                    }
                     self.active_client(client_id).ok();
                     //if "ClientDetectionResponse"== t {
                     //}
                 }
             }
             BiStreamManageCmd::ConnClose(client_id) => {
-                self.conn_cache.remove(&client_id);
+                self.remove_conn(&client_id);
                 if let Some(config_addr) = &self.config_addr {
                     config_addr.do_send(ConfigCmd::RemoveSubscribeClient(client_id.clone()))
                 }
@@ -237,7 +507,8 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
                 //println!("|AddConn|conn size: {}",self.conn_cache.len());
             }
             BiStreamManageCmd::ActiveClinet(client_id) => {
-                self.active_client(client_id)?;
+                let client_info = self.active_client(client_id)?;
+                return Ok(BiStreamManageResult::ClientInfo(client_info));
             }
             BiStreamManageCmd::NotifyConfig(config_key, client_id_set) => {
                 let request = ConfigChangeNotifyRequest {
@@ -273,11 +544,14 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
                     "NotifySubscriberRequest",
                     serde_json::to_string(&request).unwrap(),
                 ));
-                for item in &client_id_set {
-                    if let Some(item) = self.conn_cache.get(item) {
+                let mut delivered = HashSet::new();
+                for client_id in client_id_set {
+                    if let Some(item) = self.conn_cache.get(&client_id) {
                         item.conn.do_send(BiStreamSenderCmd::Send(payload.clone()));
+                        delivered.insert(client_id);
                     }
                 }
+                return Ok(BiStreamManageResult::NotifyResult(delivered));
             }
             BiStreamManageCmd::QueryConnList => {
                 let mut list = Vec::with_capacity(self.conn_cache.len());
@@ -286,6 +560,22 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
                 }
                 return Ok(BiStreamManageResult::ConnList(list));
             }
+            BiStreamManageCmd::QueryConnDetailPage(param) => {
+                let (size, list) = self.query_conn_detail_page(&param);
+                return Ok(BiStreamManageResult::ConnDetailPage(size, list));
+            }
+            BiStreamManageCmd::CloseConn(client_id) => {
+                return Ok(BiStreamManageResult::CloseResult(
+                    self.close_conn(client_id),
+                ));
+            }
+            BiStreamManageCmd::Rebalance { manual } => {
+                self.rebalance(manual, ctx);
+            }
+            BiStreamManageCmd::QueryRebalanceRecords => {
+                let list = self.rebalance_records.iter().rev().cloned().collect();
+                return Ok(BiStreamManageResult::RebalanceRecords(list));
+            }
         }
         Ok(BiStreamManageResult::None)
     }
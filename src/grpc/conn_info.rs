@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::api_model::ConnectionSetupRequest;
+
+/// nacos sdk 连接标签中的应用名
+pub const LABEL_APP_NAME: &str = "AppName";
+pub const LABEL_APP: &str = "app";
+pub const LABEL_MODULE: &str = "module";
+
+///
+/// 客户端建立连接时上报的信息
+#[derive(Debug, Clone, Default)]
+pub struct ConnClientInfo {
+    pub client_version: Arc<String>,
+    pub tenant: Arc<String>,
+    pub labels: HashMap<String, String>,
+    pub abilities: Option<serde_json::Value>,
+    pub ability_table: Option<HashMap<String, bool>>,
+}
+
+impl ConnClientInfo {
+    pub fn get_app_name(&self) -> &str {
+        self.labels
+            .get(LABEL_APP_NAME)
+            .or_else(|| self.labels.get(LABEL_APP))
+            .map(|e| e.as_str())
+            .unwrap_or_default()
+    }
+
+    pub fn get_module(&self) -> &str {
+        self.labels
+            .get(LABEL_MODULE)
+            .map(|e| e.as_str())
+            .unwrap_or_default()
+    }
+}
+
+impl From<ConnectionSetupRequest> for ConnClientInfo {
+    fn from(value: ConnectionSetupRequest) -> Self {
+        Self {
+            client_version: Arc::new(value.client_version.unwrap_or_default()),
+            tenant: Arc::new(value.tenant.unwrap_or_default()),
+            labels: value.labels.unwrap_or_default(),
+            abilities: value.abilities,
+            ability_table: value.ability_table,
+        }
+    }
+}
+
+///
+/// 连接查询条件,ip与版本号按包含匹配,应用名按相等匹配
+#[derive(Debug, Clone, Default)]
+pub struct ConnQueryParam {
+    pub ip: Option<String>,
+    pub app_name: Option<String>,
+    pub client_version: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl ConnQueryParam {
+    pub fn is_match(&self, ip: &str, info: Option<&ConnClientInfo>) -> bool {
+        if let Some(v) = &self.ip {
+            if !ip.contains(v.as_str()) {
+                return false;
+            }
+        }
+        if let Some(v) = &self.app_name {
+            if info.map(|e| e.get_app_name()).unwrap_or_default() != v {
+                return false;
+            }
+        }
+        if let Some(v) = &self.client_version {
+            if !info
+                .map(|e| e.client_version.contains(v.as_str()))
+                .unwrap_or_default()
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+///
+/// 连接详情
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnDetailInfo {
+    pub client_id: Arc<String>,
+    pub ip: Arc<String>,
+    pub client_version: Arc<String>,
+    pub app_name: String,
+    pub module: String,
+    pub tenant: Arc<String>,
+    pub labels: HashMap<String, String>,
+    pub abilities: Option<serde_json::Value>,
+    pub ability_table: Option<HashMap<String, bool>>,
+    pub connect_time: u64,
+    pub last_active_time: u64,
+}
+
+impl ConnDetailInfo {
+    pub fn new(
+        client_id: Arc<String>,
+        ip: Arc<String>,
+        info: Option<&ConnClientInfo>,
+        connect_time: u64,
+        last_active_time: u64,
+    ) -> Self {
+        let mut v = Self {
+            client_id,
+            ip,
+            connect_time,
+            last_active_time,
+            ..Default::default()
+        };
+        if let Some(info) = info {
+            v.client_version = info.client_version.clone();
+            v.app_name = info.get_app_name().to_owned();
+            v.module = info.get_module().to_owned();
+            v.tenant = info.tenant.clone();
+            v.labels = info.labels.clone();
+            v.abilities = info.abilities.clone();
+            v.ability_table = info.ability_table.clone();
+        }
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conn_query_param() {
+        let request: ConnectionSetupRequest = serde_json::from_str(
+            r#"{"clientVersion":"Nacos-Java-Client:v2.2.0","tenant":"","labels":{"source":"sdk","module":"config","AppName":"demo"},"abilities":{"remoteAbility":{"supportRemoteConnection":true}}}"#,
+        )
+        .unwrap();
+        let info: ConnClientInfo = request.into();
+        assert_eq!(info.get_app_name(), "demo");
+        assert_eq!(info.get_module(), "config");
+        let param = ConnQueryParam {
+            ip: Some("192.168.1".to_owned()),
+            app_name: Some("demo".to_owned()),
+            client_version: Some("v2.2".to_owned()),
+            ..Default::default()
+        };
+        assert!(param.is_match("192.168.1.10", Some(&info)));
+        assert!(!param.is_match("10.0.0.1", Some(&info)));
+        assert!(!param.is_match("192.168.1.10", None));
+        assert!(ConnQueryParam::default().is_match("10.0.0.1", None));
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::AppSysConfig;
+
+#[derive(Debug, Clone, Default)]
+pub struct ConnRebalanceConfig {
+    pub enable: bool,
+    pub interval_second: u64,
+    /// 连接数超过平均值的百分比才触发迁移
+    pub threshold_percent: usize,
+    /// 每轮最多迁移的连接数
+    pub batch_size: usize,
+}
+
+impl ConnRebalanceConfig {
+    pub fn new(sys_config: &AppSysConfig) -> Self {
+        Self {
+            enable: sys_config.grpc_rebalance_enable,
+            interval_second: sys_config.grpc_rebalance_interval_second.max(5),
+            threshold_percent: sys_config.grpc_rebalance_threshold_percent,
+            batch_size: sys_config.grpc_rebalance_batch_size,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeConnCount {
+    /// 节点的sdk地址(ip:port),旧版本节点没有该地址
+    pub sdk_addr: Option<Arc<String>>,
+    pub conn_count: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConnRebalancePlan {
+    pub avg_count: usize,
+    /// 迁移目标节点sdk地址及迁移连接数
+    pub targets: Vec<(Option<Arc<String>>, usize)>,
+}
+
+impl ConnRebalancePlan {
+    pub fn reset_count(&self) -> usize {
+        self.targets.iter().map(|e| e.1).sum()
+    }
+
+    ///
+    /// 计算本节点需要迁移到其它节点的连接数,优先迁移到连接数最少的节点
+    pub fn build(
+        local_count: usize,
+        others: &[NodeConnCount],
+        threshold_percent: usize,
+        batch_size: usize,
+    ) -> Self {
+        let node_count = others.len() + 1;
+        let total: usize = local_count + others.iter().map(|e| e.conn_count).sum::<usize>();
+        let avg_count = total.div_ceil(node_count);
+        let mut plan = Self {
+            avg_count,
+            targets: vec![],
+        };
+        let limit = avg_count + avg_count * threshold_percent / 100;
+        if others.is_empty() || batch_size == 0 || local_count <= limit {
+            return plan;
+        }
+        let mut remain = (local_count - avg_count).min(batch_size);
+        let mut nodes: Vec<&NodeConnCount> =
+            others.iter().filter(|e| e.conn_count < avg_count).collect();
+        nodes.sort_by_key(|e| e.conn_count);
+        for node in nodes {
+            if remain == 0 {
+                break;
+            }
+            let count = (avg_count - node.conn_count).min(remain);
+            plan.targets.push((node.sdk_addr.clone(), count));
+            remain -= count;
+        }
+        plan
+    }
+}
+
+///
+/// 拆分节点同步过来的sdk地址(ip:port),用于通知sdk重连
+pub fn split_sdk_server_addr(addr: &str) -> Option<(String, String)> {
+    let (ip, port) = addr.rsplit_once(':')?;
+    let port: u16 = port.parse().ok()?;
+    Some((ip.to_owned(), port.to_string()))
+}
+
+///
+/// 连接迁移记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnRebalanceRecord {
+    pub time: i64,
+    pub manual: bool,
+    pub conn_count: usize,
+    pub avg_count: usize,
+    pub reset_count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(addr: &str, conn_count: usize) -> NodeConnCount {
+        NodeConnCount {
+            sdk_addr: Some(Arc::new(addr.to_owned())),
+            conn_count,
+        }
+    }
+
+    #[test]
+    fn test_rebalance_plan() {
+        let others = vec![node("127.0.0.1:8849", 10), node("127.0.0.1:8850", 0)];
+        let plan = ConnRebalancePlan::build(110, &others, 20, 100);
+        assert_eq!(plan.avg_count, 40);
+        assert_eq!(plan.targets.len(), 2);
+        assert_eq!(
+            plan.targets[0].0.as_ref().map(|e| e.as_str()),
+            Some("127.0.0.1:8850")
+        );
+        assert_eq!(plan.targets[0].1, 40);
+        assert_eq!(plan.targets[1].1, 30);
+        //每轮迁移数量受限
+        let plan = ConnRebalancePlan::build(110, &others, 20, 20);
+        assert_eq!(plan.reset_count(), 20);
+        //未超过阈值不迁移
+        let others = vec![node("127.0.0.1:8849", 30), node("127.0.0.1:8850", 30)];
+        let plan = ConnRebalancePlan::build(35, &others, 20, 100);
+        assert!(plan.targets.is_empty());
+        let plan = ConnRebalancePlan::build(35, &[], 0, 100);
+        assert!(plan.targets.is_empty());
+    }
+
+    #[test]
+    fn test_split_sdk_server_addr() {
+        assert_eq!(
+            split_sdk_server_addr("192.168.1.10:8848"),
+            Some(("192.168.1.10".to_owned(), "8848".to_owned()))
+        );
+        assert_eq!(
+            split_sdk_server_addr("192.168.1.10:80"),
+            Some(("192.168.1.10".to_owned(), "80".to_owned()))
+        );
+        assert_eq!(split_sdk_server_addr("192.168.1.10"), None);
+    }
+}
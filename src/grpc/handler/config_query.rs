@@ -6,6 +6,7 @@ use crate::config::config_type::ConfigType;
 use crate::config::ConfigUtils;
 use crate::grpc::api_model::NOT_FOUND;
 use crate::grpc::HandlerResult;
+use crate::raft::cluster::model::{ReadConsistency, CONSISTENCY_PARAM};
 use crate::{
     common::appdata::AppShareData,
     config::core::{ConfigActor, ConfigCmd, ConfigKey, ConfigResult},
@@ -38,6 +39,13 @@ impl PayloadHandler for ConfigQueryRequestHandler {
         request_payload: crate::grpc::nacos_proto::Payload,
         _request_meta: crate::grpc::RequestMeta,
     ) -> anyhow::Result<HandlerResult> {
+        let consistency = ReadConsistency::from_param(
+            request_payload
+                .metadata
+                .as_ref()
+                .and_then(|e| e.headers.get(CONSISTENCY_PARAM))
+                .map(|e| e.as_str()),
+        );
         let body_vec = request_payload.body.unwrap_or_default().value;
         let request: ConfigQueryRequest = serde_json::from_slice(&body_vec)?;
         let cmd = ConfigCmd::GET(ConfigKey::new(
@@ -49,6 +57,20 @@ impl PayloadHandler for ConfigQueryRequestHandler {
             request_id: request.request_id,
             ..Default::default()
         };
+        if let Err(err) = self
+            .app_data
+            .config_route
+            .check_read_consistency(consistency)
+            .await
+        {
+            response.result_code = ERROR_CODE;
+            response.error_code = ERROR_CODE;
+            response.message = Some(err.to_string());
+            return Ok(HandlerResult::success(PayloadUtils::build_payload(
+                "ErrorResponse",
+                serde_json::to_string(&response)?,
+            )));
+        }
         match self.app_data.config_addr.send(cmd).await {
             Ok(res) => {
                 //let res:ConfigResult = res.unwrap();
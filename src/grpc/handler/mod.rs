@@ -15,11 +15,14 @@ use self::{
 use super::{
     api_model::{BaseResponse, ServerCheckResponse, SUCCESS_CODE},
     nacos_proto::Payload,
+    quota::{GrpcQuotaConfig, NamespaceRequestLimiter, RequestNamespaceProbe, OVER_THRESHOLD_CODE},
     HandleLogArgs, HandlerResult, PayloadHandler, PayloadUtils, RequestMeta,
 };
 use crate::grpc::handler::raft_append::RaftAppendRequestHandler;
 use crate::grpc::handler::raft_snapshot::RaftSnapshotRequestHandler;
 use crate::grpc::handler::raft_vote::RaftVoteRequestHandler;
+use crate::metrics::metrics_key::MetricsKey;
+use crate::metrics::model::{MetricsItem, MetricsRecord, MetricsRequest};
 use async_trait::async_trait;
 
 pub mod config_change_batch_listen;
@@ -45,6 +48,9 @@ pub(crate) const HEALTH_CHECK_REQUEST: &str = "HealthCheckRequest";
 pub(crate) const SERVER_CHECK_REQUEST: &str = "ServerCheckRequest";
 pub(crate) const RAFT_APPEND_REQUEST: &str = "RaftAppendRequest";
 pub(crate) const RAFT_SNAPSHOT_REQUEST: &str = "RaftSnapshotRequest";
+/// 镜像分片请求体编码方式;为空时请求体为json
+pub(crate) const RAFT_SNAPSHOT_ENCODING_HEADER: &str = "encoding";
+pub(crate) const RAFT_SNAPSHOT_ENCODING_PB: &str = "pb";
 pub(crate) const RAFT_VOTE_REQUEST: &str = "RaftVoteRequest";
 pub(crate) const RAFT_ROUTE_REQUEST: &str = "RaftRouteRequest";
 pub(crate) const NAMING_ROUTE_REQUEST: &str = "NamingRouteRequest";
@@ -63,14 +69,21 @@ pub(crate) const SERVICE_LIST_REQUEST: &str = "ServiceListRequest";
 pub struct InvokerHandler {
     app: Arc<AppShareData>,
     handlers: Vec<(String, Box<dyn PayloadHandler + Send + Sync + 'static>)>,
+    config_query_limiter: NamespaceRequestLimiter,
+    config_publish_limiter: NamespaceRequestLimiter,
+    naming_register_limiter: NamespaceRequestLimiter,
 }
 pub struct HealthCheckRequestHandler {}
 
 impl InvokerHandler {
     pub fn new(app: Arc<AppShareData>) -> Self {
+        let quota_config = GrpcQuotaConfig::new(&app.sys_config);
         let mut this = Self {
             handlers: Default::default(),
             app,
+            config_query_limiter: NamespaceRequestLimiter::new(quota_config.config_query_qps),
+            config_publish_limiter: NamespaceRequestLimiter::new(quota_config.config_publish_qps),
+            naming_register_limiter: NamespaceRequestLimiter::new(quota_config.naming_register_qps),
         };
         this.add_handler(HEALTH_CHECK_REQUEST, Box::new(HealthCheckRequestHandler {}));
         this
@@ -123,6 +136,48 @@ impl InvokerHandler {
             || NAMING_ROUTE_REQUEST.eq(t)
     }
 
+    fn get_request_limiter(&self, t: &str) -> Option<&NamespaceRequestLimiter> {
+        if CONFIG_QUERY_REQUEST.eq(t) {
+            Some(&self.config_query_limiter)
+        } else if CONFIG_PUBLISH_REQUEST.eq(t) {
+            Some(&self.config_publish_limiter)
+        } else if INSTANCE_REQUEST.eq(t) || BATCH_INSTANCE_REQUEST.eq(t) {
+            Some(&self.naming_register_limiter)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// 按命名空间校验请求qps,注销实例请求不限流
+    fn check_request_limit(&self, t: &str, request_payload: &Payload) -> Result<(), String> {
+        let limiter = if let Some(v) = self.get_request_limiter(t) {
+            v
+        } else {
+            return Ok(());
+        };
+        let probe: RequestNamespaceProbe = request_payload
+            .body
+            .as_ref()
+            .and_then(|e| serde_json::from_slice(&e.value).ok())
+            .unwrap_or_default();
+        if probe.is_deregister() || limiter.acquire(probe.get_namespace()) {
+            Ok(())
+        } else {
+            self.app
+                .metrics_manager
+                .do_send(MetricsRequest::Record(MetricsItem::new(
+                    MetricsKey::GrpcRequestLimitRejectCount,
+                    MetricsRecord::CounterInc(1),
+                )));
+            Err(format!(
+                "{} over qps limit,namespace:{}",
+                t,
+                probe.get_namespace()
+            ))
+        }
+    }
+
     pub fn add_raft_handler(&mut self, app_data: &Arc<AppShareData>) {
         self.add_handler(
             RAFT_APPEND_REQUEST,
@@ -210,6 +265,7 @@ impl PayloadHandler for InvokerHandler {
                 let response = ServerCheckResponse {
                     result_code: SUCCESS_CODE,
                     connection_id: Some(request_meta.connection_id.as_ref().to_owned()),
+                    support_ability_negotiation: true,
                     ..Default::default()
                 };
                 return Ok(HandlerResult::success(PayloadUtils::build_payload(
@@ -233,6 +289,9 @@ impl PayloadHandler for InvokerHandler {
                     "request cluster token is invalid".to_string(),
                 ));
             }
+            if let Err(msg) = self.check_request_limit(url, &request_payload) {
+                return Ok(HandlerResult::error(OVER_THRESHOLD_CODE, msg));
+            }
             //println!("InvokerHandler type:{}",url);
             if let Some(handler) = self.match_handler(url) {
                 return handler.handle(request_payload, request_meta).await;
@@ -5,7 +5,7 @@ use std::sync::{
     Arc,
 };
 
-use crate::grpc::HandlerResult;
+use crate::grpc::{HandlerResult, RequestMeta};
 use crate::{
     common::appdata::AppShareData,
     grpc::{
@@ -92,6 +92,21 @@ impl InstanceRequestHandler {
             Err(anyhow::format_err!("instance is empty"))
         }
     }
+
+    ///
+    /// 支持能力协商的sdk客户端按服务端能力表注册持久化实例,服务端未声明supportPersistentInstanceByGrpc时
+    /// 仍通过grpc注册持久化实例直接拒绝,避免实例随连接断开被删除
+    pub(crate) fn check_persistent_instance(
+        instance: &Instance,
+        request_meta: &RequestMeta,
+    ) -> anyhow::Result<()> {
+        if !instance.ephemeral && request_meta.is_sdk_ability_client() {
+            return Err(anyhow::format_err!(
+                "persistent instance register by grpc is not supported"
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -110,7 +125,22 @@ impl PayloadHandler for InstanceRequestHandler {
                 is_de_register = true;
             }
         }
-        let instance = Self::convert_to_instance(request, request_meta.connection_id)?;
+        let instance = Self::convert_to_instance(request, request_meta.connection_id.clone())?;
+        let mut response = InstanceResponse {
+            request_id,
+            ..Default::default()
+        };
+        if !is_de_register {
+            if let Err(err) = Self::check_persistent_instance(&instance, &request_meta) {
+                response.result_code = ERROR_CODE;
+                response.error_code = 500u16;
+                response.message = Some(err.to_string());
+                return Ok(HandlerResult::success(PayloadUtils::build_payload(
+                    "ErrorResponse",
+                    serde_json::to_string(&response)?,
+                )));
+            }
+        }
         let cmd = if is_de_register {
             NamingCmd::Delete(instance)
         } else {
@@ -123,10 +153,6 @@ impl PayloadHandler for InstanceRequestHandler {
             };
             NamingCmd::Update(instance, Some(update_tag))
         };
-        let mut response = InstanceResponse {
-            request_id,
-            ..Default::default()
-        };
         match self.app_data.naming_addr.send(cmd).await {
             Ok(_res) => {
                 //let res:ConfigResult = res.unwrap();
@@ -153,3 +179,39 @@ impl PayloadHandler for InstanceRequestHandler {
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::ability::{SDK_CLIENT_DISTRIBUTED_LOCK, SDK_CLIENT_FUZZY_WATCH};
+    use crate::grpc::conn_info::ConnClientInfo;
+    use std::collections::HashMap;
+
+    fn build_request_meta(ability_table: Option<HashMap<String, bool>>) -> RequestMeta {
+        RequestMeta {
+            client_info: Some(Arc::new(ConnClientInfo {
+                ability_table,
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_persistent_instance() {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.ephemeral = false;
+        //nacos sdk建连时声明的客户端能力表
+        let mut table = HashMap::new();
+        table.insert(SDK_CLIENT_FUZZY_WATCH.to_owned(), true);
+        table.insert(SDK_CLIENT_DISTRIBUTED_LOCK.to_owned(), true);
+        let meta = build_request_meta(Some(table.clone()));
+        assert!(InstanceRequestHandler::check_persistent_instance(&instance, &meta).is_err());
+        //未声明能力的旧版本客户端保持原有行为
+        let meta = build_request_meta(None);
+        assert!(InstanceRequestHandler::check_persistent_instance(&instance, &meta).is_ok());
+        instance.ephemeral = true;
+        let meta = build_request_meta(Some(table));
+        assert!(InstanceRequestHandler::check_persistent_instance(&instance, &meta).is_ok());
+    }
+}
@@ -186,6 +186,36 @@ impl NamingRouteRequestHandler {
                 }
                 args.merge_args(tmp_args);
             }
+            NamingRouteRequest::UpdateMetadataBatch(batch) => {
+                args.add_str(batch.namespace_id.as_str())
+                    .add_key_split()
+                    .add_str(batch.group_name.as_str())
+                    .add_key_split()
+                    .add_str(batch.service_name.as_str())
+                    .add_item_split()
+                    .add_string(format!("{:?}", batch.action));
+            }
+            NamingRouteRequest::DrainInstance(param) => {
+                args.add_str(param.namespace_id.as_str())
+                    .add_key_split()
+                    .add_str(param.group_name.as_str())
+                    .add_key_split()
+                    .add_str(param.service_name.as_str())
+                    .add_item_split()
+                    .add_string(format!("{:?}", param.mode));
+            }
+            NamingRouteRequest::SyncConnCount {
+                node_id,
+                conn_count,
+                sdk_addr,
+            } => {
+                args.add_string(node_id.to_string())
+                    .add_key_split()
+                    .add_string(conn_count.to_string())
+                    .add_key_split()
+                    .add_string(format!("{:?}", sdk_addr));
+            }
+            NamingRouteRequest::GrpcConnRebalance => {}
         }
         Ok(args.to_string())
     }
@@ -222,7 +252,9 @@ impl PayloadHandler for NamingRouteRequestHandler {
         if let Some(v) = request_payload.body.as_ref() {
             let request: Result<NamingRouteRequest, _> = serde_json::from_slice(&v.value);
             if let Ok(request) = &request {
-                if let NamingRouteRequest::Ping(_) = request {
+                if let NamingRouteRequest::Ping(_) | NamingRouteRequest::SyncConnCount { .. } =
+                    request
+                {
                     return HandleLogArgs::Ignore;
                 }
                 if let Ok(v) = Self::get_req_args(request) {
@@ -235,7 +267,7 @@ impl PayloadHandler for NamingRouteRequestHandler {
             e.headers
                 .get(crate::common::constant::GRPC_HEAD_KEY_SUB_NAME)
         }) {
-            if v == "Ping" {
+            if v == "Ping" || v == "SyncConnCount" {
                 return HandleLogArgs::Ignore;
             }
             args.add_str("NamingRoute")
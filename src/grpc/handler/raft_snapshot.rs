@@ -1,8 +1,10 @@
 use std::sync::Arc;
 
 use crate::common::appdata::AppShareData;
+use crate::grpc::handler::{RAFT_SNAPSHOT_ENCODING_HEADER, RAFT_SNAPSHOT_ENCODING_PB};
 use crate::grpc::nacos_proto::Payload;
 use crate::grpc::{HandlerResult, PayloadHandler, PayloadUtils, RequestMeta};
+use crate::raft::filestore::model::{InstallSnapshotRequestDto, InstallSnapshotResponseDto};
 use async_trait::async_trait;
 
 pub struct RaftSnapshotRequestHandler {
@@ -22,10 +24,47 @@ impl PayloadHandler for RaftSnapshotRequestHandler {
         request_payload: Payload,
         _request_meta: RequestMeta,
     ) -> anyhow::Result<HandlerResult> {
+        let is_pb = request_payload
+            .metadata
+            .as_ref()
+            .and_then(|e| e.headers.get(RAFT_SNAPSHOT_ENCODING_HEADER))
+            .map(|v| v == RAFT_SNAPSHOT_ENCODING_PB)
+            .unwrap_or(false);
         let body_vec = request_payload.body.unwrap_or_default().value;
-        let request: async_raft_ext::raft::InstallSnapshotRequest =
-            serde_json::from_slice(&body_vec)?;
+        let request: async_raft_ext::raft::InstallSnapshotRequest = if is_pb {
+            InstallSnapshotRequestDto::from_bytes(&body_vec)?.into()
+        } else {
+            serde_json::from_slice(&body_vec)?
+        };
+        let (last_index, last_term, offset, done) = (
+            request.last_included_index,
+            request.last_included_term,
+            request.offset,
+            request.done,
+        );
+        let store = &self.app_data.raft_store;
+        //第一个分片时检查是否可以续传
+        let resume_offset = if offset == 0 && !done {
+            store
+                .prepare_install_snapshot(last_index, last_term, request.data.clone())
+                .await?
+        } else {
+            0
+        };
         let res = self.app_data.raft.install_snapshot(request).await?;
+        if offset > 0 && !done {
+            //当前分片之前的数据已写入镜像文件
+            if let Err(err) = store
+                .save_install_snapshot_offset(last_index, last_term, offset)
+                .await
+            {
+                log::warn!("save install snapshot offset error,{}", err);
+            }
+        }
+        let res = InstallSnapshotResponseDto {
+            term: res.term,
+            resume_offset,
+        };
         let value = serde_json::to_string(&res)?;
         let payload = PayloadUtils::build_payload("RaftSnapshotResponse", value);
         Ok(HandlerResult::success(payload))
@@ -7,6 +7,7 @@ impl Handler<MetricsQuery> for BiStreamManage {
     type Result = anyhow::Result<Vec<MetricsItem>>;
 
     fn handle(&mut self, _msg: MetricsQuery, _ctx: &mut Self::Context) -> Self::Result {
+        let conn_limit_reject_count = std::mem::take(&mut self.conn_limit_reject_count);
         let list = vec![
             MetricsItem {
                 metrics_type: MetricsKey::GrpcConnSize,
@@ -20,6 +21,10 @@ impl Handler<MetricsQuery> for BiStreamManage {
                 metrics_type: MetricsKey::GrpcConnResponseTimeoutSetItemSize,
                 record: MetricsRecord::Gauge(self.response_time_set.item_size() as f32),
             },
+            MetricsItem {
+                metrics_type: MetricsKey::GrpcConnLimitRejectCount,
+                record: MetricsRecord::CounterInc(conn_limit_reject_count),
+            },
         ];
         Ok(list)
     }
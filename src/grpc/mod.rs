@@ -1,15 +1,20 @@
 use self::api_model::BaseResponse;
+use self::conn_info::ConnClientInfo;
 use crate::common::model::TokenSession;
 use async_trait::async_trait;
 use std::fmt::Display;
 use std::{collections::HashMap, sync::Arc};
 
+pub mod ability;
 pub mod api_model;
 pub mod bistream_conn;
 pub mod bistream_manage;
+pub mod conn_info;
+pub mod conn_rebalance;
 pub mod handler;
 pub mod metrics;
 pub mod nacos_proto;
+pub mod quota;
 pub mod server;
 
 #[derive(Default)]
@@ -20,6 +25,30 @@ pub struct RequestMeta {
     pub labels: HashMap<String, String>,
     pub token_session: Option<Arc<TokenSession>>,
     pub cluster_token_is_valid: bool,
+    pub client_info: Option<Arc<ConnClientInfo>>,
+}
+
+impl RequestMeta {
+    ///
+    /// 查询客户端建连时声明的能力,客户端未声明时返回None
+    pub fn get_client_ability(&self, key: &str) -> Option<bool> {
+        ability::get_ability(
+            self.client_info
+                .as_ref()
+                .and_then(|e| e.ability_table.as_ref()),
+            key,
+        )
+    }
+
+    ///
+    /// 客户端是否在建连时声明了sdk客户端能力
+    pub fn is_sdk_ability_client(&self) -> bool {
+        ability::is_sdk_ability_client(
+            self.client_info
+                .as_ref()
+                .and_then(|e| e.ability_table.as_ref()),
+        )
+    }
 }
 
 pub struct HandlerResult {
@@ -151,6 +180,22 @@ impl PayloadUtils {
         }
     }
 
+    pub fn build_bytes_payload(
+        url: &str,
+        value: Vec<u8>,
+        headers: HashMap<String, String>,
+    ) -> nacos_proto::Payload {
+        let body = nacos_proto::Any {
+            type_url: "".into(),
+            value,
+        };
+        let meta = Self::new_metadata(url, "", headers);
+        nacos_proto::Payload {
+            body: Some(body),
+            metadata: Some(meta),
+        }
+    }
+
     pub fn get_payload_header(payload: &nacos_proto::Payload) -> String {
         let mut str = String::default();
         if let Some(meta) = &payload.metadata {
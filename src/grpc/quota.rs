@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use ratelimiter_rs::RateLimiter;
+use serde::Deserialize;
+
+use crate::common::AppSysConfig;
+use crate::now_millis_i64;
+
+/// 请求超过限流阈值的错误码,与nacos OVER_THRESHOLD一致
+pub const OVER_THRESHOLD_CODE: u16 = 503;
+
+/// 单个限流器最多记录的命名空间数,超过后淘汰最久未访问的命名空间
+const MAX_LIMITER_KEY_SIZE: usize = 10000;
+
+#[derive(Debug, Clone, Default)]
+pub struct GrpcQuotaConfig {
+    /// 全局最大连接数,0表示不限制
+    pub max_conn: usize,
+    /// 单个来源ip最大连接数,0表示不限制
+    pub max_conn_per_ip: usize,
+    /// 每个命名空间的qps,0表示不限制
+    pub config_query_qps: i32,
+    pub config_publish_qps: i32,
+    pub naming_register_qps: i32,
+}
+
+impl GrpcQuotaConfig {
+    pub fn new(sys_config: &AppSysConfig) -> Self {
+        Self {
+            max_conn: sys_config.grpc_max_conn,
+            max_conn_per_ip: sys_config.grpc_max_conn_per_ip,
+            config_query_qps: sys_config.grpc_config_query_qps,
+            config_publish_qps: sys_config.grpc_config_publish_qps,
+            naming_register_qps: sys_config.grpc_naming_register_qps,
+        }
+    }
+}
+
+///
+/// 从连接id(node_id_ip:port)中取出客户端ip
+pub fn get_conn_client_ip(client_id: &str) -> Arc<String> {
+    let ip = client_id
+        .split_once('_')
+        .and_then(|(_, addr)| addr.parse::<SocketAddr>().ok())
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_default();
+    Arc::new(ip)
+}
+
+///
+/// grpc连接数限制
+#[derive(Debug, Default)]
+pub struct ConnQuota {
+    pub max_conn: usize,
+    pub max_conn_per_ip: usize,
+    pub(crate) ip_conn_counts: HashMap<Arc<String>, usize>,
+}
+
+impl ConnQuota {
+    pub fn new(max_conn: usize, max_conn_per_ip: usize) -> Self {
+        Self {
+            max_conn,
+            max_conn_per_ip,
+            ip_conn_counts: Default::default(),
+        }
+    }
+
+    pub fn check(&self, ip: &Arc<String>, conn_count: usize) -> Result<(), String> {
+        if self.max_conn > 0 && conn_count >= self.max_conn {
+            return Err(format!("over max connection limit {}", self.max_conn));
+        }
+        if self.max_conn_per_ip > 0 && !ip.is_empty() {
+            let ip_count = self.ip_conn_counts.get(ip).cloned().unwrap_or_default();
+            if ip_count >= self.max_conn_per_ip {
+                return Err(format!(
+                    "ip {} over max connection limit {}",
+                    ip, self.max_conn_per_ip
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add(&mut self, ip: Arc<String>) {
+        if let Some(v) = self.ip_conn_counts.get_mut(&ip) {
+            *v += 1;
+        } else {
+            self.ip_conn_counts.insert(ip, 1);
+        }
+    }
+
+    pub fn remove(&mut self, ip: &Arc<String>) {
+        if let Some(v) = self.ip_conn_counts.get_mut(ip) {
+            if *v > 1 {
+                *v -= 1;
+            } else {
+                self.ip_conn_counts.remove(ip);
+            }
+        }
+    }
+}
+
+struct NamespaceLimiterItem {
+    limiter: RateLimiter,
+    /// 最近一次访问序号,用于淘汰最久未访问的命名空间
+    access_seq: u64,
+}
+
+///
+/// 按命名空间计数的请求限流
+pub struct NamespaceRequestLimiter {
+    qps: i32,
+    max_key_size: usize,
+    limiters: Mutex<(u64, HashMap<String, NamespaceLimiterItem>)>,
+}
+
+impl NamespaceRequestLimiter {
+    pub fn new(qps: i32) -> Self {
+        Self {
+            qps,
+            max_key_size: MAX_LIMITER_KEY_SIZE,
+            limiters: Default::default(),
+        }
+    }
+
+    pub fn acquire(&self, namespace: &str) -> bool {
+        if self.qps <= 0 {
+            return true;
+        }
+        if let Ok(mut guard) = self.limiters.lock() {
+            let (seq, limiters) = &mut *guard;
+            *seq += 1;
+            if limiters.len() >= self.max_key_size && !limiters.contains_key(namespace) {
+                let oldest = limiters
+                    .iter()
+                    .min_by_key(|(_, v)| v.access_seq)
+                    .map(|(k, _)| k.to_owned());
+                if let Some(key) = oldest {
+                    limiters.remove(&key);
+                }
+            }
+            let item =
+                limiters
+                    .entry(namespace.to_owned())
+                    .or_insert_with(|| NamespaceLimiterItem {
+                        limiter: RateLimiter::load(1000, 0, now_millis_i64()),
+                        access_seq: 0,
+                    });
+            item.access_seq = *seq;
+            item.limiter.acquire(self.qps, self.qps as i64)
+        } else {
+            true
+        }
+    }
+}
+
+///
+/// 只解析请求中的命名空间与类型字段,用于限流
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestNamespaceProbe {
+    pub tenant: Option<String>,
+    pub namespace: Option<String>,
+    pub r#type: Option<String>,
+}
+
+impl RequestNamespaceProbe {
+    pub fn get_namespace(&self) -> &str {
+        let v = self
+            .namespace
+            .as_ref()
+            .or(self.tenant.as_ref())
+            .map(|e| e.as_str())
+            .unwrap_or_default();
+        if v.is_empty() {
+            crate::naming::DEFAULT_NAMESPACE
+        } else {
+            v
+        }
+    }
+
+    pub fn is_deregister(&self) -> bool {
+        self.r#type.as_deref() == Some("deRegisterInstance")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conn_quota() {
+        let mut quota = ConnQuota::new(3, 2);
+        let ip = get_conn_client_ip("1_192.168.1.10:52012");
+        assert_eq!(ip.as_str(), "192.168.1.10");
+        assert!(quota.check(&ip, 0).is_ok());
+        quota.add(ip.clone());
+        quota.add(ip.clone());
+        assert!(quota.check(&ip, 2).is_err());
+        let other_ip = get_conn_client_ip("1_[::1]:52012");
+        assert_eq!(other_ip.as_str(), "::1");
+        assert!(quota.check(&other_ip, 2).is_ok());
+        assert!(quota.check(&other_ip, 3).is_err());
+        quota.remove(&ip);
+        assert!(quota.check(&ip, 1).is_ok());
+        quota.remove(&ip);
+        assert!(quota.ip_conn_counts.is_empty());
+    }
+
+    #[test]
+    fn test_namespace_request_limiter() {
+        let limiter = NamespaceRequestLimiter::new(2);
+        assert!(limiter.acquire("public"));
+        assert!(limiter.acquire("public"));
+        assert!(!limiter.acquire("public"));
+        assert!(limiter.acquire("dev"));
+        let limiter = NamespaceRequestLimiter::new(0);
+        for _ in 0..10 {
+            assert!(limiter.acquire("public"));
+        }
+    }
+
+    #[test]
+    fn test_namespace_request_limiter_evict() {
+        let mut limiter = NamespaceRequestLimiter::new(1);
+        limiter.max_key_size = 2;
+        assert!(limiter.acquire("public"));
+        assert!(limiter.acquire("dev"));
+        assert!(!limiter.acquire("public"));
+        //淘汰最久未访问的dev,public保留限流计数
+        assert!(limiter.acquire("test"));
+        assert!(!limiter.acquire("public"));
+        let keys: Vec<String> = {
+            let guard = limiter.limiters.lock().unwrap();
+            let mut keys: Vec<String> = guard.1.keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys, vec!["public".to_owned(), "test".to_owned()]);
+    }
+
+    #[test]
+    fn test_request_namespace_probe() {
+        let probe: RequestNamespaceProbe =
+            serde_json::from_str(r#"{"tenant":"","dataId":"foo","group":"DEFAULT_GROUP"}"#)
+                .unwrap();
+        assert_eq!(probe.get_namespace(), "public");
+        let probe: RequestNamespaceProbe =
+            serde_json::from_str(r#"{"namespace":"dev","type":"deRegisterInstance"}"#).unwrap();
+        assert_eq!(probe.get_namespace(), "dev");
+        assert!(probe.is_deregister());
+    }
+}
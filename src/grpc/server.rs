@@ -120,6 +120,12 @@ impl request_server::Request for RequestServerImpl {
             Ok(result) => {
                 let result: anyhow::Result<BiStreamManageResult> = result;
                 match result {
+                    Ok(BiStreamManageResult::ClientInfo(client_info)) => {
+                        if let Some(client_info) = &client_info {
+                            request_meta.client_version = client_info.client_version.to_string();
+                        }
+                        request_meta.client_info = client_info;
+                    }
                     Ok(_) => {}
                     Err(err) => {
                         if !ignore_active_err {
@@ -1,6 +1,9 @@
 pub mod common;
 pub mod config;
 pub mod console;
+pub mod consul;
+pub mod dns;
+pub mod eureka;
 pub mod grpc;
 pub mod metrics;
 pub mod namespace;
@@ -8,9 +11,11 @@ pub mod naming;
 pub mod openapi;
 pub mod raft;
 pub mod starter;
+pub mod tls;
 pub mod user;
 pub mod utils;
 pub mod web_config;
+pub mod xds;
 
 pub mod health;
 pub mod transfer;
@@ -9,6 +9,7 @@ use async_raft_ext::{Config, Raft, RaftStorage};
 use rnacos::common::{get_app_version, AppSysConfig};
 use rnacos::config::core::{ConfigActor, ConfigCmd};
 use rnacos::console::middle::login_middle::CheckLogin;
+use rnacos::dns::server::start_dns_server;
 use rnacos::grpc::bistream_manage::BiStreamManage;
 use rnacos::grpc::handler::InvokerHandler;
 use rnacos::grpc::nacos_proto::bi_request_stream_server::BiRequestStreamServer;
@@ -16,12 +17,21 @@ use rnacos::grpc::nacos_proto::request_server::RequestServer;
 use rnacos::grpc::server::BiRequestStreamServerImpl;
 use rnacos::grpc::PayloadUtils;
 use rnacos::naming::core::{NamingCmd, NamingResult};
+use rnacos::raft::cluster::leader_transfer::transfer_leader_on_shutdown;
 use rnacos::raft::cluster::model::RouterRequest;
 use rnacos::raft::cluster::route::{ConfigRoute, RaftAddrRouter};
+use rnacos::raft::filestore::inspect::{
+    inspect_raft_index, inspect_raft_logs, inspect_raft_snapshot,
+};
+use rnacos::raft::filestore::verify::verify_data;
 use rnacos::raft::network::core::RaftRouter;
 use rnacos::raft::network::factory::{RaftClusterRequestSender, RaftConnectionFactory};
 use rnacos::raft::store::ClientRequest;
 use rnacos::starter::{build_share_data, config_factory};
+use rnacos::tls::grpc::tls_incoming;
+use rnacos::tls::{TlsConfig, TlsManage};
+use rnacos::xds::manage::XdsManage;
+use rnacos::xds::server::start_xds_server;
 use rnacos::{grpc::server::RequestServerImpl, naming::core::NamingActor, openapi};
 use sled::Db;
 use std::collections::{BTreeMap, HashSet};
@@ -31,20 +41,23 @@ use std::sync::Arc;
 use std::time::Duration;
 use tonic::transport::Server;
 
+use crate::cli::{Cli, Commands};
+use actix_web::dev::ServerHandle;
 use actix_web::{middleware, HttpServer};
 use clap::Parser;
 use env_logger::TimestampPrecision;
 use env_logger_timezone_fmt::{TimeZoneFormat, TimeZoneFormatEnv};
 use mimalloc::MiMalloc;
-use crate::cli::{Cli, Commands};
 use rnacos::common::appdata::AppShareData;
 use rnacos::openapi::middle::auth_middle::ApiCheckAuth;
 use rnacos::raft::NacosRaft;
+use rnacos::transfer::data_to_raft::data_to_raft;
 use rnacos::transfer::data_to_sqlite::data_to_sqlite;
 use rnacos::transfer::mysql_to_data::mysql_to_data;
 use rnacos::transfer::openapi_to_data::openapi_to_data;
 use rnacos::transfer::sqlite_to_data::sqlite_to_data;
 use rnacos::web_config::{app_config, console_config};
+use rustls::ServerConfig;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
@@ -63,7 +76,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .format(move |buf, record| TimeZoneFormat::new(buf, &timezone_fmt).write(record))
         .init();
     if let Some(cmd) = cli_opt.command {
-        return run_subcommand(cmd).await;
+        return run_subcommand(cmd, sys_config).await;
     }
     // 这里不使用log:info避免日志等级高于info时不打印
     println!("version:{}, RUST_LOG:{}", get_app_version(), &rust_log);
@@ -74,6 +87,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let grpc_addr = sys_config.get_grpc_addr();
     log::info!("http server addr:{}", &http_addr);
     log::info!("grpc server addr:{}", &grpc_addr);
+    let tls_manage = if let Some(tls_config) = TlsConfig::from_sys_config(&sys_config) {
+        let tls_manage = Arc::new(TlsManage::new(tls_config)?);
+        tls_manage.clone().start_reload_task();
+        log::info!("tls enabled");
+        Some(tls_manage)
+    } else {
+        None
+    };
 
     let mut invoker = InvokerHandler::new(app_data.clone());
     invoker.add_config_handler(&app_data);
@@ -81,21 +102,42 @@ async fn main() -> Result<(), Box<dyn Error>> {
     invoker.add_raft_handler(&app_data);
 
     let grpc_app_data = app_data.clone();
+    let grpc_tls_config = match &tls_manage {
+        Some(tls_manage) => Some(tls_manage.build_grpc_server_config()?),
+        None => None,
+    };
 
     tokio::spawn(async move {
-        let addr = grpc_addr.parse().unwrap();
         let request_server = RequestServerImpl::new(grpc_app_data.clone(), invoker);
         let bi_request_stream_server = BiRequestStreamServerImpl::new(grpc_app_data.clone());
-        Server::builder()
+        let router = Server::builder()
             .add_service(RequestServer::new(request_server))
-            .add_service(BiRequestStreamServer::new(bi_request_stream_server))
-            .serve(addr)
-            .await
-            .unwrap();
+            .add_service(BiRequestStreamServer::new(bi_request_stream_server));
+        if let Some(tls_config) = grpc_tls_config {
+            let incoming = tls_incoming(&grpc_addr, tls_config).await.unwrap();
+            router.serve_with_incoming(incoming).await.unwrap();
+        } else {
+            let addr = grpc_addr.parse().unwrap();
+            router.serve(addr).await.unwrap();
+        }
     });
 
+    if sys_config.dns_enable {
+        start_dns_server(app_data.naming_addr.clone(), sys_config.clone()).await?;
+    }
+
+    let xds_manage: Option<actix::Addr<XdsManage>> = factory_data.get_actor();
+    if let Some(xds_manage) = xds_manage {
+        start_xds_server(xds_manage, sys_config.clone()).await?;
+    }
+
     if sys_config.http_console_port > 0 {
         let app_console_data = app_data.clone();
+        //控制台不校验客户端证书
+        let console_tls_config = match &tls_manage {
+            Some(tls_manage) => Some(tls_manage.build_server_config(false)?),
+            None => None,
+        };
 
         std::thread::spawn(move || {
             actix_rt::System::with_tokio_rt(|| {
@@ -104,10 +146,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     .build()
                     .unwrap()
             })
-            .block_on(run_console_web(app_console_data));
+            .block_on(run_console_web(app_console_data, console_tls_config));
         });
     }
 
+    let shutdown_app_data = app_data.clone();
     let mut server = HttpServer::new(move || {
         let app_data = app_data.clone();
         let config_addr = app_data.config_addr.clone();
@@ -129,10 +172,56 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
     // 这里不使用log:info避免日志等级高于info时不打印
     println!("rnacos started");
-    server.bind(http_addr)?.run().await?;
+    if let Some(tls_manage) = tls_manage {
+        server = server.bind_rustls_021(http_addr, tls_manage.build_server_config(true)?)?;
+    } else {
+        server = server.bind(http_addr)?;
+    }
+    let server = server.disable_signals().run();
+    tokio::spawn(wait_shutdown_signal(server.handle(), shutdown_app_data));
+    server.await?;
     Ok(())
 }
 
+///
+/// 收到退出信号后,leader节点先转移leader再停止服务
+async fn wait_shutdown_signal(server_handle: ServerHandle, app_data: Arc<AppShareData>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = sigterm.recv() => {},
+                }
+            }
+            Err(_) => {
+                tokio::signal::ctrl_c().await.ok();
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.ok();
+    log::info!("receive shutdown signal");
+    //leader转移与服务停止共用一个退出截止时间
+    let deadline = tokio::time::Instant::now()
+        + Duration::from_secs(app_data.sys_config.shutdown_timeout_second);
+    if tokio::time::timeout_at(deadline, transfer_leader_on_shutdown(&app_data))
+        .await
+        .is_err()
+    {
+        log::warn!("shutdown leader transfer timeout");
+    }
+    if tokio::time::timeout_at(deadline, server_handle.stop(true))
+        .await
+        .is_err()
+    {
+        log::warn!("graceful shutdown timeout, force stop server");
+        server_handle.stop(false).await;
+    }
+}
+
 fn init_env(env_path: &str) {
     //let env_path = std::env::var("RNACOS_ENV_FILE").unwrap_or_default();
     if env_path.is_empty() {
@@ -142,7 +231,10 @@ fn init_env(env_path: &str) {
     }
 }
 
-async fn run_subcommand(commands: Commands) -> Result<(), Box<dyn Error>> {
+async fn run_subcommand(
+    commands: Commands,
+    sys_config: Arc<AppSysConfig>,
+) -> Result<(), Box<dyn Error>> {
     match commands {
         Commands::DataToSqlite { file, out } => {
             log::info!("middle data to sqlite, from:{file} to:{out}");
@@ -165,15 +257,45 @@ async fn run_subcommand(commands: Commands) -> Result<(), Box<dyn Error>> {
             log::info!("openapi to middle data, from:{host} to:{out}");
             openapi_to_data(&host, &username, &password, &out).await?;
         }
+        Commands::DataToRaft {
+            node_id,
+            node_addr,
+            dir,
+            file,
+        } => {
+            let node_id = node_id.unwrap_or(sys_config.raft_node_id);
+            let node_addr = node_addr.unwrap_or(sys_config.raft_node_addr.clone());
+            let dir = dir.unwrap_or(sys_config.local_db_dir.clone());
+            log::info!("middle data to raft, from:{file} to:{dir}, node_id:{node_id}, node_addr:{node_addr}");
+            data_to_raft(&file, &dir, node_id, &node_addr).await?;
+        }
+        Commands::VerifyData { dir } => {
+            log::info!("verify raft data, dir:{dir}");
+            let report = verify_data(&dir).await?;
+            println!("{}", report);
+            if !report.is_ok() {
+                return Err("raft data is corrupt".into());
+            }
+        }
+        Commands::RaftIndex { dir } => {
+            let info = inspect_raft_index(&dir).await?;
+            println!("{}", serde_json::to_string_pretty(&info)?);
+        }
+        Commands::RaftLogs { start, limit, dir } => {
+            inspect_raft_logs(&dir, start, limit).await?;
+        }
+        Commands::RaftSnapshot { id, limit, dir } => {
+            inspect_raft_snapshot(&dir, id, limit).await?;
+        }
     }
     Ok(())
 }
 
-async fn run_console_web(source_app_data: Arc<AppShareData>) {
+async fn run_console_web(source_app_data: Arc<AppShareData>, tls_config: Option<ServerConfig>) {
     let http_console_addr = source_app_data.sys_config.get_http_console_addr();
     log::info!("new console server http addr:{}", &http_console_addr);
     let app_data = Data::new(source_app_data.clone());
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         let source_app_data = source_app_data.clone();
         let config_addr = app_data.config_addr.clone();
         let naming_addr = app_data.naming_addr.clone();
@@ -189,10 +311,11 @@ async fn run_console_web(source_app_data: Arc<AppShareData>) {
             .wrap(middleware::Compress::default())
             .configure(console_config)
     })
-    .workers(2)
-    .bind(http_console_addr)
-    .unwrap()
-    .run()
-    .await
-    .ok();
+    .workers(2);
+    let server = if let Some(tls_config) = tls_config {
+        server.bind_rustls_021(http_console_addr, tls_config)
+    } else {
+        server.bind(http_console_addr)
+    };
+    server.unwrap().run().await.ok();
 }
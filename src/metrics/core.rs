@@ -13,6 +13,7 @@ use crate::metrics::timeline::core::MetricsTimelineManager;
 use crate::metrics::timeline::model::{MetricsSnapshot, TimelineGroupType};
 use crate::naming::core::NamingActor;
 use crate::now_millis;
+use crate::raft::cluster::node_status::RaftMetricsSource;
 use actix::prelude::*;
 use bean_factory::{bean, BeanFactory, FactoryData, Inject};
 use bytes::BytesMut;
@@ -31,6 +32,7 @@ pub struct MetricsManager {
     naming_actor: Option<Addr<NamingActor>>,
     config_actor: Option<Addr<ConfigActor>>,
     bi_stream_manage: Option<Addr<BiStreamManage>>,
+    raft_metrics_source: Option<RaftMetricsSource>,
     metrics_timeline_manager: MetricsTimelineManager,
     system: System,
     current_process_id: u32,
@@ -59,6 +61,7 @@ impl MetricsManager {
             naming_actor: None,
             config_actor: None,
             bi_stream_manage: None,
+            raft_metrics_source: None,
             metrics_timeline_manager: MetricsTimelineManager::new(),
             system,
             current_process_id,
@@ -139,6 +142,16 @@ impl MetricsManager {
         Ok(list)
     }
 
+    async fn do_peek_raft_metrics(
+        raft_metrics_source: Option<RaftMetricsSource>,
+    ) -> Vec<MetricsItem> {
+        if let Some(raft_metrics_source) = raft_metrics_source {
+            raft_metrics_source.collect().await
+        } else {
+            vec![]
+        }
+    }
+
     fn update_peek_metrics(&mut self, r: anyhow::Result<Vec<MetricsItem>>) {
         if let Ok(list) = r {
             for item in list {
@@ -236,15 +249,24 @@ impl MetricsManager {
         let naming_actor = self.naming_actor.clone();
         let config_actor = self.config_actor.clone();
         let bi_stream_manage = self.bi_stream_manage.clone();
-        async move { Self::do_peek_metrics(naming_actor, config_actor, bi_stream_manage).await }
-            .into_actor(self)
-            .map(|r, act, ctx| {
-                //Self::log_metrics(&r);
-                act.update_peek_metrics(r);
-                act.after_peek_metrics();
-                act.hb(ctx);
-            })
-            .spawn(ctx);
+        let raft_metrics_source = self.raft_metrics_source.clone();
+        async move {
+            let mut list = Self::do_peek_raft_metrics(raft_metrics_source).await;
+            Self::do_peek_metrics(naming_actor, config_actor, bi_stream_manage)
+                .await
+                .map(|mut v| {
+                    list.append(&mut v);
+                    list
+                })
+        }
+        .into_actor(self)
+        .map(|r, act, ctx| {
+            //Self::log_metrics(&r);
+            act.update_peek_metrics(r);
+            act.after_peek_metrics();
+            act.hb(ctx);
+        })
+        .spawn(ctx);
     }
 
     fn build_snapshot(&self, now_ms: u64) -> MetricsSnapshot {
@@ -307,6 +329,14 @@ impl Inject for MetricsManager {
         self.naming_actor = factory_data.get_actor();
         self.config_actor = factory_data.get_actor();
         self.bi_stream_manage = factory_data.get_actor();
+        if let (Some(raft), Some(raft_store), Some(cluster_sender)) = (
+            factory_data.get_bean(),
+            factory_data.get_bean(),
+            factory_data.get_bean(),
+        ) {
+            self.raft_metrics_source =
+                Some(RaftMetricsSource::new(raft, raft_store, cluster_sender));
+        }
         self.metrics_timeline_manager
             .set_least_interval(self.app_sys_config.metrics_collect_interval_second);
         if self.app_sys_config.metrics_enable {
@@ -45,18 +45,35 @@ pub enum MetricsKey {
     NamingIndexTenantSize,
     NamingIndexGroupSize,
     NamingIndexServiceSize,
+    NamingInstanceRegisterCount,
+    NamingInstanceDeregisterCount,
+    NamingInstanceUnhealthyCount,
     //grpc
     GrpcConnSize,
     GrpcConnActiveTimeoutSetItemSize,
     GrpcConnResponseTimeoutSetItemSize,
+    GrpcConnLimitRejectCount,
     //grpc request
     GrpcRequestHandleRtHistogram,
     GrpcRequestHandleRtSummary,
     GrpcRequestTotalCount,
+    GrpcRequestLimitRejectCount,
     //http api request
     HttpRequestHandleRtHistogram,
     HttpRequestHandleRtSummary,
     HttpRequestTotalCount,
+    //backup
+    BackupLastStatus,
+    BackupSuccessCount,
+    BackupFailureCount,
+    //raft
+    RaftCurrentTerm,
+    RaftLastLogIndex,
+    RaftLastApplied,
+    RaftSnapshotIndex,
+    RaftIsLeader,
+    RaftReplicationLag,
+    RaftLeaderReachable,
 }
 
 lazy_static! {
@@ -98,18 +115,35 @@ lazy_static! {
         MetricsKey::NamingIndexTenantSize,
         MetricsKey::NamingIndexGroupSize,
         MetricsKey::NamingIndexServiceSize,
+        MetricsKey::NamingInstanceRegisterCount,
+        MetricsKey::NamingInstanceDeregisterCount,
+        MetricsKey::NamingInstanceUnhealthyCount,
         //grpc
         MetricsKey::GrpcConnSize,
         MetricsKey::GrpcConnActiveTimeoutSetItemSize,
         MetricsKey::GrpcConnResponseTimeoutSetItemSize,
+        MetricsKey::GrpcConnLimitRejectCount,
         //grpc request
         MetricsKey::GrpcRequestHandleRtHistogram,
         MetricsKey::GrpcRequestHandleRtSummary,
         MetricsKey::GrpcRequestTotalCount,
+        MetricsKey::GrpcRequestLimitRejectCount,
         //http request
         MetricsKey::HttpRequestHandleRtHistogram,
         MetricsKey::HttpRequestHandleRtSummary,
         MetricsKey::HttpRequestTotalCount,
+        //backup
+        MetricsKey::BackupLastStatus,
+        MetricsKey::BackupSuccessCount,
+        MetricsKey::BackupFailureCount,
+        //raft
+        MetricsKey::RaftCurrentTerm,
+        MetricsKey::RaftLastLogIndex,
+        MetricsKey::RaftLastApplied,
+        MetricsKey::RaftSnapshotIndex,
+        MetricsKey::RaftIsLeader,
+        MetricsKey::RaftReplicationLag,
+        MetricsKey::RaftLeaderReachable,
     ];
 
     pub static ref HISTOGRAM_SUMMARY_MAP: HashMap<MetricsKey,MetricsKey> = MetricsKey::build_histogram_summary_map();
@@ -160,6 +194,9 @@ impl MetricsKey {
             MetricsKey::NamingIndexTenantSize => "naming_index_tenant_size",
             MetricsKey::NamingIndexGroupSize => "naming_index_group_size",
             MetricsKey::NamingIndexServiceSize => "naming_index_service_size",
+            MetricsKey::NamingInstanceRegisterCount => "naming_instance_register_count",
+            MetricsKey::NamingInstanceDeregisterCount => "naming_instance_deregister_count",
+            MetricsKey::NamingInstanceUnhealthyCount => "naming_instance_unhealthy_count",
             MetricsKey::GrpcConnSize => "grpc_conn_size",
             MetricsKey::GrpcConnActiveTimeoutSetItemSize => {
                 "grpc_conn_active_timeout_set_item_size"
@@ -170,9 +207,21 @@ impl MetricsKey {
             MetricsKey::GrpcRequestHandleRtHistogram => "grpc_request_handle_rt_histogram",
             MetricsKey::GrpcRequestHandleRtSummary => "grpc_request_handle_rt_summary",
             MetricsKey::GrpcRequestTotalCount => "grpc_request_total_count",
+            MetricsKey::GrpcConnLimitRejectCount => "grpc_conn_limit_reject_count",
+            MetricsKey::GrpcRequestLimitRejectCount => "grpc_request_limit_reject_count",
             MetricsKey::HttpRequestHandleRtHistogram => "http_request_handle_rt_histogram",
             MetricsKey::HttpRequestHandleRtSummary => "http_request_handle_rt_summary",
             MetricsKey::HttpRequestTotalCount => "http_request_total_count",
+            MetricsKey::BackupLastStatus => "backup_last_status",
+            MetricsKey::BackupSuccessCount => "backup_success_count",
+            MetricsKey::BackupFailureCount => "backup_failure_count",
+            MetricsKey::RaftCurrentTerm => "raft_current_term",
+            MetricsKey::RaftLastLogIndex => "raft_last_log_index",
+            MetricsKey::RaftLastApplied => "raft_last_applied",
+            MetricsKey::RaftSnapshotIndex => "raft_snapshot_index",
+            MetricsKey::RaftIsLeader => "raft_is_leader",
+            MetricsKey::RaftReplicationLag => "raft_replication_lag",
+            MetricsKey::RaftLeaderReachable => "raft_leader_reachable",
         }
     }
 
@@ -234,6 +283,9 @@ impl MetricsKey {
             MetricsKey::NamingIndexTenantSize => "Naming index tenant size",
             MetricsKey::NamingIndexGroupSize => "Naming index group size",
             MetricsKey::NamingIndexServiceSize => "Naming index service size",
+            MetricsKey::NamingInstanceRegisterCount => "Naming instance register event count",
+            MetricsKey::NamingInstanceDeregisterCount => "Naming instance deregister event count",
+            MetricsKey::NamingInstanceUnhealthyCount => "Naming instance unhealthy event count",
             MetricsKey::GrpcConnSize => "Grpc conn size",
             MetricsKey::GrpcConnActiveTimeoutSetItemSize => {
                 "Grpc conn active timeout set item size"
@@ -246,11 +298,27 @@ impl MetricsKey {
             }
             MetricsKey::GrpcRequestHandleRtSummary => "Grpc request handle rt summary, unit is ms",
             MetricsKey::GrpcRequestTotalCount => "Grpc request total count",
+            MetricsKey::GrpcConnLimitRejectCount => "Grpc conn rejected by limit count",
+            MetricsKey::GrpcRequestLimitRejectCount => "Grpc request rejected by limit count",
             MetricsKey::HttpRequestHandleRtHistogram => {
                 "Http request handle rt histogram,unit is ms"
             }
             MetricsKey::HttpRequestHandleRtSummary => "Http request handle rt summary,unit is ms",
             MetricsKey::HttpRequestTotalCount => "Http request total count",
+            MetricsKey::BackupLastStatus => {
+                "Last scheduled backup status,1 is success,0 is failure"
+            }
+            MetricsKey::BackupSuccessCount => "Scheduled backup success count",
+            MetricsKey::BackupFailureCount => "Scheduled backup failure count",
+            MetricsKey::RaftCurrentTerm => "Raft current term",
+            MetricsKey::RaftLastLogIndex => "Raft last log index",
+            MetricsKey::RaftLastApplied => "Raft last applied log index",
+            MetricsKey::RaftSnapshotIndex => "Raft last snapshot log index",
+            MetricsKey::RaftIsLeader => "Raft node is leader,1 is leader,0 is not",
+            MetricsKey::RaftLeaderReachable => {
+                "Raft leader log index is reachable,1 is reachable,0 is not"
+            }
+            MetricsKey::RaftReplicationLag => "Raft log count behind the leader",
             //default describe
             //_ => "Some help info",
         }
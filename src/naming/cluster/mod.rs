@@ -14,6 +14,7 @@ use self::{
     node_manage::{NodeManageRequest, NodeManageResponse},
 };
 use crate::common::constant::GRPC_HEAD_KEY_CLUSTER_ID;
+use crate::grpc::bistream_manage::BiStreamManageCmd;
 use crate::metrics::model::{MetricsRequest, MetricsResponse};
 use crate::naming::cluster::model::SnapshotForSend;
 use crate::naming::model::{DistroData, Instance};
@@ -208,6 +209,38 @@ pub async fn handle_naming_route(
                 }
             }
         }
+        NamingRouteRequest::UpdateMetadataBatch(batch) => {
+            let res = app
+                .naming_addr
+                .send(NamingCmd::UpdateMetadataBatch(batch))
+                .await??;
+            if let NamingResult::UpdatedInstanceIds(updated) = res {
+                return Ok(NamingRouterResponse::UpdatedInstanceIds(updated));
+            }
+        }
+        NamingRouteRequest::DrainInstance(param) => {
+            let res = app
+                .naming_addr
+                .send(NamingCmd::DrainInstance(param))
+                .await??;
+            if let NamingResult::UpdatedInstanceIds(updated) = res {
+                return Ok(NamingRouterResponse::UpdatedInstanceIds(updated));
+            }
+        }
+        NamingRouteRequest::SyncConnCount {
+            node_id,
+            conn_count,
+            sdk_addr,
+        } => {
+            app.naming_inner_node_manage
+                .do_send(NodeManageRequest::UpdateConnCount(
+                    node_id, conn_count, sdk_addr,
+                ));
+        }
+        NamingRouteRequest::GrpcConnRebalance => {
+            app.bi_stream_manage
+                .do_send(BiStreamManageCmd::Rebalance { manual: true });
+        }
     };
     Ok(NamingRouterResponse::None)
 }
@@ -1,5 +1,8 @@
 use crate::metrics::timeline::model::{TimelineQueryParam, TimelineQueryResponse};
-use crate::naming::model::{Instance, InstanceKey, InstanceUpdateTag, ServiceDetailDto};
+use crate::naming::model::{
+    Instance, InstanceDrainParam, InstanceKey, InstanceMetadataBatch, InstanceUpdateTag,
+    ServiceDetailDto,
+};
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -47,6 +50,16 @@ pub enum NamingRouteRequest {
     MetricsTimelineQuery(TimelineQueryParam),
     SyncDistroClientInstances(HashMap<Arc<String>, HashSet<InstanceKey>>),
     QueryDistroInstanceSnapshot(Vec<InstanceKey>),
+    UpdateMetadataBatch(InstanceMetadataBatch),
+    DrainInstance(InstanceDrainParam),
+    SyncConnCount {
+        node_id: u64,
+        conn_count: usize,
+        /// 节点的sdk地址(ip:port),旧版本节点没有该字段
+        #[serde(default)]
+        sdk_addr: Option<Arc<String>>,
+    },
+    GrpcConnRebalance,
 }
 
 impl NamingRouteRequest {
@@ -65,6 +78,10 @@ impl NamingRouteRequest {
             NamingRouteRequest::MetricsTimelineQuery(_) => "MetricsTimelineQuery",
             NamingRouteRequest::SyncDistroClientInstances(_) => "SyncDistroClientInstances",
             NamingRouteRequest::QueryDistroInstanceSnapshot(_) => "QueryDistroInstanceSnapshot",
+            NamingRouteRequest::UpdateMetadataBatch(_) => "UpdateMetadataBatch",
+            NamingRouteRequest::DrainInstance(_) => "DrainInstance",
+            NamingRouteRequest::SyncConnCount { .. } => "SyncConnCount",
+            NamingRouteRequest::GrpcConnRebalance => "GrpcConnRebalance",
         }
     }
 }
@@ -73,6 +90,7 @@ impl NamingRouteRequest {
 pub enum NamingRouterResponse {
     None,
     MetricsTimeLineResponse(TimelineQueryResponse),
+    UpdatedInstanceIds(Vec<String>),
 }
 
 #[derive(Message, Debug, Clone)]
@@ -100,6 +118,15 @@ impl ProcessRange {
         Self { index, len }
     }
 
+    ///
+    /// 不负责任何服务的管理范围,用于不参与划分的learner节点
+    pub fn none() -> Self {
+        Self {
+            index: usize::MAX,
+            len: usize::MAX,
+        }
+    }
+
     pub fn is_range(&self, hash_value: usize) -> bool {
         self.len < 2 || (hash_value % self.len) == self.index
     }
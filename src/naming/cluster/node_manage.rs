@@ -14,6 +14,7 @@ use crate::{
 };
 use actix::prelude::*;
 use bean_factory::{bean, Inject};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::{
     collections::{hash_map::DefaultHasher, BTreeMap, HashSet},
@@ -22,7 +23,7 @@ use std::{
     time::Duration,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NodeStatus {
     Valid,
     Unvalid,
@@ -41,6 +42,16 @@ pub struct ClusterNode {
     pub is_local: bool,
     pub addr: Arc<String>,
     pub status: NodeStatus,
+    /// 节点的sdk grpc连接数
+    pub conn_count: usize,
+    /// 节点的sdk地址(ip:port)
+    pub sdk_addr: Option<Arc<String>>,
+    /// 是否为raft learner节点
+    pub learner: bool,
+    /// 是否参与服务管理范围划分
+    pub distro: bool,
+    /// 最近一次收到节点心跳的时间
+    pub last_active_time: u64,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -53,6 +64,10 @@ pub struct ClusterInnerNode {
     pub last_active_time: u64,
     pub sync_sender: Option<Addr<ClusteSyncSender>>,
     pub client_set: HashSet<Arc<String>>,
+    pub conn_count: usize,
+    pub sdk_addr: Option<Arc<String>>,
+    pub learner: bool,
+    pub distro: bool,
 }
 
 impl ClusterInnerNode {
@@ -69,10 +84,34 @@ impl From<ClusterInnerNode> for ClusterNode {
             is_local: value.is_local,
             addr: value.addr,
             status: value.status,
+            conn_count: value.conn_count,
+            sdk_addr: value.sdk_addr,
+            learner: value.learner,
+            distro: value.distro,
+            last_active_time: value.last_active_time,
         }
     }
 }
 
+///
+/// 根据raft节点地址与投票成员构建naming集群节点,不在投票成员中的节点为learner;
+/// 投票成员未知时全部按投票节点处理
+pub fn build_naming_nodes(
+    node_addrs: &HashMap<u64, Arc<String>>,
+    voters: &HashSet<u64>,
+) -> Vec<(u64, Arc<String>, bool)> {
+    node_addrs
+        .iter()
+        .map(|(id, addr)| {
+            (
+                *id,
+                addr.clone(),
+                !voters.is_empty() && !voters.contains(id),
+            )
+        })
+        .collect()
+}
+
 #[bean(inject)]
 pub struct InnerNodeManage {
     local_id: u64,
@@ -83,10 +122,14 @@ pub struct InnerNodeManage {
     current_range: ProcessRange,
     history_ranges: Vec<(ProcessRange, u64)>,
     last_send_distor_data_time: i32,
+    local_conn_count: usize,
+    local_sdk_addr: Arc<String>,
+    /// learner节点是否参与服务管理范围划分,集群各节点需要配置一致
+    learner_distro: bool,
 }
 
 impl InnerNodeManage {
-    pub fn new(local_id: u64) -> Self {
+    pub fn new(local_id: u64, learner_distro: bool, local_sdk_addr: Arc<String>) -> Self {
         Self {
             local_id,
             cluster_sender: None,
@@ -96,10 +139,13 @@ impl InnerNodeManage {
             current_range: ProcessRange { index: 0, len: 1 },
             history_ranges: Vec::new(),
             last_send_distor_data_time: 0,
+            local_conn_count: 0,
+            local_sdk_addr,
+            learner_distro,
         }
     }
 
-    fn update_nodes(&mut self, nodes: Vec<(u64, Arc<String>)>, ctx: &mut Context<Self>) {
+    fn update_nodes(&mut self, nodes: Vec<(u64, Arc<String>, bool)>, ctx: &mut Context<Self>) {
         if self.cluster_sender.is_none() {
             log::warn!("InnerNodeManage cluster_sender is none");
             return;
@@ -116,12 +162,18 @@ impl InnerNodeManage {
             self.all_nodes.remove(&key);
         }
         let now = now_millis();
-        for (key, addr) in nodes {
+        for (key, addr, learner) in nodes {
+            let distro = !learner || self.learner_distro;
             if let Some(node) = self.all_nodes.get_mut(&key) {
                 if let Some(sender) = node.sync_sender.as_ref() {
                     sender.do_send(SyncSenderSetCmd::UpdateTargetAddr(addr.clone()));
                 };
                 node.addr = addr;
+                if node.distro != distro {
+                    is_change = true;
+                }
+                node.learner = learner;
+                node.distro = distro;
             } else {
                 let is_local = self.local_id == key;
                 let sync_sender = if is_local {
@@ -147,6 +199,14 @@ impl InnerNodeManage {
                     status: NodeStatus::Valid,
                     last_active_time: now,
                     client_set: Default::default(),
+                    conn_count: 0,
+                    sdk_addr: if is_local {
+                        Some(self.local_sdk_addr.clone())
+                    } else {
+                        None
+                    },
+                    learner,
+                    distro,
                 };
                 self.all_nodes.insert(key, node);
             }
@@ -196,28 +256,47 @@ impl InnerNodeManage {
     }
 
     fn update_nodes_index(&mut self) {
-        for (i, value) in self.all_nodes.values_mut().enumerate() {
-            value.index = i as u64;
+        //只有参与服务管理范围划分的节点有序号
+        let mut i = 0;
+        for value in self.all_nodes.values_mut() {
+            value.index = i;
+            if value.distro {
+                i += 1;
+            }
         }
     }
 
     fn get_this_node(&self) -> ClusterInnerNode {
-        if let Some(node) = self.all_nodes.get(&self.local_id) {
+        let mut node = if let Some(node) = self.all_nodes.get(&self.local_id) {
             node.to_owned()
         } else {
             ClusterInnerNode {
                 id: self.local_id,
                 is_local: true,
+                distro: true,
                 ..Default::default()
             }
-        }
+        };
+        node.conn_count = self.local_conn_count;
+        node.sdk_addr = Some(self.local_sdk_addr.clone());
+        node.last_active_time = now_millis();
+        node
     }
 
     fn get_all_nodes(&self) -> Vec<ClusterNode> {
         if self.all_nodes.is_empty() {
             vec![self.get_this_node().into()]
         } else {
-            self.all_nodes.values().cloned().map(|e| e.into()).collect()
+            self.all_nodes
+                .values()
+                .map(|e| {
+                    if e.is_local {
+                        self.get_this_node().into()
+                    } else {
+                        e.to_owned().into()
+                    }
+                })
+                .collect()
         }
     }
 
@@ -247,9 +326,16 @@ impl InnerNodeManage {
         if self.all_nodes.is_empty() {
             ProcessRange::new(0, 1)
         } else {
+            let this_node = self.get_this_node();
+            if !this_node.distro {
+                return ProcessRange::none();
+            }
             ProcessRange::new(
-                self.get_this_node().index as usize,
-                self.all_nodes.iter().filter(|(_, v)| v.is_valid()).count(),
+                this_node.index as usize,
+                self.all_nodes
+                    .iter()
+                    .filter(|(_, v)| v.distro && v.is_valid())
+                    .count(),
             )
         }
     }
@@ -385,6 +471,12 @@ impl InnerNodeManage {
     fn ping_other(&mut self) {
         let req = SyncSenderRequest(NamingRouteRequest::Ping(self.local_id));
         self.send_to_other_node(req, false);
+        let req = SyncSenderRequest(NamingRouteRequest::SyncConnCount {
+            node_id: self.local_id,
+            conn_count: self.local_conn_count,
+            sdk_addr: Some(self.local_sdk_addr.clone()),
+        });
+        self.send_to_other_node(req, false);
     }
 
     fn send_distort_data(&mut self, ctx: &mut Context<Self>) {
@@ -460,6 +552,18 @@ impl InnerNodeManage {
         }
     }
 
+    fn update_conn_count(
+        &mut self,
+        node_id: u64,
+        conn_count: usize,
+        sdk_addr: Option<Arc<String>>,
+    ) {
+        if let Some(node) = self.all_nodes.get_mut(&node_id) {
+            node.conn_count = conn_count;
+            node.sdk_addr = sdk_addr;
+        }
+    }
+
     fn node_add_client(&mut self, node_id: u64, client_id: Arc<String>) {
         if client_id.is_empty() {
             return;
@@ -514,7 +618,8 @@ impl Inject for InnerNodeManage {
 #[derive(Message, Debug)]
 #[rtype(result = "anyhow::Result<NodeManageResponse>")]
 pub enum NodeManageRequest {
-    UpdateNodes(Vec<(u64, Arc<String>)>),
+    /// (节点id,地址,是否为learner)
+    UpdateNodes(Vec<(u64, Arc<String>, bool)>),
     GetThisNode,
     GetAllNodes,
     GetNode(u64),
@@ -528,6 +633,8 @@ pub enum NodeManageRequest {
     QueryOwnerRange(ProcessRange),
     SendSnapshot(u64, SnapshotForSend),
     QueryDiffClientInstances(u64, Vec<InstanceKey>),
+    UpdateLocalConnCount(usize),
+    UpdateConnCount(u64, usize, Option<Arc<String>>),
 }
 
 pub enum NodeManageResponse {
@@ -599,6 +706,15 @@ impl Handler<NodeManageRequest> for InnerNodeManage {
                 self.send_diff_instance_to_node(node_id, diff_instances);
                 Ok(NodeManageResponse::None)
             }
+            NodeManageRequest::UpdateLocalConnCount(conn_count) => {
+                self.local_conn_count = conn_count;
+                Ok(NodeManageResponse::None)
+            }
+            NodeManageRequest::UpdateConnCount(node_id, conn_count, sdk_addr) => {
+                self.active_node(node_id);
+                self.update_conn_count(node_id, conn_count, sdk_addr);
+                Ok(NodeManageResponse::None)
+            }
         }
     }
 }
@@ -617,7 +733,13 @@ impl NodeManage {
         let mut hasher = DefaultHasher::new();
         v.hash(&mut hasher);
         let hash_value: usize = hasher.finish() as usize;
-        let nodes = self.get_all_valid_nodes().await.unwrap_or_default();
+        let nodes: Vec<ClusterNode> = self
+            .get_all_valid_nodes()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|e| e.distro)
+            .collect();
         if nodes.is_empty() {
             NamingRouteAddr::Local(0)
         } else {
@@ -648,6 +770,17 @@ impl NodeManage {
         }
     }
 
+    pub async fn get_all_nodes(&self) -> anyhow::Result<Vec<ClusterNode>> {
+        let resp: NodeManageResponse = self
+            .inner_node_manage
+            .send(NodeManageRequest::GetAllNodes)
+            .await??;
+        match resp {
+            NodeManageResponse::AllNodes(nodes) => Ok(nodes),
+            _ => Err(anyhow::anyhow!("get_all_nodes error NodeManageResponse!")),
+        }
+    }
+
     pub async fn get_all_valid_nodes(&self) -> anyhow::Result<Vec<ClusterNode>> {
         let resp: NodeManageResponse = self
             .inner_node_manage
@@ -677,3 +810,31 @@ impl NodeManage {
             .do_send(NodeManageRequest::ActiveNode(node_id))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_naming_nodes() {
+        let mut node_addrs = HashMap::new();
+        node_addrs.insert(1, Arc::new("127.0.0.1:9848".to_owned()));
+        node_addrs.insert(2, Arc::new("127.0.0.1:9849".to_owned()));
+        node_addrs.insert(3, Arc::new("127.0.0.1:9850".to_owned()));
+        let voters: HashSet<u64> = vec![1, 2].into_iter().collect();
+        let mut nodes = build_naming_nodes(&node_addrs, &voters);
+        nodes.sort_by_key(|e| e.0);
+        let learners: Vec<bool> = nodes.iter().map(|e| e.2).collect();
+        assert_eq!(learners, vec![false, false, true]);
+        let nodes = build_naming_nodes(&node_addrs, &HashSet::new());
+        assert!(nodes.iter().all(|e| !e.2));
+    }
+
+    #[test]
+    fn test_process_range_none() {
+        let range = ProcessRange::none();
+        for hash_value in [0usize, 1, 2, usize::MAX - 1, usize::MAX] {
+            assert!(!range.is_range(hash_value));
+        }
+    }
+}
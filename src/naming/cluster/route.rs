@@ -5,7 +5,9 @@ use crate::{
     grpc::PayloadUtils,
     naming::{
         core::{NamingActor, NamingCmd, NamingResult},
-        model::{Instance, InstanceUpdateTag},
+        model::{
+            Instance, InstanceDrainParam, InstanceMetadataBatch, InstanceUpdateTag, ServiceKey,
+        },
     },
     raft::network::factory::RaftClusterRequestSender,
 };
@@ -86,17 +88,7 @@ impl NamingRoute {
                 instance: instance.clone(),
             }
         };
-        let mut send_extend_infos = self.send_extend_infos.clone();
-        send_extend_infos.insert(
-            GRPC_HEAD_KEY_SUB_NAME.to_string(),
-            req.get_sub_name().to_string(),
-        );
-        let request = serde_json::to_string(&req).unwrap_or_default();
-        let payload =
-            PayloadUtils::build_full_payload(NAMING_ROUTE_REQUEST, request, "", send_extend_infos);
-        let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
-        let body_vec = resp_payload.body.unwrap_or_default().value;
-        let _: NamingRouterResponse = serde_json::from_slice(&body_vec)?;
+        self.send_route_request(addr, req).await?;
 
         //路由在其它节点后，立即同步本节点
         if is_update {
@@ -123,6 +115,100 @@ impl NamingRoute {
         Ok(())
     }
 
+    async fn send_route_request(
+        &self,
+        addr: Arc<String>,
+        req: NamingRouteRequest,
+    ) -> anyhow::Result<NamingRouterResponse> {
+        let mut send_extend_infos = self.send_extend_infos.clone();
+        send_extend_infos.insert(
+            GRPC_HEAD_KEY_SUB_NAME.to_string(),
+            req.get_sub_name().to_string(),
+        );
+        let request = serde_json::to_string(&req).unwrap_or_default();
+        let payload =
+            PayloadUtils::build_full_payload(NAMING_ROUTE_REQUEST, request, "", send_extend_infos);
+        let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
+        let body_vec = resp_payload.body.unwrap_or_default().value;
+        let resp: NamingRouterResponse = serde_json::from_slice(&body_vec)?;
+        Ok(resp)
+    }
+
+    ///
+    /// 路由到服务所属节点处理,返回更新的实例id列表
+    async fn route_to_owner<T>(
+        &self,
+        key: &ServiceKey,
+        param: T,
+        to_cmd: fn(T) -> NamingCmd,
+        to_req: fn(T) -> NamingRouteRequest,
+    ) -> anyhow::Result<Vec<String>> {
+        match self.node_manage.route_addr(key).await {
+            NamingRouteAddr::Local(_) => {
+                let res: NamingResult = self.naming_addr.send(to_cmd(param)).await??;
+                if let NamingResult::UpdatedInstanceIds(updated) = res {
+                    Ok(updated)
+                } else {
+                    Ok(vec![])
+                }
+            }
+            NamingRouteAddr::Remote(_, addr) => {
+                let res = self.send_route_request(addr, to_req(param)).await?;
+                if let NamingRouterResponse::UpdatedInstanceIds(updated) = res {
+                    Ok(updated)
+                } else {
+                    Ok(vec![])
+                }
+            }
+        }
+    }
+
+    ///
+    /// 批量更新实例元数据,路由到服务所属节点处理
+    /// 所属节点更新后通过集群同步机制通知其它节点
+    pub async fn update_instance_metadata_batch(
+        &self,
+        batch: InstanceMetadataBatch,
+    ) -> anyhow::Result<Vec<String>> {
+        let key = batch.get_service_key();
+        self.route_to_owner(
+            &key,
+            batch,
+            NamingCmd::UpdateMetadataBatch,
+            NamingRouteRequest::UpdateMetadataBatch,
+        )
+        .await
+    }
+
+    ///
+    /// 实例下线引流,路由到实例所在节点处理
+    /// grpc实例由持有连接的节点管理,不一定是服务所属节点
+    pub async fn drain_instance(&self, param: InstanceDrainParam) -> anyhow::Result<Vec<String>> {
+        let cmd = NamingCmd::QueryDrainNodes(param.clone());
+        let nodes = match self.naming_addr.send(cmd).await?? {
+            NamingResult::NodeIds(nodes) => nodes,
+            _ => vec![],
+        };
+        let mut updated = vec![];
+        for node_id in nodes {
+            if node_id == 0 {
+                let cmd = NamingCmd::DrainInstance(param.clone());
+                if let NamingResult::UpdatedInstanceIds(ids) = self.naming_addr.send(cmd).await?? {
+                    updated.extend(ids);
+                }
+            } else {
+                let addr = self.node_manage.get_node_addr(node_id).await?;
+                let req = NamingRouteRequest::DrainInstance(param.clone());
+                if let NamingRouterResponse::UpdatedInstanceIds(ids) =
+                    self.send_route_request(addr, req).await?
+                {
+                    updated.extend(ids);
+                }
+            }
+        }
+        Ok(updated)
+    }
+
     pub async fn delete_instance(&self, instance: Instance) -> anyhow::Result<()> {
         let key = instance.get_service_key();
         match self.node_manage.route_addr(&key).await {
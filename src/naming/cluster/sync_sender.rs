@@ -92,7 +92,9 @@ impl Handler<SyncSenderRequest> for ClusteSyncSender {
             {
                 Ok(v) => v,
                 Err(err) => {
-                    if let NamingRouteRequest::Ping(_) = &req {
+                    if let NamingRouteRequest::Ping(_) | NamingRouteRequest::SyncConnCount { .. } =
+                        &req
+                    {
                         //ping 不重试
                         return Err(err);
                     }
@@ -15,8 +15,13 @@ use super::cluster::model::{
 };
 use super::cluster::node_manage::{InnerNodeManage, NodeManageRequest};
 use super::filter::InstanceFilterUtils;
+use super::instance_event::{
+    InstanceEvent, InstanceEventManage, InstanceEventQueryParam, InstanceEventType,
+    DEFAULT_EVENT_HISTORY_SIZE, EVENT_DETAIL_HEARTBEAT_TIMEOUT,
+};
 use super::listener::{InnerNamingListener, ListenerItem, NamingListenerCmd};
 use super::model::InstanceKey;
+use super::model::InstanceMetadataBatch;
 use super::model::InstanceShortKey;
 use super::model::InstanceUpdateTag;
 use super::model::ServiceDetailDto;
@@ -24,6 +29,7 @@ use super::model::ServiceInfo;
 use super::model::ServiceKey;
 use super::model::UpdateInstanceType;
 use super::model::{DistroData, Instance};
+use super::model::{InstanceDrainInfo, InstanceDrainMode, InstanceDrainParam};
 use super::naming_delay_nofity::DelayNotifyActor;
 use super::naming_delay_nofity::DelayNotifyCmd;
 use super::naming_subscriber::NamingListenerItem;
@@ -58,6 +64,7 @@ use crate::common::constant::EMPTY_ARC_STRING;
 use crate::metrics::metrics_key::MetricsKey;
 use crate::metrics::model::{MetricsItem, MetricsQuery, MetricsRecord};
 use crate::namespace::NamespaceActor;
+use crate::xds::manage::{XdsManage, XdsManageCmd};
 use actix::prelude::*;
 use regex::Regex;
 
@@ -73,11 +80,19 @@ pub struct NamingActor {
     pub(crate) sys_config: NamingSysConfig,
     pub(crate) empty_service_set: TimeoutSet<ServiceKey>,
     pub(crate) instance_metadate_set: TimeoutSet<InstanceKey>,
+    //下线引流中的实例
+    pub(crate) drain_instances: HashMap<InstanceKey, InstanceDrainInfo>,
+    //实例生命周期事件记录
+    pub(crate) instance_events: InstanceEventManage,
     pub(crate) namespace_index: NamespaceIndex,
     pub(crate) client_instance_set: HashMap<Arc<String>, HashSet<InstanceKey>>,
     pub(crate) cluster_node_manage: Option<Addr<InnerNodeManage>>,
     pub(crate) cluster_delay_notify: Option<Addr<ClusterInstanceDelayNotifyActor>>,
     pub(crate) namespace_actor: Option<Addr<NamespaceActor>>,
+    //开启xds时通知envoy服务变更
+    pub(crate) xds_manage: Option<Addr<XdsManage>>,
+    //服务实例变更序号,用于唤醒consul阻塞查询
+    pub(crate) service_change_sender: tokio::sync::watch::Sender<u64>,
     pub(crate) current_range: Option<ProcessRange>,
     pub(crate) node_id: u64,
     /// 用于注入测试异常场景
@@ -111,6 +126,7 @@ impl Inject for NamingActor {
         self.cluster_node_manage = factory_data.get_actor();
         self.cluster_delay_notify = factory_data.get_actor();
         self.namespace_actor = factory_data.get_actor();
+        self.xds_manage = factory_data.get_actor();
         self.namespace_index.namespace_actor = self.namespace_actor.clone();
         let sys_config: Option<Arc<AppSysConfig>> = factory_data.get_bean();
         if let Some(sys_config) = sys_config {
@@ -119,6 +135,7 @@ impl Inject for NamingActor {
             self.sys_config.instance_timeout_millis =
                 sys_config.naming_instance_timeout as i64 + 3000;
             self.node_id = sys_config.raft_node_id;
+            self.instance_events.capacity = sys_config.naming_event_history_size;
             log::info!("NamingActor change naming timeout info from env,health_timeout:{},instance_timeout:{}"
                 ,self.sys_config.instance_health_timeout_millis,self.sys_config.instance_timeout_millis)
         }
@@ -146,11 +163,15 @@ impl NamingActor {
             empty_service_set: Default::default(),
             namespace_index: NamespaceIndex::new(),
             instance_metadate_set: Default::default(),
+            drain_instances: Default::default(),
+            instance_events: InstanceEventManage::new(DEFAULT_EVENT_HISTORY_SIZE),
             client_instance_set: Default::default(),
             cluster_node_manage: None,
             cluster_delay_notify: None,
             current_range: None,
             namespace_actor: None,
+            xds_manage: None,
+            service_change_sender: tokio::sync::watch::channel(0).0,
             node_id: 0,
             disable_notify: false,
             //dal_addr,
@@ -254,7 +275,7 @@ impl NamingActor {
         if let Some(service) = self.service_map.get(&service_map_key) {
             if service.instance_size <= 0 {
                 //控制台发起的不校验过期时间标记
-                self.clear_one_empty_service(service_map_key.clone(), 0x7fff_ffff_ffff_ffff);
+                self.clear_one_empty_service(service_map_key, 0x7fff_ffff_ffff_ffff);
                 Ok(())
             } else {
                 Err(anyhow::anyhow!(
@@ -266,12 +287,33 @@ impl NamingActor {
         }
     }
 
+    ///
+    /// 服务实例变更(包含其它节点同步过来的变更)时更新服务变更时间并通知xds
+    fn mark_service_changed(&mut self, tag: &UpdateInstanceType, key: &ServiceKey) {
+        if matches!(
+            tag,
+            UpdateInstanceType::New | UpdateInstanceType::Remove | UpdateInstanceType::UpdateValue
+        ) {
+            if let Some(service) = self.service_map.get_mut(key) {
+                //记录服务实例最近变更时间,保持单调递增
+                service.last_modified_millis =
+                    (service.last_modified_millis + 1).max(now_millis_i64());
+            }
+            self.service_change_sender.send_modify(|v| *v += 1);
+            //同步过来的变更也要通知xds,非服务所属节点上的envoy才能拿到最新实例
+            if let Some(xds_manage) = &self.xds_manage {
+                xds_manage.do_send(XdsManageCmd::ServiceChanged);
+            }
+        }
+    }
+
     fn do_notify(
         &mut self,
         tag: &UpdateInstanceType,
         key: ServiceKey,
         instance: Option<Arc<Instance>>,
     ) {
+        self.mark_service_changed(tag, &key);
         #[cfg(feature = "debug")]
         if self.disable_notify {
             return;
@@ -347,6 +389,15 @@ impl NamingActor {
         let old_instance = service.remove_instance(instance_id, client_id);
         let now = now_millis();
         let tag = if let Some(old_instance) = &old_instance {
+            self.instance_events.add_event(
+                key,
+                InstanceEvent::new(
+                    InstanceEventType::Deregister,
+                    old_instance,
+                    now_millis_i64(),
+                    self.node_id,
+                ),
+            );
             real_client_id = Some(old_instance.client_id.clone());
             let short_key = old_instance.get_short_key();
             if service.exist_priority_metadata(&short_key) {
@@ -417,11 +468,30 @@ impl NamingActor {
             }
         }
         let instance_short_key = instance.get_short_key();
+        let old_instance = service.get_instance(&instance_short_key);
 
         let (tag, replace_old_client_id) = service.update_instance(instance, tag, from_sync);
+        let mut updated_instance = None;
         if let UpdateInstanceType::UpdateOtherClusterMetaData(_, _) = &tag {
             return tag;
         }
+        if matches!(
+            tag,
+            UpdateInstanceType::New
+                | UpdateInstanceType::UpdateValue
+                | UpdateInstanceType::UpdateTime
+        ) {
+            //心跳更新也可能使实例恢复健康
+            let new_instance = service.get_instance(&instance_short_key);
+            let events = InstanceEvent::diff(
+                old_instance.as_deref(),
+                new_instance.as_deref(),
+                now_millis_i64(),
+                self.node_id,
+            );
+            self.instance_events.add_events(key, events);
+            updated_instance = new_instance;
+        }
         if let Some(replace_old_client_id) = replace_old_client_id {
             if let Some(set) = self.client_instance_set.get_mut(&replace_old_client_id) {
                 set.remove(&instance_key);
@@ -431,10 +501,188 @@ impl NamingActor {
             //change notify
             let instance = service.get_instance(&instance_short_key);
             self.do_notify(&tag, key.clone(), instance);
+        } else {
+            //同步过来的变更不再转发,只更新服务变更时间
+            self.mark_service_changed(&tag, key);
+        }
+        if let Some(instance) = updated_instance {
+            self.restore_drain_instance(&instance_key, &instance);
         }
         tag
     }
 
+    ///
+    /// 批量更新或删除实例元数据,返回命中的实例id列表
+    /// 变更按控制台更新处理,元数据作为高优先级元数据保存
+    pub(crate) fn update_instance_metadata_batch(
+        &mut self,
+        batch: InstanceMetadataBatch,
+    ) -> Vec<String> {
+        let key = batch.get_service_key();
+        let instances = if let Some(service) = self.service_map.get(&key) {
+            service
+                .instances
+                .values()
+                .filter(|e| batch.is_match(e))
+                .cloned()
+                .collect::<Vec<_>>()
+        } else {
+            return vec![];
+        };
+        let mut updated = Vec::with_capacity(instances.len());
+        for old_instance in instances {
+            updated.push(old_instance.get_nacos_instance_id());
+            let metadata = batch.apply_metadata(&old_instance.metadata);
+            if &metadata == old_instance.metadata.as_ref() {
+                continue;
+            }
+            let mut instance = old_instance.as_ref().clone();
+            instance.metadata = Arc::new(metadata);
+            let tag = InstanceUpdateTag {
+                weight: false,
+                metadata: true,
+                enabled: false,
+                ephemeral: false,
+                from_update: true,
+            };
+            self.update_instance(&key, instance, Some(tag), false);
+        }
+        updated
+    }
+
+    ///
+    /// 标记实例下线引流或取消下线,返回命中的实例id列表
+    /// 只处理本节点管理的实例,其它节点通过集群同步获取变更
+    /// 其它节点管理的实例(如连接在其它节点的grpc实例)由路由转发到实例所在节点处理
+    pub(crate) fn drain_instance(&mut self, param: InstanceDrainParam) -> Vec<String> {
+        let key = param.get_service_key();
+        let instances = if let Some(service) = self.service_map.get(&key) {
+            service
+                .instances
+                .values()
+                .filter(|e| !e.is_from_cluster() && param.instance.is_match(e))
+                .cloned()
+                .collect::<Vec<_>>()
+        } else {
+            return vec![];
+        };
+        let now = now_millis_i64();
+        let mut updated = Vec::with_capacity(instances.len());
+        for old_instance in instances {
+            updated.push(old_instance.get_nacos_instance_id());
+            let instance_key = old_instance.get_instance_key();
+            if param.cancel {
+                if let Some(info) = self.drain_instances.remove(&instance_key) {
+                    let mut instance = old_instance.as_ref().clone();
+                    instance.weight = info.origin_weight;
+                    instance.enabled = info.origin_enabled;
+                    instance.metadata = Arc::new(InstanceDrainInfo::remove_from_metadata(
+                        &old_instance.metadata,
+                    ));
+                    self.update_drain_instance(&key, instance, true);
+                }
+                continue;
+            }
+            if self.drain_instances.contains_key(&instance_key) {
+                continue;
+            }
+            let info = InstanceDrainInfo::new(param.mode, &old_instance, now, param.drain_seconds);
+            let mut instance = old_instance.as_ref().clone();
+            instance.metadata = Arc::new(info.apply_to_metadata(&old_instance.metadata));
+            if param.mode == InstanceDrainMode::Offline {
+                instance.enabled = false;
+            }
+            self.drain_instances.insert(instance_key, info);
+            self.update_drain_instance(&key, instance, true);
+        }
+        updated
+    }
+
+    ///
+    /// 查询命中下线引流的实例所在节点,0表示本节点
+    pub(crate) fn get_drain_nodes(&self, param: &InstanceDrainParam) -> Vec<u64> {
+        let mut nodes = vec![];
+        if let Some(service) = self.service_map.get(&param.get_service_key()) {
+            for instance in service.instances.values() {
+                if param.instance.is_match(instance) && !nodes.contains(&instance.from_cluster) {
+                    nodes.push(instance.from_cluster);
+                }
+            }
+        }
+        nodes
+    }
+
+    fn update_drain_instance(&mut self, key: &ServiceKey, instance: Instance, metadata: bool) {
+        let tag = InstanceUpdateTag {
+            weight: true,
+            metadata,
+            enabled: true,
+            ephemeral: false,
+            from_update: true,
+        };
+        self.update_instance(key, instance, Some(tag), false);
+    }
+
+    ///
+    /// 本节点管理的实例带有下线引流元数据但没有下线记录时(如实例转由本节点管理),恢复下线记录
+    fn restore_drain_instance(&mut self, instance_key: &InstanceKey, instance: &Instance) {
+        if instance.is_from_cluster() || self.drain_instances.contains_key(instance_key) {
+            return;
+        }
+        if let Some(info) = InstanceDrainInfo::from_instance(instance) {
+            self.drain_instances.insert(instance_key.clone(), info);
+        }
+    }
+
+    ///
+    /// 下线完成后清除高优先级元数据中的下线引流状态,避免实例重新注册后再次被下线
+    fn clear_drain_metadata(&mut self, key: &ServiceKey, short_key: &InstanceShortKey) {
+        if let Some(service) = self.service_map.get_mut(key) {
+            if let Some(metadata) = service.instance_metadata_map.get(short_key) {
+                let metadata = InstanceDrainInfo::remove_from_metadata(metadata);
+                service
+                    .instance_metadata_map
+                    .insert(short_key.clone(), Arc::new(metadata));
+            }
+        }
+    }
+
+    ///
+    /// 按阶梯降低下线实例的权重,下线时间结束后删除实例;
+    /// 实例已因心跳超时被删除时直接清理下线记录
+    pub(crate) fn drain_check(&mut self) {
+        if self.drain_instances.is_empty() {
+            return;
+        }
+        let now = now_millis_i64();
+        let mut finished = vec![];
+        let mut steps = vec![];
+        for (instance_key, info) in self.drain_instances.iter_mut() {
+            if info.is_finished(now) {
+                finished.push(instance_key.clone());
+            } else if let Some(weight) = info.next_step_weight(now) {
+                steps.push((instance_key.clone(), weight));
+            }
+        }
+        for (instance_key, weight) in steps {
+            let key = instance_key.get_service_key();
+            if let Some(old_instance) = self.get_instance(&key, &instance_key.get_short_key()) {
+                let mut instance = old_instance.as_ref().clone();
+                instance.weight = weight;
+                self.update_drain_instance(&key, instance, false);
+            } else {
+                self.drain_instances.remove(&instance_key);
+            }
+        }
+        for instance_key in finished {
+            self.drain_instances.remove(&instance_key);
+            let key = instance_key.get_service_key();
+            let short_key = instance_key.get_short_key();
+            self.clear_drain_metadata(&key, &short_key);
+            self.remove_instance(&key, &short_key, None);
+        }
+    }
+
     pub(crate) fn remove_client_instance(&mut self, client_id: &Arc<String>) {
         if let Some(keys) = self.client_instance_set.remove(client_id) {
             for instance_key in keys {
@@ -558,10 +806,34 @@ impl NamingActor {
         let mut change_list = vec![];
         for item in self.service_map.values_mut() {
             let service_key = item.get_service_key();
-            let (rlist, ulist) = item.time_check(healthy_time, offline_time);
+            let (rlist, ulist) = item.time_check(current_time, healthy_time, offline_time);
             size += rlist.len() + ulist.len();
+            for short_key in &ulist {
+                if let Some(instance) = item.get_instance(short_key) {
+                    self.instance_events.add_event(
+                        &service_key,
+                        InstanceEvent::new(
+                            InstanceEventType::Unhealthy,
+                            &instance,
+                            current_time,
+                            self.node_id,
+                        )
+                        .with_detail(EVENT_DETAIL_HEARTBEAT_TIMEOUT),
+                    );
+                }
+            }
             if !rlist.is_empty() {
                 for short_key in &rlist {
+                    self.instance_events.add_event(
+                        &service_key,
+                        InstanceEvent::new_by_short_key(
+                            InstanceEventType::Deregister,
+                            short_key,
+                            current_time,
+                            self.node_id,
+                        )
+                        .with_detail(EVENT_DETAIL_HEARTBEAT_TIMEOUT),
+                    );
                     if item.exist_priority_metadata(short_key) {
                         let instance_key = InstanceKey::new_by_service_key(
                             &service_key,
@@ -673,6 +945,39 @@ impl NamingActor {
         (size, service_names)
     }
 
+    ///
+    /// 查询所有匹配条件服务的实例列表
+    pub fn get_instance_list_by_param(
+        &self,
+        param: &ServiceQueryParam,
+        only_healthy: bool,
+    ) -> Vec<Arc<Instance>> {
+        let (_, service_keys) = self.namespace_index.query_service_page(param);
+        let mut rlist = vec![];
+        for key in &service_keys {
+            rlist.append(&mut self.get_instance_list(key, "", only_healthy));
+        }
+        rlist
+    }
+
+    ///
+    /// 查询匹配服务的全部实例及服务最近变更时间
+    pub fn get_instance_list_with_index(
+        &self,
+        param: &ServiceQueryParam,
+    ) -> (i64, Vec<Arc<Instance>>) {
+        let (_, service_keys) = self.namespace_index.query_service_page(param);
+        let mut index = 0;
+        let mut rlist = vec![];
+        for key in &service_keys {
+            if let Some(service) = self.service_map.get(key) {
+                index = index.max(service.last_modified_millis);
+                rlist.append(&mut service.get_instance_list(vec![], false, false));
+            }
+        }
+        (index, rlist)
+    }
+
     pub fn get_subscribers_list(
         &self,
         page_size: usize,
@@ -771,6 +1076,7 @@ impl NamingActor {
                 self.namespace_index
                     .remove_service(&service.get_service_key());
                 self.service_map.remove(&service_map_key);
+                self.instance_events.remove_service(&service_map_key);
                 log::info!("clear_empty_service:{:?}", &service_map_key);
             }
         }
@@ -796,6 +1102,7 @@ impl NamingActor {
         ctx.run_later(Duration::from_millis(2000), |act, ctx| {
             act.clear_empty_service();
             act.clear_timeout_instance_metadata();
+            act.drain_check();
             let addr = ctx.address();
             addr.do_send(NamingCmd::PeekListenerTimeout);
             act.instance_time_out_heartbeat(ctx);
@@ -1012,6 +1319,11 @@ pub enum NamingCmd {
     QueryServiceSubscribersPage(ServiceKey, usize, usize),
     //查询服务实际信息列表
     QueryServiceInfoPage(ServiceQueryParam),
+    //查询所有匹配服务的实例列表
+    QueryInstanceListByParam(ServiceQueryParam, bool),
+    //查询匹配服务的全部实例及最近变更时间
+    QueryInstanceListWithIndex(ServiceQueryParam),
+    SubscribeServiceChange,
     //CreateService(ServiceDetailDto),
     UpdateService(ServiceDetailDto),
     UpdateServiceFromCluster(ServiceDetailDto),
@@ -1024,19 +1336,35 @@ pub enum NamingCmd {
     RemoveClientsFromCluster(Vec<Arc<String>>),
     RemoveClientFromCluster(Arc<String>),
     QueryClientInstanceCount,
+    QueryClientSubscribes(Vec<Arc<String>>),
     QueryDalAddr,
     QuerySnapshot(Vec<ProcessRange>),
     ClusterRefreshProcessRange(ProcessRange),
     ReceiveSnapshot(SnapshotForReceive),
     QueryGrpcDistroData,
-    DiffGrpcDistroData { cluster_id: u64, data: DistroData },
+    DiffGrpcDistroData {
+        cluster_id: u64,
+        data: DistroData,
+    },
     QueryDistroInstanceSnapshot(Vec<InstanceKey>),
+    UpdateMetadataBatch(InstanceMetadataBatch),
+    DrainInstance(InstanceDrainParam),
+    QueryDrainNodes(InstanceDrainParam),
+    QueryInstanceEvents(InstanceEventQueryParam),
+    QueryGroupInstanceEvents {
+        namespace_id: Arc<String>,
+        group_name: Arc<String>,
+        start_time: i64,
+    },
+    QueryPushServiceInfo(ServiceKey),
 }
 
 pub enum NamingResult {
     NULL,
     Instance(Arc<Instance>),
     InstanceList(Vec<Arc<Instance>>),
+    InstanceListWithIndex(i64, Vec<Arc<Instance>>),
+    ServiceChangeReceiver(tokio::sync::watch::Receiver<u64>),
     InstanceListString(String),
     ServiceInfo(ServiceInfo),
     ServicePage((usize, Vec<Arc<String>>)),
@@ -1048,6 +1376,12 @@ pub enum NamingResult {
     GrpcDistroData(DistroData),
     DiffDistroData(DistroData),
     DistroInstancesSnapshot(Vec<Arc<Instance>>),
+    UpdatedInstanceIds(Vec<String>),
+    NodeIds(Vec<u64>),
+    InstanceEvents(Vec<InstanceEvent>),
+    GroupInstanceEvents(Vec<(Arc<String>, InstanceEvent)>),
+    PushServiceInfo(ServiceInfo, ServiceMetadata),
+    ClientSubscribes(HashMap<Arc<String>, Vec<ServiceKey>>),
 }
 
 impl Supervised for NamingActor {
@@ -1146,6 +1480,16 @@ impl Handler<NamingCmd> for NamingActor {
             NamingCmd::QueryServiceInfoPage(param) => Ok(NamingResult::ServiceInfoPage(
                 self.get_service_info_page(param),
             )),
+            NamingCmd::QueryInstanceListByParam(param, only_healthy) => Ok(
+                NamingResult::InstanceList(self.get_instance_list_by_param(&param, only_healthy)),
+            ),
+            NamingCmd::QueryInstanceListWithIndex(param) => {
+                let (index, list) = self.get_instance_list_with_index(&param);
+                Ok(NamingResult::InstanceListWithIndex(index, list))
+            }
+            NamingCmd::SubscribeServiceChange => Ok(NamingResult::ServiceChangeReceiver(
+                self.service_change_sender.subscribe(),
+            )),
             NamingCmd::PeekListenerTimeout => {
                 self.time_check();
                 //self.notify_check();
@@ -1190,6 +1534,10 @@ impl Handler<NamingCmd> for NamingActor {
                 self.remove_client_instance(&client_id);
                 Ok(NamingResult::NULL)
             }
+            NamingCmd::QueryClientSubscribes(client_ids) => {
+                let map = self.subscriber.get_client_subscribes(&client_ids);
+                Ok(NamingResult::ClientSubscribes(map))
+            }
             NamingCmd::QueryDalAddr => {
                 //Ok(NamingResult::DalAddr(self.dal_addr.clone()))
                 Ok(NamingResult::NULL)
@@ -1268,6 +1616,33 @@ impl Handler<NamingCmd> for NamingActor {
                 let instances = self.build_distro_instances(instance_keys);
                 Ok(NamingResult::DistroInstancesSnapshot(instances))
             }
+            NamingCmd::UpdateMetadataBatch(batch) => {
+                let updated = self.update_instance_metadata_batch(batch);
+                Ok(NamingResult::UpdatedInstanceIds(updated))
+            }
+            NamingCmd::DrainInstance(param) => {
+                let updated = self.drain_instance(param);
+                Ok(NamingResult::UpdatedInstanceIds(updated))
+            }
+            NamingCmd::QueryDrainNodes(param) => {
+                Ok(NamingResult::NodeIds(self.get_drain_nodes(&param)))
+            }
+            NamingCmd::QueryInstanceEvents(param) => Ok(NamingResult::InstanceEvents(
+                self.instance_events.query(&param),
+            )),
+            NamingCmd::QueryGroupInstanceEvents {
+                namespace_id,
+                group_name,
+                start_time,
+            } => Ok(NamingResult::GroupInstanceEvents(
+                self.instance_events
+                    .query_group(&namespace_id, &group_name, start_time),
+            )),
+            NamingCmd::QueryPushServiceInfo(service_key) => {
+                let service_info = self.get_service_info(&service_key, "".to_owned(), true);
+                let metadata = self.get_metadata(&service_key).unwrap_or_default();
+                Ok(NamingResult::PushServiceInfo(service_info, metadata))
+            }
         }
     }
 }
@@ -1369,3 +1744,319 @@ fn test_remove_has_instance_service() {
     assert!(naming.remove_empty_service(service_key.clone()).is_ok());
     assert!(naming.namespace_index.service_size == 0);
 }
+
+#[test]
+fn test_update_instance_metadata_batch() {
+    use super::model::{InstanceMetadataFilter, MetadataBatchAction};
+    let mut naming = NamingActor::new();
+    let mut service_key = ServiceKey::default();
+    for port in [8080, 8081] {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.init();
+        service_key = instance.get_service_key();
+        naming.update_instance(&service_key, instance, None, false);
+    }
+    let mut metadata = HashMap::new();
+    metadata.insert("status".to_owned(), "offline".to_owned());
+    let mut batch = InstanceMetadataBatch {
+        namespace_id: service_key.namespace_id.clone(),
+        group_name: service_key.group_name.clone(),
+        service_name: service_key.service_name.clone(),
+        ephemeral: true,
+        action: MetadataBatchAction::Update,
+        instances: vec![InstanceMetadataFilter {
+            ip: Arc::new("127.0.0.1".to_owned()),
+            port: 8080,
+            cluster_name: None,
+        }],
+        metadata,
+    };
+    let updated = naming.update_instance_metadata_batch(batch.clone());
+    assert_eq!(updated.len(), 1);
+    let short_key = InstanceShortKey::new(Arc::new("127.0.0.1".to_owned()), 8080);
+    let instance = naming.get_instance(&service_key, &short_key).unwrap();
+    assert_eq!(instance.metadata.get("status").unwrap(), "offline");
+    let other_key = InstanceShortKey::new(Arc::new("127.0.0.1".to_owned()), 8081);
+    let other = naming.get_instance(&service_key, &other_key).unwrap();
+    assert!(other.metadata.get("status").is_none());
+
+    batch.action = MetadataBatchAction::Delete;
+    batch.instances = vec![];
+    let updated = naming.update_instance_metadata_batch(batch);
+    assert_eq!(updated.len(), 2);
+    let instance = naming.get_instance(&service_key, &short_key).unwrap();
+    assert!(instance.metadata.get("status").is_none());
+}
+
+#[test]
+fn test_get_instance_list_by_param() {
+    let mut naming = NamingActor::new();
+    for (namespace_id, group_name, port) in [
+        ("public", "DEFAULT_GROUP", 8080),
+        ("public", "DEFAULT_GROUP", 8081),
+        ("public", "OTHER_GROUP", 8082),
+        ("dev", "DEFAULT_GROUP", 8083),
+    ] {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = Arc::new(namespace_id.to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.group_name = Arc::new(group_name.to_owned());
+        instance.init();
+        let service_key = instance.get_service_key();
+        naming.update_instance(&service_key, instance, None, false);
+    }
+    let param = ServiceQueryParam {
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    assert_eq!(naming.get_instance_list_by_param(&param, true).len(), 4);
+    let param = ServiceQueryParam {
+        namespace_id: Some(Arc::new("public".to_owned())),
+        group: Some(Arc::new("DEFAULT_GROUP".to_owned())),
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let list = naming.get_instance_list_by_param(&param, true);
+    let mut ports: Vec<u32> = list.iter().map(|e| e.port).collect();
+    ports.sort();
+    assert_eq!(ports, vec![8080, 8081]);
+}
+
+#[test]
+fn test_get_instance_list_with_index() {
+    let mut naming = NamingActor::new();
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    instance.init();
+    let service_key = instance.get_service_key();
+    naming.update_instance(&service_key, instance.clone(), None, false);
+    let param = ServiceQueryParam {
+        namespace_id: Some(service_key.namespace_id.clone()),
+        service: Some(service_key.service_name.clone()),
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let (index, list) = naming.get_instance_list_with_index(&param);
+    assert!(index > 0);
+    assert_eq!(list.len(), 1);
+
+    naming.remove_instance(&service_key, &instance.get_short_key(), None);
+    let (new_index, list) = naming.get_instance_list_with_index(&param);
+    assert!(new_index > index);
+    assert!(list.is_empty());
+
+    //其它节点同步过来的变更也要更新index
+    let mut sync_instance = instance.clone();
+    sync_instance.port = 8081;
+    sync_instance.init();
+    naming.update_instance(&service_key, sync_instance, None, true);
+    let (sync_index, list) = naming.get_instance_list_with_index(&param);
+    assert!(sync_index > new_index);
+    assert_eq!(list.len(), 1);
+}
+
+#[test]
+fn test_service_change_notify() {
+    let mut naming = NamingActor::new();
+    let mut receiver = naming.service_change_sender.subscribe();
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    instance.init();
+    let service_key = instance.get_service_key();
+    naming.update_instance(&service_key, instance.clone(), None, false);
+    assert!(receiver.has_changed().unwrap());
+    receiver.borrow_and_update();
+    assert!(!receiver.has_changed().unwrap());
+    let mut sync_instance = instance.clone();
+    sync_instance.port = 8081;
+    sync_instance.init();
+    naming.update_instance(&service_key, sync_instance, None, true);
+    assert!(receiver.has_changed().unwrap());
+}
+
+#[test]
+fn test_instance_custom_heart_beat_timeout() {
+    use super::PRESERVED_HEART_BEAT_TIMEOUT;
+    let mut naming = NamingActor::new();
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    let mut metadata = HashMap::new();
+    metadata.insert(PRESERVED_HEART_BEAT_TIMEOUT.to_owned(), "60000".to_owned());
+    instance.metadata = Arc::new(metadata);
+    instance.init();
+    let short_key = instance.get_short_key();
+    let service_key = instance.get_service_key();
+    naming.update_instance(&service_key, instance, None, false);
+    let service = naming.service_map.get_mut(&service_key).unwrap();
+    let last_modified = service
+        .get_instance(&short_key)
+        .unwrap()
+        .last_modified_millis;
+    let now = last_modified + 20_000;
+    let (_, ulist) = service.time_check(now, now - 15_000, now - 30_000);
+    assert!(ulist.is_empty());
+    let now = last_modified + 61_000;
+    let (_, ulist) = service.time_check(now, now - 15_000, now - 30_000);
+    assert_eq!(ulist.len(), 1);
+}
+
+#[test]
+fn test_drain_instance() {
+    use super::model::InstanceMetadataFilter;
+    use super::PRESERVED_DRAIN_INFO;
+    let mut naming = NamingActor::new();
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    instance.weight = 2f32;
+    instance.init();
+    let short_key = instance.get_short_key();
+    let instance_key = instance.get_instance_key();
+    let service_key = instance.get_service_key();
+    naming.update_instance(&service_key, instance, None, false);
+    let mut param = InstanceDrainParam {
+        namespace_id: service_key.namespace_id.clone(),
+        group_name: service_key.group_name.clone(),
+        service_name: service_key.service_name.clone(),
+        instance: InstanceMetadataFilter {
+            ip: short_key.ip.clone(),
+            port: short_key.port,
+            cluster_name: None,
+        },
+        mode: InstanceDrainMode::Weight,
+        drain_seconds: 10,
+        cancel: false,
+    };
+    assert_eq!(naming.drain_instance(param.clone()).len(), 1);
+    //下线状态写入元数据,实例转由本节点管理时可以恢复下线记录
+    let instance = naming.get_instance(&service_key, &short_key).unwrap();
+    assert!(instance.metadata.contains_key(PRESERVED_DRAIN_INFO));
+    naming.drain_instances.clear();
+    naming.update_instance(&service_key, instance.as_ref().clone(), None, false);
+    assert!(naming.drain_instances.contains_key(&instance_key));
+    let now = now_millis_i64();
+    let info = naming.drain_instances.get_mut(&instance_key).unwrap();
+    info.start_time = now - 5000;
+    info.end_time = now + 5000;
+    naming.drain_check();
+    let instance = naming.get_instance(&service_key, &short_key).unwrap();
+    assert_eq!(instance.weight, 1f32);
+
+    param.cancel = true;
+    naming.drain_instance(param.clone());
+    let instance = naming.get_instance(&service_key, &short_key).unwrap();
+    assert_eq!(instance.weight, 2f32);
+    assert!(!instance.metadata.contains_key(PRESERVED_DRAIN_INFO));
+    assert!(naming.drain_instances.is_empty());
+
+    param.cancel = false;
+    param.mode = InstanceDrainMode::Offline;
+    naming.drain_instance(param);
+    let instance = naming.get_instance(&service_key, &short_key).unwrap();
+    assert!(!instance.enabled);
+    let info = naming.drain_instances.get_mut(&instance_key).unwrap();
+    info.end_time = now - 1;
+    naming.drain_check();
+    assert!(naming.get_instance(&service_key, &short_key).is_none());
+    assert!(naming.drain_instances.is_empty());
+    let priority_metadata = naming
+        .service_map
+        .get(&service_key)
+        .and_then(|e| e.instance_metadata_map.get(&short_key).cloned())
+        .unwrap_or_default();
+    assert!(!priority_metadata.contains_key(PRESERVED_DRAIN_INFO));
+}
+
+#[test]
+fn test_drain_instance_from_cluster() {
+    use super::model::InstanceMetadataFilter;
+    let build_instance = |from_cluster: u64| {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.client_id = Arc::new("grpc_conn_1".to_owned());
+        instance.from_cluster = from_cluster;
+        instance.init();
+        instance
+    };
+    let instance = build_instance(2);
+    let short_key = instance.get_short_key();
+    let service_key = instance.get_service_key();
+    let param = InstanceDrainParam {
+        namespace_id: service_key.namespace_id.clone(),
+        group_name: service_key.group_name.clone(),
+        service_name: service_key.service_name.clone(),
+        instance: InstanceMetadataFilter {
+            ip: short_key.ip.clone(),
+            port: short_key.port,
+            cluster_name: None,
+        },
+        mode: InstanceDrainMode::Offline,
+        drain_seconds: 10,
+        cancel: false,
+    };
+    //服务所属节点上,连接在节点2的grpc实例由节点2管理,需要转发到节点2处理
+    let mut owner = NamingActor::new();
+    owner.update_instance(&service_key, instance, None, true);
+    assert_eq!(owner.get_drain_nodes(&param), vec![2]);
+    assert!(owner.drain_instance(param.clone()).is_empty());
+    assert!(owner.drain_instances.is_empty());
+
+    //持有连接的节点2处理下线
+    let mut conn_node = NamingActor::new();
+    conn_node.update_instance(&service_key, build_instance(0), None, false);
+    assert_eq!(conn_node.get_drain_nodes(&param), vec![0]);
+    assert_eq!(conn_node.drain_instance(param).len(), 1);
+    let instance = conn_node.get_instance(&service_key, &short_key).unwrap();
+    assert!(!instance.enabled);
+}
+
+#[test]
+fn test_instance_event_history() {
+    let mut naming = NamingActor::new();
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    instance.init();
+    let service_key = instance.get_service_key();
+    naming.update_instance(&service_key, instance.clone(), None, false);
+    let mut unhealthy_instance = instance.clone();
+    unhealthy_instance.healthy = false;
+    naming.update_instance(&service_key, unhealthy_instance, None, false);
+    naming.remove_instance(&service_key, &instance.get_short_key(), None);
+    let param = InstanceEventQueryParam {
+        service_key: service_key.clone(),
+        ..Default::default()
+    };
+    let events = naming.instance_events.query(&param);
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].event_type, InstanceEventType::Deregister);
+    assert_eq!(events[1].event_type, InstanceEventType::Unhealthy);
+    assert_eq!(events[2].event_type, InstanceEventType::Register);
+    let param = InstanceEventQueryParam {
+        service_key: service_key.clone(),
+        event_type: Some(InstanceEventType::Register),
+        ..Default::default()
+    };
+    assert_eq!(naming.instance_events.query(&param).len(), 1);
+
+    //清除空服务时同时清除事件记录
+    naming.clear_one_empty_service(service_key.clone(), 0x7fff_ffff_ffff_ffff);
+    assert!(naming.service_map.get(&service_key).is_none());
+    assert!(!naming
+        .instance_events
+        .history_map
+        .contains_key(&service_key));
+}
@@ -0,0 +1,397 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::naming::model::{Instance, InstanceShortKey, ServiceKey};
+
+pub const DEFAULT_EVENT_HISTORY_SIZE: usize = 256;
+pub const EVENT_DETAIL_HEARTBEAT_TIMEOUT: &str = "heartbeat timeout";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum InstanceEventType {
+    #[default]
+    Register,
+    Deregister,
+    Healthy,
+    Unhealthy,
+    MetadataChange,
+}
+
+impl InstanceEventType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Register" => Some(Self::Register),
+            "Deregister" => Some(Self::Deregister),
+            "Healthy" => Some(Self::Healthy),
+            "Unhealthy" => Some(Self::Unhealthy),
+            "MetadataChange" => Some(Self::MetadataChange),
+            _ => None,
+        }
+    }
+}
+
+///
+/// 实例生命周期事件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceEvent {
+    pub event_type: InstanceEventType,
+    pub ip: Arc<String>,
+    pub port: u32,
+    pub cluster_name: String,
+    /// 事件发生时间,毫秒
+    pub time: i64,
+    /// 变更来源节点id
+    pub source_node: u64,
+    pub from_grpc: bool,
+    pub detail: String,
+}
+
+impl InstanceEvent {
+    pub fn new(
+        event_type: InstanceEventType,
+        instance: &Instance,
+        time: i64,
+        local_node_id: u64,
+    ) -> Self {
+        let source_node = if instance.is_from_cluster() {
+            instance.from_cluster
+        } else {
+            local_node_id
+        };
+        Self {
+            event_type,
+            ip: instance.ip.clone(),
+            port: instance.port,
+            cluster_name: instance.cluster_name.clone(),
+            time,
+            source_node,
+            from_grpc: instance.from_grpc,
+            detail: String::new(),
+        }
+    }
+
+    ///
+    /// 过期删除的实例只保留了ip与端口
+    pub fn new_by_short_key(
+        event_type: InstanceEventType,
+        short_key: &InstanceShortKey,
+        time: i64,
+        local_node_id: u64,
+    ) -> Self {
+        Self {
+            event_type,
+            ip: short_key.ip.clone(),
+            port: short_key.port,
+            cluster_name: String::new(),
+            time,
+            source_node: local_node_id,
+            from_grpc: false,
+            detail: String::new(),
+        }
+    }
+
+    pub fn with_detail(mut self, detail: &str) -> Self {
+        detail.clone_into(&mut self.detail);
+        self
+    }
+
+    ///
+    /// 对比实例变更前后的状态,生成对应的事件
+    pub fn diff(
+        old: Option<&Instance>,
+        new: Option<&Instance>,
+        time: i64,
+        local_node_id: u64,
+    ) -> Vec<Self> {
+        let mut list = vec![];
+        match (old, new) {
+            (None, Some(new)) => {
+                list.push(Self::new(
+                    InstanceEventType::Register,
+                    new,
+                    time,
+                    local_node_id,
+                ));
+            }
+            (Some(old), None) => {
+                list.push(Self::new(
+                    InstanceEventType::Deregister,
+                    old,
+                    time,
+                    local_node_id,
+                ));
+            }
+            (Some(old), Some(new)) => {
+                if old.healthy && !new.healthy {
+                    list.push(Self::new(
+                        InstanceEventType::Unhealthy,
+                        new,
+                        time,
+                        local_node_id,
+                    ));
+                } else if !old.healthy && new.healthy {
+                    list.push(Self::new(
+                        InstanceEventType::Healthy,
+                        new,
+                        time,
+                        local_node_id,
+                    ));
+                }
+                if old.metadata != new.metadata {
+                    list.push(Self::new(
+                        InstanceEventType::MetadataChange,
+                        new,
+                        time,
+                        local_node_id,
+                    ));
+                }
+            }
+            (None, None) => {}
+        }
+        list
+    }
+}
+
+///
+/// 单个服务的实例事件环形缓冲,超过容量后丢弃最早的事件
+#[derive(Debug, Clone, Default)]
+pub struct InstanceEventHistory {
+    events: VecDeque<InstanceEvent>,
+}
+
+impl InstanceEventHistory {
+    pub fn push(&mut self, event: InstanceEvent, capacity: usize) {
+        while self.events.len() >= capacity && !self.events.is_empty() {
+            self.events.pop_front();
+        }
+        if capacity > 0 {
+            self.events.push_back(event);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    ///
+    /// 按时间范围查询事件,最新的事件在前
+    pub fn query(&self, param: &InstanceEventQueryParam) -> Vec<InstanceEvent> {
+        self.events
+            .iter()
+            .rev()
+            .filter(|e| param.is_match(e))
+            .take(param.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InstanceEventQueryParam {
+    pub service_key: ServiceKey,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub ip: Option<String>,
+    pub port: Option<u32>,
+    pub event_type: Option<InstanceEventType>,
+    pub limit: Option<usize>,
+}
+
+impl InstanceEventQueryParam {
+    pub fn is_match(&self, event: &InstanceEvent) -> bool {
+        if let Some(start_time) = self.start_time {
+            if event.time < start_time {
+                return false;
+            }
+        }
+        if let Some(end_time) = self.end_time {
+            if event.time > end_time {
+                return false;
+            }
+        }
+        if let Some(ip) = &self.ip {
+            if ip.as_str() != event.ip.as_str() {
+                return false;
+            }
+        }
+        if let Some(port) = self.port {
+            if port != event.port {
+                return false;
+            }
+        }
+        if let Some(event_type) = self.event_type {
+            if event_type != event.event_type {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+///
+/// 实例事件计数,定时导出到监控指标后清零
+#[derive(Debug, Clone, Default)]
+pub struct InstanceEventCounter {
+    pub register: u64,
+    pub deregister: u64,
+    pub unhealthy: u64,
+}
+
+impl InstanceEventCounter {
+    pub fn record(&mut self, event_type: InstanceEventType) {
+        match event_type {
+            InstanceEventType::Register => self.register += 1,
+            InstanceEventType::Deregister => self.deregister += 1,
+            InstanceEventType::Unhealthy => self.unhealthy += 1,
+            _ => {}
+        }
+    }
+
+    pub fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InstanceEventManage {
+    pub(crate) capacity: usize,
+    pub(crate) history_map: HashMap<ServiceKey, InstanceEventHistory>,
+    pub(crate) counter: InstanceEventCounter,
+}
+
+impl InstanceEventManage {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_event(&mut self, key: &ServiceKey, event: InstanceEvent) {
+        self.counter.record(event.event_type);
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(history) = self.history_map.get_mut(key) {
+            history.push(event, self.capacity);
+        } else {
+            let mut history = InstanceEventHistory::default();
+            history.push(event, self.capacity);
+            self.history_map.insert(key.clone(), history);
+        }
+    }
+
+    pub fn add_events(&mut self, key: &ServiceKey, events: Vec<InstanceEvent>) {
+        for event in events {
+            self.add_event(key, event);
+        }
+    }
+
+    pub fn query(&self, param: &InstanceEventQueryParam) -> Vec<InstanceEvent> {
+        if let Some(history) = self.history_map.get(&param.service_key) {
+            history.query(param)
+        } else {
+            vec![]
+        }
+    }
+
+    ///
+    /// 查询命名空间与分组下所有服务在开始时间之后的事件,同一服务的事件按时间先后排列
+    pub fn query_group(
+        &self,
+        namespace_id: &str,
+        group_name: &str,
+        start_time: i64,
+    ) -> Vec<(Arc<String>, InstanceEvent)> {
+        let mut list = vec![];
+        for (key, history) in &self.history_map {
+            if key.namespace_id.as_str() != namespace_id || key.group_name.as_str() != group_name {
+                continue;
+            }
+            for event in history.events.iter().filter(|e| e.time >= start_time) {
+                list.push((key.service_name.clone(), event.clone()));
+            }
+        }
+        list
+    }
+
+    pub fn remove_service(&mut self, key: &ServiceKey) {
+        self.history_map.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_instance(ip: &str, healthy: bool) -> Instance {
+        let mut instance = Instance::new(ip.to_owned(), 8080);
+        instance.healthy = healthy;
+        instance
+    }
+
+    #[test]
+    fn test_event_diff() {
+        let old = build_instance("127.0.0.1", true);
+        let mut new = build_instance("127.0.0.1", false);
+        let mut metadata = HashMap::new();
+        metadata.insert("k".to_owned(), "v".to_owned());
+        new.metadata = Arc::new(metadata);
+        let events = InstanceEvent::diff(Some(&old), Some(&new), 1, 1);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, InstanceEventType::Unhealthy);
+        assert_eq!(events[1].event_type, InstanceEventType::MetadataChange);
+        assert_eq!(events[0].source_node, 1);
+        let events = InstanceEvent::diff(None, Some(&old), 1, 1);
+        assert_eq!(events[0].event_type, InstanceEventType::Register);
+        let events = InstanceEvent::diff(Some(&old), Some(&old), 1, 1);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_event_history_ring_buffer() {
+        let key = ServiceKey::new("public", "DEFAULT_GROUP", "foo");
+        let mut manage = InstanceEventManage::new(3);
+        let instance = build_instance("127.0.0.1", true);
+        for i in 0..5 {
+            manage.add_event(
+                &key,
+                InstanceEvent::new(InstanceEventType::Register, &instance, i, 1),
+            );
+        }
+        let param = InstanceEventQueryParam {
+            service_key: key.clone(),
+            ..Default::default()
+        };
+        let list = manage.query(&param);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].time, 4);
+        assert_eq!(list[2].time, 2);
+        let param = InstanceEventQueryParam {
+            service_key: key,
+            start_time: Some(3),
+            end_time: Some(3),
+            ..Default::default()
+        };
+        let list = manage.query(&param);
+        assert_eq!(list.len(), 1);
+        assert_eq!(manage.counter.take().register, 5);
+        assert_eq!(manage.counter.register, 0);
+
+        let other_key = ServiceKey::new("public", "OTHER_GROUP", "bar");
+        manage.add_event(
+            &other_key,
+            InstanceEvent::new(InstanceEventType::Register, &instance, 5, 1),
+        );
+        let list = manage.query_group("public", "DEFAULT_GROUP", 3);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].0.as_str(), "foo");
+        assert_eq!(list[0].1.time, 3);
+    }
+}
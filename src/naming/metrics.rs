@@ -8,6 +8,8 @@ impl Handler<MetricsQuery> for NamingActor {
 
     fn handle(&mut self, _: MetricsQuery, _ctx: &mut Self::Context) -> Self::Result {
         let (group_size, service_size) = self.namespace_index.get_service_count();
+        //事件计数按采集周期的增量上报
+        let event_counter = self.instance_events.counter.take();
         let list = vec![
             MetricsItem {
                 metrics_type: MetricsKey::NamingServiceSize,
@@ -85,6 +87,18 @@ impl Handler<MetricsQuery> for NamingActor {
                 metrics_type: MetricsKey::NamingIndexServiceSize,
                 record: MetricsRecord::Gauge(service_size as f32),
             },
+            MetricsItem {
+                metrics_type: MetricsKey::NamingInstanceRegisterCount,
+                record: MetricsRecord::CounterInc(event_counter.register),
+            },
+            MetricsItem {
+                metrics_type: MetricsKey::NamingInstanceDeregisterCount,
+                record: MetricsRecord::CounterInc(event_counter.deregister),
+            },
+            MetricsItem {
+                metrics_type: MetricsKey::NamingInstanceUnhealthyCount,
+                record: MetricsRecord::CounterInc(event_counter.unhealthy),
+            },
         ];
         Ok(list)
     }
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 pub mod api_model;
 pub mod core;
 pub(crate) mod filter;
+pub mod instance_event;
 pub mod listener;
 pub mod model;
 pub mod naming_delay_nofity;
@@ -29,6 +30,16 @@ pub const DEFAULT_NAMESPACE: &str = "public";
 pub const DEFAULT_CLUSTER: &str = "DEFAULT";
 pub const DEFAULT_GROUP: &str = "DEFAULT_GROUP";
 
+//兼容nacos实例元数据中自定义的心跳与删除超时时间(毫秒)
+pub const PRESERVED_HEART_BEAT_TIMEOUT: &str = "preserved.heart.beat.timeout";
+pub const PRESERVED_IP_DELETE_TIMEOUT: &str = "preserved.ip.delete.timeout";
+//实例元数据,为true时不做心跳过期检查(如consul不带TTL检查注册的实例)
+pub const PRESERVED_HEART_BEAT_DISABLE: &str = "preserved.heart.beat.disable";
+//实例元数据,记录下线引流状态,随实例同步到其它节点
+pub const PRESERVED_DRAIN_INFO: &str = "preserved.drain.info";
+//服务元数据,为true时不推送会使健康实例数降为0的变更
+pub const PRESERVED_PUSH_EMPTY_PROTECTION: &str = "preserved.push.empty.protection";
+
 impl NamingUtils {
     pub fn get_group_and_service_name(service_name: &str, group_name: &str) -> String {
         format!("{}@@{}", group_name, service_name)
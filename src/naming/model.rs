@@ -2,6 +2,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::{collections::HashMap, sync::Arc};
 
+use crate::naming::{
+    PRESERVED_DRAIN_INFO, PRESERVED_HEART_BEAT_DISABLE, PRESERVED_HEART_BEAT_TIMEOUT,
+    PRESERVED_IP_DELETE_TIMEOUT,
+};
 use crate::now_millis_i64;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,8 +47,34 @@ impl Instance {
     }
 
     pub fn is_enable_timeout(&self) -> bool {
-        //grpc 不走过期检查
-        !self.from_grpc && !self.is_from_cluster()
+        //grpc 与标记不做心跳检查的实例不走过期检查
+        !self.from_grpc && !self.is_from_cluster() && !self.is_heart_beat_disabled()
+    }
+
+    pub fn is_heart_beat_disabled(&self) -> bool {
+        self.metadata
+            .get(PRESERVED_HEART_BEAT_DISABLE)
+            .map(|v| v == "true")
+            .unwrap_or(false)
+    }
+
+    ///
+    /// 实例元数据中自定义的心跳超时时间
+    pub fn get_heart_beat_timeout(&self) -> Option<i64> {
+        self.get_metadata_millis(PRESERVED_HEART_BEAT_TIMEOUT)
+    }
+
+    ///
+    /// 实例元数据中自定义的删除超时时间
+    pub fn get_ip_delete_timeout(&self) -> Option<i64> {
+        self.get_metadata_millis(PRESERVED_IP_DELETE_TIMEOUT)
+    }
+
+    fn get_metadata_millis(&self, key: &str) -> Option<i64> {
+        self.metadata
+            .get(key)
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
     }
 
     pub fn generate_key(&mut self) {
@@ -107,6 +137,15 @@ impl Instance {
     pub fn get_id_string(&self) -> String {
         format!("{}#{}", &self.ip, &self.port)
     }
+
+    ///
+    /// nacos v2 格式的实例id: ip#port#cluster#group@@service
+    pub fn get_nacos_instance_id(&self) -> String {
+        format!(
+            "{}#{}#{}#{}",
+            &self.ip, &self.port, &self.cluster_name, &self.group_service
+        )
+    }
 }
 
 impl Default for Instance {
@@ -297,6 +336,208 @@ impl InstanceShortKey {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetadataBatchAction {
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadataFilter {
+    pub ip: Arc<String>,
+    pub port: u32,
+    pub cluster_name: Option<String>,
+}
+
+impl InstanceMetadataFilter {
+    pub fn is_match(&self, instance: &Instance) -> bool {
+        self.port == instance.port
+            && self.ip == instance.ip
+            && self
+                .cluster_name
+                .as_ref()
+                .map(|v| v.is_empty() || v == &instance.cluster_name)
+                .unwrap_or(true)
+    }
+}
+
+///
+/// 批量更新/删除服务下实例的元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadataBatch {
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    pub service_name: Arc<String>,
+    pub ephemeral: bool,
+    pub action: MetadataBatchAction,
+    /// 为空时对服务下所有同类型(ephemeral)实例生效
+    pub instances: Vec<InstanceMetadataFilter>,
+    pub metadata: HashMap<String, String>,
+}
+
+impl InstanceMetadataBatch {
+    pub fn get_service_key(&self) -> ServiceKey {
+        ServiceKey::new_by_arc(
+            self.namespace_id.clone(),
+            self.group_name.clone(),
+            self.service_name.clone(),
+        )
+    }
+
+    pub fn is_match(&self, instance: &Instance) -> bool {
+        if instance.ephemeral != self.ephemeral {
+            return false;
+        }
+        self.instances.is_empty() || self.instances.iter().any(|e| e.is_match(instance))
+    }
+
+    pub fn apply_metadata(&self, metadata: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut new_metadata = metadata.clone();
+        match self.action {
+            MetadataBatchAction::Update => {
+                for (k, v) in &self.metadata {
+                    new_metadata.insert(k.to_owned(), v.to_owned());
+                }
+            }
+            MetadataBatchAction::Delete => {
+                for k in self.metadata.keys() {
+                    new_metadata.remove(k);
+                }
+            }
+        }
+        new_metadata
+    }
+}
+
+/// 权重渐降的阶梯数,每一阶都会通知订阅方
+pub const DRAIN_WEIGHT_STEPS: u32 = 10;
+pub const DEFAULT_DRAIN_SECONDS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstanceDrainMode {
+    /// 在下线时间内逐步降低权重
+    Weight,
+    /// 立即设置为不可用,不再出现在实例列表中
+    Offline,
+}
+
+impl InstanceDrainMode {
+    pub fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("offline") {
+            Self::Offline
+        } else {
+            Self::Weight
+        }
+    }
+}
+
+///
+/// 实例下线引流,下线时间结束后删除实例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceDrainParam {
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    pub service_name: Arc<String>,
+    pub instance: InstanceMetadataFilter,
+    pub mode: InstanceDrainMode,
+    pub drain_seconds: u64,
+    /// 取消下线,恢复原权重与状态
+    pub cancel: bool,
+}
+
+impl InstanceDrainParam {
+    pub fn get_service_key(&self) -> ServiceKey {
+        ServiceKey::new_by_arc(
+            self.namespace_id.clone(),
+            self.group_name.clone(),
+            self.service_name.clone(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceDrainInfo {
+    pub mode: InstanceDrainMode,
+    pub origin_weight: f32,
+    pub origin_enabled: bool,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// 当前权重阶梯,不写入元数据,重新加载时按时间重新计算
+    #[serde(skip)]
+    pub current_step: u32,
+}
+
+impl InstanceDrainInfo {
+    pub fn new(mode: InstanceDrainMode, instance: &Instance, now: i64, drain_seconds: u64) -> Self {
+        Self {
+            mode,
+            origin_weight: instance.weight,
+            origin_enabled: instance.enabled,
+            start_time: now,
+            end_time: now + drain_seconds as i64 * 1000,
+            current_step: DRAIN_WEIGHT_STEPS,
+        }
+    }
+
+    ///
+    /// 从实例元数据中恢复下线引流状态
+    pub fn from_instance(instance: &Instance) -> Option<Self> {
+        let value = instance.metadata.get(PRESERVED_DRAIN_INFO)?;
+        match serde_json::from_str::<Self>(value) {
+            Ok(mut info) => {
+                info.current_step = DRAIN_WEIGHT_STEPS;
+                Some(info)
+            }
+            Err(err) => {
+                log::warn!("parse instance drain info error,{}", err);
+                None
+            }
+        }
+    }
+
+    ///
+    /// 下线引流状态写入元数据,通过控制台高优先级元数据保存,sdk重新注册时不会丢失
+    pub fn apply_to_metadata(&self, metadata: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut metadata = metadata.clone();
+        metadata.insert(
+            PRESERVED_DRAIN_INFO.to_owned(),
+            serde_json::to_string(self).unwrap_or_default(),
+        );
+        metadata
+    }
+
+    pub fn remove_from_metadata(metadata: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut metadata = metadata.clone();
+        metadata.remove(PRESERVED_DRAIN_INFO);
+        metadata
+    }
+
+    pub fn is_finished(&self, now: i64) -> bool {
+        now >= self.end_time
+    }
+
+    ///
+    /// 进入新的权重阶梯时返回新权重
+    pub fn next_step_weight(&mut self, now: i64) -> Option<f32> {
+        if self.mode != InstanceDrainMode::Weight || self.is_finished(now) {
+            return None;
+        }
+        let total = (self.end_time - self.start_time).max(1);
+        let remaining = (self.end_time - now).min(total);
+        let step = ((remaining * DRAIN_WEIGHT_STEPS as i64 + total - 1) / total) as u32;
+        if step < self.current_step {
+            self.current_step = step;
+            Some(self.origin_weight * step as f32 / DRAIN_WEIGHT_STEPS as f32)
+        } else {
+            None
+        }
+    }
+}
+
 pub enum UpdateInstanceType {
     None,
     New,
@@ -1,21 +1,98 @@
 #![allow(unused_imports)]
 
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
 use actix::prelude::*;
 use bean_factory::{bean, Inject};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     common::delay_notify::{DelayNotify, NotifyEvent},
-    grpc::bistream_manage::{BiStreamManage, BiStreamManageCmd},
-    now_millis,
+    grpc::bistream_manage::{BiStreamManage, BiStreamManageCmd, BiStreamManageResult},
+    now_millis, now_millis_i64,
 };
 
 use super::{
     core::{NamingActor, NamingCmd, NamingResult},
     model::{ServiceInfo, ServiceKey},
+    service::ServiceMetadata,
 };
 
+/// 保留最近的推送记录条数
+const PUSH_AUDIT_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PushResult {
+    #[default]
+    Pushed,
+    ///推送空保护,未推送
+    Protected,
+    QueryError,
+    ///本节点没有客户端连接
+    NotConnected,
+    SendError,
+}
+
+///
+/// 服务实例推送记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PushAuditRecord {
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    pub service_name: Arc<String>,
+    pub client_id: Arc<String>,
+    pub instance_count: usize,
+    pub healthy_count: usize,
+    pub result: PushResult,
+    pub time: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PushAuditQueryParam {
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub service_name: Option<String>,
+    pub client_id: Option<String>,
+    pub result: Option<PushResult>,
+    pub limit: Option<usize>,
+}
+
+impl PushAuditQueryParam {
+    pub fn is_match(&self, record: &PushAuditRecord) -> bool {
+        if let Some(v) = &self.namespace_id {
+            if v.as_str() != record.namespace_id.as_str() {
+                return false;
+            }
+        }
+        if let Some(v) = &self.group_name {
+            if v.as_str() != record.group_name.as_str() {
+                return false;
+            }
+        }
+        if let Some(v) = &self.service_name {
+            if v.as_str() != record.service_name.as_str() {
+                return false;
+            }
+        }
+        if let Some(v) = &self.client_id {
+            if v.as_str() != record.client_id.as_str() {
+                return false;
+            }
+        }
+        if let Some(v) = self.result {
+            if v != record.result {
+                return false;
+            }
+        }
+        true
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct NamingDelayEvent {
     pub key: ServiceKey,
@@ -46,12 +123,56 @@ impl NotifyEvent for NamingDelayEvent {
     }
 }
 
+///
+/// 待推送的服务事件
+pub struct PushItem {
+    event: NamingDelayEvent,
+    instance_count: usize,
+    healthy_count: usize,
+    time: i64,
+}
+
+impl PushItem {
+    fn build_records<F>(&self, f: F) -> Vec<PushAuditRecord>
+    where
+        F: Fn(&Arc<String>) -> PushResult,
+    {
+        self.event
+            .client_id_set
+            .iter()
+            .map(|client_id| PushAuditRecord {
+                namespace_id: self.event.key.namespace_id.clone(),
+                group_name: self.event.key.group_name.clone(),
+                service_name: self.event.key.service_name.clone(),
+                client_id: client_id.clone(),
+                instance_count: self.instance_count,
+                healthy_count: self.healthy_count,
+                result: f(client_id),
+                time: self.time,
+            })
+            .collect()
+    }
+
+    ///
+    /// delivered为已发送的客户端,为空表示发送失败
+    fn build_push_records(&self, delivered: Option<&HashSet<Arc<String>>>) -> Vec<PushAuditRecord> {
+        self.build_records(|client_id| match delivered {
+            Some(set) if set.contains(client_id) => PushResult::Pushed,
+            Some(_) => PushResult::NotConnected,
+            None => PushResult::SendError,
+        })
+    }
+}
+
 #[bean(inject)]
 pub struct DelayNotifyActor {
     inner_delay_notify: DelayNotify<ServiceKey, NamingDelayEvent>,
     conn_manage: Option<Addr<BiStreamManage>>,
     naming_addr: Option<Addr<NamingActor>>,
     delay: u64,
+    //最近一次推送的健康实例数,用于推送空保护
+    last_push_healthy_counts: HashMap<ServiceKey, usize>,
+    push_audit: VecDeque<PushAuditRecord>,
 }
 
 impl Default for DelayNotifyActor {
@@ -67,6 +188,8 @@ impl DelayNotifyActor {
             conn_manage: None,
             naming_addr: None,
             delay: 500,
+            last_push_healthy_counts: Default::default(),
+            push_audit: Default::default(),
         }
     }
 
@@ -74,29 +197,38 @@ impl DelayNotifyActor {
         ctx.run_later(Duration::from_millis(500), |act, ctx| {
             let events = act.inner_delay_notify.timeout().unwrap_or_default();
             let naming_addr = act.naming_addr.clone();
-            async move {
-                Self::fill_event_data_and_notify(naming_addr, events).await;
-            }
-            .into_actor(act)
-            .map(|_, act, ctx| {
-                act.notify_heartbeat(ctx);
-            })
-            .wait(ctx);
+            async move { Self::fill_event_data(naming_addr, events).await }
+                .into_actor(act)
+                .then(|events, act, _ctx| {
+                    let pushes = act.notify_events(events);
+                    let conn_manage = act.conn_manage.clone();
+                    async move { Self::send_events(conn_manage, pushes).await }.into_actor(act)
+                })
+                .map(|records, act, ctx| {
+                    for record in records {
+                        act.add_push_audit(record);
+                    }
+                    act.notify_heartbeat(ctx);
+                })
+                .wait(ctx);
         });
     }
 
-    async fn fill_event_data_and_notify(
+    async fn fill_event_data(
         naming_addr: Option<Addr<NamingActor>>,
         events: Vec<NamingDelayEvent>,
-    ) {
+    ) -> Vec<(NamingDelayEvent, ServiceMetadata)> {
+        let mut list = Vec::with_capacity(events.len());
         if let Some(naming_addr) = naming_addr {
             for mut event in events {
                 //println!("fill_event_data_and_notify, {:?}",&event.key);
-                let cmd = NamingCmd::QueryServiceInfo(event.key.clone(), "".to_owned(), true);
+                let cmd = NamingCmd::QueryPushServiceInfo(event.key.clone());
+                let mut metadata = ServiceMetadata::default();
                 match naming_addr.send(cmd).await {
                     Ok(res) => {
-                        if let Ok(NamingResult::ServiceInfo(service_info)) = res {
+                        if let Ok(NamingResult::PushServiceInfo(service_info, v)) = res {
                             event.service_info = Some(service_info);
+                            metadata = v;
                         } else {
                             log::error!("fill_event_data_and_notify service_info is empty");
                         }
@@ -105,9 +237,122 @@ impl DelayNotifyActor {
                         log::error!("fill_event_data_and_notify error");
                     }
                 };
-                event.on_event().ok();
+                list.push((event, metadata));
             }
         }
+        list
+    }
+
+    ///
+    /// 判断各服务是否需要推送,返回需要推送的事件;未推送的事件直接记录推送记录
+    fn notify_events(&mut self, events: Vec<(NamingDelayEvent, ServiceMetadata)>) -> Vec<PushItem> {
+        let now = now_millis_i64();
+        let mut pushes = vec![];
+        for (event, metadata) in events {
+            let (instance_count, healthy_count) =
+                if let Some(hosts) = event.service_info.as_ref().and_then(|e| e.hosts.as_ref()) {
+                    let healthy_count = hosts.iter().filter(|e| e.healthy && e.enabled).count();
+                    (hosts.len(), healthy_count)
+                } else {
+                    (0, 0)
+                };
+            let item = PushItem {
+                event,
+                instance_count,
+                healthy_count,
+                time: now,
+            };
+            if item.event.service_info.is_none() {
+                self.add_push_audits(item.build_records(|_| PushResult::QueryError));
+            } else if self.is_push_empty_protected(&item.event.key, &metadata, healthy_count) {
+                log::warn!(
+                    "push empty protection,hold back naming push,service:{:?}",
+                    &item.event.key
+                );
+                self.add_push_audits(item.build_records(|_| PushResult::Protected));
+            } else {
+                if healthy_count > 0 {
+                    self.last_push_healthy_counts
+                        .insert(item.event.key.clone(), healthy_count);
+                } else {
+                    self.last_push_healthy_counts.remove(&item.event.key);
+                }
+                pushes.push(item);
+            }
+        }
+        pushes
+    }
+
+    ///
+    /// 推送服务实例,按推送结果生成推送记录
+    async fn send_events(
+        conn_manage: Option<Addr<BiStreamManage>>,
+        pushes: Vec<PushItem>,
+    ) -> Vec<PushAuditRecord> {
+        let mut records = vec![];
+        for item in pushes {
+            let delivered = if let (Some(conn_manage), Some(service_info)) =
+                (conn_manage.as_ref(), item.event.service_info.clone())
+            {
+                match conn_manage
+                    .send(BiStreamManageCmd::NotifyNaming(
+                        item.event.key.clone(),
+                        item.event.client_id_set.clone(),
+                        service_info,
+                    ))
+                    .await
+                {
+                    Ok(Ok(BiStreamManageResult::NotifyResult(delivered))) => Some(delivered),
+                    _ => None,
+                }
+            } else {
+                None
+            };
+            records.append(&mut item.build_push_records(delivered.as_ref()));
+        }
+        records
+    }
+
+    ///
+    /// 开启推送空保护的服务,健康实例数从非0降为0时不推送
+    fn is_push_empty_protected(
+        &self,
+        key: &ServiceKey,
+        metadata: &ServiceMetadata,
+        healthy_count: usize,
+    ) -> bool {
+        metadata.push_empty_protection
+            && healthy_count == 0
+            && self
+                .last_push_healthy_counts
+                .get(key)
+                .map(|v| *v > 0)
+                .unwrap_or(false)
+    }
+
+    fn add_push_audits(&mut self, records: Vec<PushAuditRecord>) {
+        for record in records {
+            self.add_push_audit(record);
+        }
+    }
+
+    fn add_push_audit(&mut self, record: PushAuditRecord) {
+        if self.push_audit.len() >= PUSH_AUDIT_SIZE {
+            self.push_audit.pop_front();
+        }
+        self.push_audit.push_back(record);
+    }
+
+    ///
+    /// 查询推送记录,最新的记录在前
+    fn query_push_audit(&self, param: &PushAuditQueryParam) -> Vec<PushAuditRecord> {
+        self.push_audit
+            .iter()
+            .rev()
+            .filter(|e| param.is_match(e))
+            .take(param.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
     }
 }
 
@@ -145,10 +390,12 @@ impl Supervised for DelayNotifyActor {
 #[rtype(result = "anyhow::Result<DelayNotifyResult>")]
 pub enum DelayNotifyCmd {
     Notify(ServiceKey, HashSet<Arc<String>>),
+    QueryPushAudit(PushAuditQueryParam),
 }
 
 pub enum DelayNotifyResult {
     None,
+    PushAudit(Vec<PushAuditRecord>),
 }
 
 impl Handler<DelayNotifyCmd> for DelayNotifyActor {
@@ -166,7 +413,101 @@ impl Handler<DelayNotifyCmd> for DelayNotifyActor {
                 self.inner_delay_notify
                     .add_event(self.delay, event.key.clone(), event)?;
             }
+            DelayNotifyCmd::QueryPushAudit(param) => {
+                return Ok(DelayNotifyResult::PushAudit(self.query_push_audit(&param)));
+            }
         }
         Ok(DelayNotifyResult::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::naming::model::Instance;
+
+    fn build_event(key: &ServiceKey, healthy: bool) -> NamingDelayEvent {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.healthy = healthy;
+        let mut client_id_set = HashSet::new();
+        client_id_set.insert(Arc::new("client01".to_owned()));
+        NamingDelayEvent {
+            key: key.clone(),
+            client_id_set,
+            service_info: Some(ServiceInfo {
+                hosts: Some(vec![Arc::new(instance)]),
+                ..Default::default()
+            }),
+            conn_manage: None,
+        }
+    }
+
+    fn notify_and_push(
+        actor: &mut DelayNotifyActor,
+        events: Vec<(NamingDelayEvent, ServiceMetadata)>,
+        delivered: Option<&HashSet<Arc<String>>>,
+    ) {
+        for item in actor.notify_events(events) {
+            let records = item.build_push_records(delivered);
+            actor.add_push_audits(records);
+        }
+    }
+
+    #[test]
+    fn test_push_empty_protection() {
+        let key = ServiceKey::new("public", "DEFAULT_GROUP", "foo");
+        let metadata = ServiceMetadata {
+            push_empty_protection: true,
+            ..Default::default()
+        };
+        let mut delivered = HashSet::new();
+        delivered.insert(Arc::new("client01".to_owned()));
+        let mut actor = DelayNotifyActor::new();
+        notify_and_push(
+            &mut actor,
+            vec![(build_event(&key, true), metadata.clone())],
+            Some(&delivered),
+        );
+        notify_and_push(
+            &mut actor,
+            vec![(build_event(&key, false), metadata.clone())],
+            Some(&delivered),
+        );
+        notify_and_push(
+            &mut actor,
+            vec![(build_event(&key, false), ServiceMetadata::default())],
+            Some(&delivered),
+        );
+        let list = actor.query_push_audit(&PushAuditQueryParam::default());
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].result, PushResult::Pushed);
+        assert_eq!(list[1].result, PushResult::Protected);
+        assert_eq!(list[2].result, PushResult::Pushed);
+        assert_eq!(list[2].healthy_count, 1);
+        let param = PushAuditQueryParam {
+            result: Some(PushResult::Protected),
+            ..Default::default()
+        };
+        assert_eq!(actor.query_push_audit(&param).len(), 1);
+    }
+
+    #[test]
+    fn test_push_audit_result() {
+        let key = ServiceKey::new("public", "DEFAULT_GROUP", "foo");
+        let mut actor = DelayNotifyActor::new();
+        notify_and_push(
+            &mut actor,
+            vec![(build_event(&key, true), ServiceMetadata::default())],
+            Some(&HashSet::new()),
+        );
+        notify_and_push(
+            &mut actor,
+            vec![(build_event(&key, true), ServiceMetadata::default())],
+            None,
+        );
+        let list = actor.query_push_audit(&PushAuditQueryParam::default());
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].result, PushResult::SendError);
+        assert_eq!(list[1].result, PushResult::NotConnected);
+    }
+}
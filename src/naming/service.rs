@@ -8,6 +8,7 @@ use std::{
 
 use crate::common::constant::EMPTY_ARC_STRING;
 use crate::naming::cluster::model::ProcessRange;
+use crate::naming::PRESERVED_PUSH_EMPTY_PROTECTION;
 use crate::now_millis;
 use actix_web::rt;
 use inner_mem_cache::TimeoutSet;
@@ -24,6 +25,7 @@ use super::{
 #[derive(Debug, Clone, Default)]
 pub struct ServiceMetadata {
     pub protect_threshold: f32,
+    pub push_empty_protection: bool,
 }
 
 type InstanceMetaData = Arc<HashMap<String, String>>;
@@ -208,8 +210,11 @@ impl Service {
         }
     }
 
+    ///
+    /// 实例元数据自定义的超时时间只在大于全局超时时间时生效,未到期的实例延后检查
     pub(crate) fn time_check(
         &mut self,
+        current_time: i64,
         healthy_time: i64,
         offline_time: i64,
     ) -> (Vec<InstanceShortKey>, Vec<InstanceShortKey>) {
@@ -219,6 +224,14 @@ impl Service {
                 if !instance.is_enable_timeout() || instance.last_modified_millis > offline_time {
                     continue;
                 }
+                if let Some(timeout) = instance.get_ip_delete_timeout() {
+                    let expire_time = instance.last_modified_millis + timeout;
+                    if expire_time > current_time {
+                        let check_time = expire_time - (current_time - offline_time);
+                        self.unhealthy_timeout_set.add(check_time as u64, key);
+                        continue;
+                    }
+                }
             }
             self.remove_instance(&key, None);
             remove_list.push(key);
@@ -229,6 +242,14 @@ impl Service {
                 if !instance.is_enable_timeout() || instance.last_modified_millis > healthy_time {
                     continue;
                 }
+                if let Some(timeout) = instance.get_heart_beat_timeout() {
+                    let expire_time = instance.last_modified_millis + timeout;
+                    if expire_time > current_time {
+                        let check_time = expire_time - (current_time - healthy_time);
+                        self.healthy_timeout_set.add(check_time as u64, key);
+                        continue;
+                    }
+                }
             }
             self.update_instance_healthy_invalid(&key);
             update_list.push(key);
@@ -326,6 +347,11 @@ impl Service {
     pub fn get_metadata(&self) -> ServiceMetadata {
         ServiceMetadata {
             protect_threshold: self.protect_threshold,
+            push_empty_protection: self
+                .metadata
+                .get(PRESERVED_PUSH_EMPTY_PROTECTION)
+                .map(|v| v == "true")
+                .unwrap_or(false),
         }
     }
 
@@ -96,6 +96,7 @@ async fn do_login(
                 username: user.username,
                 roles: user.roles.unwrap_or_default(),
                 extend_infos: user.extend_info.unwrap_or_default(),
+                namespace_privilege: user.namespace_privilege,
             });
             let cache_req = CacheManagerReq::Set {
                 key: CacheKey::new(CacheType::ApiTokenSession, token.clone()),
@@ -21,7 +21,7 @@ use crate::config::ConfigUtils;
 use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
 use crate::merge_web_param;
 use crate::openapi::constant::EMPTY;
-use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
+use crate::raft::cluster::model::{DelConfigReq, ReadConsistency, SetConfigReq};
 use crate::utils::select_option_by_clone;
 
 pub(super) fn service() -> Scope {
@@ -48,6 +48,7 @@ pub struct ConfigWebParams {
     pub search: Option<String>,   //search type
     pub page_no: Option<usize>,   //use at search
     pub page_size: Option<usize>, //use at search
+    pub consistency: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -99,6 +100,7 @@ impl ConfigWebParams {
             search: OptionUtils::select(self.search, other.search),
             page_no: OptionUtils::select(self.page_no, other.page_no),
             page_size: OptionUtils::select(self.page_size, other.page_size),
+            consistency: OptionUtils::select(self.consistency, other.consistency),
         }
     }
 
@@ -274,6 +276,14 @@ pub(crate) async fn get_config(
         }
     };
     let param = web_param.to_confirmed_param();
+    let consistency = ReadConsistency::from_param(web_param.consistency.as_deref());
+    if let Err(err) = appdata
+        .config_route
+        .check_read_consistency(consistency)
+        .await
+    {
+        return HttpResponse::ServiceUnavailable().body(err.to_string());
+    }
     match param {
         Ok(p) => {
             let cmd = ConfigCmd::GET(ConfigKey::new(&p.data_id, &p.group, &p.tenant));
@@ -8,7 +8,7 @@ pub mod v2;
 
 /// current implement for version 1
 pub fn openapi_service(conf: RouteConf) -> Vec<Scope> {
-    vec![openapi_v1_route(conf)]
+    vec![openapi_v1_route(conf.clone()), v2::openapi_v2_route(conf)]
 }
 
 pub fn openapi_v1_route(_conf: RouteConf) -> Scope {
@@ -1 +1,292 @@
+use std::sync::Arc;
 
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder, Scope};
+
+use crate::common::appdata::AppShareData;
+use crate::common::model::TokenSession;
+use crate::config::config_index::ConfigQueryParam;
+use crate::config::core::{ConfigCmd, ConfigKey, ConfigResult};
+use crate::config::dal::ConfigHistoryParam;
+use crate::openapi::config::v2::model::{
+    ConfigBasicInfo, ConfigHistoryItem, ConfigHistoryV2Params, ConfigV2Params, PageV2,
+};
+use crate::openapi::constant::EMPTY;
+use crate::openapi::v2::model::{
+    ApiResult, ERROR_CODE_PARAMETER_MISSING, ERROR_CODE_RESOURCE_NOT_FOUND,
+};
+use crate::raft::cluster::model::{DelConfigReq, ReadConsistency, SetConfigReq};
+use crate::{api_namespace_privilege, api_v2_no_namespace_permission, merge_web_param};
+
+pub(super) fn config_service() -> Scope {
+    web::scope("/config").service(
+        web::resource(EMPTY)
+            .route(web::get().to(get_config))
+            .route(web::post().to(publish_config))
+            .route(web::put().to(publish_config))
+            .route(web::delete().to(remove_config)),
+    )
+}
+
+pub(super) fn history_service() -> Scope {
+    web::scope("/history")
+        .service(web::resource(EMPTY).route(web::get().to(get_history)))
+        .service(web::resource("/list").route(web::get().to(query_history_list)))
+        .service(web::resource("/previous").route(web::get().to(get_previous_history)))
+        .service(web::resource("/configs").route(web::get().to(query_namespace_configs)))
+}
+
+fn param_error(msg: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResult::<()>::error(
+        ERROR_CODE_PARAMETER_MISSING,
+        msg,
+        (),
+    ))
+}
+
+pub async fn get_config(
+    req: HttpRequest,
+    param: web::Query<ConfigV2Params>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = match param.0.to_key() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&key.tenant) {
+        api_v2_no_namespace_permission!(&key.tenant);
+    }
+    let consistency = ReadConsistency::from_param(param.consistency.as_deref());
+    if let Err(e) = appdata
+        .config_route
+        .check_read_consistency(consistency)
+        .await
+    {
+        return HttpResponse::ServiceUnavailable().json(ApiResult::server_error(e.to_string()));
+    }
+    match appdata.config_addr.send(ConfigCmd::GET(key)).await {
+        Ok(Ok(ConfigResult::Data { value, .. })) => {
+            HttpResponse::Ok().json(ApiResult::success(value))
+        }
+        Ok(_) => HttpResponse::NotFound().json(ApiResult::<()>::error(
+            ERROR_CODE_RESOURCE_NOT_FOUND,
+            "config data not exist".to_owned(),
+            (),
+        )),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
+
+pub async fn publish_config(
+    req: HttpRequest,
+    param: web::Query<ConfigV2Params>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let key = match param.to_key() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let content = match &param.content {
+        Some(v) if !v.is_empty() => v.to_owned(),
+        _ => return param_error("required parameter 'content' is missing".to_owned()),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&key.tenant) {
+        api_v2_no_namespace_permission!(&key.tenant);
+    }
+    let mut set_req = SetConfigReq::new(key, Arc::new(content));
+    set_req.config_type = param.get_config_type();
+    set_req.desc = param.desc.map(Arc::new);
+    set_req.op_user = req
+        .extensions()
+        .get::<Arc<TokenSession>>()
+        .map(|session| session.username.clone());
+    match appdata.config_route.set_config(set_req).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(true)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
+
+pub async fn remove_config(
+    req: HttpRequest,
+    param: web::Query<ConfigV2Params>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let key = match param.to_key() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&key.tenant) {
+        api_v2_no_namespace_permission!(&key.tenant);
+    }
+    match appdata
+        .config_route
+        .del_config(DelConfigReq::new(key))
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(true)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
+
+///
+/// 查询配置历史记录,按id倒序返回
+async fn query_histories(
+    key: &ConfigKey,
+    offset: usize,
+    limit: Option<usize>,
+    appdata: &web::Data<Arc<AppShareData>>,
+) -> anyhow::Result<(usize, Vec<ConfigHistoryItem>)> {
+    let param = ConfigHistoryParam {
+        data_id: Some(key.data_id.as_ref().to_owned()),
+        group: Some(key.group.as_ref().to_owned()),
+        tenant: Some(key.tenant.as_ref().to_owned()),
+        offset: Some(offset as i64),
+        limit: limit.map(|v| v as i64),
+        ..Default::default()
+    };
+    let cmd = ConfigCmd::QueryHistoryPageInfo(Box::new(param));
+    match appdata.config_addr.send(cmd).await?? {
+        ConfigResult::ConfigHistoryInfoPage(total, list) => Ok((
+            total,
+            list.into_iter().map(ConfigHistoryItem::from).collect(),
+        )),
+        _ => Err(anyhow::anyhow!("query config history error")),
+    }
+}
+
+///
+/// 按id查询单条历史记录,previous为true时查询该id的上一个版本
+async fn query_history(
+    key: ConfigKey,
+    id: u64,
+    previous: bool,
+    appdata: &web::Data<Arc<AppShareData>>,
+) -> anyhow::Result<Option<ConfigHistoryItem>> {
+    let cmd = ConfigCmd::QueryHistoryInfo { key, id, previous };
+    match appdata.config_addr.send(cmd).await?? {
+        ConfigResult::ConfigHistoryInfo(info) => Ok(info.map(ConfigHistoryItem::from)),
+        _ => Err(anyhow::anyhow!("query config history error")),
+    }
+}
+
+pub async fn query_history_list(
+    req: HttpRequest,
+    param: web::Query<ConfigHistoryV2Params>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = match param.to_key() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&key.tenant) {
+        api_v2_no_namespace_permission!(&key.tenant);
+    }
+    let page_no = param.page_no.unwrap_or(1).max(1);
+    let page_size = param.page_size.unwrap_or(100).max(1);
+    let offset = (page_no - 1) * page_size;
+    match query_histories(&key, offset, Some(page_size), &appdata).await {
+        Ok((total_count, page_items)) => HttpResponse::Ok().json(ApiResult::success(PageV2 {
+            total_count,
+            page_number: page_no,
+            pages_available: total_count.div_ceil(page_size),
+            page_items,
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
+
+pub async fn get_history(
+    req: HttpRequest,
+    param: web::Query<ConfigHistoryV2Params>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = match param.to_key() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let nid = match param.nid {
+        Some(v) => v,
+        None => return param_error("required parameter 'nid' is missing".to_owned()),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&key.tenant) {
+        api_v2_no_namespace_permission!(&key.tenant);
+    }
+    match query_history(key, nid as u64, false, &appdata).await {
+        Ok(Some(item)) => HttpResponse::Ok().json(ApiResult::success(item)),
+        Ok(None) => HttpResponse::NotFound().json(ApiResult::<()>::error(
+            ERROR_CODE_RESOURCE_NOT_FOUND,
+            "certain config history does not exist".to_owned(),
+            (),
+        )),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
+
+pub async fn get_previous_history(
+    req: HttpRequest,
+    param: web::Query<ConfigHistoryV2Params>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = match param.to_key() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let id = match param.id {
+        Some(v) => v,
+        None => return param_error("required parameter 'id' is missing".to_owned()),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&key.tenant) {
+        api_v2_no_namespace_permission!(&key.tenant);
+    }
+    match query_history(key, id as u64, true, &appdata).await {
+        Ok(Some(item)) => HttpResponse::Ok().json(ApiResult::success(item)),
+        Ok(None) => HttpResponse::NotFound().json(ApiResult::<()>::error(
+            ERROR_CODE_RESOURCE_NOT_FOUND,
+            "previous config history does not exist".to_owned(),
+            (),
+        )),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
+
+pub async fn query_namespace_configs(
+    req: HttpRequest,
+    param: web::Query<ConfigHistoryV2Params>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let tenant = Arc::new(param.get_tenant());
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&tenant) {
+        api_v2_no_namespace_permission!(&tenant);
+    }
+    let query_param = ConfigQueryParam {
+        tenant: Some(tenant),
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let cmd = ConfigCmd::QueryPageInfo(Box::new(query_param));
+    match appdata.config_addr.send(cmd).await {
+        Ok(Ok(ConfigResult::ConfigInfoPage(_, list))) => {
+            HttpResponse::Ok().json(ApiResult::success(
+                list.into_iter()
+                    .map(ConfigBasicInfo::from)
+                    .collect::<Vec<_>>(),
+            ))
+        }
+        Ok(Ok(_)) => HttpResponse::InternalServerError()
+            .json(ApiResult::server_error("query config error".to_owned())),
+        Ok(Err(e)) => {
+            HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string()))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
@@ -1 +1,13 @@
+use actix_web::{web, Scope};
+
+use crate::openapi::constant::CONFIG_V2_BASE_PATH;
+use crate::openapi::RouteConf;
+
 mod api;
+pub mod model;
+
+pub fn openapi_v2_route(_conf: RouteConf) -> Scope {
+    web::scope(CONFIG_V2_BASE_PATH)
+        .service(api::config_service())
+        .service(api::history_service())
+}
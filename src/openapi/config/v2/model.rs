@@ -0,0 +1,164 @@
+use crate::common::option_utils::OptionUtils;
+use crate::common::string_utils::StringUtils;
+use crate::config::config_type::ConfigType;
+use crate::config::core::{ConfigHistoryInfoDto, ConfigInfoDto, ConfigKey};
+use crate::config::model::HISTORY_OP_TYPE_UPDATE;
+use crate::config::utils::param_utils;
+use crate::config::ConfigUtils;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const DEFAULT_GROUP: &str = "DEFAULT_GROUP";
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigV2Params {
+    pub data_id: Option<String>,
+    pub group: Option<String>,
+    pub namespace_id: Option<String>,
+    /// 兼容v1 tenant参数
+    pub tenant: Option<String>,
+    pub content: Option<String>,
+    pub desc: Option<String>,
+    pub r#type: Option<String>,
+    /// 读取一致性级别,linearizable表示线性一致读
+    pub consistency: Option<String>,
+}
+
+impl ConfigV2Params {
+    pub(crate) fn merge(self, o: Self) -> Self {
+        Self {
+            data_id: OptionUtils::select(self.data_id, o.data_id),
+            group: OptionUtils::select(self.group, o.group),
+            namespace_id: OptionUtils::select(self.namespace_id, o.namespace_id),
+            tenant: OptionUtils::select(self.tenant, o.tenant),
+            content: OptionUtils::select(self.content, o.content),
+            desc: OptionUtils::select(self.desc, o.desc),
+            r#type: OptionUtils::select(self.r#type, o.r#type),
+            consistency: OptionUtils::select(self.consistency, o.consistency),
+        }
+    }
+
+    pub(crate) fn get_tenant(&self) -> Option<String> {
+        OptionUtils::select(self.namespace_id.clone(), self.tenant.clone())
+    }
+
+    pub(crate) fn to_key(&self) -> Result<ConfigKey, String> {
+        let tenant = self.get_tenant();
+        param_utils::check_tenant(&tenant).map_err(|e| e.to_string())?;
+        let data_id = match &self.data_id {
+            Some(v) if !v.is_empty() => v.to_owned(),
+            _ => return Err("required parameter 'dataId' is missing".to_owned()),
+        };
+        let group = match &self.group {
+            Some(v) if !v.is_empty() => v.to_owned(),
+            _ => DEFAULT_GROUP.to_owned(),
+        };
+        let key = ConfigKey::new(
+            &data_id,
+            &group,
+            &ConfigUtils::default_tenant(tenant.unwrap_or_default()),
+        );
+        key.is_valid().map_err(|e| e.to_string())?;
+        Ok(key)
+    }
+
+    pub(crate) fn get_config_type(&self) -> Option<Arc<String>> {
+        StringUtils::map_not_empty(self.r#type.clone())
+            .map(|v| ConfigType::new_by_value(v.as_ref()).get_value())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHistoryV2Params {
+    pub data_id: Option<String>,
+    pub group: Option<String>,
+    pub namespace_id: Option<String>,
+    pub tenant: Option<String>,
+    /// 历史记录id, /history 接口使用
+    pub nid: Option<i64>,
+    /// 历史记录id, /history/previous 接口使用
+    pub id: Option<i64>,
+    pub page_no: Option<usize>,
+    pub page_size: Option<usize>,
+}
+
+impl ConfigHistoryV2Params {
+    pub(crate) fn get_tenant(&self) -> String {
+        ConfigUtils::default_tenant(
+            OptionUtils::select(self.namespace_id.clone(), self.tenant.clone()).unwrap_or_default(),
+        )
+    }
+
+    pub(crate) fn to_key(&self) -> Result<ConfigKey, String> {
+        ConfigV2Params {
+            data_id: self.data_id.clone(),
+            group: self.group.clone(),
+            namespace_id: self.namespace_id.clone(),
+            tenant: self.tenant.clone(),
+            ..Default::default()
+        }
+        .to_key()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHistoryItem {
+    pub id: String,
+    pub data_id: Option<String>,
+    pub group: Option<String>,
+    pub tenant: Option<String>,
+    pub content: Option<String>,
+    pub op_type: String,
+    pub last_modified_time: Option<i64>,
+}
+
+impl From<ConfigHistoryInfoDto> for ConfigHistoryItem {
+    fn from(v: ConfigHistoryInfoDto) -> Self {
+        Self {
+            id: v.id.unwrap_or_default().to_string(),
+            data_id: v.data_id,
+            group: v.group,
+            tenant: v.tenant,
+            content: v.content,
+            //旧版本历史记录没有操作类型,按更新处理
+            op_type: v
+                .op_type
+                .unwrap_or_else(|| HISTORY_OP_TYPE_UPDATE.to_owned()),
+            last_modified_time: v.modified_time,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBasicInfo {
+    pub data_id: Arc<String>,
+    pub group: Arc<String>,
+    pub tenant: Arc<String>,
+    pub md5: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+}
+
+impl From<ConfigInfoDto> for ConfigBasicInfo {
+    fn from(v: ConfigInfoDto) -> Self {
+        Self {
+            data_id: v.data_id,
+            group: v.group,
+            tenant: v.tenant,
+            md5: v.md5,
+            desc: v.desc,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PageV2<T> {
+    pub total_count: usize,
+    pub page_number: usize,
+    pub pages_available: usize,
+    pub page_items: Vec<T>,
+}
@@ -1,6 +1,8 @@
 use crate::common::appdata::AppShareData;
 use crate::health::model::{CheckHealthResult, HealthManagerRequest, HealthManagerResponse};
+use crate::transfer::backup_scheduler::{BackupSchedulerRequest, BackupSchedulerResponse};
 use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
 use std::sync::Arc;
 
 pub(crate) async fn health_info(appdata: web::Data<Arc<AppShareData>>) -> impl Responder {
@@ -20,9 +22,27 @@ pub(crate) async fn health_info(appdata: web::Data<Arc<AppShareData>>) -> impl R
     }
 }
 
+///
+/// 定时备份状态;备份失败不影响节点健康状态
+pub(crate) async fn backup_info(appdata: web::Data<Arc<AppShareData>>) -> impl Responder {
+    if let Ok(Ok(BackupSchedulerResponse::Status { enable, status })) = appdata
+        .backup_scheduler
+        .send(BackupSchedulerRequest::QueryStatus)
+        .await
+    {
+        HttpResponse::Ok().json(json!({
+            "enable": enable,
+            "status": status,
+        }))
+    } else {
+        HttpResponse::InternalServerError().body("request backup_scheduler error")
+    }
+}
+
 pub fn health_config(config: &mut web::ServiceConfig) {
     config
         .service(web::resource("/health").route(web::get().to(health_info)))
         .service(web::resource("/nacos/health").route(web::get().to(health_info)))
-        .service(web::resource("/rnacos/health").route(web::get().to(health_info)));
+        .service(web::resource("/rnacos/health").route(web::get().to(health_info)))
+        .service(web::resource("/rnacos/health/backup").route(web::get().to(backup_info)));
 }
@@ -1,5 +1,5 @@
 use crate::common::appdata::AppShareData;
-use crate::common::constant::{AUTHORIZATION_HEADER, EMPTY_ARC_STRING};
+use crate::common::constant::{AUTHORIZATION_HEADER, CONSUL_TOKEN_HEADER, EMPTY_ARC_STRING};
 use crate::common::datetime_utils;
 use crate::common::model::TokenSession;
 use crate::metrics::core::MetricsManager;
@@ -24,6 +24,8 @@ lazy_static::lazy_static! {
         "/nacos/v1/auth/login", "/nacos/v1/auth/users/login","/nacos/metrics"
     ];
     pub static ref API_PATH: Regex = Regex::new(r"(?i)/nacos/.*").unwrap();
+    pub static ref CONSUL_API_PATH: Regex = Regex::new(r"^/v1/(agent|catalog|health|kv)/").unwrap();
+    pub static ref EUREKA_API_PATH: Regex = Regex::new(r"^/eureka/(v2/)?apps/").unwrap();
     pub static ref IGNORE_METRICS_PATH: Vec<&'static str> = vec![
         "/nacos/v1/cs/configs/listener"
     ];
@@ -84,8 +86,12 @@ where
         let mut request = req;
         let enable_auth = self.app_share_data.sys_config.openapi_enable_auth;
         let path = request.path();
+        //eureka只校验注册、注销等写操作,查询接口保持匿名访问
+        let is_eureka_write =
+            EUREKA_API_PATH.is_match(path) && request.method() != actix_web::http::Method::GET;
         let is_check_path = if enable_auth {
-            API_PATH.is_match(path) && !IGNORE_PATH.contains(&path)
+            (API_PATH.is_match(path) || CONSUL_API_PATH.is_match(path) || is_eureka_write)
+                && !IGNORE_PATH.contains(&path)
         } else {
             true
         };
@@ -98,6 +104,8 @@ where
             let token = if enable_auth && is_check_path {
                 if let Some(v) = request.headers().get(AUTHORIZATION_HEADER) {
                     Arc::new(v.to_str().unwrap_or_default().to_owned())
+                } else if let Some(v) = request.headers().get(CONSUL_TOKEN_HEADER) {
+                    Arc::new(v.to_str().unwrap_or_default().to_owned())
                 } else if let Ok(info) =
                     serde_urlencoded::from_str::<AccessInfo>(request.query_string())
                 {
@@ -67,25 +67,7 @@ pub async fn update_instance(
     appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
     let param = merge_web_param!(param.0, payload);
-    let update_tag = InstanceUpdateTag {
-        weight: match &param.weight {
-            Some(v) => *v != 1.0f32,
-            None => false,
-        },
-        metadata: match &param.metadata {
-            Some(v) => !v.is_empty() && v != "{}",
-            None => false,
-        },
-        enabled: match &param.enabled {
-            Some(v) => true,
-            None => false,
-        },
-        ephemeral: match &param.ephemeral {
-            Some(v) => true,
-            None => false,
-        },
-        from_update: true,
-    };
+    let update_tag = param.get_update_tag();
     let instance = param.convert_to_instance();
     match instance {
         Ok(instance) => {
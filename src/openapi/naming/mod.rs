@@ -7,11 +7,16 @@ mod catalog;
 pub(crate) mod instance;
 pub mod model;
 mod operator;
+mod prometheus;
 pub(crate) mod service;
 mod v2;
 
 pub fn openapi_service(conf: RouteConf) -> Vec<Scope> {
-    vec![openapi_v1_route(conf)]
+    vec![
+        openapi_v1_route(conf.clone()),
+        v2::openapi_v2_route(conf),
+        prometheus::service(),
+    ]
 }
 
 pub fn openapi_v1_route(_conf: RouteConf) -> Scope {
@@ -1,7 +1,9 @@
 #![allow(unused_imports, unused_assignments, unused_variables)]
+use crate::common::model::privilege::NamespacePrivilegeGroup;
 use crate::common::option_utils::OptionUtils;
-use crate::naming::model::{Instance, ServiceKey};
+use crate::naming::model::{Instance, InstanceUpdateTag, ServiceKey};
 use crate::naming::service::SubscriberInfoDto;
+use crate::naming::service_index::ServiceQueryParam;
 use crate::naming::NamingUtils;
 use crate::utils::get_bool_from_string;
 use serde::{Deserialize, Serialize};
@@ -42,6 +44,22 @@ impl InstanceWebParams {
         }
     }
 
+    pub(crate) fn get_update_tag(&self) -> InstanceUpdateTag {
+        InstanceUpdateTag {
+            weight: match &self.weight {
+                Some(v) => *v != 1.0f32,
+                None => false,
+            },
+            metadata: match &self.metadata {
+                Some(v) => !v.is_empty() && v != "{}",
+                None => false,
+            },
+            enabled: self.enabled.is_some(),
+            ephemeral: self.ephemeral.is_some(),
+            from_update: true,
+        }
+    }
+
     pub(crate) fn convert_to_instance(self) -> Result<Instance, String> {
         let ip = if let Some(ip) = self.ip {
             Arc::new(ip)
@@ -108,6 +126,7 @@ pub struct InstanceWebQueryListParams {
     pub namespace_id: Option<String>,
     pub service_name: Option<String>,
     pub group_name: Option<String>,
+    #[serde(alias = "clusterName")]
     pub clusters: Option<String>,
     pub healthy_only: Option<String>,
     #[serde(rename = "clientIP")]
@@ -358,3 +377,110 @@ pub struct ServiceQuerySubscribersListResponce {
     pub count: usize,
     pub subscribers: Vec<Arc<SubscriberInfoDto>>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PrometheusSdParams {
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub service_name: Option<String>,
+    pub metadata_key: Option<String>,
+    pub metadata_value: Option<String>,
+}
+
+impl PrometheusSdParams {
+    pub fn get_namespace_id(&self) -> Option<Arc<String>> {
+        self.namespace_id
+            .clone()
+            .map(|v| Arc::new(NamingUtils::default_namespace(v)))
+    }
+
+    pub fn to_param(&self, namespace_privilege: NamespacePrivilegeGroup) -> ServiceQueryParam {
+        let (group, service) = match self.service_name.as_ref() {
+            Some(v) if v.contains("@@") => match NamingUtils::split_group_and_serivce_name(v) {
+                Some((group, service)) => (Some(group), Some(service)),
+                None => (self.group_name.clone(), Some(v.to_owned())),
+            },
+            _ => (self.group_name.clone(), self.service_name.clone()),
+        };
+        ServiceQueryParam {
+            namespace_id: self.get_namespace_id(),
+            group: group.map(Arc::new),
+            service: service.map(Arc::new),
+            namespace_privilege,
+            limit: 0xffff_ffff,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// 按元数据key过滤,设置metadataValue时需要值也相等
+    pub fn match_metadata(&self, instance: &Instance) -> bool {
+        let key = match self.metadata_key.as_ref() {
+            Some(v) if !v.is_empty() => v,
+            _ => return true,
+        };
+        match (instance.metadata.get(key), self.metadata_value.as_ref()) {
+            (Some(v), Some(expect)) if !expect.is_empty() => v == expect,
+            (Some(_), _) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+///
+/// prometheus http_sd 格式的监控目标
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct PrometheusTargetGroup {
+    pub targets: Vec<String>,
+    pub labels: HashMap<String, String>,
+}
+
+impl PrometheusTargetGroup {
+    pub fn from_instance(instance: &Instance) -> Self {
+        let target = if instance.ip.contains(':') {
+            format!("[{}]:{}", &instance.ip, instance.port)
+        } else {
+            format!("{}:{}", &instance.ip, instance.port)
+        };
+        let mut labels = HashMap::with_capacity(instance.metadata.len() + 4);
+        //元数据中不合法的label名称字符替换为'_',以'__'开头的保留label忽略
+        for (k, v) in instance.metadata.iter() {
+            let name = Self::label_name(k);
+            if !name.is_empty() && !name.starts_with("__") {
+                labels.insert(name, v.to_owned());
+            }
+        }
+        labels.insert(
+            "namespace".to_owned(),
+            instance.namespace_id.as_ref().to_owned(),
+        );
+        labels.insert("group".to_owned(), instance.group_name.as_ref().to_owned());
+        labels.insert(
+            "service".to_owned(),
+            instance.service_name.as_ref().to_owned(),
+        );
+        labels.insert("cluster".to_owned(), instance.cluster_name.to_owned());
+        Self {
+            targets: vec![target],
+            labels,
+        }
+    }
+
+    fn label_name(key: &str) -> String {
+        let mut name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+        name
+    }
+}
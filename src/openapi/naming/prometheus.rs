@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder, Scope};
+
+use crate::common::appdata::AppShareData;
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::openapi::constant::EMPTY;
+use crate::openapi::naming::model::{PrometheusSdParams, PrometheusTargetGroup};
+use crate::{api_namespace_privilege, api_v2_no_namespace_permission};
+
+///
+/// prometheus http_sd 服务发现接口,兼容nacos路径格式
+pub(super) fn service() -> Scope {
+    web::scope("/prometheus")
+        .service(web::resource(EMPTY).route(web::get().to(query_sd_targets)))
+        .service(web::resource("/namespaceId/{namespaceId}").route(web::get().to(query_sd_targets)))
+        .service(
+            web::resource("/namespaceId/{namespaceId}/service/{service}")
+                .route(web::get().to(query_sd_targets)),
+        )
+}
+
+pub async fn query_sd_targets(
+    req: HttpRequest,
+    param: web::Query<PrometheusSdParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let mut param = param.0;
+    if let Some(namespace_id) = req.match_info().get("namespaceId") {
+        param.namespace_id = Some(namespace_id.to_owned());
+    }
+    if let Some(service) = req.match_info().get("service") {
+        param.service_name = Some(service.to_owned());
+    }
+    let namespace_privilege = api_namespace_privilege!(req);
+    if let Some(namespace_id) = param.get_namespace_id() {
+        if !namespace_privilege.check_permission(&namespace_id) {
+            api_v2_no_namespace_permission!(&namespace_id);
+        }
+    }
+    let cmd = NamingCmd::QueryInstanceListByParam(param.to_param(namespace_privilege), true);
+    match appdata.naming_addr.send(cmd).await {
+        Ok(Ok(NamingResult::InstanceList(list))) => {
+            let targets: Vec<PrometheusTargetGroup> = list
+                .iter()
+                .filter(|e| e.healthy && e.enabled && param.match_metadata(e))
+                .map(|e| PrometheusTargetGroup::from_instance(e))
+                .collect();
+            HttpResponse::Ok().json(targets)
+        }
+        Ok(Ok(_)) => HttpResponse::InternalServerError().body("naming result error"),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
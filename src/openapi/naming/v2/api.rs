@@ -1 +1,497 @@
+use std::collections::HashMap;
+use std::sync::Arc;
 
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder, Scope};
+
+use crate::common::appdata::AppShareData;
+use crate::grpc::handler::converter::ModelConverter;
+use crate::naming::api_model::ServiceInfoParam;
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::naming::model::{InstanceUpdateTag, MetadataBatchAction, ServiceKey};
+use crate::naming::service_index::ServiceQueryParam;
+use crate::naming::{
+    NamingUtils, CLIENT_BEAT_INTERVAL_KEY, LIGHT_BEAT_ENABLED_KEY, RESPONSE_CODE_KEY,
+    RESPONSE_CODE_OK,
+};
+use crate::openapi::constant::EMPTY;
+use crate::openapi::naming::model::{
+    BeatRequest, InstanceWebParams, InstanceWebQueryListParams, ServiceQueryListRequest,
+};
+use crate::openapi::naming::v2::model::{
+    ClusterInfo, InstanceDrainParams, InstanceMetadataBatchParams, InstanceMetadataBatchResult,
+    ServiceDetailResult, ServiceListResult,
+};
+use crate::openapi::v2::model::{
+    ApiResult, ERROR_CODE_PARAMETER_MISSING, ERROR_CODE_PARAMETER_VALIDATE,
+    ERROR_CODE_RESOURCE_NOT_FOUND,
+};
+use crate::utils::get_bool_from_string;
+use crate::{api_namespace_privilege, api_v2_no_namespace_permission, merge_web_param};
+
+pub(super) fn instance_service() -> Scope {
+    web::scope("/instance")
+        .service(
+            web::resource(EMPTY)
+                .route(web::get().to(get_instance))
+                .route(web::post().to(register_instance))
+                .route(web::put().to(update_instance))
+                .route(web::delete().to(deregister_instance)),
+        )
+        .service(web::resource("/list").route(web::get().to(get_instance_list)))
+        .service(web::resource("/beat").route(web::put().to(beat_instance)))
+        .service(
+            web::resource("/metadata/batch")
+                .route(web::put().to(update_instance_metadata_batch))
+                .route(web::delete().to(remove_instance_metadata_batch)),
+        )
+        .service(
+            web::resource("/drain")
+                .route(web::put().to(drain_instance))
+                .route(web::delete().to(cancel_drain_instance)),
+        )
+}
+
+pub(super) fn service_service() -> Scope {
+    web::scope("/service")
+        .service(
+            web::resource(EMPTY)
+                .route(web::get().to(get_service))
+                .route(web::post().to(update_service))
+                .route(web::put().to(update_service))
+                .route(web::delete().to(remove_service)),
+        )
+        .service(web::resource("/list").route(web::get().to(get_service_list)))
+}
+
+fn param_error(msg: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResult::<()>::error(
+        ERROR_CODE_PARAMETER_MISSING,
+        msg,
+        (),
+    ))
+}
+
+fn not_found(msg: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(ApiResult::<()>::error(
+        ERROR_CODE_RESOURCE_NOT_FOUND,
+        msg.to_owned(),
+        (),
+    ))
+}
+
+fn server_error(msg: String) -> HttpResponse {
+    HttpResponse::InternalServerError().json(ApiResult::server_error(msg))
+}
+
+pub async fn get_instance(
+    req: HttpRequest,
+    param: web::Query<InstanceWebParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let instance = match param.0.convert_to_instance() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&instance.namespace_id) {
+        api_v2_no_namespace_permission!(&instance.namespace_id);
+    }
+    match appdata.naming_addr.send(NamingCmd::Query(instance)).await {
+        Ok(Ok(NamingResult::Instance(v))) => HttpResponse::Ok().json(ApiResult::success(
+            ModelConverter::to_api_instance(v.as_ref().clone()),
+        )),
+        Ok(Ok(_)) => not_found("instance not exist"),
+        Ok(Err(e)) => server_error(e.to_string()),
+        Err(e) => server_error(e.to_string()),
+    }
+}
+
+pub async fn register_instance(
+    req: HttpRequest,
+    param: web::Query<InstanceWebParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    do_update_instance(req, param, None, appdata).await
+}
+
+pub async fn update_instance(
+    req: HttpRequest,
+    param: web::Query<InstanceWebParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let update_tag = param.get_update_tag();
+    do_update_instance(req, param, Some(update_tag), appdata).await
+}
+
+async fn do_update_instance(
+    req: HttpRequest,
+    param: InstanceWebParams,
+    update_tag: Option<InstanceUpdateTag>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> HttpResponse {
+    let instance = match param.convert_to_instance() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    if !instance.check_vaild() {
+        return HttpResponse::BadRequest().json(ApiResult::<()>::error(
+            ERROR_CODE_PARAMETER_VALIDATE,
+            "instance check is invalid".to_owned(),
+            (),
+        ));
+    }
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&instance.namespace_id) {
+        api_v2_no_namespace_permission!(&instance.namespace_id);
+    }
+    match appdata
+        .naming_route
+        .update_instance(instance, update_tag)
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success("ok")),
+        Err(e) => server_error(e.to_string()),
+    }
+}
+
+pub async fn deregister_instance(
+    req: HttpRequest,
+    param: web::Query<InstanceWebParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let instance = match param.convert_to_instance() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&instance.namespace_id) {
+        api_v2_no_namespace_permission!(&instance.namespace_id);
+    }
+    match appdata.naming_route.delete_instance(instance).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success("ok")),
+        Err(e) => server_error(e.to_string()),
+    }
+}
+
+pub async fn get_instance_list(
+    req: HttpRequest,
+    param: web::Query<InstanceWebQueryListParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let only_healthy = get_bool_from_string(&param.healthy_only, false);
+    let (key, clusters) = match param.to_clusters_key() {
+        Ok(v) => v,
+        Err(e) => return param_error(e),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&key.namespace_id) {
+        api_v2_no_namespace_permission!(&key.namespace_id);
+    }
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryServiceInfo(key, clusters, only_healthy))
+        .await
+    {
+        Ok(Ok(NamingResult::ServiceInfo(v))) => {
+            HttpResponse::Ok().json(ApiResult::success(ModelConverter::to_api_service_info(v)))
+        }
+        Ok(Ok(_)) => server_error("query instance list error".to_owned()),
+        Ok(Err(e)) => server_error(e.to_string()),
+        Err(e) => server_error(e.to_string()),
+    }
+}
+
+pub async fn beat_instance(
+    req: HttpRequest,
+    param: web::Query<BeatRequest>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let instance = match param.convert_to_instance() {
+        Ok(v) => v,
+        Err(e) => return param_error(e.to_string()),
+    };
+    if !instance.check_vaild() {
+        return HttpResponse::BadRequest().json(ApiResult::<()>::error(
+            ERROR_CODE_PARAMETER_VALIDATE,
+            "instance check is invalid".to_owned(),
+            (),
+        ));
+    }
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&instance.namespace_id) {
+        api_v2_no_namespace_permission!(&instance.namespace_id);
+    }
+    let tag = InstanceUpdateTag {
+        weight: false,
+        enabled: false,
+        ephemeral: false,
+        metadata: false,
+        from_update: false,
+    };
+    match appdata
+        .naming_route
+        .update_instance(instance, Some(tag))
+        .await
+    {
+        Ok(_) => {
+            let mut result = HashMap::new();
+            result.insert(RESPONSE_CODE_KEY, serde_json::json!(RESPONSE_CODE_OK));
+            result.insert(CLIENT_BEAT_INTERVAL_KEY, serde_json::json!(5000));
+            result.insert(LIGHT_BEAT_ENABLED_KEY, serde_json::json!(true));
+            HttpResponse::Ok().json(ApiResult::success(result))
+        }
+        Err(e) => server_error(e.to_string()),
+    }
+}
+
+pub async fn get_service(
+    req: HttpRequest,
+    param: web::Query<ServiceInfoParam>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let service = match param.0.build_service_info() {
+        Ok(v) => v,
+        Err(e) => return param_error(e.to_string()),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&service.namespace_id) {
+        api_v2_no_namespace_permission!(&service.namespace_id);
+    }
+    let query_param = ServiceQueryParam {
+        namespace_id: Some(service.namespace_id.clone()),
+        group: Some(service.group_name.clone()),
+        service: Some(service.service_name.clone()),
+        limit: 1,
+        ..Default::default()
+    };
+    let info = match appdata
+        .naming_addr
+        .send(NamingCmd::QueryServiceInfoPage(query_param))
+        .await
+    {
+        Ok(Ok(NamingResult::ServiceInfoPage((_, mut list)))) if !list.is_empty() => list.remove(0),
+        Ok(Ok(_)) => return not_found("service not exist"),
+        Ok(Err(e)) => return server_error(e.to_string()),
+        Err(e) => return server_error(e.to_string()),
+    };
+    let key = service.to_service_key();
+    let mut cluster_map: HashMap<String, ClusterInfo> = HashMap::new();
+    if let Ok(Ok(NamingResult::InstanceList(instances))) = appdata
+        .naming_addr
+        .send(NamingCmd::QueryAllInstanceList(key.clone()))
+        .await
+    {
+        for instance in instances {
+            cluster_map
+                .entry(instance.cluster_name.clone())
+                .or_insert_with(|| ClusterInfo {
+                    cluster_name: instance.cluster_name.clone(),
+                    hosts: vec![],
+                })
+                .hosts
+                .push(ModelConverter::to_api_instance(instance.as_ref().clone()));
+        }
+    }
+    HttpResponse::Ok().json(ApiResult::success(ServiceDetailResult {
+        namespace_id: key.namespace_id,
+        group_name: info.group_name,
+        service_name: info.service_name,
+        protect_threshold: info.protect_threshold,
+        metadata: info.metadata,
+        cluster_map,
+    }))
+}
+
+pub async fn update_service(
+    req: HttpRequest,
+    param: web::Query<ServiceInfoParam>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let service = match param.build_service_info() {
+        Ok(v) => v,
+        Err(e) => return param_error(e.to_string()),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&service.namespace_id) {
+        api_v2_no_namespace_permission!(&service.namespace_id);
+    }
+    match appdata
+        .naming_addr
+        .send(NamingCmd::UpdateService(service))
+        .await
+    {
+        Ok(Ok(_)) => HttpResponse::Ok().json(ApiResult::success("ok")),
+        Ok(Err(e)) => server_error(e.to_string()),
+        Err(e) => server_error(e.to_string()),
+    }
+}
+
+pub async fn remove_service(
+    req: HttpRequest,
+    param: web::Query<ServiceInfoParam>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let service = match param.build_service_info() {
+        Ok(v) => v,
+        Err(e) => return param_error(e.to_string()),
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&service.namespace_id) {
+        api_v2_no_namespace_permission!(&service.namespace_id);
+    }
+    match appdata
+        .naming_addr
+        .send(NamingCmd::RemoveService(service.to_service_key()))
+        .await
+    {
+        Ok(Ok(_)) => HttpResponse::Ok().json(ApiResult::success("ok")),
+        Ok(Err(e)) => server_error(e.to_string()),
+        Err(e) => server_error(e.to_string()),
+    }
+}
+
+pub async fn get_service_list(
+    req: HttpRequest,
+    param: web::Query<ServiceQueryListRequest>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let page_size = param.page_size.unwrap_or(20);
+    let page_index = param.page_no.unwrap_or(1);
+    let namespace_id =
+        NamingUtils::default_namespace(param.namespace_id.clone().unwrap_or_default());
+    let group = NamingUtils::default_group(param.group_name.clone().unwrap_or_default());
+    let key = ServiceKey::new(&namespace_id, &group, "");
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&key.namespace_id) {
+        api_v2_no_namespace_permission!(&key.namespace_id);
+    }
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryServicePage(key, page_size, page_index))
+        .await
+    {
+        Ok(Ok(NamingResult::ServicePage((count, services)))) => {
+            HttpResponse::Ok().json(ApiResult::success(ServiceListResult { count, services }))
+        }
+        Ok(Ok(_)) => server_error("query service list error".to_owned()),
+        Ok(Err(e)) => server_error(e.to_string()),
+        Err(e) => server_error(e.to_string()),
+    }
+}
+
+pub async fn update_instance_metadata_batch(
+    req: HttpRequest,
+    param: web::Query<InstanceMetadataBatchParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    do_instance_metadata_batch(req, param, MetadataBatchAction::Update, appdata).await
+}
+
+pub async fn remove_instance_metadata_batch(
+    req: HttpRequest,
+    param: web::Query<InstanceMetadataBatchParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    do_instance_metadata_batch(req, param, MetadataBatchAction::Delete, appdata).await
+}
+
+async fn do_instance_metadata_batch(
+    req: HttpRequest,
+    param: InstanceMetadataBatchParams,
+    action: MetadataBatchAction,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> HttpResponse {
+    let batch = match param.build_batch(action) {
+        Ok(v) => v,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ApiResult::error(
+                ERROR_CODE_PARAMETER_MISSING,
+                e,
+                InstanceMetadataBatchResult::default(),
+            ))
+        }
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&batch.namespace_id) {
+        api_v2_no_namespace_permission!(&batch.namespace_id);
+    }
+    match appdata
+        .naming_route
+        .update_instance_metadata_batch(batch)
+        .await
+    {
+        Ok(updated) => {
+            HttpResponse::Ok().json(ApiResult::success(InstanceMetadataBatchResult { updated }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
+
+pub async fn drain_instance(
+    req: HttpRequest,
+    param: web::Query<InstanceDrainParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    do_drain_instance(req, param, false, appdata).await
+}
+
+pub async fn cancel_drain_instance(
+    req: HttpRequest,
+    param: web::Query<InstanceDrainParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    do_drain_instance(req, param, true, appdata).await
+}
+
+async fn do_drain_instance(
+    req: HttpRequest,
+    param: InstanceDrainParams,
+    cancel: bool,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> HttpResponse {
+    let param = match param.build_param(cancel) {
+        Ok(v) => v,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ApiResult::error(
+                ERROR_CODE_PARAMETER_MISSING,
+                e,
+                InstanceMetadataBatchResult::default(),
+            ))
+        }
+    };
+    let namespace_privilege = api_namespace_privilege!(req);
+    if !namespace_privilege.check_permission(&param.namespace_id) {
+        api_v2_no_namespace_permission!(&param.namespace_id);
+    }
+    match appdata.naming_route.drain_instance(param).await {
+        Ok(updated) if updated.is_empty() => HttpResponse::NotFound().json(ApiResult::error(
+            ERROR_CODE_RESOURCE_NOT_FOUND,
+            "instance not found".to_owned(),
+            InstanceMetadataBatchResult::default(),
+        )),
+        Ok(updated) => {
+            HttpResponse::Ok().json(ApiResult::success(InstanceMetadataBatchResult { updated }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(ApiResult::server_error(e.to_string())),
+    }
+}
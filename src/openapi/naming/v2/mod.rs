@@ -1 +1,13 @@
+use actix_web::{web, Scope};
+
+use crate::openapi::constant::NAMING_V2_BASE_PATH;
+use crate::openapi::RouteConf;
+
 mod api;
+pub mod model;
+
+pub fn openapi_v2_route(_conf: RouteConf) -> Scope {
+    web::scope(NAMING_V2_BASE_PATH)
+        .service(api::instance_service())
+        .service(api::service_service())
+}
@@ -0,0 +1,176 @@
+use crate::common::option_utils::OptionUtils;
+use crate::grpc::api_model::Instance as ApiInstance;
+use crate::naming::model::{
+    InstanceDrainMode, InstanceDrainParam, InstanceMetadataBatch, InstanceMetadataFilter,
+    MetadataBatchAction, DEFAULT_DRAIN_SECONDS,
+};
+use crate::naming::NamingUtils;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const CONSISTENCY_TYPE_PERSIST: &str = "persist";
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadataBatchParams {
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub service_name: Option<String>,
+    /// ephemeral 或 persist,默认为ephemeral
+    pub consistency_type: Option<String>,
+    /// json格式的实例列表: [{"ip":"127.0.0.1","port":8080,"clusterName":"DEFAULT"}]
+    pub instances: Option<String>,
+    pub metadata: Option<String>,
+}
+
+impl InstanceMetadataBatchParams {
+    pub(crate) fn merge(self, o: Self) -> Self {
+        Self {
+            namespace_id: OptionUtils::select(self.namespace_id, o.namespace_id),
+            group_name: OptionUtils::select(self.group_name, o.group_name),
+            service_name: OptionUtils::select(self.service_name, o.service_name),
+            consistency_type: OptionUtils::select(self.consistency_type, o.consistency_type),
+            instances: OptionUtils::select(self.instances, o.instances),
+            metadata: OptionUtils::select(self.metadata, o.metadata),
+        }
+    }
+
+    pub(crate) fn build_batch(
+        self,
+        action: MetadataBatchAction,
+    ) -> Result<InstanceMetadataBatch, String> {
+        let grouped_name = self.service_name.unwrap_or_default();
+        let (mut group_name, service_name) =
+            match NamingUtils::split_group_and_serivce_name(&grouped_name) {
+                Some(v) => v,
+                None => return Err("required parameter 'serviceName' is missing".to_owned()),
+            };
+        if let Some(v) = self.group_name {
+            if !v.is_empty() {
+                group_name = v;
+            }
+        }
+        let metadata = match self.metadata {
+            Some(v) => NamingUtils::parse_metadata(&v).map_err(|e| e.to_string())?,
+            None => return Err("required parameter 'metadata' is missing".to_owned()),
+        };
+        let instances = match self.instances {
+            Some(v) if !v.is_empty() => serde_json::from_str::<Vec<InstanceMetadataFilter>>(&v)
+                .map_err(|e| format!("instances format incorrect,{}", e))?,
+            _ => vec![],
+        };
+        let ephemeral = !self
+            .consistency_type
+            .unwrap_or_default()
+            .eq_ignore_ascii_case(CONSISTENCY_TYPE_PERSIST);
+        Ok(InstanceMetadataBatch {
+            namespace_id: Arc::new(NamingUtils::default_namespace(
+                self.namespace_id.unwrap_or_default(),
+            )),
+            group_name: Arc::new(group_name),
+            service_name: Arc::new(service_name),
+            ephemeral,
+            action,
+            instances,
+            metadata,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceDrainParams {
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub service_name: Option<String>,
+    pub ip: Option<String>,
+    pub port: Option<u32>,
+    pub cluster_name: Option<String>,
+    /// weight 或 offline,默认为weight
+    pub mode: Option<String>,
+    pub drain_seconds: Option<u64>,
+}
+
+impl InstanceDrainParams {
+    pub(crate) fn merge(self, o: Self) -> Self {
+        Self {
+            namespace_id: OptionUtils::select(self.namespace_id, o.namespace_id),
+            group_name: OptionUtils::select(self.group_name, o.group_name),
+            service_name: OptionUtils::select(self.service_name, o.service_name),
+            ip: OptionUtils::select(self.ip, o.ip),
+            port: OptionUtils::select(self.port, o.port),
+            cluster_name: OptionUtils::select(self.cluster_name, o.cluster_name),
+            mode: OptionUtils::select(self.mode, o.mode),
+            drain_seconds: OptionUtils::select(self.drain_seconds, o.drain_seconds),
+        }
+    }
+
+    pub(crate) fn build_param(self, cancel: bool) -> Result<InstanceDrainParam, String> {
+        let grouped_name = self.service_name.unwrap_or_default();
+        let (mut group_name, service_name) =
+            match NamingUtils::split_group_and_serivce_name(&grouped_name) {
+                Some(v) => v,
+                None => return Err("required parameter 'serviceName' is missing".to_owned()),
+            };
+        if let Some(v) = self.group_name {
+            if !v.is_empty() {
+                group_name = v;
+            }
+        }
+        let ip = match self.ip {
+            Some(v) if !v.is_empty() => v,
+            _ => return Err("required parameter 'ip' is missing".to_owned()),
+        };
+        let port = match self.port {
+            Some(v) if v > 0 => v,
+            _ => return Err("required parameter 'port' is missing".to_owned()),
+        };
+        Ok(InstanceDrainParam {
+            namespace_id: Arc::new(NamingUtils::default_namespace(
+                self.namespace_id.unwrap_or_default(),
+            )),
+            group_name: Arc::new(group_name),
+            service_name: Arc::new(service_name),
+            instance: InstanceMetadataFilter {
+                ip: Arc::new(ip),
+                port,
+                cluster_name: self.cluster_name,
+            },
+            mode: InstanceDrainMode::from_name(&self.mode.unwrap_or_default()),
+            drain_seconds: self.drain_seconds.unwrap_or(DEFAULT_DRAIN_SECONDS),
+            cancel,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadataBatchResult {
+    pub updated: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceListResult {
+    pub count: usize,
+    pub services: Vec<Arc<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterInfo {
+    pub cluster_name: String,
+    pub hosts: Vec<ApiInstance>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceDetailResult {
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    pub service_name: Arc<String>,
+    pub protect_threshold: Option<f32>,
+    pub metadata: Option<Arc<HashMap<String, String>>>,
+    pub cluster_map: HashMap<String, ClusterInfo>,
+}
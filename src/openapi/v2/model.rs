@@ -1,5 +1,12 @@
 use serde::{Deserialize, Serialize};
 
+/// nacos v2 open api 错误码
+pub const ERROR_CODE_PARAMETER_MISSING: i32 = 10000;
+pub const ERROR_CODE_ACCESS_DENIED: i32 = 10001;
+pub const ERROR_CODE_PARAMETER_VALIDATE: i32 = 20002;
+pub const ERROR_CODE_RESOURCE_NOT_FOUND: i32 = 20004;
+pub const ERROR_CODE_SERVER_ERROR: i32 = 30000;
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ApiResult<T>
 where
@@ -31,6 +38,6 @@ where
     }
 
     pub fn server_error(data: T) -> Self {
-        Self::error(30000, "server error".into(), data)
+        Self::error(ERROR_CODE_SERVER_ERROR, "server error".into(), data)
     }
 }
@@ -0,0 +1,420 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_raft_ext::RaftMetrics;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use super::model::{RouterRequest, RouterResponse};
+use crate::common::appdata::AppShareData;
+use crate::grpc::handler::RAFT_ROUTE_REQUEST;
+use crate::grpc::PayloadUtils;
+use crate::now_millis;
+
+/// 等待目标节点成为leader的超时时间,需要大于选举超时时间
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(10);
+/// 等待目标节点追上日志的重试次数
+const CATCH_UP_RETRY: usize = 5;
+const CATCH_UP_INTERVAL: Duration = Duration::from_millis(200);
+
+///
+/// leader转移期间暂停向所有follower发送心跳;
+/// follower在最近一次心跳后的最小选举超时时间内会拒绝投票,只暂停目标节点时超过3个投票节点的集群中目标节点拿不到多数票。
+/// 暂停后follower选举超时以更高的任期发起选举,原leader收到更高任期的投票请求后退为follower
+#[derive(Debug, Default)]
+pub struct LeaderTransferGuard {
+    paused: Mutex<Option<(HashSet<u64>, Instant)>>,
+}
+
+impl LeaderTransferGuard {
+    pub fn start(&self, nodes: HashSet<u64>, timeout: Duration) {
+        if let Ok(mut v) = self.paused.lock() {
+            *v = Some((nodes, Instant::now() + timeout));
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut v) = self.paused.lock() {
+            *v = None;
+        }
+    }
+
+    pub fn is_paused(&self, node_id: u64) -> bool {
+        if let Ok(v) = self.paused.lock() {
+            if let Some((nodes, deadline)) = v.as_ref() {
+                return nodes.contains(&node_id) && Instant::now() < *deadline;
+            }
+        }
+        false
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderTransferResult {
+    pub from: u64,
+    pub target: u64,
+    pub success: bool,
+    pub leader: Option<u64>,
+    pub cost_ms: u64,
+}
+
+///
+/// 选择日志已追上leader的节点;未指定目标时选择日志最新的节点
+pub fn select_transfer_target(
+    leader_log_index: u64,
+    nodes: &[(u64, u64)],
+    target: Option<u64>,
+) -> Option<u64> {
+    nodes
+        .iter()
+        .filter(|(id, index)| {
+            *index >= leader_log_index && target.map(|t| t == *id).unwrap_or(true)
+        })
+        .max_by_key(|(id, index)| (*index, std::cmp::Reverse(*id)))
+        .map(|(id, _)| *id)
+}
+
+async fn query_node_log_index(app: &Arc<AppShareData>, node_id: u64) -> anyhow::Result<u64> {
+    let addr = app.raft_store.get_target_addr(node_id).await?;
+    let request = serde_json::to_string(&RouterRequest::RaftLogIndex).unwrap_or_default();
+    let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
+    let resp_payload = app.cluster_sender.send_request(addr, payload).await?;
+    let body_vec = resp_payload.body.unwrap_or_default().value;
+    let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+    match resp {
+        RouterResponse::RaftLogIndex { last_log_index } => Ok(last_log_index),
+        _ => Err(anyhow::anyhow!("query node {} log index error", node_id)),
+    }
+}
+
+async fn find_caught_up_target(
+    app: &Arc<AppShareData>,
+    candidates: &[u64],
+    target: Option<u64>,
+) -> anyhow::Result<u64> {
+    for _ in 0..CATCH_UP_RETRY {
+        let leader_log_index = app.raft.metrics().borrow().last_log_index;
+        let mut nodes = Vec::with_capacity(candidates.len());
+        for node_id in candidates {
+            match query_node_log_index(app, *node_id).await {
+                Ok(index) => nodes.push((*node_id, index)),
+                Err(err) => log::warn!("leader transfer query node {} error,{}", node_id, err),
+            }
+        }
+        if let Some(v) = select_transfer_target(leader_log_index, &nodes, target) {
+            return Ok(v);
+        }
+        tokio::time::sleep(CATCH_UP_INTERVAL).await;
+    }
+    Err(anyhow::anyhow!(
+        "no caught up follower to transfer leadership"
+    ))
+}
+
+///
+/// 暂停向所有follower发送心跳,等待其它节点当选leader
+async fn wait_leader_transfer(
+    mut metrics: watch::Receiver<RaftMetrics>,
+    guard: &LeaderTransferGuard,
+    node_id: u64,
+    followers: HashSet<u64>,
+) -> Option<u64> {
+    guard.start(followers, TRANSFER_TIMEOUT);
+    let wait = async move {
+        loop {
+            let leader = metrics.borrow().current_leader;
+            if leader.is_some() && leader != Some(node_id) {
+                return leader;
+            }
+            if metrics.changed().await.is_err() {
+                return None;
+            }
+        }
+    };
+    let leader = tokio::time::timeout(TRANSFER_TIMEOUT, wait)
+        .await
+        .unwrap_or_default();
+    guard.clear();
+    leader
+}
+
+///
+/// 将leader转移到指定节点,未指定时选择日志最新的follower;非leader节点转发到leader处理
+pub async fn transfer_leader(
+    app: &Arc<AppShareData>,
+    target: Option<u64>,
+) -> anyhow::Result<LeaderTransferResult> {
+    let node_id = app.sys_config.raft_node_id;
+    match app.raft.current_leader().await {
+        Some(leader) if leader == node_id => {}
+        Some(leader) => {
+            let addr = app.raft_store.get_target_addr(leader).await?;
+            let req = RouterRequest::TransferLeader { target };
+            let request = serde_json::to_string(&req).unwrap_or_default();
+            let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
+            let resp_payload = app.cluster_sender.send_request(addr, payload).await?;
+            let body_vec = resp_payload.body.unwrap_or_default().value;
+            let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+            return match resp {
+                RouterResponse::TransferLeaderResult { result } => Ok(result),
+                _ => Err(anyhow::anyhow!("transfer leader response error")),
+            };
+        }
+        None => return Err(anyhow::anyhow!("unknown the raft leader addr!")),
+    }
+    let membership = app.raft.metrics().borrow().membership_config.clone();
+    if membership.members_after_consensus.is_some() {
+        return Err(anyhow::anyhow!("the raft membership change is in progress"));
+    }
+    if let Some(target) = target {
+        if target == node_id {
+            return Err(anyhow::anyhow!("node {} is already the leader", target));
+        }
+        if !membership.members.contains(&target) {
+            return Err(anyhow::anyhow!("node {} is not a voter", target));
+        }
+    }
+    let followers: HashSet<u64> = membership
+        .members
+        .iter()
+        .filter(|e| **e != node_id)
+        .cloned()
+        .collect();
+    let candidates: Vec<u64> = membership
+        .members
+        .iter()
+        .filter(|e| **e != node_id)
+        .cloned()
+        .collect();
+    let target = find_caught_up_target(app, &candidates, target).await?;
+    let start = now_millis();
+    log::info!("leader transfer start,from:{},target:{}", node_id, target);
+    //目标节点日志已追上,所有follower暂停心跳后由选举超时最先触发的节点当选
+    let leader =
+        wait_leader_transfer(app.raft.metrics(), &app.leader_transfer, node_id, followers).await;
+    let result = LeaderTransferResult {
+        from: node_id,
+        target,
+        success: leader == Some(target),
+        leader,
+        cost_ms: now_millis() - start,
+    };
+    log::info!("leader transfer end,{:?}", &result);
+    Ok(result)
+}
+
+///
+/// 节点退出前如果是leader则主动转移leader,减少选举等待时间
+pub async fn transfer_leader_on_shutdown(app: &Arc<AppShareData>) {
+    if !app.sys_config.raft_transfer_leader_on_shutdown
+        || app.raft.current_leader().await != Some(app.sys_config.raft_node_id)
+    {
+        return;
+    }
+    match transfer_leader(app, None).await {
+        Ok(result) => log::info!("shutdown leader transfer,success:{}", result.success),
+        Err(err) => log::warn!("shutdown leader transfer error,{}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raft::filestore::core::FileStore;
+    use crate::raft::filestore::raftapply::StateApplyManager;
+    use crate::raft::filestore::raftindex::RaftIndexManager;
+    use crate::raft::filestore::raftlog::RaftLogManager;
+    use crate::raft::filestore::raftsnapshot::RaftSnapshotManager;
+    use crate::raft::store::{ClientRequest, ClientResponse};
+    use actix::Actor;
+    use async_raft_ext::raft::{
+        AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest,
+        InstallSnapshotResponse, VoteRequest, VoteResponse,
+    };
+    use async_raft_ext::{Config, NodeId, Raft, RaftNetwork, State};
+    use std::collections::HashMap;
+    use std::sync::RwLock;
+
+    type TestRaft = Raft<ClientRequest, ClientResponse, TestRaftNetwork, FileStore>;
+
+    #[derive(Default)]
+    struct TestRaftNodes {
+        rafts: RwLock<HashMap<u64, Arc<TestRaft>>>,
+        /// 最先发起选举的节点
+        first_candidate: Mutex<Option<u64>>,
+    }
+
+    impl TestRaftNodes {
+        fn get(&self, node_id: u64) -> anyhow::Result<Arc<TestRaft>> {
+            self.rafts
+                .read()
+                .unwrap()
+                .get(&node_id)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("node {} is not exist", node_id))
+        }
+    }
+
+    ///
+    /// 进程内转发raft请求,与RaftRouter一样在leader转移期间暂停心跳
+    struct TestRaftNetwork {
+        nodes: Arc<TestRaftNodes>,
+        leader_transfer: Arc<LeaderTransferGuard>,
+    }
+
+    #[async_trait::async_trait]
+    impl RaftNetwork<ClientRequest> for TestRaftNetwork {
+        async fn append_entries(
+            &self,
+            target: NodeId,
+            req: AppendEntriesRequest<ClientRequest>,
+        ) -> anyhow::Result<AppendEntriesResponse> {
+            if self.leader_transfer.is_paused(target) {
+                return Err(anyhow::anyhow!("leader transfer to node {}", target));
+            }
+            Ok(self.nodes.get(target)?.append_entries(req).await?)
+        }
+
+        async fn install_snapshot(
+            &self,
+            target: NodeId,
+            req: InstallSnapshotRequest,
+        ) -> anyhow::Result<InstallSnapshotResponse> {
+            Ok(self.nodes.get(target)?.install_snapshot(req).await?)
+        }
+
+        async fn vote(&self, target: NodeId, req: VoteRequest) -> anyhow::Result<VoteResponse> {
+            if let Ok(mut v) = self.nodes.first_candidate.lock() {
+                v.get_or_insert(req.candidate_id);
+            }
+            Ok(self.nodes.get(target)?.vote(req).await?)
+        }
+    }
+
+    async fn wait_leader(mut metrics: watch::Receiver<RaftMetrics>) -> Option<u64> {
+        let wait = async move {
+            loop {
+                let leader = metrics.borrow().current_leader;
+                if leader.is_some() {
+                    return leader;
+                }
+                if metrics.changed().await.is_err() {
+                    return None;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_select_transfer_target() {
+        let nodes = vec![(2, 100), (3, 98), (4, 100)];
+        assert_eq!(select_transfer_target(100, &nodes, None), Some(2));
+        assert_eq!(select_transfer_target(100, &nodes, Some(4)), Some(4));
+        assert_eq!(select_transfer_target(100, &nodes, Some(3)), None);
+        assert_eq!(select_transfer_target(101, &nodes, None), None);
+    }
+
+    #[test]
+    fn test_leader_transfer_guard() {
+        let guard = LeaderTransferGuard::default();
+        assert!(!guard.is_paused(2));
+        guard.start(vec![2, 3].into_iter().collect(), Duration::from_secs(10));
+        assert!(guard.is_paused(2));
+        assert!(guard.is_paused(3));
+        assert!(!guard.is_paused(4));
+        guard.clear();
+        assert!(!guard.is_paused(2));
+        guard.start(vec![2].into_iter().collect(), Duration::from_secs(0));
+        assert!(!guard.is_paused(2));
+    }
+
+    #[actix_rt::test]
+    async fn test_leader_transfer_with_five_voters() {
+        let dir = std::env::temp_dir().join(format!(
+            "rnacos_leader_transfer_test_{}_{}",
+            std::process::id(),
+            now_millis()
+        ));
+        let config = Arc::new(
+            Config::build("rnacos raft test".to_owned())
+                .heartbeat_interval(50)
+                .election_timeout_min(300)
+                .election_timeout_max(600)
+                .validate()
+                .unwrap(),
+        );
+        let nodes = Arc::new(TestRaftNodes::default());
+        let members: HashSet<u64> = (1..=5).collect();
+        let mut guards = HashMap::new();
+        for node_id in members.iter().cloned() {
+            let node_dir = dir.join(node_id.to_string());
+            std::fs::create_dir_all(&node_dir).unwrap();
+            let base_path = Arc::new(node_dir.to_string_lossy().into_owned());
+            let index_manager = RaftIndexManager::new(base_path.clone()).start();
+            let log_manager =
+                RaftLogManager::new(base_path.clone(), Some(index_manager.clone())).start();
+            let snapshot_manager =
+                RaftSnapshotManager::new(base_path, Some(index_manager.clone()), 0).start();
+            let store = Arc::new(FileStore::new(
+                node_id,
+                index_manager,
+                snapshot_manager,
+                log_manager,
+                StateApplyManager::new().start(),
+            ));
+            let guard = Arc::new(LeaderTransferGuard::default());
+            let network = Arc::new(TestRaftNetwork {
+                nodes: nodes.clone(),
+                leader_transfer: guard.clone(),
+            });
+            let raft = Arc::new(Raft::new(node_id, config.clone(), network, store));
+            nodes.rafts.write().unwrap().insert(node_id, raft);
+            guards.insert(node_id, guard);
+        }
+        nodes
+            .get(1)
+            .unwrap()
+            .initialize(members.clone())
+            .await
+            .unwrap();
+        let leader = wait_leader(nodes.get(1).unwrap().metrics()).await.unwrap();
+        //等待所有节点加入集群并追上leader日志
+        let caught_up = || {
+            let leader_log_index = nodes.get(leader).unwrap().metrics().borrow().last_log_index;
+            leader_log_index > 0
+                && members.iter().all(|e| {
+                    let metrics = nodes.get(*e).unwrap().metrics().borrow().clone();
+                    metrics.state != State::NonVoter && metrics.last_log_index >= leader_log_index
+                })
+        };
+        for _ in 0..100 {
+            if caught_up() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(caught_up());
+
+        //5个投票节点,follower在心跳后的最小选举超时时间内拒绝投票;
+        //只暂停一个节点时其发起的选举拿不到多数票,暂停所有follower后最先发起选举的节点当选
+        *nodes.first_candidate.lock().unwrap() = None;
+        let followers: HashSet<u64> = members.iter().filter(|e| **e != leader).cloned().collect();
+        let guard = guards.get(&leader).unwrap();
+        let leader_raft = nodes.get(leader).unwrap();
+        let new_leader =
+            wait_leader_transfer(leader_raft.metrics(), guard, leader, followers.clone()).await;
+        assert!(new_leader.map(|e| followers.contains(&e)).unwrap_or(false));
+        assert_eq!(new_leader, *nodes.first_candidate.lock().unwrap());
+        assert!(!guard.is_paused(new_leader.unwrap()));
+
+        for node_id in members {
+            nodes.get(node_id).unwrap().shutdown().await.ok();
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
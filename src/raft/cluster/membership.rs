@@ -0,0 +1,394 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use async_raft_ext::raft::ClientWriteRequest;
+use serde::{Deserialize, Serialize};
+
+use super::model::{RouterRequest, RouterResponse};
+use crate::common::appdata::AppShareData;
+use crate::grpc::handler::RAFT_ROUTE_REQUEST;
+use crate::grpc::PayloadUtils;
+use crate::raft::filestore::core::FileStore;
+use crate::raft::store::ClientRequest;
+use crate::raft::NacosRaft;
+
+///
+/// raft成员变更请求,需要在leader节点执行
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum MembershipReq {
+    AddLearner { node_id: u64, addr: Arc<String> },
+    Promote { node_id: u64 },
+    Remove { node_id: u64 },
+    ChangeVoters { voters: BTreeSet<u64> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MembershipNode {
+    pub node_id: u64,
+    pub addr: Arc<String>,
+    pub leader: bool,
+    pub alive: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MembershipInfo {
+    pub leader: Option<u64>,
+    pub voters: Vec<MembershipNode>,
+    pub learners: Vec<MembershipNode>,
+    /// 是否有未完成的成员变更
+    pub changing: bool,
+}
+
+///
+/// 变更后的投票节点中存活节点需要超过半数,否则集群会失去多数派
+pub fn check_quorum(voters: &BTreeSet<u64>, alive_nodes: &HashSet<u64>) -> anyhow::Result<()> {
+    if voters.is_empty() {
+        return Err(anyhow::anyhow!("the raft voters can't be empty"));
+    }
+    let alive_count = voters.iter().filter(|e| alive_nodes.contains(e)).count();
+    if alive_count * 2 <= voters.len() {
+        return Err(anyhow::anyhow!(
+            "the change would lose quorum, alive voters {} of {}",
+            alive_count,
+            voters.len()
+        ));
+    }
+    Ok(())
+}
+
+///
+/// learner只记录在leader内存中,不在成员配置里;本节点在新的任期成为leader时需要重新添加learner
+#[derive(Debug, Default)]
+pub struct LearnerRestoreTracker {
+    leader_term: u64,
+}
+
+impl LearnerRestoreTracker {
+    pub fn on_metrics(&mut self, is_leader: bool, current_term: u64) -> bool {
+        if !is_leader {
+            self.leader_term = 0;
+            return false;
+        }
+        if self.leader_term == current_term {
+            return false;
+        }
+        self.leader_term = current_term;
+        true
+    }
+}
+
+///
+/// 已移除的learner;async-raft-ext不支持删除learner的复制流,
+/// 由网络层停止向已移除的learner复制日志,leader任期变更后复制流自然释放
+#[derive(Debug, Default)]
+pub struct RemovedLearners {
+    nodes: RwLock<HashSet<u64>>,
+}
+
+impl RemovedLearners {
+    pub fn add(&self, node_id: u64) {
+        if let Ok(mut v) = self.nodes.write() {
+            v.insert(node_id);
+        }
+    }
+
+    pub fn remove(&self, node_id: u64) {
+        if let Ok(mut v) = self.nodes.write() {
+            v.remove(&node_id);
+        }
+    }
+
+    pub fn contains(&self, node_id: u64) -> bool {
+        self.nodes
+            .read()
+            .map(|v| v.contains(&node_id))
+            .unwrap_or(false)
+    }
+}
+
+///
+/// 有地址但不在投票成员中的节点为learner
+pub fn get_learners(node_addrs: &HashMap<u64, Arc<String>>, voters: &HashSet<u64>) -> Vec<u64> {
+    let learners: BTreeSet<u64> = node_addrs
+        .keys()
+        .filter(|e| !voters.contains(e))
+        .cloned()
+        .collect();
+    learners.into_iter().collect()
+}
+
+///
+/// 监听本节点成为leader,重新向learner同步日志
+pub async fn watch_leader_restore_learners(raft: Arc<NacosRaft>, store: Arc<FileStore>) {
+    let mut metrics = raft.metrics();
+    let mut tracker = LearnerRestoreTracker::default();
+    loop {
+        let (is_leader, current_term, membership) = {
+            let v = metrics.borrow();
+            (
+                v.state.is_leader(),
+                v.current_term,
+                v.membership_config.clone(),
+            )
+        };
+        if tracker.on_metrics(is_leader, current_term) {
+            let mut voters = membership.members;
+            if let Some(v) = membership.members_after_consensus {
+                voters.extend(v);
+            }
+            match store.get_node_addrs().await {
+                Ok(node_addrs) => {
+                    for node_id in get_learners(&node_addrs, &voters) {
+                        log::info!("restore raft learner {} on leader change", node_id);
+                        let raft = raft.clone();
+                        //add_non_voter会等待learner追上日志,每个learner单独执行
+                        tokio::spawn(async move {
+                            if let Err(err) = raft.add_non_voter(node_id).await {
+                                log::warn!("restore raft learner {} error,{}", node_id, err);
+                            }
+                        });
+                    }
+                }
+                Err(err) => log::warn!("restore raft learners load node addrs error,{}", err),
+            }
+        }
+        if metrics.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn get_alive_nodes(app: &Arc<AppShareData>) -> anyhow::Result<HashSet<u64>> {
+    let mut alive_nodes: HashSet<u64> = app
+        .naming_node_manage
+        .get_all_valid_nodes()
+        .await?
+        .into_iter()
+        .map(|e| e.id)
+        .collect();
+    alive_nodes.insert(app.sys_config.raft_node_id);
+    Ok(alive_nodes)
+}
+
+fn get_voters(app: &Arc<AppShareData>) -> (BTreeSet<u64>, bool) {
+    let metrics = app.raft.metrics().borrow().clone();
+    let membership = metrics.membership_config;
+    let changing = membership.members_after_consensus.is_some();
+    (membership.members.into_iter().collect(), changing)
+}
+
+pub async fn query_membership(app: &Arc<AppShareData>) -> anyhow::Result<MembershipInfo> {
+    let leader = app.raft.current_leader().await;
+    let (voters, changing) = get_voters(app);
+    let alive_nodes = get_alive_nodes(app).await?;
+    let node_addrs = app.raft_store.get_node_addrs().await?;
+    let build_node = |node_id: u64| MembershipNode {
+        node_id,
+        addr: node_addrs.get(&node_id).cloned().unwrap_or_default(),
+        leader: leader == Some(node_id),
+        alive: alive_nodes.contains(&node_id),
+    };
+    let mut info = MembershipInfo {
+        leader,
+        changing,
+        ..Default::default()
+    };
+    for node_id in &voters {
+        info.voters.push(build_node(*node_id));
+    }
+    let voter_set: HashSet<u64> = voters.iter().cloned().collect();
+    for node_id in get_learners(&node_addrs, &voter_set) {
+        info.learners.push(build_node(node_id));
+    }
+    Ok(info)
+}
+
+///
+/// 成员变更请求路由到leader节点处理
+pub async fn request_membership(app: &Arc<AppShareData>, req: MembershipReq) -> anyhow::Result<()> {
+    match app.raft.current_leader().await {
+        Some(leader) if leader == app.sys_config.raft_node_id => handle_membership(app, req).await,
+        Some(leader) => {
+            let addr = app.raft_store.get_target_addr(leader).await?;
+            let req = RouterRequest::MembershipReq { req };
+            let request = serde_json::to_string(&req).unwrap_or_default();
+            let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
+            let resp_payload = app.cluster_sender.send_request(addr, payload).await?;
+            let body_vec = resp_payload.body.unwrap_or_default().value;
+            let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+            match resp {
+                RouterResponse::MembershipResult { error: None } => Ok(()),
+                RouterResponse::MembershipResult { error: Some(err) } => Err(anyhow::anyhow!(err)),
+                //旧版本leader成功时返回None
+                RouterResponse::None => Ok(()),
+                _ => Err(anyhow::anyhow!("raft membership response error")),
+            }
+        }
+        None => Err(anyhow::anyhow!("unknown the raft leader addr!")),
+    }
+}
+
+pub async fn handle_membership(app: &Arc<AppShareData>, req: MembershipReq) -> anyhow::Result<()> {
+    let (voters, changing) = get_voters(app);
+    if changing {
+        return Err(anyhow::anyhow!("the raft membership change is in progress"));
+    }
+    match req {
+        MembershipReq::AddLearner { node_id, addr } => {
+            if node_id == 0 || addr.is_empty() {
+                return Err(anyhow::anyhow!("node_id or addr is empty"));
+            }
+            if voters.contains(&node_id) {
+                return Err(anyhow::anyhow!("node {} is already a voter", node_id));
+            }
+            app.raft
+                .client_write(ClientWriteRequest::new(ClientRequest::NodeAddr {
+                    id: node_id,
+                    addr,
+                }))
+                .await?;
+            app.removed_learners.remove(node_id);
+            app.raft.add_non_voter(node_id).await?;
+        }
+        MembershipReq::Promote { node_id } => {
+            if voters.contains(&node_id) {
+                return Err(anyhow::anyhow!("node {} is already a voter", node_id));
+            }
+            if !app
+                .raft_store
+                .get_node_addrs()
+                .await?
+                .contains_key(&node_id)
+            {
+                return Err(anyhow::anyhow!("node {} is not a learner", node_id));
+            }
+            let mut new_voters = voters;
+            new_voters.insert(node_id);
+            change_voters(app, new_voters).await?;
+        }
+        MembershipReq::Remove { node_id } => {
+            if node_id == app.sys_config.raft_node_id {
+                return Err(anyhow::anyhow!(
+                    "can't remove the leader node {}, transfer the leadership first",
+                    node_id
+                ));
+            }
+            let node_addrs = app.raft_store.get_node_addrs().await?;
+            if voters.contains(&node_id) {
+                let mut new_voters = voters;
+                new_voters.remove(&node_id);
+                change_voters(app, new_voters).await?;
+            } else if node_addrs.contains_key(&node_id) {
+                //移除learner,停止向其复制日志
+                app.removed_learners.add(node_id);
+            } else {
+                return Err(anyhow::anyhow!("node {} is not exist", node_id));
+            }
+            app.raft
+                .client_write(ClientWriteRequest::new(ClientRequest::RemoveNodeAddr {
+                    id: node_id,
+                }))
+                .await?;
+        }
+        MembershipReq::ChangeVoters { voters: new_voters } => {
+            if !new_voters.contains(&app.sys_config.raft_node_id) {
+                return Err(anyhow::anyhow!(
+                    "can't remove the leader node {}, transfer the leadership first",
+                    app.sys_config.raft_node_id
+                ));
+            }
+            let node_addrs = app.raft_store.get_node_addrs().await?;
+            for node_id in &new_voters {
+                if !node_addrs.contains_key(node_id) {
+                    return Err(anyhow::anyhow!("node {} addr is unknown", node_id));
+                }
+            }
+            change_voters(app, new_voters).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn change_voters(app: &Arc<AppShareData>, new_voters: BTreeSet<u64>) -> anyhow::Result<()> {
+    let alive_nodes = get_alive_nodes(app).await?;
+    check_quorum(&new_voters, &alive_nodes)?;
+    log::info!("change raft membership,{:?}", &new_voters);
+    app.raft
+        .change_membership(new_voters.iter().cloned().collect())
+        .await?;
+    app.raft
+        .client_write(ClientWriteRequest::new(ClientRequest::Members(
+            new_voters.into_iter().collect(),
+        )))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_quorum() {
+        let alive_nodes: HashSet<u64> = vec![1, 2].into_iter().collect();
+        let voters: BTreeSet<u64> = vec![1, 2, 3].into_iter().collect();
+        assert!(check_quorum(&voters, &alive_nodes).is_ok());
+        let voters: BTreeSet<u64> = vec![1, 2, 3, 4].into_iter().collect();
+        assert!(check_quorum(&voters, &alive_nodes).is_err());
+        let voters: BTreeSet<u64> = vec![1, 3].into_iter().collect();
+        assert!(check_quorum(&voters, &alive_nodes).is_err());
+        assert!(check_quorum(&BTreeSet::new(), &alive_nodes).is_err());
+    }
+
+    #[test]
+    fn test_restore_learners_on_leader_change() {
+        let mut tracker = LearnerRestoreTracker::default();
+        assert!(!tracker.on_metrics(false, 1));
+        //follower切换为leader
+        assert!(tracker.on_metrics(true, 2));
+        assert!(!tracker.on_metrics(true, 2));
+        //leader变更到其它节点后,再次当选leader
+        assert!(!tracker.on_metrics(false, 3));
+        assert!(tracker.on_metrics(true, 4));
+
+        let node_addrs: HashMap<u64, Arc<String>> = vec![
+            (1, Arc::new("127.0.0.1:9848".to_owned())),
+            (2, Arc::new("127.0.0.1:9849".to_owned())),
+            (4, Arc::new("127.0.0.1:9851".to_owned())),
+            (3, Arc::new("127.0.0.1:9850".to_owned())),
+        ]
+        .into_iter()
+        .collect();
+        let voters: HashSet<u64> = vec![1, 2].into_iter().collect();
+        assert_eq!(get_learners(&node_addrs, &voters), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_removed_learners() {
+        let removed_learners = RemovedLearners::default();
+        assert!(!removed_learners.contains(3));
+        removed_learners.add(3);
+        assert!(removed_learners.contains(3));
+        assert!(!removed_learners.contains(4));
+        //重新添加learner后恢复复制
+        removed_learners.remove(3);
+        assert!(!removed_learners.contains(3));
+    }
+
+    #[test]
+    fn test_membership_result() {
+        let resp = RouterResponse::MembershipResult {
+            error: Some("node 3 is not exist".to_owned()),
+        };
+        let v = serde_json::to_string(&resp).unwrap();
+        let resp: RouterResponse = serde_json::from_str(&v).unwrap();
+        match resp {
+            RouterResponse::MembershipResult { error } => {
+                assert_eq!(error.as_deref(), Some("node 3 is not exist"))
+            }
+            _ => panic!("unexpected response"),
+        }
+    }
+}
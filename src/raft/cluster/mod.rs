@@ -12,7 +12,10 @@ use crate::{
     config::core::{ConfigAsyncCmd, ConfigKey},
 };
 
+pub mod leader_transfer;
+pub mod membership;
 pub mod model;
+pub mod node_status;
 pub mod route;
 pub mod routeapi;
 
@@ -62,6 +65,13 @@ pub async fn handle_route(
             app.raft.add_non_voter(node_id).await?;
             join_node(app.raft.as_ref(), app.raft_store.as_ref(), node_id).await?;
         }
+        RouterRequest::JoinLearner { node_id, node_addr } => {
+            let req = membership::MembershipReq::AddLearner {
+                node_id,
+                addr: node_addr,
+            };
+            membership::request_membership(app, req).await?;
+        }
         RouterRequest::TableManagerReq { req } => {
             let result = app
                 .raft_table_manage
@@ -95,6 +105,30 @@ pub async fn handle_route(
                 .await??;
             return Ok(RouterResponse::ImportResult { result });
         }
+        RouterRequest::ReadIndex => {
+            let index = app.config_route.leader_read_index().await?;
+            return Ok(RouterResponse::ReadIndex { index });
+        }
+        RouterRequest::MembershipReq { req } => {
+            //成员变更错误返回给请求节点
+            let error = membership::handle_membership(app, req)
+                .await
+                .err()
+                .map(|err| err.to_string());
+            return Ok(RouterResponse::MembershipResult { error });
+        }
+        RouterRequest::RaftLogIndex => {
+            let last_log_index = app.raft.metrics().borrow().last_log_index;
+            return Ok(RouterResponse::RaftLogIndex { last_log_index });
+        }
+        RouterRequest::TransferLeader { target } => {
+            let result = leader_transfer::transfer_leader(app, target).await?;
+            return Ok(RouterResponse::TransferLeaderResult { result });
+        }
+        RouterRequest::RaftNodeState => {
+            let state = node_status::load_local_state(&app.raft, &app.raft_store).await;
+            return Ok(RouterResponse::RaftNodeState { state });
+        }
     };
     Ok(RouterResponse::None)
 }
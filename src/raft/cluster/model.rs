@@ -2,6 +2,9 @@ use std::{collections::HashMap, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 
+use super::leader_transfer::LeaderTransferResult;
+use super::membership::MembershipReq;
+use super::node_status::RaftLocalState;
 use crate::config::config_type::ConfigType;
 use crate::namespace::model::{NamespaceRaftReq, NamespaceRaftResult};
 use crate::transfer::model::{TransferImportParam, TransferImportResponse};
@@ -13,6 +16,33 @@ use crate::{
     },
 };
 
+/// 配置读取一致性级别参数名,http参数与grpc header共用
+pub const CONSISTENCY_PARAM: &str = "consistency";
+pub const CONSISTENCY_LINEARIZABLE: &str = "linearizable";
+
+///
+/// 配置读取一致性级别,默认直接读取本节点缓存
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadConsistency {
+    #[default]
+    Default,
+    //读取前通过raft read-index确认读到最新已提交数据
+    Linearizable,
+}
+
+impl ReadConsistency {
+    pub fn from_param(v: Option<&str>) -> Self {
+        match v {
+            Some(v) if v.eq_ignore_ascii_case(CONSISTENCY_LINEARIZABLE) => Self::Linearizable,
+            _ => Self::Default,
+        }
+    }
+
+    pub fn is_linearizable(&self) -> bool {
+        *self == Self::Linearizable
+    }
+}
+
 pub enum RouteAddr {
     Local,
     Remote(u64, Arc<String>),
@@ -99,6 +129,11 @@ pub enum RouterRequest {
         node_id: u64,
         node_addr: Arc<String>,
     },
+    /// 以learner身份加入集群,不参与投票
+    JoinLearner {
+        node_id: u64,
+        node_addr: Arc<String>,
+    },
     TableManagerReq {
         req: TableManagerReq,
     },
@@ -115,6 +150,15 @@ pub enum RouterRequest {
         data: Vec<u8>,
         param: TransferImportParam,
     },
+    ReadIndex,
+    MembershipReq {
+        req: MembershipReq,
+    },
+    RaftLogIndex,
+    TransferLeader {
+        target: Option<u64>,
+    },
+    RaftNodeState,
 }
 
 impl From<SetConfigReq> for RouterRequest {
@@ -158,4 +202,37 @@ pub enum RouterResponse {
     CacheManagerResult { result: CacheManagerResult },
     NamespaceResult { result: NamespaceRaftResult },
     ImportResult { result: TransferImportResponse },
+    ReadIndex { index: u64 },
+    RaftLogIndex { last_log_index: u64 },
+    TransferLeaderResult { result: LeaderTransferResult },
+    RaftNodeState { state: RaftLocalState },
+    MembershipResult { error: Option<String> },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_consistency() {
+        assert!(ReadConsistency::from_param(Some("linearizable")).is_linearizable());
+        assert!(ReadConsistency::from_param(Some("Linearizable")).is_linearizable());
+        assert!(!ReadConsistency::from_param(Some("default")).is_linearizable());
+        assert!(!ReadConsistency::from_param(None).is_linearizable());
+        let req = RouterRequest::ReadIndex;
+        let v = serde_json::to_string(&req).unwrap();
+        let req: RouterRequest = serde_json::from_str(&v).unwrap();
+        assert!(matches!(req, RouterRequest::ReadIndex));
+    }
+
+    #[test]
+    fn test_join_learner_request() {
+        let req = RouterRequest::JoinLearner {
+            node_id: 4,
+            node_addr: Arc::new("127.0.0.1:9848".to_owned()),
+        };
+        let v = serde_json::to_string(&req).unwrap();
+        let req: RouterRequest = serde_json::from_str(&v).unwrap();
+        assert!(matches!(req, RouterRequest::JoinLearner { node_id: 4, .. }));
+    }
 }
@@ -0,0 +1,375 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use super::model::{RouterRequest, RouterResponse};
+use crate::common::appdata::AppShareData;
+use crate::grpc::handler::RAFT_ROUTE_REQUEST;
+use crate::grpc::PayloadUtils;
+use crate::metrics::metrics_key::MetricsKey;
+use crate::metrics::model::{MetricsItem, MetricsRecord};
+use crate::naming::cluster::node_manage::{ClusterNode, NodeStatus};
+use crate::raft::filestore::core::FileStore;
+use crate::raft::network::factory::RaftClusterRequestSender;
+use crate::raft::NacosRaft;
+
+/// 采集指标时查询leader日志序号的超时时间,避免leader异常时阻塞指标采集
+const LEADER_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// 查询集群状态时单个节点的超时时间,避免个别节点异常拖慢整体查询
+const NODE_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+///
+/// 节点本地的raft状态,由各节点自行上报
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftLocalState {
+    pub node_id: u64,
+    pub role: String,
+    pub current_term: u64,
+    pub last_log_index: u64,
+    pub last_applied: u64,
+    pub snapshot_index: u64,
+    pub current_leader: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftNodeStatusInfo {
+    pub node_id: u64,
+    pub addr: Arc<String>,
+    pub current_node: bool,
+    pub voter: bool,
+    /// 本次查询是否获取到节点状态
+    pub reachable: bool,
+    pub role: String,
+    pub current_term: u64,
+    pub last_log_index: u64,
+    pub last_applied: u64,
+    pub snapshot_index: u64,
+    /// 落后leader的日志条数,节点不可达时为空
+    pub replication_lag: Option<u64>,
+    /// 最近一次收到节点集群心跳的时间,单位毫秒
+    pub last_contact_time: u64,
+    /// naming集群中的节点状态,为空表示naming集群中没有该节点
+    pub naming_status: Option<NodeStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftClusterStatus {
+    pub leader: Option<u64>,
+    pub leader_last_log_index: u64,
+    pub max_replication_lag: u64,
+    pub nodes: Vec<RaftNodeStatusInfo>,
+}
+
+pub async fn load_local_state(raft: &NacosRaft, raft_store: &FileStore) -> RaftLocalState {
+    let metrics = raft.metrics().borrow().clone();
+    let snapshot_index = raft_store
+        .get_last_snapshot_index()
+        .await
+        .unwrap_or_default();
+    RaftLocalState {
+        node_id: metrics.id,
+        role: format!("{:?}", metrics.state),
+        current_term: metrics.current_term,
+        last_log_index: metrics.last_log_index,
+        last_applied: metrics.last_applied,
+        snapshot_index,
+        current_leader: metrics.current_leader,
+    }
+}
+
+async fn send_route_request(
+    cluster_sender: &RaftClusterRequestSender,
+    addr: Arc<String>,
+    req: &RouterRequest,
+) -> anyhow::Result<RouterResponse> {
+    let request = serde_json::to_string(req).unwrap_or_default();
+    let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
+    let resp_payload = cluster_sender.send_request(addr, payload).await?;
+    let body_vec = resp_payload.body.unwrap_or_default().value;
+    Ok(serde_json::from_slice(&body_vec)?)
+}
+
+async fn query_node_state(
+    cluster_sender: &RaftClusterRequestSender,
+    addr: Arc<String>,
+) -> anyhow::Result<RaftLocalState> {
+    match send_route_request(cluster_sender, addr.clone(), &RouterRequest::RaftNodeState).await? {
+        RouterResponse::RaftNodeState { state } => Ok(state),
+        _ => Err(anyhow::anyhow!("query node {} raft state error", &addr)),
+    }
+}
+
+///
+/// 以leader的最新日志序号计算各节点的复制延迟;leader不可达时使用可达节点中最大的日志序号
+pub fn fill_replication_lag(leader: Option<u64>, nodes: &mut [RaftNodeStatusInfo]) -> u64 {
+    let leader_index = nodes
+        .iter()
+        .find(|e| e.reachable && Some(e.node_id) == leader)
+        .map(|e| e.last_log_index)
+        .unwrap_or_else(|| {
+            nodes
+                .iter()
+                .filter(|e| e.reachable)
+                .map(|e| e.last_log_index)
+                .max()
+                .unwrap_or_default()
+        });
+    for node in nodes.iter_mut() {
+        node.replication_lag = if node.reachable {
+            Some(leader_index.saturating_sub(node.last_log_index))
+        } else {
+            None
+        };
+    }
+    leader_index
+}
+
+///
+/// 查询集群各节点的raft状态,并合并naming集群中的节点状态
+pub async fn query_cluster_status(app: &Arc<AppShareData>) -> anyhow::Result<RaftClusterStatus> {
+    let local_id = app.sys_config.raft_node_id;
+    let leader = app.raft.current_leader().await;
+    let membership = app.raft.metrics().borrow().membership_config.clone();
+    let mut voters: HashSet<u64> = membership.members;
+    if let Some(v) = membership.members_after_consensus {
+        voters.extend(v);
+    }
+    let node_addrs = app.raft_store.get_node_addrs().await?;
+    let naming_nodes: HashMap<u64, ClusterNode> = app
+        .naming_node_manage
+        .get_all_nodes()
+        .await?
+        .into_iter()
+        .map(|e| (e.id, e))
+        .collect();
+    let mut node_ids: BTreeSet<u64> = node_addrs.keys().cloned().collect();
+    node_ids.insert(local_id);
+    let mut nodes = Vec::with_capacity(node_ids.len());
+    for node_id in node_ids {
+        let addr = node_addrs.get(&node_id).cloned().unwrap_or_default();
+        let naming_node = naming_nodes.get(&node_id);
+        let info = RaftNodeStatusInfo {
+            node_id,
+            addr: addr.clone(),
+            current_node: node_id == local_id,
+            voter: voters.contains(&node_id),
+            last_contact_time: naming_node.map(|e| e.last_active_time).unwrap_or_default(),
+            naming_status: naming_node.map(|e| e.status.clone()),
+            ..Default::default()
+        };
+        nodes.push(info);
+    }
+    //并发查询各节点状态
+    let states = join_all(nodes.iter().map(|info| async move {
+        if info.node_id == local_id {
+            Ok(load_local_state(&app.raft, &app.raft_store).await)
+        } else {
+            tokio::time::timeout(
+                NODE_QUERY_TIMEOUT,
+                query_node_state(&app.cluster_sender, info.addr.clone()),
+            )
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("query node state timeout")))
+        }
+    }))
+    .await;
+    for (info, state) in nodes.iter_mut().zip(states) {
+        match state {
+            Ok(state) => {
+                info.reachable = true;
+                info.role = state.role;
+                info.current_term = state.current_term;
+                info.last_log_index = state.last_log_index;
+                info.last_applied = state.last_applied;
+                info.snapshot_index = state.snapshot_index;
+            }
+            Err(err) => log::warn!("query raft node {} state error,{}", info.node_id, err),
+        }
+    }
+    let leader_last_log_index = fill_replication_lag(leader, &mut nodes);
+    let max_replication_lag = nodes
+        .iter()
+        .filter_map(|e| e.replication_lag)
+        .max()
+        .unwrap_or_default();
+    Ok(RaftClusterStatus {
+        leader,
+        leader_last_log_index,
+        max_replication_lag,
+        nodes,
+    })
+}
+
+///
+/// leader日志序号查询失败时标记leader不可达,复制延迟指标保留上次的值
+pub fn build_leader_metrics(
+    leader_log_index: Option<u64>,
+    last_log_index: u64,
+) -> Vec<MetricsItem> {
+    let mut list = vec![MetricsItem::new(
+        MetricsKey::RaftLeaderReachable,
+        MetricsRecord::Gauge(if leader_log_index.is_some() {
+            1f32
+        } else {
+            0f32
+        }),
+    )];
+    if let Some(leader_log_index) = leader_log_index {
+        list.push(MetricsItem::new(
+            MetricsKey::RaftReplicationLag,
+            MetricsRecord::Gauge(leader_log_index.saturating_sub(last_log_index) as f32),
+        ));
+    }
+    list
+}
+
+async fn query_leader_log_index(
+    cluster_sender: &RaftClusterRequestSender,
+    addr: Arc<String>,
+) -> Option<u64> {
+    let req = send_route_request(cluster_sender, addr, &RouterRequest::RaftLogIndex);
+    match tokio::time::timeout(LEADER_QUERY_TIMEOUT, req).await {
+        Ok(Ok(RouterResponse::RaftLogIndex { last_log_index })) => Some(last_log_index),
+        _ => None,
+    }
+}
+
+///
+/// 指标采集使用的raft对象
+#[derive(Clone)]
+pub struct RaftMetricsSource {
+    raft: Arc<NacosRaft>,
+    raft_store: Arc<FileStore>,
+    cluster_sender: Arc<RaftClusterRequestSender>,
+}
+
+impl RaftMetricsSource {
+    pub fn new(
+        raft: Arc<NacosRaft>,
+        raft_store: Arc<FileStore>,
+        cluster_sender: Arc<RaftClusterRequestSender>,
+    ) -> Self {
+        Self {
+            raft,
+            raft_store,
+            cluster_sender,
+        }
+    }
+
+    pub async fn collect(&self) -> Vec<MetricsItem> {
+        collect_raft_metrics(&self.raft, &self.raft_store, &self.cluster_sender).await
+    }
+}
+
+impl std::fmt::Debug for RaftMetricsSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RaftMetricsSource").finish_non_exhaustive()
+    }
+}
+
+///
+/// 采集本节点的raft状态指标;follower向leader查询最新日志序号计算复制延迟
+pub async fn collect_raft_metrics(
+    raft: &NacosRaft,
+    raft_store: &FileStore,
+    cluster_sender: &RaftClusterRequestSender,
+) -> Vec<MetricsItem> {
+    let metrics = raft.metrics().borrow().clone();
+    let snapshot_index = raft_store
+        .get_last_snapshot_index()
+        .await
+        .unwrap_or_default();
+    let is_leader = metrics.current_leader == Some(metrics.id);
+    let mut list = vec![
+        MetricsItem::new(
+            MetricsKey::RaftCurrentTerm,
+            MetricsRecord::Gauge(metrics.current_term as f32),
+        ),
+        MetricsItem::new(
+            MetricsKey::RaftLastLogIndex,
+            MetricsRecord::Gauge(metrics.last_log_index as f32),
+        ),
+        MetricsItem::new(
+            MetricsKey::RaftLastApplied,
+            MetricsRecord::Gauge(metrics.last_applied as f32),
+        ),
+        MetricsItem::new(
+            MetricsKey::RaftSnapshotIndex,
+            MetricsRecord::Gauge(snapshot_index as f32),
+        ),
+        MetricsItem::new(
+            MetricsKey::RaftIsLeader,
+            MetricsRecord::Gauge(if is_leader { 1f32 } else { 0f32 }),
+        ),
+    ];
+    let leader_log_index = match metrics.current_leader {
+        Some(_) if is_leader => Some(metrics.last_log_index),
+        Some(leader) => match raft_store.get_target_addr(leader).await {
+            Ok(addr) => query_leader_log_index(cluster_sender, addr).await,
+            Err(_) => None,
+        },
+        None => None,
+    };
+    list.append(&mut build_leader_metrics(
+        leader_log_index,
+        metrics.last_log_index,
+    ));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_node(node_id: u64, reachable: bool, last_log_index: u64) -> RaftNodeStatusInfo {
+        RaftNodeStatusInfo {
+            node_id,
+            reachable,
+            last_log_index,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fill_replication_lag() {
+        let mut nodes = vec![
+            build_node(1, true, 100),
+            build_node(2, true, 90),
+            build_node(3, false, 0),
+        ];
+        assert_eq!(fill_replication_lag(Some(1), &mut nodes), 100);
+        let lags: Vec<Option<u64>> = nodes.iter().map(|e| e.replication_lag).collect();
+        assert_eq!(lags, vec![Some(0), Some(10), None]);
+
+        //leader不可达时使用可达节点中最大的日志序号
+        let mut nodes = vec![
+            build_node(1, false, 0),
+            build_node(2, true, 90),
+            build_node(3, true, 95),
+        ];
+        assert_eq!(fill_replication_lag(Some(1), &mut nodes), 95);
+        assert_eq!(nodes[1].replication_lag, Some(5));
+        assert_eq!(nodes[2].replication_lag, Some(0));
+    }
+
+    #[test]
+    fn test_build_leader_metrics() {
+        let list = build_leader_metrics(Some(100), 90);
+        assert_eq!(list.len(), 2);
+        assert!(matches!(list[0].record, MetricsRecord::Gauge(v) if v == 1f32));
+        assert!(
+            matches!((&list[1].metrics_type, &list[1].record), (MetricsKey::RaftReplicationLag, MetricsRecord::Gauge(v)) if *v == 10f32)
+        );
+        let list = build_leader_metrics(None, 90);
+        assert_eq!(list.len(), 1);
+        assert!(
+            matches!((&list[0].metrics_type, &list[0].record), (MetricsKey::RaftLeaderReachable, MetricsRecord::Gauge(v)) if *v == 0f32)
+        );
+    }
+}
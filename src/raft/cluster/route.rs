@@ -1,6 +1,8 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, sync::Arc, time::Duration};
 
-use super::model::{DelConfigReq, RouteAddr, RouterRequest, RouterResponse, SetConfigReq};
+use super::model::{
+    DelConfigReq, ReadConsistency, RouteAddr, RouterRequest, RouterResponse, SetConfigReq,
+};
 use crate::grpc::handler::RAFT_ROUTE_REQUEST;
 use crate::namespace::model::{NamespaceRaftReq, NamespaceRaftResult};
 use crate::raft::filestore::core::FileStore;
@@ -52,8 +54,39 @@ impl RaftAddrRouter {
             None => Ok(RouteAddr::Unknown),
         }
     }
+
+    ///
+    /// leader确认自身仍是leader后,返回当前最新日志索引作为读索引
+    pub async fn leader_read_index(&self) -> anyhow::Result<u64> {
+        self.raft.client_read().await?;
+        let index = self.raft.metrics().borrow().last_log_index;
+        Ok(index)
+    }
+
+    ///
+    /// 等待本节点状态机应用到指定日志索引
+    pub async fn wait_applied(&self, index: u64, timeout: Duration) -> anyhow::Result<()> {
+        let mut metrics = self.raft.metrics();
+        let wait = async move {
+            loop {
+                if metrics.borrow().last_applied >= index {
+                    return Ok(());
+                }
+                if metrics.changed().await.is_err() {
+                    return Err(anyhow::anyhow!("raft metrics channel is closed"));
+                }
+            }
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(v) => v,
+            Err(_) => Err(anyhow::anyhow!("wait raft applied index {} timeout", index)),
+        }
+    }
 }
 
+/// read-index读等待本节点应用日志的超时时间
+const READ_INDEX_TIMEOUT: Duration = Duration::from_secs(3);
+
 #[derive(Clone, Debug)]
 pub struct ConfigRoute {
     config_addr: Addr<ConfigActor>,
@@ -110,6 +143,47 @@ impl ConfigRoute {
         Ok(())
     }
 
+    pub async fn leader_read_index(&self) -> anyhow::Result<u64> {
+        self.raft_addr_route.leader_read_index().await
+    }
+
+    ///
+    /// 线性一致读检查;
+    /// leader确认自身身份,follower从leader获取读索引并等待本节点应用到该索引
+    pub async fn read_index(&self) -> anyhow::Result<()> {
+        let index = match self.raft_addr_route.get_route_addr().await? {
+            RouteAddr::Local => self.raft_addr_route.leader_read_index().await?,
+            RouteAddr::Remote(_, addr) => {
+                let req = RouterRequest::ReadIndex;
+                let request = serde_json::to_string(&req).unwrap_or_default();
+                let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
+                let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
+                let body_vec = resp_payload.body.unwrap_or_default().value;
+                let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+                match resp {
+                    RouterResponse::ReadIndex { index } => index,
+                    _ => return Err(anyhow::anyhow!("response type is error!")),
+                }
+            }
+            RouteAddr::Unknown => {
+                return Err(self.unknown_err());
+            }
+        };
+        self.raft_addr_route
+            .wait_applied(index, READ_INDEX_TIMEOUT)
+            .await
+    }
+
+    ///
+    /// 按一致性级别在读取配置前做检查
+    pub async fn check_read_consistency(&self, consistency: ReadConsistency) -> anyhow::Result<()> {
+        if consistency.is_linearizable() {
+            self.read_index().await
+        } else {
+            Ok(())
+        }
+    }
+
     pub async fn del_config(&self, req: DelConfigReq) -> anyhow::Result<()> {
         match self.raft_addr_route.get_route_addr().await? {
             RouteAddr::Local => {
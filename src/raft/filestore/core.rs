@@ -17,7 +17,7 @@ use async_raft_ext::raft::{Entry, MembershipConfig};
 use async_raft_ext::storage::{CurrentSnapshotData, HardState, InitialState};
 use async_raft_ext::RaftStorage;
 use async_trait::async_trait;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 pub fn vec_to_set(list: &Vec<u64>) -> HashSet<u64> {
@@ -95,6 +95,70 @@ impl FileStore {
             Err(anyhow::anyhow!("get_target_addr error"))
         }
     }
+
+    ///
+    /// 接收镜像第一个分片时调用,返回可续传的位置
+    pub async fn prepare_install_snapshot(
+        &self,
+        last_index: u64,
+        last_term: u64,
+        data: Vec<u8>,
+    ) -> anyhow::Result<u64> {
+        match self
+            .snapshot_manager
+            .send(RaftSnapshotRequest::PrepareInstall {
+                last_index,
+                last_term,
+                data,
+            })
+            .await??
+        {
+            RaftSnapshotResponse::InstallOffset(offset) => Ok(offset),
+            _ => Ok(0),
+        }
+    }
+
+    ///
+    /// 记录已接收的镜像位置
+    pub async fn save_install_snapshot_offset(
+        &self,
+        last_index: u64,
+        last_term: u64,
+        offset: u64,
+    ) -> anyhow::Result<()> {
+        self.snapshot_manager
+            .send(RaftSnapshotRequest::SaveInstallOffset {
+                last_index,
+                last_term,
+                offset,
+            })
+            .await??;
+        Ok(())
+    }
+
+    pub async fn get_last_snapshot_index(&self) -> anyhow::Result<u64> {
+        match self
+            .index_manager
+            .send(RaftIndexRequest::LoadIndexInfo)
+            .await??
+        {
+            RaftIndexResponse::RaftIndexInfo { raft_index, .. } => {
+                Ok(raft_index.last_snapshot_index)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    pub async fn get_node_addrs(&self) -> anyhow::Result<HashMap<u64, Arc<String>>> {
+        match self
+            .index_manager
+            .send(RaftIndexRequest::LoadMember)
+            .await??
+        {
+            RaftIndexResponse::MemberShip { node_addrs, .. } => Ok(node_addrs),
+            _ => Ok(HashMap::new()),
+        }
+    }
 }
 
 #[async_trait]
@@ -320,11 +384,13 @@ impl RaftStorage<ClientRequest, ClientResponse> for FileStore {
             .send(RaftSnapshotRequest::NewSnapshotForLoad)
             .await??
         {
-            RaftSnapshotResponse::NewSnapshotForLoad(path, snapshot_id) => {
+            RaftSnapshotResponse::NewSnapshotForLoad(path, snapshot_id, resume) => {
+                //续传时保留已接收的数据,否则从头写入
                 let file = tokio::fs::OpenOptions::new()
                     .read(true)
                     .write(true)
                     .create(true)
+                    .truncate(!resume)
                     .open(path.as_str())
                     .await?;
                 Ok((snapshot_id.to_string(), Box::new(file)))
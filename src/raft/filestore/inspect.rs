@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_raft_ext::raft::EntryPayload;
+use base64::{engine::general_purpose, Engine};
+use quick_protobuf::BytesReader;
+use serde::Serialize;
+use tokio::{fs::OpenOptions, io::AsyncReadExt};
+
+use crate::common::constant::{CONFIG_TREE_NAME, NAMESPACE_TREE_NAME, USER_TREE_NAME};
+use crate::common::protobuf_utils::MessageBufReader;
+use crate::config::model::ConfigValueDO;
+use crate::namespace::model::NamespaceDO;
+use crate::raft::store::ClientRequest;
+use crate::user::model::UserDo;
+
+use super::{
+    log::LogRecord,
+    model::{LogRecordDto, SnapshotRecordDto},
+    raftlog::RaftLogManager,
+    raftsnapshot::{RaftSnapshotManager, SnapshotReader},
+    verify::{read_log_header, read_raft_index},
+};
+
+const READ_BUF_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSegmentInfo {
+    pub id: u64,
+    pub pre_term: u64,
+    pub start_index: u64,
+    /// 实际读取到的结束序号(不包含)
+    pub end_index: u64,
+    pub split_off_index: u64,
+    pub is_close: bool,
+    pub mark_remove: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftIndexInfo {
+    pub last_applied_log: u64,
+    pub current_term: u64,
+    pub voted_for: u64,
+    pub member: Vec<u64>,
+    pub member_after_consensus: Vec<u64>,
+    pub node_addrs: BTreeMap<u64, Arc<String>>,
+    pub current_log: u64,
+    pub last_snapshot: u64,
+    pub last_snapshot_index: u64,
+    pub last_snapshot_term: u64,
+    pub logs: Vec<LogSegmentInfo>,
+    pub snapshots: Vec<(u64, u64)>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntryInfo {
+    pub index: u64,
+    pub term: u64,
+    pub payload: serde_json::Value,
+}
+
+impl From<LogRecordDto> for LogEntryInfo {
+    fn from(record: LogRecordDto) -> Self {
+        let payload = serde_json::from_slice::<EntryPayload<ClientRequest>>(&record.value)
+            .ok()
+            .and_then(|v| serde_json::to_value(v).ok())
+            .unwrap_or_else(|| {
+                serde_json::Value::String(String::from_utf8_lossy(&record.value).to_string())
+            });
+        Self {
+            index: record.index,
+            term: record.term,
+            payload,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotRecordInfo {
+    pub tree: Arc<String>,
+    pub key: String,
+    pub op_type: u32,
+    pub value: serde_json::Value,
+}
+
+impl From<SnapshotRecordDto> for SnapshotRecordInfo {
+    fn from(record: SnapshotRecordDto) -> Self {
+        let tree = record.tree.as_str();
+        let value = if tree == CONFIG_TREE_NAME.as_str() {
+            ConfigValueDO::from_bytes(&record.value)
+                .ok()
+                .and_then(|v| serde_json::to_value(v).ok())
+        } else if tree == NAMESPACE_TREE_NAME.as_str() {
+            NamespaceDO::from_bytes(&record.value)
+                .ok()
+                .and_then(|v| serde_json::to_value(v).ok())
+        } else if tree == USER_TREE_NAME.as_str() {
+            //不输出密码
+            UserDo::from_bytes(&record.value).ok().and_then(|mut v| {
+                v.password = String::new();
+                v.password_hash = None;
+                serde_json::to_value(v).ok()
+            })
+        } else {
+            None
+        };
+        let value = value.unwrap_or_else(|| {
+            serde_json::Value::String(general_purpose::STANDARD.encode(&record.value))
+        });
+        Self {
+            tree: record.tree,
+            key: String::from_utf8_lossy(&record.key).to_string(),
+            op_type: record.op_type,
+            value,
+        }
+    }
+}
+
+///
+/// 只读方式顺序读取日志文件记录,回调返回false时停止读取
+pub async fn scan_log_file<F>(path: &str, mut f: F) -> anyhow::Result<u64>
+where
+    F: FnMut(LogRecordDto) -> bool,
+{
+    let mut file = OpenOptions::new().read(true).open(path).await?;
+    read_log_header(&mut file).await?;
+    let mut count = 0;
+    let mut message_reader = MessageBufReader::new();
+    let mut buf = vec![0u8; READ_BUF_SIZE];
+    loop {
+        while let Some(v) = message_reader.next_message_vec() {
+            let mut reader = BytesReader::from_bytes(v);
+            let record: LogRecord = reader.read_message(v)?;
+            count += 1;
+            if !f(record.into()) {
+                return Ok(count);
+            }
+        }
+        let read_len = file.read(&mut buf).await?;
+        if read_len == 0 {
+            break;
+        }
+        message_reader.append_next_buf(&buf[..read_len]);
+        if message_reader.is_empty() {
+            break;
+        }
+    }
+    Ok(count)
+}
+
+pub async fn inspect_raft_index(dir: &str) -> anyhow::Result<RaftIndexInfo> {
+    let index_path = Path::new(dir).join("index").to_string_lossy().into_owned();
+    let (last_applied_log, raft_index) = read_raft_index(&index_path).await?;
+    let mut logs = Vec::with_capacity(raft_index.logs.len());
+    for log_range in &raft_index.logs {
+        let count = if log_range.mark_remove {
+            0
+        } else {
+            scan_log_file(&RaftLogManager::get_log_path(dir, log_range), |_| true)
+                .await
+                .unwrap_or_default()
+        };
+        logs.push(LogSegmentInfo {
+            id: log_range.id,
+            pre_term: log_range.pre_term,
+            start_index: log_range.start_index,
+            end_index: log_range.start_index + count,
+            split_off_index: log_range.split_off_index,
+            is_close: log_range.is_close,
+            mark_remove: log_range.mark_remove,
+        });
+    }
+    Ok(RaftIndexInfo {
+        last_applied_log,
+        current_term: raft_index.current_term,
+        voted_for: raft_index.voted_for,
+        member: raft_index.member,
+        member_after_consensus: raft_index.member_after_consensus,
+        node_addrs: raft_index.node_addrs.into_iter().collect(),
+        current_log: raft_index.current_log,
+        last_snapshot: raft_index.last_snapshot,
+        last_snapshot_index: raft_index.last_snapshot_index,
+        last_snapshot_term: raft_index.last_snapshot_term,
+        logs,
+        snapshots: raft_index
+            .snapshots
+            .iter()
+            .map(|e| (e.id, e.end_index))
+            .collect(),
+    })
+}
+
+///
+/// 从start序号开始输出最多limit条日志,每条一行json
+pub async fn inspect_raft_logs(dir: &str, start: u64, limit: u64) -> anyhow::Result<()> {
+    let index_path = Path::new(dir).join("index").to_string_lossy().into_owned();
+    let (_, raft_index) = read_raft_index(&index_path).await?;
+    let mut remain = limit;
+    for log_range in &raft_index.logs {
+        if log_range.mark_remove || remain == 0 {
+            continue;
+        }
+        scan_log_file(&RaftLogManager::get_log_path(dir, log_range), |record| {
+            if record.index < start {
+                return true;
+            }
+            let entry: LogEntryInfo = record.into();
+            println!("{}", serde_json::to_string(&entry).unwrap_or_default());
+            remain -= 1;
+            remain > 0
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+///
+/// 输出镜像头信息与最多limit条记录;未指定镜像id时使用最新镜像
+pub async fn inspect_raft_snapshot(dir: &str, id: Option<u64>, limit: u64) -> anyhow::Result<()> {
+    let id = match id {
+        Some(v) => v,
+        None => {
+            let index_path = Path::new(dir).join("index").to_string_lossy().into_owned();
+            let (_, raft_index) = read_raft_index(&index_path).await?;
+            raft_index.last_snapshot
+        }
+    };
+    let mut reader = SnapshotReader::init(&RaftSnapshotManager::get_snapshot_path(dir, id)).await?;
+    let header = reader.get_header();
+    let header_json = serde_json::json!({
+        "id": id,
+        "lastIndex": header.last_index,
+        "lastTerm": header.last_term,
+        "member": header.member,
+        "memberAfterConsensus": header.member_after_consensus,
+        "nodeAddrs": header.node_addrs.iter().collect::<BTreeMap<_, _>>(),
+        "compressType": header.compress_type,
+    });
+    println!("{}", header_json);
+    let mut count = 0;
+    while count < limit {
+        match reader.read_record().await? {
+            Some(record) => {
+                let record: SnapshotRecordInfo = record.into();
+                println!("{}", serde_json::to_string(&record).unwrap_or_default());
+                count += 1;
+            }
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raft::filestore::raftlog::LogInnerManager;
+    use crate::raft::filestore::StoreUtils;
+    use async_raft_ext::raft::Entry;
+
+    #[actix_rt::test]
+    async fn test_scan_log_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "rnacos_inspect_test_{}_{}",
+            std::process::id(),
+            crate::now_millis()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("log_1").to_string_lossy().into_owned();
+        let mut manager = LogInnerManager::init(path.clone(), 1, 0, 0).await.unwrap();
+        for i in 1..=10u64 {
+            let entry = Entry {
+                term: 1,
+                index: i,
+                payload: EntryPayload::Normal(async_raft_ext::raft::EntryNormal {
+                    data: ClientRequest::NodeAddr {
+                        id: i,
+                        addr: Arc::new(format!("127.0.0.1:{}", 9848 + i)),
+                    },
+                }),
+            };
+            let record = StoreUtils::entry_to_record(&entry).unwrap();
+            manager.write(&record).await.unwrap();
+        }
+        manager.flush_log().await.unwrap();
+        drop(manager);
+        let mut entries = vec![];
+        let count = scan_log_file(&path, |record| {
+            entries.push(LogEntryInfo::from(record));
+            entries.len() < 5
+        })
+        .await
+        .unwrap();
+        assert_eq!(count, 5);
+        assert_eq!(entries[0].index, 1);
+        assert!(entries[0].payload.to_string().contains("127.0.0.1:9849"));
+        assert_eq!(scan_log_file(&path, |_| true).await.unwrap(), 10);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -20,6 +20,7 @@ pub struct LogRecord<'a> {
     pub index: u64,
     pub term: u64,
     pub value: Cow<'a, [u8]>,
+    pub crc: u32,
 }
 
 impl<'a> MessageRead<'a> for LogRecord<'a> {
@@ -30,6 +31,7 @@ impl<'a> MessageRead<'a> for LogRecord<'a> {
                 Ok(8) => msg.index = r.read_uint64(bytes)?,
                 Ok(16) => msg.term = r.read_uint64(bytes)?,
                 Ok(42) => msg.value = r.read_bytes(bytes).map(Cow::Borrowed)?,
+                Ok(56) => msg.crc = r.read_uint32(bytes)?,
                 Ok(t) => { r.read_unknown(bytes, t)?; }
                 Err(e) => return Err(e),
             }
@@ -44,12 +46,14 @@ impl<'a> MessageWrite for LogRecord<'a> {
         + if self.index == 0u64 { 0 } else { 1 + sizeof_varint(*(&self.index) as u64) }
         + if self.term == 0u64 { 0 } else { 1 + sizeof_varint(*(&self.term) as u64) }
         + if self.value == Cow::Borrowed(b"") { 0 } else { 1 + sizeof_len((&self.value).len()) }
+        + if self.crc == 0u32 { 0 } else { 1 + sizeof_varint(*(&self.crc) as u64) }
     }
 
     fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
         if self.index != 0u64 { w.write_with_tag(8, |w| w.write_uint64(*&self.index))?; }
         if self.term != 0u64 { w.write_with_tag(16, |w| w.write_uint64(*&self.term))?; }
         if self.value != Cow::Borrowed(b"") { w.write_with_tag(42, |w| w.write_bytes(&**&self.value))?; }
+        if self.crc != 0u32 { w.write_with_tag(56, |w| w.write_uint32(*&self.crc))?; }
         Ok(())
     }
 }
@@ -63,6 +67,8 @@ pub struct SnapshotHeader<'a> {
     pub member_after_consensus: Vec<u64>,
     pub node_addrs: Vec<log::NodeAddrItem<'a>>,
     pub extend: Cow<'a, [u8]>,
+    pub compress_type: u32,
+    pub version: u32,
 }
 
 impl<'a> MessageRead<'a> for SnapshotHeader<'a> {
@@ -76,6 +82,8 @@ impl<'a> MessageRead<'a> for SnapshotHeader<'a> {
                 Ok(34) => msg.member_after_consensus = r.read_packed(bytes, |r, bytes| Ok(r.read_uint64(bytes)?))?,
                 Ok(42) => msg.node_addrs.push(r.read_message::<log::NodeAddrItem>(bytes)?),
                 Ok(50) => msg.extend = r.read_bytes(bytes).map(Cow::Borrowed)?,
+                Ok(56) => msg.compress_type = r.read_uint32(bytes)?,
+                Ok(64) => msg.version = r.read_uint32(bytes)?,
                 Ok(t) => { r.read_unknown(bytes, t)?; }
                 Err(e) => return Err(e),
             }
@@ -93,6 +101,8 @@ impl<'a> MessageWrite for SnapshotHeader<'a> {
         + if self.member_after_consensus.is_empty() { 0 } else { 1 + sizeof_len(self.member_after_consensus.iter().map(|s| sizeof_varint(*(s) as u64)).sum::<usize>()) }
         + self.node_addrs.iter().map(|s| 1 + sizeof_len((s).get_size())).sum::<usize>()
         + if self.extend == Cow::Borrowed(b"") { 0 } else { 1 + sizeof_len((&self.extend).len()) }
+        + if self.compress_type == 0u32 { 0 } else { 1 + sizeof_varint(*(&self.compress_type) as u64) }
+        + if self.version == 0u32 { 0 } else { 1 + sizeof_varint(*(&self.version) as u64) }
     }
 
     fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
@@ -102,6 +112,8 @@ impl<'a> MessageWrite for SnapshotHeader<'a> {
         w.write_packed_with_tag(34, &self.member_after_consensus, |w, m| w.write_uint64(*m), &|m| sizeof_varint(*(m) as u64))?;
         for s in &self.node_addrs { w.write_with_tag(42, |w| w.write_message(s))?; }
         if self.extend != Cow::Borrowed(b"") { w.write_with_tag(50, |w| w.write_bytes(&**&self.extend))?; }
+        if self.compress_type != 0u32 { w.write_with_tag(56, |w| w.write_uint32(*&self.compress_type))?; }
+        if self.version != 0u32 { w.write_with_tag(64, |w| w.write_uint32(*&self.version))?; }
         Ok(())
     }
 }
@@ -113,6 +125,7 @@ pub struct LogSnapshotItem<'a> {
     pub key: Cow<'a, [u8]>,
     pub value: Cow<'a, [u8]>,
     pub op_type: u32,
+    pub crc: u32,
 }
 
 impl<'a> MessageRead<'a> for LogSnapshotItem<'a> {
@@ -124,6 +137,7 @@ impl<'a> MessageRead<'a> for LogSnapshotItem<'a> {
                 Ok(34) => msg.key = r.read_bytes(bytes).map(Cow::Borrowed)?,
                 Ok(42) => msg.value = r.read_bytes(bytes).map(Cow::Borrowed)?,
                 Ok(48) => msg.op_type = r.read_uint32(bytes)?,
+                Ok(56) => msg.crc = r.read_uint32(bytes)?,
                 Ok(t) => { r.read_unknown(bytes, t)?; }
                 Err(e) => return Err(e),
             }
@@ -139,6 +153,7 @@ impl<'a> MessageWrite for LogSnapshotItem<'a> {
         + if self.key == Cow::Borrowed(b"") { 0 } else { 1 + sizeof_len((&self.key).len()) }
         + if self.value == Cow::Borrowed(b"") { 0 } else { 1 + sizeof_len((&self.value).len()) }
         + if self.op_type == 0u32 { 0 } else { 1 + sizeof_varint(*(&self.op_type) as u64) }
+        + if self.crc == 0u32 { 0 } else { 1 + sizeof_varint(*(&self.crc) as u64) }
     }
 
     fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
@@ -146,6 +161,7 @@ impl<'a> MessageWrite for LogSnapshotItem<'a> {
         if self.key != Cow::Borrowed(b"") { w.write_with_tag(34, |w| w.write_bytes(&**&self.key))?; }
         if self.value != Cow::Borrowed(b"") { w.write_with_tag(42, |w| w.write_bytes(&**&self.value))?; }
         if self.op_type != 0u32 { w.write_with_tag(48, |w| w.write_uint32(*&self.op_type))?; }
+        if self.crc != 0u32 { w.write_with_tag(56, |w| w.write_uint32(*&self.crc))?; }
         Ok(())
     }
 }
@@ -5,6 +5,7 @@ use self::model::LogRecordDto;
 use super::store::ClientRequest;
 
 pub mod core;
+pub mod inspect;
 pub mod log;
 pub mod model;
 pub mod raftapply;
@@ -12,6 +13,7 @@ pub mod raftdata;
 pub mod raftindex;
 pub mod raftlog;
 pub mod raftsnapshot;
+pub mod verify;
 
 pub struct StoreUtils;
 
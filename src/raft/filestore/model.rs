@@ -1,9 +1,10 @@
 use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
-use async_raft_ext::raft::InstallSnapshotRequest;
+use async_raft_ext::raft::{InstallSnapshotRequest, InstallSnapshotResponse};
 use async_trait::async_trait;
 use binrw::prelude::*;
 use prost::Message;
+use serde::{Deserialize, Serialize};
 
 use crate::raft::store::ClientRequest;
 
@@ -12,6 +13,15 @@ use super::log::{
 };
 
 pub const LOG_INDEX_HEADER_LEN: u64 = 32;
+pub const LOG_INDEX_HEADER_MAGIC: u32 = 0x42313644;
+/// 日志记录带crc校验值的文件版本
+pub const LOG_RECORD_CRC_VERSION: u16 = 1;
+/// 镜像记录区不压缩
+pub const SNAPSHOT_COMPRESS_NONE: u32 = 0;
+/// 镜像记录区使用gzip压缩
+pub const SNAPSHOT_COMPRESS_GZIP: u32 = 1;
+/// 镜像记录带crc校验值的文件版本
+pub const SNAPSHOT_RECORD_CRC_VERSION: u32 = 1;
 
 ///
 /// ----
@@ -45,8 +55,8 @@ pub struct LogIndexHeaderDo {
 impl LogIndexHeaderDo {
     pub fn new() -> Self {
         Self {
-            magic: 0x42313644,
-            version: 0,
+            magic: LOG_INDEX_HEADER_MAGIC,
+            version: LOG_RECORD_CRC_VERSION,
             last_term: 0,
             first_index: 0,
             data_area_index: 4096,
@@ -88,10 +98,37 @@ impl LogRecordDto {
             //key: Cow::Borrowed(&self.key),
             value: Cow::Borrowed(&self.value),
             //op_type: self.op_type,
+            crc: log_record_crc(self.index, self.term, &self.value),
         }
     }
 }
 
+pub fn log_record_crc(index: u64, term: u64, value: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&index.to_be_bytes());
+    hasher.update(&term.to_be_bytes());
+    hasher.update(value);
+    hasher.finalize()
+}
+
+///
+/// 校验日志记录;旧版本文件中没有crc的记录不校验
+pub fn check_log_record(record: &LogRecord, version: u16) -> anyhow::Result<()> {
+    if record.crc == 0 && version < LOG_RECORD_CRC_VERSION {
+        return Ok(());
+    }
+    let crc = log_record_crc(record.index, record.term, &record.value);
+    if crc != record.crc {
+        return Err(anyhow::anyhow!(
+            "raft log record crc error,index:{},crc:{},expect:{}",
+            record.index,
+            record.crc,
+            crc
+        ));
+    }
+    Ok(())
+}
+
 impl<'a> From<LogRecord<'a>> for LogRecordDto {
     fn from(value: LogRecord<'a>) -> Self {
         Self {
@@ -112,6 +149,8 @@ pub struct SnapshotHeaderDto {
     pub member: Vec<u64>,
     pub member_after_consensus: Vec<u64>,
     pub node_addrs: HashMap<u64, Arc<String>>,
+    pub compress_type: u32,
+    pub version: u32,
 }
 
 impl<'a> From<SnapshotHeader<'a>> for SnapshotHeaderDto {
@@ -126,6 +165,8 @@ impl<'a> From<SnapshotHeader<'a>> for SnapshotHeaderDto {
             member: value.member,
             member_after_consensus: value.member_after_consensus,
             node_addrs,
+            compress_type: value.compress_type,
+            version: value.version,
         }
     }
 }
@@ -146,6 +187,8 @@ impl SnapshotHeaderDto {
             member_after_consensus: self.member_after_consensus.clone(),
             node_addrs,
             extend: Cow::Owned(Vec::new()),
+            compress_type: self.compress_type,
+            version: self.version,
         }
     }
 }
@@ -176,10 +219,37 @@ impl SnapshotRecordDto {
             key: Cow::Borrowed(&self.key),
             value: Cow::Borrowed(&self.value),
             op_type: self.op_type,
+            crc: snapshot_record_crc(&self.tree, &self.key, &self.value, self.op_type),
         }
     }
 }
 
+pub fn snapshot_record_crc(tree: &str, key: &[u8], value: &[u8], op_type: u32) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(tree.as_bytes());
+    hasher.update(key);
+    hasher.update(value);
+    hasher.update(&op_type.to_be_bytes());
+    hasher.finalize()
+}
+
+///
+/// 校验镜像记录;旧版本镜像中没有crc的记录不校验
+pub fn check_snapshot_record(record: &LogSnapshotItem, version: u32) -> anyhow::Result<()> {
+    if version < SNAPSHOT_RECORD_CRC_VERSION {
+        return Ok(());
+    }
+    let crc = snapshot_record_crc(&record.tree, &record.key, &record.value, record.op_type);
+    if crc != record.crc {
+        return Err(anyhow::anyhow!(
+            "raft snapshot record crc error,crc:{},expect:{}",
+            record.crc,
+            crc
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct RaftIndexDto {
     pub logs: Vec<LogRange>,
@@ -325,6 +395,22 @@ impl From<InstallSnapshotRequestDto> for InstallSnapshotRequest {
     }
 }
 
+///
+/// 在InstallSnapshotResponse基础上返回续传位置,旧版本节点忽略该字段
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallSnapshotResponseDto {
+    pub term: u64,
+    #[serde(default)]
+    pub resume_offset: u64,
+}
+
+impl From<InstallSnapshotResponseDto> for InstallSnapshotResponse {
+    fn from(value: InstallSnapshotResponseDto) -> Self {
+        Self { term: value.term }
+    }
+}
+
 impl InstallSnapshotRequestDto {
     pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
         let mut data_bytes: Vec<u8> = Vec::new();
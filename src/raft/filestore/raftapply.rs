@@ -3,7 +3,10 @@ use std::sync::Arc;
 
 use super::{
     log::SnapshotRange,
-    model::{ApplyRequestDto, LogRecordLoader, MemberShip, SnapshotHeaderDto},
+    model::{
+        ApplyRequestDto, LogRecordLoader, MemberShip, SnapshotHeaderDto, SNAPSHOT_COMPRESS_NONE,
+        SNAPSHOT_RECORD_CRC_VERSION,
+    },
     raftindex::{RaftIndexManager, RaftIndexRequest, RaftIndexResponse},
     raftlog::{RaftLogManager, RaftLogManagerAsyncRequest},
     raftsnapshot::{
@@ -113,6 +116,12 @@ impl LogRecordLoader for LogRecordLoaderInstance {
                 ClientRequest::NamespaceReq(req) => {
                     self.data_wrap.namespace.send(req).await.ok();
                 }
+                ClientRequest::RemoveNodeAddr { id } => {
+                    self.index_manager
+                        .send(RaftIndexRequest::RemoveNodeAddr(id))
+                        .await
+                        .ok();
+                }
             },
             _ => {}
         }
@@ -409,6 +418,11 @@ impl StateApplyManager {
                     raft_data_wrap.namespace.do_send(req);
                 }
             }
+            ClientRequest::RemoveNodeAddr { id } => {
+                if let Some(index_manager) = &self.index_manager {
+                    index_manager.do_send(RaftIndexRequest::RemoveNodeAddr(id));
+                }
+            }
         };
         Ok(())
     }
@@ -485,6 +499,10 @@ impl StateApplyManager {
                 raft_data_wrap.namespace.send(req).await??;
                 Ok(ClientResponse::Success)
             }
+            ClientRequest::RemoveNodeAddr { id } => {
+                index_manager.do_send(RaftIndexRequest::RemoveNodeAddr(id));
+                Ok(ClientResponse::Success)
+            }
         };
         index_manager.do_send(RaftIndexRequest::SaveLastAppliedLog(last_applied_log));
         r
@@ -533,6 +551,9 @@ impl StateApplyManager {
             member: member_ship.member,
             member_after_consensus: member_ship.member_after_consensus,
             node_addrs: member_ship.node_addrs,
+            //压缩方式由RaftSnapshotManager按配置设置
+            compress_type: SNAPSHOT_COMPRESS_NONE,
+            version: SNAPSHOT_RECORD_CRC_VERSION,
         };
         let (writer, snapshot_id, path) = match snapshot_manager
             .send(RaftSnapshotRequest::NewSnapshot(header.clone()))
@@ -1,5 +1,9 @@
 #![allow(clippy::suspicious_open_options)]
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+};
 
 use actix::prelude::*;
 use bean_factory::{bean, Inject};
@@ -13,7 +17,7 @@ use crate::common::{
     byte_utils::{bin_to_id, id_to_bin},
     protobuf_utils::FileMessageReader,
 };
-use crate::naming::cluster::node_manage::{InnerNodeManage, NodeManageRequest};
+use crate::naming::cluster::node_manage::{build_naming_nodes, InnerNodeManage, NodeManageRequest};
 
 use super::{
     log::{LogRange, RaftIndex, SnapshotRange},
@@ -174,27 +178,18 @@ impl RaftIndexManager {
             .wait(ctx);
     }
 
-    fn do_notify_membership(&self, is_change_member: bool) {
+    fn do_notify_membership(&self) {
         if let (Some(naming_node_manage), Some(inner_manager)) =
             (&self.naming_inner_node_manage, self.inner.as_ref())
         {
-            let mut nodes = vec![];
-            if is_change_member {
-                for nid in &inner_manager.raft_index.member {
-                    if let Some(addr) = inner_manager.raft_index.node_addrs.get(nid) {
-                        nodes.push((*nid, addr.to_owned()))
-                    }
-                }
-                for nid in &inner_manager.raft_index.member_after_consensus {
-                    if let Some(addr) = inner_manager.raft_index.node_addrs.get(nid) {
-                        nodes.push((*nid, addr.to_owned()))
-                    }
-                }
-            } else {
-                for (nid, addr) in &inner_manager.raft_index.node_addrs {
-                    nodes.push((*nid, addr.to_owned()));
-                }
-            }
+            let raft_index = &inner_manager.raft_index;
+            let voters: HashSet<u64> = raft_index
+                .member
+                .iter()
+                .chain(raft_index.member_after_consensus.iter())
+                .cloned()
+                .collect();
+            let nodes = build_naming_nodes(&raft_index.node_addrs, &voters);
             naming_node_manage.do_send(NodeManageRequest::UpdateNodes(nodes));
         }
     }
@@ -267,7 +262,7 @@ impl RaftIndexManager {
         .map(|(v, change_member), act, _ctx| {
             act.inner = v;
             if change_member {
-                act.do_notify_membership(true);
+                act.do_notify_membership();
             }
         })
         .wait(ctx);
@@ -353,6 +348,22 @@ impl RaftIndexManager {
         }
     }
 
+    pub fn remove_node_addr(
+        &mut self,
+        ctx: &mut Context<Self>,
+        id: u64,
+    ) -> anyhow::Result<RaftIndexResponse> {
+        if let Some(inner) = self.inner.as_mut() {
+            if inner.raft_index.node_addrs.remove(&id).is_none() {
+                return Ok(RaftIndexResponse::None);
+            }
+            let index_info = inner.raft_index.clone();
+            self.write_index(ctx, index_info, true)
+        } else {
+            Err(Self::inner_is_empty_error())
+        }
+    }
+
     pub fn write_hard_state(
         &mut self,
         ctx: &mut Context<Self>,
@@ -389,7 +400,7 @@ impl Inject for RaftIndexManager {
         _ctx: &mut Self::Context,
     ) {
         self.naming_inner_node_manage = factory_data.get_actor();
-        self.do_notify_membership(false);
+        self.do_notify_membership();
     }
 }
 
@@ -410,6 +421,7 @@ pub enum RaftIndexRequest {
     },
     //SaveNodeAddr(HashMap<u64, Arc<String>>),
     AddNodeAddr(u64, Arc<String>),
+    RemoveNodeAddr(u64),
     SaveHardState {
         current_term: u64,
         voted_for: u64,
@@ -453,6 +465,7 @@ impl Handler<RaftIndexRequest> for RaftIndexManager {
             } => self.write_member(ctx, member, member_after_consensus, node_addr),
             //RaftIndexRequest::SaveNodeAddr(node_addr) => self.write_node_addr(ctx, node_addr),
             RaftIndexRequest::AddNodeAddr(id, node_addr) => self.add_node_addr(ctx, id, node_addr),
+            RaftIndexRequest::RemoveNodeAddr(id) => self.remove_node_addr(ctx, id),
             RaftIndexRequest::SaveHardState {
                 current_term,
                 voted_for,
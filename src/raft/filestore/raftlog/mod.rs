@@ -25,20 +25,20 @@ use crate::{
         inner_sizeof_varint, read_varint64_offset, write_varint64, FileMessageReader,
         MessageBufReader,
     },
-    raft::filestore::model::LOG_INDEX_HEADER_LEN,
+    raft::filestore::model::{LOG_INDEX_HEADER_LEN, LOG_INDEX_HEADER_MAGIC},
 };
 
 use super::{
-    model::{LogIndexHeaderDo, LogRecordDto},
+    model::{check_log_record, LogIndexHeaderDo, LogRecordDto},
     raftindex::{RaftIndexManager, RaftIndexRequest, RaftIndexResponse},
 };
 
 const LOG_DATA_BUF_SIZE: u64 = 1024 * 1024;
 
 #[derive(Debug, Default, Clone)]
-struct InnerIdxDto {
-    log_index: u64,
-    file_index: u64,
+pub(crate) struct InnerIdxDto {
+    pub(crate) log_index: u64,
+    pub(crate) file_index: u64,
 }
 
 pub enum LogWriteMark {
@@ -140,6 +140,12 @@ impl LogInnerManager {
             let _len = data_file.read(&mut data_buf).await?;
             let mut stream = Cursor::new(&data_buf);
             let header: LogIndexHeaderDo = stream.read_be()?;
+            if header.magic != LOG_INDEX_HEADER_MAGIC {
+                return Err(anyhow::anyhow!(
+                    "raft log file header magic error,{}",
+                    &log_path
+                ));
+            }
             let (indexs, index_cursor) = Self::read_indexs(
                 &data_buf[(LOG_INDEX_HEADER_LEN as usize)..],
                 first_index,
@@ -195,7 +201,7 @@ impl LogInnerManager {
         Ok(this)
     }
 
-    async fn flush_log(&mut self) -> anyhow::Result<()> {
+    pub(crate) async fn flush_log(&mut self) -> anyhow::Result<()> {
         let end_index = self.get_end_index();
         if self.last_flush_index < end_index {
             self.data_file.flush().await?;
@@ -205,7 +211,7 @@ impl LogInnerManager {
         Ok(())
     }
 
-    fn read_indexs(
+    pub(crate) fn read_indexs(
         index_buf: &[u8],
         first_index: InnerIdxDto,
         index_interval: u64,
@@ -452,6 +458,7 @@ impl LogInnerManager {
             while let Some(v) = message_reader.next_message_vec() {
                 let mut reader = BytesReader::from_bytes(v);
                 let item: LogRecord = reader.read_message(v)?;
+                check_log_record(&item, self.header.version)?;
                 let dto = item.into();
                 rlist.push(dto);
                 c -= 1;
@@ -522,6 +529,7 @@ impl LogInnerManager {
             while let Some(v) = message_reader.next_message_vec() {
                 let mut reader = BytesReader::from_bytes(v);
                 let item: LogRecord = reader.read_message(v)?;
+                check_log_record(&item, self.header.version)?;
                 let dto = item.into();
                 //rlist.push(dto);
                 loader.load(dto).await?;
@@ -1290,7 +1298,7 @@ impl RaftLogManager {
         }
     }
 
-    fn get_log_path(base_path: &str, log_range: &LogRange) -> String {
+    pub(crate) fn get_log_path(base_path: &str, log_range: &LogRange) -> String {
         Path::new(base_path)
             .join(format!("log_{}", log_range.id))
             .to_string_lossy()
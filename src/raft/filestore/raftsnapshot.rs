@@ -1,9 +1,11 @@
 #![allow(clippy::suspicious_open_options)]
-use std::{path::Path, sync::Arc};
+use std::{io::Write, path::Path, sync::Arc};
 
 use actix::prelude::*;
 use bean_factory::{bean, Inject};
+use flate2::{write::GzDecoder, write::GzEncoder, Compression};
 use quick_protobuf::{BytesReader, Writer};
+use serde::{Deserialize, Serialize};
 use tokio::{
     fs::OpenOptions,
     io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
@@ -13,23 +15,41 @@ use crate::common::protobuf_utils::MessageBufReader;
 
 use super::{
     log::{LogSnapshotItem, SnapshotHeader, SnapshotRange},
-    model::{SnapshotHeaderDto, SnapshotRecordDto},
+    model::{
+        check_snapshot_record, SnapshotHeaderDto, SnapshotRecordDto, SNAPSHOT_COMPRESS_GZIP,
+        SNAPSHOT_COMPRESS_NONE,
+    },
     raftindex::{RaftIndexManager, RaftIndexRequest, RaftIndexResponse},
 };
 
+/// 压缩数据写入文件前的缓存大小
+const COMPRESS_BUF_SIZE: usize = 64 * 1024;
+/// 接收镜像进度文件名
+const INSTALL_PROGRESS_FILE: &str = "snapshot_install_progress";
+
 #[derive(Debug)]
 pub struct SnapshotWriter {
     file: tokio::fs::File,
+    encoder: Option<GzEncoder<Vec<u8>>>,
 }
 
 impl SnapshotWriter {
     pub async fn init(path: &str, header: SnapshotHeaderDto) -> anyhow::Result<Self> {
+        let encoder = match header.compress_type {
+            SNAPSHOT_COMPRESS_NONE => None,
+            SNAPSHOT_COMPRESS_GZIP => Some(GzEncoder::new(
+                Vec::with_capacity(COMPRESS_BUF_SIZE),
+                Compression::default(),
+            )),
+            v => return Err(anyhow::anyhow!("unsupported snapshot compress type:{}", v)),
+        };
         let mut file = OpenOptions::new()
             .read(true)
             .write(true)
             //.append(true)
             //.create_new(true)
             .create(true)
+            .truncate(true)
             .open(path)
             .await?;
         let mut buf = Vec::new();
@@ -37,11 +57,20 @@ impl SnapshotWriter {
         let record = header.to_record_do();
         writer.write_message(&record)?;
         file.write_all(&buf).await?;
-        Ok(Self { file })
+        Ok(Self { file, encoder })
     }
 
     pub async fn write(&mut self, buf: &[u8]) -> anyhow::Result<()> {
-        self.file.write_all(buf).await?;
+        if let Some(encoder) = self.encoder.as_mut() {
+            encoder.write_all(buf)?;
+            //压缩数据达到缓存大小后再写入文件
+            if encoder.get_ref().len() >= COMPRESS_BUF_SIZE {
+                self.file.write_all(encoder.get_ref()).await?;
+                encoder.get_mut().clear();
+            }
+        } else {
+            self.file.write_all(buf).await?;
+        }
         Ok(())
     }
 
@@ -49,11 +78,17 @@ impl SnapshotWriter {
         let mut buf = Vec::new();
         let mut writer = Writer::new(&mut buf);
         writer.write_message(&record.to_record_do())?;
-        self.file.write_all(&buf).await?;
-        Ok(())
+        self.write(&buf).await
     }
 
+    ///
+    /// 开启压缩时flush会结束压缩流,之后不能再写入记录
     pub async fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(encoder) = self.encoder.as_mut() {
+            encoder.try_finish()?;
+            self.file.write_all(encoder.get_ref()).await?;
+            encoder.get_mut().clear();
+        }
         self.file.flush().await?;
         Ok(())
     }
@@ -170,6 +205,7 @@ pub struct SnapshotReader {
     file: Box<tokio::fs::File>,
     header: SnapshotHeaderDto,
     message_reader: MessageBufReader,
+    decoder: Option<GzDecoder<Vec<u8>>>,
     is_end: bool,
 }
 
@@ -180,45 +216,67 @@ impl SnapshotReader {
         file.seek(std::io::SeekFrom::Start(0)).await?;
         let read_len = file.read(&mut buf).await?;
         message_reader.append_next_buf(&buf[..read_len]);
-        if let Some(v) = message_reader.next_message_vec() {
+        let header: SnapshotHeaderDto = if let Some(v) = message_reader.next_message_vec() {
             let mut reader = BytesReader::from_bytes(v);
             let header: SnapshotHeader = reader.read_message(v)?;
-            Ok(Self {
-                file,
-                header: header.into(),
-                message_reader,
-                is_end: false,
-            })
+            header.into()
         } else {
-            Err(anyhow::anyhow!("read snapshot head error"))
+            return Err(anyhow::anyhow!("read snapshot head error"));
+        };
+        let decoder = match header.compress_type {
+            SNAPSHOT_COMPRESS_NONE => None,
+            SNAPSHOT_COMPRESS_GZIP => Some(GzDecoder::new(Vec::new())),
+            v => return Err(anyhow::anyhow!("unsupported snapshot compress type:{}", v)),
+        };
+        let mut snapshot_reader = Self {
+            file,
+            header,
+            message_reader,
+            decoder,
+            is_end: false,
+        };
+        if snapshot_reader.decoder.is_some() {
+            //头信息之后的数据需要先解压
+            let remaining = snapshot_reader.message_reader.take_remaining();
+            snapshot_reader.append_data(&remaining)?;
         }
+        Ok(snapshot_reader)
     }
 
     pub async fn init(path: &str) -> anyhow::Result<Self> {
-        let mut file = Box::new(OpenOptions::new().read(true).open(path).await?);
-        let mut message_reader = MessageBufReader::new();
-        let mut buf = vec![0u8; 1024];
-        let read_len = file.read(&mut buf).await?;
-        message_reader.append_next_buf(&buf[..read_len]);
-        if let Some(v) = message_reader.next_message_vec() {
-            let mut reader = BytesReader::from_bytes(v);
-            let header: SnapshotHeader = reader.read_message(v)?;
-            Ok(Self {
-                file,
-                header: header.into(),
-                message_reader,
-                is_end: false,
-            })
-        } else {
-            Err(anyhow::anyhow!("read snapshot head error"))
-        }
+        let file = Box::new(OpenOptions::new().read(true).open(path).await?);
+        Self::init_by_file(file).await
     }
 
     pub fn get_header(&self) -> &SnapshotHeaderDto {
         &self.header
     }
 
+    fn append_data(&mut self, buf: &[u8]) -> anyhow::Result<()> {
+        if let Some(decoder) = self.decoder.as_mut() {
+            decoder.write_all(buf)?;
+            decoder.flush()?;
+            self.message_reader.append_next_buf(decoder.get_ref());
+            decoder.get_mut().clear();
+        } else {
+            self.message_reader.append_next_buf(buf);
+        }
+        Ok(())
+    }
+
     pub async fn read_record(&mut self) -> anyhow::Result<Option<SnapshotRecordDto>> {
+        match self.read_record_with_check().await? {
+            Some((record, Ok(_))) => Ok(Some(record)),
+            Some((_, Err(err))) => Err(err),
+            None => Ok(None),
+        }
+    }
+
+    ///
+    /// 读取记录并返回crc校验结果,校验失败时可以继续读取后续记录
+    pub async fn read_record_with_check(
+        &mut self,
+    ) -> anyhow::Result<Option<(SnapshotRecordDto, anyhow::Result<()>)>> {
         if self.is_end {
             return Ok(None);
         }
@@ -226,20 +284,38 @@ impl SnapshotReader {
             if let Some(v) = self.message_reader.next_message_vec() {
                 let mut reader = BytesReader::from_bytes(v);
                 let item: LogSnapshotItem = reader.read_message(v)?;
-                let dto = item.into();
-                return Ok(Some(dto));
+                let check = check_snapshot_record(&item, self.header.version);
+                return Ok(Some((item.into(), check)));
             }
             let mut buf = vec![0u8; 1024];
             let read_len = self.file.read(&mut buf).await?;
             if read_len == 0 {
+                if let Some(mut decoder) = self.decoder.take() {
+                    //结束压缩流,同时校验压缩数据是否完整
+                    decoder.try_finish()?;
+                    self.message_reader.append_next_buf(decoder.get_ref());
+                    continue;
+                }
                 self.is_end = true;
                 return Ok(None);
             }
-            self.message_reader.append_next_buf(&buf[..read_len]);
+            self.append_data(&buf[..read_len])?;
         }
     }
 }
 
+///
+/// 接收leader镜像的进度,用于中断后续传
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInstallProgress {
+    pub id: u64,
+    pub last_index: u64,
+    pub last_term: u64,
+    /// 该位置之前的数据已写入镜像文件
+    pub offset: u64,
+}
+
 #[bean(inject)]
 #[derive(Default)]
 pub struct RaftSnapshotManager {
@@ -249,10 +325,17 @@ pub struct RaftSnapshotManager {
     building: Option<SnapshotRange>,
     index_manager: Option<Addr<RaftIndexManager>>,
     is_init: bool,
+    /// 新打包镜像使用的压缩方式
+    compress_type: u32,
+    installing: Option<SnapshotInstallProgress>,
 }
 
 impl RaftSnapshotManager {
-    pub fn new(base_path: Arc<String>, index_manager: Option<Addr<RaftIndexManager>>) -> Self {
+    pub fn new(
+        base_path: Arc<String>,
+        index_manager: Option<Addr<RaftIndexManager>>,
+        compress_type: u32,
+    ) -> Self {
         Self {
             base_path,
             snapshots: Vec::default(),
@@ -260,13 +343,102 @@ impl RaftSnapshotManager {
             building: None,
             index_manager,
             is_init: false,
+            compress_type,
+            installing: None,
         }
     }
 
     fn init(&mut self, ctx: &mut Context<Self>) {
+        self.load_install_progress();
         self.load_index_info(ctx);
     }
 
+    fn get_install_progress_path(&self) -> String {
+        Path::new(self.base_path.as_str())
+            .join(INSTALL_PROGRESS_FILE)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn load_install_progress(&mut self) {
+        if let Ok(content) = std::fs::read(self.get_install_progress_path()) {
+            self.installing = serde_json::from_slice(&content).ok();
+        }
+    }
+
+    fn save_install_progress(&mut self, progress: Option<SnapshotInstallProgress>) {
+        let path = self.get_install_progress_path();
+        if let Some(progress) = &progress {
+            if let Err(err) = serde_json::to_vec(progress)
+                .map_err(anyhow::Error::from)
+                .and_then(|v| std::fs::write(&path, v).map_err(anyhow::Error::from))
+            {
+                log::warn!("save snapshot install progress error,{}", err);
+            }
+        } else if self.installing.is_some() {
+            std::fs::remove_file(&path).ok();
+        }
+        self.installing = progress;
+    }
+
+    ///
+    /// 接收镜像第一个分片时调用,同一镜像且已写入的数据与分片一致时返回续传位置;否则清空镜像文件从头接收
+    fn prepare_install(
+        &mut self,
+        last_index: u64,
+        last_term: u64,
+        data: &[u8],
+    ) -> anyhow::Result<u64> {
+        let next_id = self.get_next_id()?;
+        let path = Self::get_snapshot_path(&self.base_path, next_id);
+        if let Some(progress) = &self.installing {
+            if progress.id == next_id
+                && progress.last_index == last_index
+                && progress.last_term == last_term
+                && progress.offset > 0
+                && Self::file_starts_with(&path, data)
+            {
+                log::info!(
+                    "resume install snapshot,last_index:{},offset:{}",
+                    last_index,
+                    progress.offset
+                );
+                return Ok(progress.offset);
+            }
+        }
+        if let Ok(file) = std::fs::OpenOptions::new().write(true).open(&path) {
+            file.set_len(0)?;
+        }
+        self.save_install_progress(Some(SnapshotInstallProgress {
+            id: next_id,
+            last_index,
+            last_term,
+            offset: 0,
+        }));
+        Ok(0)
+    }
+
+    fn file_starts_with(path: &str, data: &[u8]) -> bool {
+        let mut buf = vec![0u8; data.len()];
+        std::fs::File::open(path)
+            .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut buf))
+            .map(|_| buf == data)
+            .unwrap_or(false)
+    }
+
+    fn save_install_offset(&mut self, last_index: u64, last_term: u64, offset: u64) {
+        if let Some(progress) = &self.installing {
+            if progress.last_index == last_index
+                && progress.last_term == last_term
+                && progress.offset != offset
+            {
+                let mut progress = progress.clone();
+                progress.offset = offset;
+                self.save_install_progress(Some(progress));
+            }
+        }
+    }
+
     fn load_index_info(&mut self, ctx: &mut Context<Self>) {
         //加载索引文件、构建raft日志
         let index_manager = self.index_manager.clone();
@@ -328,7 +500,7 @@ impl RaftSnapshotManager {
         Ok(next_id)
     }
 
-    fn get_snapshot_path(base_path: &str, id: u64) -> String {
+    pub(crate) fn get_snapshot_path(base_path: &str, id: u64) -> String {
         Path::new(base_path)
             .join(format!("snapshot_{}", id))
             .to_string_lossy()
@@ -350,6 +522,9 @@ impl RaftSnapshotManager {
         snapshot_range: SnapshotRange,
     ) -> anyhow::Result<()> {
         self.building.take();
+        if matches!(&self.installing,Some(v) if v.id <= snapshot_range.id) {
+            self.save_install_progress(None);
+        }
         //1. 删除历史镜像
         let old_snapshot_len = self.snapshots.len();
         let split_index = if old_snapshot_len > 1 {
@@ -446,13 +621,28 @@ pub enum RaftSnapshotRequest {
     NewSnapshot(SnapshotHeaderDto),
     NewSnapshotForLoad,
     CompleteSnapshot(SnapshotRange),
-    InstallSnapshot { end_index: u64, snapshot_id: u64 },
+    InstallSnapshot {
+        end_index: u64,
+        snapshot_id: u64,
+    },
+    PrepareInstall {
+        last_index: u64,
+        last_term: u64,
+        data: Vec<u8>,
+    },
+    SaveInstallOffset {
+        last_index: u64,
+        last_term: u64,
+        offset: u64,
+    },
 }
 
 pub enum RaftSnapshotResponse {
     LastSnapshot(Option<String>, Option<SnapshotHeaderDto>),
     NewSnapshot(Addr<SnapshotWriterActor>, u64, Arc<String>),
-    NewSnapshotForLoad(String, u64),
+    /// (path,snapshot_id,是否续传)
+    NewSnapshotForLoad(String, u64, bool),
+    InstallOffset(u64),
     None,
 }
 
@@ -471,7 +661,8 @@ impl Handler<RaftSnapshotRequest> for RaftSnapshotManager {
                     self.last_header.clone(),
                 ))
             }
-            RaftSnapshotRequest::NewSnapshot(header) => {
+            RaftSnapshotRequest::NewSnapshot(mut header) => {
+                header.compress_type = self.compress_type;
                 let next_id = self.get_next_id()?;
                 let path = Arc::new(Self::get_snapshot_path(&self.base_path, next_id));
                 let writer = self.new_writer(ctx, header, path.clone());
@@ -480,7 +671,10 @@ impl Handler<RaftSnapshotRequest> for RaftSnapshotManager {
             RaftSnapshotRequest::NewSnapshotForLoad => {
                 let next_id = self.get_next_id()?;
                 let path = Self::get_snapshot_path(&self.base_path, next_id);
-                Ok(RaftSnapshotResponse::NewSnapshotForLoad(path, next_id))
+                let resume = matches!(&self.installing,Some(v) if v.id == next_id && v.offset > 0);
+                Ok(RaftSnapshotResponse::NewSnapshotForLoad(
+                    path, next_id, resume,
+                ))
             }
             RaftSnapshotRequest::CompleteSnapshot(snapshot_range) => {
                 self.complete_snapshot(ctx, snapshot_range).ok();
@@ -493,6 +687,262 @@ impl Handler<RaftSnapshotRequest> for RaftSnapshotManager {
                 self.install_snapshot(ctx, snapshot_id, end_index).ok();
                 Ok(RaftSnapshotResponse::None)
             }
+            RaftSnapshotRequest::PrepareInstall {
+                last_index,
+                last_term,
+                data,
+            } => {
+                let offset = self.prepare_install(last_index, last_term, &data)?;
+                Ok(RaftSnapshotResponse::InstallOffset(offset))
+            }
+            RaftSnapshotRequest::SaveInstallOffset {
+                last_index,
+                last_term,
+                offset,
+            } => {
+                self.save_install_offset(last_index, last_term, offset);
+                Ok(RaftSnapshotResponse::None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raft::filestore::model::SNAPSHOT_RECORD_CRC_VERSION;
+    use crate::raft::network::core::SnapshotResumeOffsets;
+    use async_raft_ext::raft::InstallSnapshotRequest;
+    use std::collections::HashMap;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "{}_{}_{}",
+            name,
+            std::process::id(),
+            crate::now_millis()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn write_snapshot(path: &str, compress_type: u32, count: usize) -> u64 {
+        let header = SnapshotHeaderDto {
+            last_index: 100,
+            last_term: 2,
+            member: vec![1, 2, 3],
+            member_after_consensus: vec![],
+            node_addrs: HashMap::new(),
+            compress_type,
+            version: SNAPSHOT_RECORD_CRC_VERSION,
+        };
+        let mut writer = SnapshotWriter::init(path, header).await.unwrap();
+        for i in 0..count {
+            let record = SnapshotRecordDto {
+                tree: Arc::new("T_CONFIG".to_owned()),
+                key: format!("data_{}#DEFAULT_GROUP#", i).into_bytes(),
+                value: format!("config content value {}", i % 10).into_bytes(),
+                op_type: 0,
+            };
+            writer.write_record(&record).await.unwrap();
+        }
+        writer.flush().await.unwrap();
+        std::fs::metadata(path).unwrap().len()
+    }
+
+    #[actix_rt::test]
+    async fn test_compress_snapshot() {
+        let dir = temp_dir("rnacos_snapshot_compress_test");
+        let path = dir.join("snapshot_1").to_string_lossy().into_owned();
+        let gzip_path = dir.join("snapshot_2").to_string_lossy().into_owned();
+        let count = 10000;
+        let size = write_snapshot(&path, SNAPSHOT_COMPRESS_NONE, count).await;
+        let gzip_size = write_snapshot(&gzip_path, SNAPSHOT_COMPRESS_GZIP, count).await;
+        assert!(gzip_size < size / 2);
+
+        let mut reader = SnapshotReader::init(&gzip_path).await.unwrap();
+        assert_eq!(reader.get_header().last_index, 100);
+        assert_eq!(reader.get_header().compress_type, SNAPSHOT_COMPRESS_GZIP);
+        let mut read_count = 0;
+        while let Some(record) = reader.read_record().await.unwrap() {
+            assert_eq!(
+                record.key,
+                format!("data_{}#DEFAULT_GROUP#", read_count).into_bytes()
+            );
+            read_count += 1;
+        }
+        assert_eq!(read_count, count);
+
+        //截断的压缩镜像读取时报错
+        let content = std::fs::read(&gzip_path).unwrap();
+        std::fs::write(&gzip_path, &content[..content.len() - 16]).unwrap();
+        let mut reader = SnapshotReader::init(&gzip_path).await.unwrap();
+        let mut result = Ok(None);
+        for _ in 0..=count {
+            result = reader.read_record().await;
+            if !matches!(result, Ok(Some(_))) {
+                break;
+            }
+        }
+        assert!(result.is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    async fn prepare_install(manager: &Addr<RaftSnapshotManager>, data: &[u8]) -> u64 {
+        match manager
+            .send(RaftSnapshotRequest::PrepareInstall {
+                last_index: 100,
+                last_term: 2,
+                data: data.to_vec(),
+            })
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            RaftSnapshotResponse::InstallOffset(offset) => offset,
+            _ => panic!("unexpected response"),
+        }
+    }
+
+    async fn open_for_load(manager: &Addr<RaftSnapshotManager>) -> (tokio::fs::File, u64, bool) {
+        match manager
+            .send(RaftSnapshotRequest::NewSnapshotForLoad)
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            RaftSnapshotResponse::NewSnapshotForLoad(path, id, resume) => {
+                let file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(!resume)
+                    .open(path)
+                    .await
+                    .unwrap();
+                (file, id, resume)
+            }
+            _ => panic!("unexpected response"),
+        }
+    }
+
+    async fn write_chunk(file: &mut tokio::fs::File, offset: u64, data: &[u8]) {
+        file.seek(std::io::SeekFrom::Start(offset)).await.unwrap();
+        file.write_all(data).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_resume_install_snapshot() {
+        let dir = temp_dir("rnacos_snapshot_resume_test");
+        let source_path = dir.join("source").to_string_lossy().into_owned();
+        write_snapshot(&source_path, SNAPSHOT_COMPRESS_GZIP, 2000).await;
+        let content = std::fs::read(&source_path).unwrap();
+        let chunks: Vec<(u64, &[u8])> = content
+            .chunks(1024)
+            .enumerate()
+            .map(|(i, v)| ((i * 1024) as u64, v))
+            .collect();
+        assert!(chunks.len() > 4);
+        let base_path = Arc::new(dir.join("raft").to_string_lossy().into_owned());
+        std::fs::create_dir_all(base_path.as_str()).unwrap();
+        let index_manager = RaftIndexManager::new(base_path.clone()).start();
+
+        //接收到第3个分片后中断
+        let manager =
+            RaftSnapshotManager::new(base_path.clone(), Some(index_manager.clone()), 0).start();
+        assert_eq!(prepare_install(&manager, chunks[0].1).await, 0);
+        let (mut file, _, resume) = open_for_load(&manager).await;
+        assert!(!resume);
+        for (offset, data) in &chunks[..3] {
+            write_chunk(&mut file, *offset, data).await;
+            manager
+                .send(RaftSnapshotRequest::SaveInstallOffset {
+                    last_index: 100,
+                    last_term: 2,
+                    offset: *offset,
+                })
+                .await
+                .unwrap()
+                .unwrap();
+        }
+        drop(file);
+        drop(manager);
+
+        //重启后从最后保存的位置续传
+        let manager =
+            RaftSnapshotManager::new(base_path.clone(), Some(index_manager.clone()), 0).start();
+        let resume_offset = prepare_install(&manager, chunks[0].1).await;
+        assert_eq!(resume_offset, chunks[2].0);
+        let (mut file, snapshot_id, resume) = open_for_load(&manager).await;
+        assert!(resume);
+        write_chunk(&mut file, 0, chunks[0].1).await;
+        let resume_offsets = SnapshotResumeOffsets::default();
+        resume_offsets.update(1, 100, 2, 0, false, resume_offset);
+        let mut send_count = 0;
+        for (offset, data) in &chunks[1..] {
+            let req = InstallSnapshotRequest {
+                term: 2,
+                leader_id: 1,
+                last_included_index: 100,
+                last_included_term: 2,
+                offset: *offset,
+                data: data.to_vec(),
+                done: false,
+            };
+            if resume_offsets.can_skip(1, &req) {
+                continue;
+            }
+            send_count += 1;
+            write_chunk(&mut file, *offset, data).await;
+        }
+        file.flush().await.unwrap();
+        drop(file);
+        assert_eq!(send_count, chunks.len() - 2);
+        let path = RaftSnapshotManager::get_snapshot_path(&base_path, snapshot_id);
+        assert_eq!(std::fs::read(&path).unwrap(), content);
+
+        manager
+            .send(RaftSnapshotRequest::InstallSnapshot {
+                end_index: 100,
+                snapshot_id,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!Path::new(base_path.as_str())
+            .join(INSTALL_PROGRESS_FILE)
+            .exists());
+        let mut reader = SnapshotReader::init(&path).await.unwrap();
+        let mut read_count = 0;
+        while reader.read_record().await.unwrap().is_some() {
+            read_count += 1;
+        }
+        assert_eq!(read_count, 2000);
+
+        //不同镜像不续传,从头接收
+        let manager = RaftSnapshotManager::new(base_path, Some(index_manager), 0).start();
+        manager
+            .send(RaftSnapshotRequest::SaveInstallOffset {
+                last_index: 100,
+                last_term: 2,
+                offset: 1024,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        match manager
+            .send(RaftSnapshotRequest::PrepareInstall {
+                last_index: 200,
+                last_term: 3,
+                data: chunks[0].1.to_vec(),
+            })
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            RaftSnapshotResponse::InstallOffset(offset) => assert_eq!(offset, 0),
+            _ => panic!("unexpected response"),
         }
+        std::fs::remove_dir_all(&dir).ok();
     }
 }
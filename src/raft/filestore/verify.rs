@@ -0,0 +1,433 @@
+use std::fmt::{Display, Formatter};
+use std::io::Cursor;
+use std::path::Path;
+
+use binrw::BinReaderExt;
+use quick_protobuf::BytesReader;
+use tokio::{fs::OpenOptions, io::AsyncReadExt};
+
+use crate::common::byte_utils::bin_to_id;
+use crate::common::protobuf_utils::{FileMessageReader, MessageBufReader};
+
+use super::{
+    log::{LogRange, LogRecord, RaftIndex},
+    model::{
+        check_log_record, LogIndexHeaderDo, RaftIndexDto, LOG_INDEX_HEADER_LEN,
+        LOG_INDEX_HEADER_MAGIC,
+    },
+    raftlog::{InnerIdxDto, LogInnerManager, RaftLogManager},
+    raftsnapshot::{RaftSnapshotManager, SnapshotReader},
+};
+
+const READ_BUF_SIZE: usize = 64 * 1024;
+
+///
+/// 数据文件中的损坏区间,end_index为0表示到文件结尾
+#[derive(Debug, Clone, Default)]
+pub struct VerifyIssue {
+    pub file: String,
+    pub start_index: u64,
+    pub end_index: u64,
+    pub msg: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    pub log_file_count: usize,
+    pub log_record_count: u64,
+    pub snapshot_file_count: usize,
+    pub snapshot_record_count: u64,
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn add_issue(&mut self, file: &str, start_index: u64, end_index: u64, msg: String) {
+        self.issues.push(VerifyIssue {
+            file: file.to_owned(),
+            start_index,
+            end_index,
+            msg,
+        });
+    }
+}
+
+impl Display for VerifyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "log files:{},log records:{},snapshot files:{},snapshot records:{}",
+            self.log_file_count,
+            self.log_record_count,
+            self.snapshot_file_count,
+            self.snapshot_record_count
+        )?;
+        for issue in &self.issues {
+            if issue.end_index > 0 {
+                writeln!(
+                    f,
+                    "[CORRUPT] {} [{},{}): {}",
+                    issue.file, issue.start_index, issue.end_index, issue.msg
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "[CORRUPT] {} [{},end): {}",
+                    issue.file, issue.start_index, issue.msg
+                )?;
+            }
+        }
+        if self.is_ok() {
+            write!(f, "verify ok")
+        } else {
+            write!(f, "verify failed, {} corrupt ranges", self.issues.len())
+        }
+    }
+}
+
+///
+/// 离线校验raft数据目录中的索引、日志与镜像文件
+pub async fn verify_data(dir: &str) -> anyhow::Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    let index_path = Path::new(dir).join("index").to_string_lossy().into_owned();
+    let (_, raft_index) = match read_raft_index(&index_path).await {
+        Ok(v) => v,
+        Err(err) => {
+            report.add_issue(&index_path, 0, 0, err.to_string());
+            return Ok(report);
+        }
+    };
+    for log_range in &raft_index.logs {
+        if log_range.mark_remove {
+            continue;
+        }
+        let path = RaftLogManager::get_log_path(dir, log_range);
+        report.log_file_count += 1;
+        verify_log_file(&path, log_range, &mut report).await;
+    }
+    for snapshot_range in &raft_index.snapshots {
+        let path = RaftSnapshotManager::get_snapshot_path(dir, snapshot_range.id);
+        report.snapshot_file_count += 1;
+        verify_snapshot_file(&path, snapshot_range.end_index, &mut report).await;
+    }
+    Ok(report)
+}
+
+///
+/// 只读方式读取索引文件,返回last_applied_log与索引内容
+pub(crate) async fn read_raft_index(path: &str) -> anyhow::Result<(u64, RaftIndexDto)> {
+    let mut file = OpenOptions::new().read(true).open(path).await?;
+    let mut header_buf = vec![0u8; 8];
+    file.read_exact(&mut header_buf).await?;
+    let last_applied_log = bin_to_id(&header_buf);
+    let mut file_reader = FileMessageReader::new(file, 8);
+    let buf = file_reader.read_next().await?;
+    let mut reader = BytesReader::from_bytes(&buf);
+    let index: RaftIndex = reader.read_message(&buf)?;
+    Ok((last_applied_log, index.into()))
+}
+
+///
+/// 读取日志文件头与索引区,读取后文件位置为数据区起始位置
+pub(crate) async fn read_log_header(
+    file: &mut tokio::fs::File,
+) -> anyhow::Result<(LogIndexHeaderDo, Vec<u8>)> {
+    let mut header_buf = vec![0u8; LOG_INDEX_HEADER_LEN as usize];
+    file.read_exact(&mut header_buf).await?;
+    let header: LogIndexHeaderDo = Cursor::new(&header_buf).read_be()?;
+    if header.magic != LOG_INDEX_HEADER_MAGIC {
+        return Err(anyhow::anyhow!("header magic error,{:x}", header.magic));
+    }
+    let data_area_index = header.data_area_index as u64;
+    if data_area_index < LOG_INDEX_HEADER_LEN {
+        return Err(anyhow::anyhow!(
+            "header data area index error,{}",
+            data_area_index
+        ));
+    }
+    let mut index_buf = vec![0u8; (data_area_index - LOG_INDEX_HEADER_LEN) as usize];
+    file.read_exact(&mut index_buf).await?;
+    Ok((header, index_buf))
+}
+
+pub(crate) async fn verify_log_file(path: &str, log_range: &LogRange, report: &mut VerifyReport) {
+    let start_index = log_range.start_index;
+    let mut file = match OpenOptions::new().read(true).open(path).await {
+        Ok(v) => v,
+        Err(err) => {
+            report.add_issue(path, start_index, 0, format!("open file error,{}", err));
+            return;
+        }
+    };
+    let (header, index_buf) = match read_log_header(&mut file).await {
+        Ok(v) => v,
+        Err(err) => {
+            report.add_issue(path, start_index, 0, format!("read header error,{}", err));
+            return;
+        }
+    };
+    let data_area_index = header.data_area_index as u64;
+    let first_index = InnerIdxDto {
+        log_index: start_index,
+        file_index: data_area_index,
+    };
+    let indexs =
+        match LogInnerManager::read_indexs(&index_buf, first_index, header.index_interval as u64) {
+            Ok((v, _)) => v,
+            Err(err) => {
+                report.add_issue(path, start_index, 0, format!("read index error,{}", err));
+                return;
+            }
+        };
+    let mut indexs = indexs.into_iter().peekable();
+    let mut expect_index = start_index;
+    let mut position = data_area_index;
+    //连续crc错误的起始序号
+    let mut bad_start: Option<u64> = None;
+    let mut message_reader = MessageBufReader::new();
+    let mut buf = vec![0u8; READ_BUF_SIZE];
+    loop {
+        while let Some(v) = message_reader.next_message_vec() {
+            while let Some(item) = indexs.peek() {
+                if item.log_index > expect_index {
+                    break;
+                }
+                if item.log_index == expect_index && item.file_index != position {
+                    report.add_issue(
+                        path,
+                        expect_index,
+                        expect_index + 1,
+                        format!("index position error,{}!={}", item.file_index, position),
+                    );
+                }
+                indexs.next();
+            }
+            position += v.len() as u64;
+            let mut reader = BytesReader::from_bytes(v);
+            let record: LogRecord = match reader.read_message(v) {
+                Ok(v) => v,
+                Err(err) => {
+                    report.add_issue(path, expect_index, 0, format!("decode error,{}", err));
+                    return;
+                }
+            };
+            if record.index != expect_index {
+                report.add_issue(
+                    path,
+                    expect_index,
+                    0,
+                    format!("record index error,{}", record.index),
+                );
+                return;
+            }
+            report.log_record_count += 1;
+            match (check_log_record(&record, header.version), bad_start) {
+                (Ok(_), Some(bad)) => {
+                    report.add_issue(path, bad, expect_index, "crc error".to_owned());
+                    bad_start = None;
+                }
+                (Err(_), None) => bad_start = Some(expect_index),
+                _ => {}
+            }
+            expect_index += 1;
+        }
+        let read_len = match file.read(&mut buf).await {
+            Ok(v) => v,
+            Err(err) => {
+                report.add_issue(path, expect_index, 0, format!("read error,{}", err));
+                return;
+            }
+        };
+        if read_len == 0 {
+            if !message_reader.is_empty() {
+                report.add_issue(path, expect_index, 0, "record is truncated".to_owned());
+            }
+            break;
+        }
+        message_reader.append_next_buf(&buf[..read_len]);
+        if message_reader.is_empty() {
+            break;
+        }
+    }
+    if let Some(bad) = bad_start {
+        report.add_issue(path, bad, expect_index, "crc error".to_owned());
+    }
+}
+
+pub(crate) async fn verify_snapshot_file(path: &str, end_index: u64, report: &mut VerifyReport) {
+    let mut reader = match SnapshotReader::init(path).await {
+        Ok(v) => v,
+        Err(err) => {
+            report.add_issue(path, end_index, 0, format!("read header error,{}", err));
+            return;
+        }
+    };
+    if reader.get_header().last_index != end_index {
+        report.add_issue(
+            path,
+            end_index,
+            0,
+            format!(
+                "snapshot last index error,{}!={}",
+                reader.get_header().last_index,
+                end_index
+            ),
+        );
+    }
+    //crc错误的记录数与第一条错误记录的位置
+    let mut crc_error_count = 0u64;
+    let mut first_crc_error = 0u64;
+    loop {
+        match reader.read_record_with_check().await {
+            Ok(Some((_, check))) => {
+                if check.is_err() {
+                    if crc_error_count == 0 {
+                        first_crc_error = report.snapshot_record_count;
+                    }
+                    crc_error_count += 1;
+                }
+                report.snapshot_record_count += 1;
+            }
+            Ok(None) => break,
+            Err(err) => {
+                report.add_issue(
+                    path,
+                    end_index,
+                    0,
+                    format!(
+                        "decode record error after {} records,{}",
+                        report.snapshot_record_count, err
+                    ),
+                );
+                break;
+            }
+        }
+    }
+    if crc_error_count > 0 {
+        report.add_issue(
+            path,
+            end_index,
+            0,
+            format!(
+                "{} records crc error, first at record {}",
+                crc_error_count, first_crc_error
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raft::filestore::model::{
+        LogRecordDto, SnapshotHeaderDto, SnapshotRecordDto, SNAPSHOT_RECORD_CRC_VERSION,
+    };
+    use crate::raft::filestore::raftsnapshot::SnapshotWriter;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+    #[actix_rt::test]
+    async fn test_verify_log_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "rnacos_verify_test_{}_{}",
+            std::process::id(),
+            crate::now_millis()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("log_1").to_string_lossy().into_owned();
+        let mut manager = LogInnerManager::init(path.clone(), 1, 0, 0).await.unwrap();
+        for i in 1..=300u64 {
+            let record = LogRecordDto {
+                index: i,
+                term: 1,
+                value: format!("value_{:04}", i).into_bytes(),
+            };
+            manager.write(&record).await.unwrap();
+        }
+        manager.flush_log().await.unwrap();
+        drop(manager);
+        let log_range = LogRange {
+            id: 1,
+            start_index: 1,
+            ..Default::default()
+        };
+        let mut report = VerifyReport::default();
+        verify_log_file(&path, &log_range, &mut report).await;
+        assert!(report.is_ok());
+        assert_eq!(report.log_record_count, 300);
+
+        //修改第一条记录的值
+        let content = std::fs::read(&path).unwrap();
+        let offset = content
+            .windows(10)
+            .position(|w| w == b"value_0001")
+            .unwrap();
+        let mut file = OpenOptions::new().write(true).open(&path).await.unwrap();
+        file.seek(std::io::SeekFrom::Start(offset as u64))
+            .await
+            .unwrap();
+        file.write_all(b"VALUE").await.unwrap();
+        file.flush().await.unwrap();
+        let mut report = VerifyReport::default();
+        verify_log_file(&path, &log_range, &mut report).await;
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].start_index, 1);
+        assert_eq!(report.issues[0].end_index, 2);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[actix_rt::test]
+    async fn test_verify_snapshot_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "rnacos_verify_snapshot_test_{}_{}",
+            std::process::id(),
+            crate::now_millis()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("snapshot_1").to_string_lossy().into_owned();
+        let header = SnapshotHeaderDto {
+            last_index: 100,
+            last_term: 2,
+            member: vec![1],
+            member_after_consensus: vec![],
+            node_addrs: HashMap::new(),
+            compress_type: 0,
+            version: SNAPSHOT_RECORD_CRC_VERSION,
+        };
+        let mut writer = SnapshotWriter::init(&path, header).await.unwrap();
+        for i in 0..10 {
+            let record = SnapshotRecordDto {
+                tree: Arc::new("T_CONFIG".to_owned()),
+                key: format!("data_{}", i).into_bytes(),
+                value: format!("value_{:04}", i).into_bytes(),
+                op_type: 0,
+            };
+            writer.write_record(&record).await.unwrap();
+        }
+        writer.flush().await.unwrap();
+        drop(writer);
+        let mut report = VerifyReport::default();
+        verify_snapshot_file(&path, 100, &mut report).await;
+        assert!(report.is_ok());
+        assert_eq!(report.snapshot_record_count, 10);
+
+        //修改第三条记录的值
+        let mut content = std::fs::read(&path).unwrap();
+        let offset = content
+            .windows(10)
+            .position(|w| w == b"value_0002")
+            .unwrap();
+        content[offset..offset + 5].copy_from_slice(b"VALUE");
+        std::fs::write(&path, &content).unwrap();
+        let mut report = VerifyReport::default();
+        verify_snapshot_file(&path, 100, &mut report).await;
+        assert_eq!(report.snapshot_record_count, 10);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].msg.contains("first at record 2"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
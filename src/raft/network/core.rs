@@ -1,29 +1,97 @@
-use crate::grpc::handler::{RAFT_APPEND_REQUEST, RAFT_SNAPSHOT_REQUEST, RAFT_VOTE_REQUEST};
+use crate::grpc::handler::{
+    RAFT_APPEND_REQUEST, RAFT_SNAPSHOT_ENCODING_HEADER, RAFT_SNAPSHOT_ENCODING_PB,
+    RAFT_SNAPSHOT_REQUEST, RAFT_VOTE_REQUEST,
+};
 use async_raft_ext::raft::{
     AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, InstallSnapshotResponse,
     VoteRequest, VoteResponse,
 };
 use async_raft_ext::{NodeId, RaftNetwork};
 use async_trait::async_trait;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use crate::grpc::nacos_proto::Payload;
 use crate::grpc::PayloadUtils;
+use crate::raft::cluster::leader_transfer::LeaderTransferGuard;
+use crate::raft::cluster::membership::RemovedLearners;
 use crate::raft::filestore::core::FileStore;
+use crate::raft::filestore::model::{InstallSnapshotRequestDto, InstallSnapshotResponseDto};
 use crate::raft::store::ClientRequest;
 
 use super::factory::RaftClusterRequestSender;
 
+///
+/// 记录各节点镜像续传位置(last_index,last_term,offset),跳过节点已接收的分片
+#[derive(Debug, Default)]
+pub struct SnapshotResumeOffsets {
+    offsets: Mutex<HashMap<NodeId, (u64, u64, u64)>>,
+}
+
+impl SnapshotResumeOffsets {
+    pub fn can_skip(&self, target: NodeId, req: &InstallSnapshotRequest) -> bool {
+        if req.offset == 0 || req.done {
+            return false;
+        }
+        match self.offsets.lock().unwrap().get(&target) {
+            Some((last_index, last_term, offset)) => {
+                *last_index == req.last_included_index
+                    && *last_term == req.last_included_term
+                    && req.offset + req.data.len() as u64 <= *offset
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// 第一个分片的响应中返回续传位置,传输结束后清除
+    pub fn update(
+        &self,
+        target: NodeId,
+        last_index: u64,
+        last_term: u64,
+        offset: u64,
+        done: bool,
+        resume_offset: u64,
+    ) {
+        let mut offsets = self.offsets.lock().unwrap();
+        if done {
+            offsets.remove(&target);
+        } else if offset == 0 {
+            if resume_offset > 0 {
+                offsets.insert(target, (last_index, last_term, resume_offset));
+            } else {
+                offsets.remove(&target);
+            }
+        }
+    }
+}
+
 pub struct RaftRouter {
     store: Arc<FileStore>, //get target addr
     cluster_sender: Arc<RaftClusterRequestSender>,
+    leader_transfer: Arc<LeaderTransferGuard>,
+    removed_learners: Arc<RemovedLearners>,
+    /// 镜像分片是否使用protobuf编码,需集群节点都支持后开启
+    snapshot_pb_encoding: bool,
+    snapshot_resume: SnapshotResumeOffsets,
 }
 
 impl RaftRouter {
-    pub fn new(store: Arc<FileStore>, cluster_sender: Arc<RaftClusterRequestSender>) -> Self {
+    pub fn new(
+        store: Arc<FileStore>,
+        cluster_sender: Arc<RaftClusterRequestSender>,
+        leader_transfer: Arc<LeaderTransferGuard>,
+        removed_learners: Arc<RemovedLearners>,
+        snapshot_pb_encoding: bool,
+    ) -> Self {
         Self {
             store,
             cluster_sender,
+            leader_transfer,
+            removed_learners,
+            snapshot_pb_encoding,
+            snapshot_resume: SnapshotResumeOffsets::default(),
         }
     }
 
@@ -40,6 +108,12 @@ impl RaftNetwork<ClientRequest> for RaftRouter {
         target: NodeId,
         req: AppendEntriesRequest<ClientRequest>,
     ) -> anyhow::Result<AppendEntriesResponse> {
+        if self.leader_transfer.is_paused(target) {
+            return Err(anyhow::anyhow!("leader transfer to node {}", target));
+        }
+        if self.removed_learners.contains(target) {
+            return Err(anyhow::anyhow!("the learner {} is removed", target));
+        }
         let request = serde_json::to_string(&req).unwrap_or_default();
         let payload = PayloadUtils::build_payload(RAFT_APPEND_REQUEST, request);
         let resp_payload = self.send_request(target, payload).await?;
@@ -53,12 +127,43 @@ impl RaftNetwork<ClientRequest> for RaftRouter {
         target: NodeId,
         req: InstallSnapshotRequest,
     ) -> anyhow::Result<InstallSnapshotResponse> {
-        let request = serde_json::to_string(&req).unwrap_or_default();
-        let payload = PayloadUtils::build_payload(RAFT_SNAPSHOT_REQUEST, request);
+        if self.removed_learners.contains(target) {
+            return Err(anyhow::anyhow!("the learner {} is removed", target));
+        }
+        if self.snapshot_resume.can_skip(target, &req) {
+            return Ok(InstallSnapshotResponse { term: req.term });
+        }
+        let (last_index, last_term, offset, done) = (
+            req.last_included_index,
+            req.last_included_term,
+            req.offset,
+            req.done,
+        );
+        let payload = if self.snapshot_pb_encoding {
+            //镜像分片使用protobuf编码,避免json数组放大数据
+            let request = InstallSnapshotRequestDto::from(req).to_bytes()?;
+            let mut headers = HashMap::new();
+            headers.insert(
+                RAFT_SNAPSHOT_ENCODING_HEADER.to_owned(),
+                RAFT_SNAPSHOT_ENCODING_PB.to_owned(),
+            );
+            PayloadUtils::build_bytes_payload(RAFT_SNAPSHOT_REQUEST, request, headers)
+        } else {
+            let request = serde_json::to_string(&req).unwrap_or_default();
+            PayloadUtils::build_payload(RAFT_SNAPSHOT_REQUEST, request)
+        };
         let resp_payload = self.send_request(target, payload).await?;
         let body_vec = resp_payload.body.unwrap_or_default().value;
-        let res: InstallSnapshotResponse = serde_json::from_slice(&body_vec)?;
-        Ok(res)
+        let res: InstallSnapshotResponseDto = serde_json::from_slice(&body_vec)?;
+        self.snapshot_resume.update(
+            target,
+            last_index,
+            last_term,
+            offset,
+            done,
+            res.resume_offset,
+        );
+        Ok(res.into())
     }
 
     async fn vote(&self, target: NodeId, req: VoteRequest) -> anyhow::Result<VoteResponse> {
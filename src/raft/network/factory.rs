@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use crate::common::AppSysConfig;
 use crate::grpc::handler::CLUSTER_TOKEN;
+use crate::tls::TlsConfig;
 use actix::prelude::*;
 use inner_mem_cache::MemCache;
 use tonic::transport::Channel;
@@ -77,13 +78,16 @@ impl RaftClusterRequestSender {
 pub struct RaftConnectionFactory {
     channel_cache: MemCache<Arc<String>, Arc<Channel>>,
     cache_ses: i32,
+    //开启TLS时节点间使用https连接
+    tls_config: Option<Arc<TlsConfig>>,
 }
 
 impl RaftConnectionFactory {
-    pub fn new(cache_ses: i32) -> Self {
+    pub fn new(cache_ses: i32, tls_config: Option<Arc<TlsConfig>>) -> Self {
         Self {
             channel_cache: MemCache::<Arc<String>, Arc<Channel>>::new(),
             cache_ses,
+            tls_config,
         }
     }
 
@@ -92,8 +96,17 @@ impl RaftConnectionFactory {
         if let Ok(channel) = self.channel_cache.get(&key) {
             Ok(channel)
         } else {
-            let addr = format!("http://{}", &key);
-            let channel = Arc::new(Channel::from_shared(addr)?.connect_lazy()?);
+            let channel = if let Some(tls_config) = &self.tls_config {
+                let addr = format!("https://{}", &key);
+                let client_tls_config = tls_config.build_cluster_client_config()?;
+                Channel::from_shared(addr)?
+                    .tls_config(client_tls_config)?
+                    .connect_lazy()?
+            } else {
+                let addr = format!("http://{}", &key);
+                Channel::from_shared(addr)?.connect_lazy()?
+            };
+            let channel = Arc::new(channel);
             self.channel_cache.set(key, channel.clone(), self.cache_ses);
             Ok(channel)
         }
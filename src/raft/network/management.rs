@@ -7,6 +7,8 @@ use actix_web::Responder;
 use async_raft_ext::raft::ClientWriteRequest;
 
 use crate::common::appdata::AppShareData;
+use crate::raft::cluster::leader_transfer;
+use crate::raft::cluster::membership::{handle_membership, MembershipReq};
 use crate::raft::join_node;
 use crate::raft::store::ClientRequest;
 use crate::raft::store::NodeId;
@@ -25,8 +27,11 @@ pub async fn join_learner(
             addr,
         }))
         .await
-        .unwrap();
-    app.raft.add_non_voter(node_id).await.unwrap();
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    app.raft
+        .add_non_voter(node_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
     join_node(app.raft.as_ref(), app.raft_store.as_ref(), node_id)
         .await
         .ok();
@@ -51,8 +56,11 @@ pub async fn add_learner(
             addr,
         }))
         .await
-        .unwrap();
-    app.raft.add_non_voter(node_id).await.unwrap();
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    app.raft
+        .add_non_voter(node_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
     Ok("{\"ok\":1}")
 }
 
@@ -62,10 +70,28 @@ pub async fn change_membership(
     app: Data<Arc<AppShareData>>,
     req: Json<HashSet<NodeId>>,
 ) -> actix_web::Result<impl Responder> {
-    app.raft.change_membership(req.0).await.unwrap();
+    let req = MembershipReq::ChangeVoters {
+        voters: req.0.into_iter().collect(),
+    };
+    handle_membership(app.get_ref(), req)
+        .await
+        .map_err(actix_web::error::ErrorBadRequest)?;
     Ok("{\"ok\":1}")
 }
 
+/// Transfer the leadership to a caught up follower.
+/// The target is chosen automatically when it is null.
+//#[post("/transfer-leader")]
+pub async fn transfer_leader(
+    app: Data<Arc<AppShareData>>,
+    req: Json<Option<NodeId>>,
+) -> actix_web::Result<impl Responder> {
+    let result = leader_transfer::transfer_leader(app.get_ref(), req.0)
+        .await
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    Ok(Json(result))
+}
+
 /// Initialize a single-node cluster.
 //#[post("/init")]
 pub async fn init(app: Data<Arc<AppShareData>>) -> actix_web::Result<impl Responder> {
@@ -79,7 +105,7 @@ pub async fn init(app: Data<Arc<AppShareData>>) -> actix_web::Result<impl Respon
             addr: Arc::new(app.sys_config.raft_node_addr.to_owned()),
         }))
         .await
-        .unwrap();
+        .map_err(actix_web::error::ErrorInternalServerError)?;
     Ok("{\"ok\":1}")
 }
 
@@ -21,6 +21,10 @@ pub fn raft_config(config: &mut web::ServiceConfig) {
                 web::resource("/change-membership")
                     .route(web::post().to(management::change_membership)),
             )
+            .service(
+                web::resource("/transfer-leader")
+                    .route(web::post().to(management::transfer_leader)),
+            )
             .service(web::resource("/metrics").route(web::get().to(management::metrics))),
     );
     // for debug
@@ -40,6 +40,9 @@ pub enum ClientRequest {
     },
     TableManagerReq(TableManagerReq),
     NamespaceReq(NamespaceRaftReq),
+    RemoveNodeAddr {
+        id: u64,
+    },
 }
 
 impl AppData for ClientRequest {}
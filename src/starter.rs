@@ -5,6 +5,9 @@ use crate::grpc::handler::RAFT_ROUTE_REQUEST;
 use crate::health::core::HealthManager;
 use crate::metrics::core::MetricsManager;
 use crate::namespace::NamespaceActor;
+use crate::raft::cluster::leader_transfer::LeaderTransferGuard;
+use crate::raft::cluster::membership::watch_leader_restore_learners;
+use crate::raft::cluster::membership::RemovedLearners;
 use crate::raft::cluster::route::RaftRequestRoute;
 use crate::raft::filestore::core::FileStore;
 use crate::raft::filestore::raftapply::StateApplyManager;
@@ -12,8 +15,11 @@ use crate::raft::filestore::raftdata::RaftDataWrap;
 use crate::raft::filestore::raftindex::RaftIndexManager;
 use crate::raft::filestore::raftlog::RaftLogManager;
 use crate::raft::filestore::raftsnapshot::RaftSnapshotManager;
+use crate::tls::TlsConfig;
+use crate::transfer::backup_scheduler::BackupScheduler;
 use crate::transfer::reader::TransferImportManager;
 use crate::transfer::writer::TransferWriterManager;
+use crate::xds::manage::XdsManage;
 use crate::{
     common::{appdata::AppShareData, AppSysConfig},
     config::core::ConfigActor,
@@ -70,7 +76,9 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     ));
 
     //raft
-    let conn_factory = RaftConnectionFactory::new(60).start();
+    let conn_factory =
+        RaftConnectionFactory::new(60, TlsConfig::from_sys_config(&sys_config).map(Arc::new))
+            .start();
     factory.register(BeanDefinition::actor_from_obj(conn_factory.clone()));
     let cluster_sender = Arc::new(RaftClusterRequestSender::new(
         conn_factory,
@@ -80,7 +88,11 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
 
     let log_manager = RaftLogManager::new(base_path.clone(), Some(index_manager.clone()));
     let log_manager = create_actor_at_thread(log_manager);
-    let snapshot_manager = RaftSnapshotManager::new(base_path.clone(), Some(index_manager.clone()));
+    let snapshot_manager = RaftSnapshotManager::new(
+        base_path.clone(),
+        Some(index_manager.clone()),
+        sys_config.raft_snapshot_compress_type,
+    );
     let apply_manager = StateApplyManager::new();
     let (snapshot_manager, apply_manager) =
         create_actor_at_thread2(snapshot_manager, apply_manager);
@@ -105,7 +117,18 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
         apply_manager,
     ));
     factory.register(BeanDefinition::from_obj(store.clone()));
-    let raft = build_raft(&sys_config, store.clone(), cluster_sender.clone()).await?;
+    let leader_transfer = Arc::new(LeaderTransferGuard::default());
+    factory.register(BeanDefinition::from_obj(leader_transfer.clone()));
+    let removed_learners = Arc::new(RemovedLearners::default());
+    factory.register(BeanDefinition::from_obj(removed_learners.clone()));
+    let raft = build_raft(
+        &sys_config,
+        store.clone(),
+        cluster_sender.clone(),
+        leader_transfer,
+        removed_learners,
+    )
+    .await?;
     factory.register(BeanDefinition::from_obj(raft.clone()));
     let table_manage = TableManager::new().start();
     factory.register(BeanDefinition::actor_with_inject_from_obj(
@@ -131,8 +154,12 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     ));
     factory.register(BeanDefinition::from_obj(config_route.clone()));
 
-    let naming_inner_node_manage_addr =
-        InnerNodeManage::new(sys_config.raft_node_id.to_owned()).start();
+    let naming_inner_node_manage_addr = InnerNodeManage::new(
+        sys_config.raft_node_id.to_owned(),
+        sys_config.naming_learner_distro,
+        Arc::new(sys_config.grpc_rebalance_sdk_addr.clone()),
+    )
+    .start();
     factory.register(BeanDefinition::actor_with_inject_from_obj(
         naming_inner_node_manage_addr.clone(),
     ));
@@ -197,6 +224,12 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     ));
     let health_manager = HealthManager::new().start();
     factory.register(BeanDefinition::actor_with_inject_from_obj(health_manager));
+    let backup_scheduler = BackupScheduler::new(sys_config.clone()).start();
+    factory.register(BeanDefinition::actor_with_inject_from_obj(backup_scheduler));
+    if sys_config.xds_enable {
+        let xds_manage = XdsManage::new().start();
+        factory.register(BeanDefinition::actor_with_inject_from_obj(xds_manage));
+    }
     Ok(factory.init().await)
 }
 
@@ -211,6 +244,7 @@ pub fn build_share_data(factory_data: FactoryData) -> anyhow::Result<Arc<AppShar
     let app_data = Arc::new(AppShareData {
         config_addr: factory_data.get_actor().unwrap(),
         naming_addr: factory_data.get_actor().unwrap(),
+        naming_delay_notify: factory_data.get_actor().unwrap(),
         bi_stream_manage: factory_data.get_actor().unwrap(),
         raft: factory_data.get_bean().unwrap(),
         raft_store: factory_data.get_bean().unwrap(),
@@ -232,6 +266,9 @@ pub fn build_share_data(factory_data: FactoryData) -> anyhow::Result<Arc<AppShar
         transfer_writer_manager: factory_data.get_actor().unwrap(),
         transfer_import_manager: factory_data.get_actor().unwrap(),
         health_manager: factory_data.get_actor().unwrap(),
+        backup_scheduler: factory_data.get_actor().unwrap(),
+        leader_transfer: factory_data.get_bean().unwrap(),
+        removed_learners: factory_data.get_bean().unwrap(),
         factory_data,
     });
     Ok(app_data)
@@ -241,6 +278,8 @@ async fn build_raft(
     sys_config: &Arc<AppSysConfig>,
     store: Arc<FileStore>,
     cluster_sender: Arc<RaftClusterRequestSender>,
+    leader_transfer: Arc<LeaderTransferGuard>,
+    removed_learners: Arc<RemovedLearners>,
 ) -> anyhow::Result<Arc<NacosRaft>> {
     match store.get_last_log_index().await {
         Ok(last_log) => log::info!(
@@ -257,17 +296,24 @@ async fn build_raft(
         .snapshot_policy(async_raft_ext::SnapshotPolicy::LogsSinceLast(
             sys_config.raft_snapshot_log_size,
         ))
-        .snapshot_max_chunk_size(3 * 1024 * 1024)
+        .snapshot_max_chunk_size(sys_config.raft_snapshot_chunk_size)
         .validate()
         .unwrap();
     let config = Arc::new(config);
-    let network = Arc::new(RaftRouter::new(store.clone(), cluster_sender.clone()));
+    let network = Arc::new(RaftRouter::new(
+        store.clone(),
+        cluster_sender.clone(),
+        leader_transfer,
+        removed_learners,
+        sys_config.raft_snapshot_pb_encoding,
+    ));
     let raft = Arc::new(Raft::new(
         sys_config.raft_node_id.to_owned(),
         config,
         network,
         store.clone(),
     ));
+    tokio::spawn(watch_leader_restore_learners(raft.clone(), store.clone()));
     if sys_config.raft_auto_init {
         tokio::spawn(auto_init_raft(store, raft.clone(), sys_config.clone()));
     } else if !sys_config.raft_join_addr.is_empty() {
@@ -334,9 +380,12 @@ async fn auto_join_raft(
     if state.last_log_term == 0 {
         //wait for self raft network started
         tokio::time::sleep(Duration::from_millis(500)).await;
-        let req = RouterRequest::JoinNode {
-            node_id: sys_config.raft_node_id.to_owned(),
-            node_addr: Arc::new(sys_config.raft_node_addr.to_owned()),
+        let node_id = sys_config.raft_node_id.to_owned();
+        let node_addr = Arc::new(sys_config.raft_node_addr.to_owned());
+        let req = if sys_config.raft_learner {
+            RouterRequest::JoinLearner { node_id, node_addr }
+        } else {
+            RouterRequest::JoinNode { node_id, node_addr }
         };
         let request = serde_json::to_string(&req).unwrap_or_default();
         let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
@@ -344,10 +393,11 @@ async fn auto_join_raft(
             .send_request(Arc::new(sys_config.raft_join_addr.to_owned()), payload)
             .await?;
         log::info!(
-            "auto join raft,join_addr:{}.node_id:{},addr:{}",
+            "auto join raft,join_addr:{}.node_id:{},addr:{},learner:{}",
             &sys_config.raft_join_addr,
             &sys_config.raft_node_id,
-            &sys_config.raft_node_addr
+            &sys_config.raft_node_addr,
+            sys_config.raft_learner
         );
     }
     Ok(())
@@ -0,0 +1,96 @@
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use rustls::ServerConfig;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::server::Connected;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+///
+/// 完成TLS握手的grpc连接
+pub struct TlsConn {
+    inner: TlsStream<TcpStream>,
+}
+
+impl Connected for TlsConn {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.inner.get_ref().0.peer_addr().ok()
+    }
+}
+
+impl AsyncRead for TlsConn {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConn {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+///
+/// 监听grpc端口并完成TLS握手,握手失败的连接不影响其它连接
+pub async fn tls_incoming(
+    addr: &str,
+    config: ServerConfig,
+) -> anyhow::Result<ReceiverStream<Result<TlsConn, io::Error>>> {
+    let listener = TcpListener::bind(addr).await?;
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let (tx, rx) = tokio::sync::mpsc::channel(128);
+    tokio::spawn(async move {
+        while !tx.is_closed() {
+            let (stream, remote_addr) = match listener.accept().await {
+                Ok(v) => v,
+                Err(err) => {
+                    log::error!("grpc tls accept error,{}", err);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            stream.set_nodelay(true).ok();
+            let acceptor = acceptor.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(inner)) => {
+                        tx.send(Ok(TlsConn { inner })).await.ok();
+                    }
+                    Ok(Err(err)) => {
+                        log::warn!("grpc tls handshake error,addr:{},{}", remote_addr, err);
+                    }
+                    Err(_) => {
+                        log::warn!("grpc tls handshake timeout,addr:{}", remote_addr);
+                    }
+                }
+            });
+        }
+    });
+    Ok(ReceiverStream::new(rx))
+}
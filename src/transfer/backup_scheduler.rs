@@ -0,0 +1,379 @@
+use crate::common::AppSysConfig;
+use crate::metrics::core::MetricsManager;
+use crate::metrics::metrics_key::MetricsKey;
+use crate::metrics::model::{MetricsItem, MetricsRecord, MetricsRequest};
+use crate::now_millis;
+use crate::raft::NacosRaft;
+use crate::transfer::model::{
+    TransferBackupParam, TransferManagerAsyncRequest, TransferManagerResponse,
+};
+use crate::transfer::s3::{S3Config, S3Uploader};
+use crate::transfer::writer::TransferWriterManager;
+use actix::prelude::*;
+use bean_factory::{bean, BeanFactory, FactoryData, Inject};
+use chrono::{FixedOffset, Local, Offset, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const BACKUP_FILE_PREFIX: &str = "rnacos_backup_";
+pub const BACKUP_FILE_SUFFIX: &str = ".data";
+const MIN_INTERVAL_SECOND: u64 = 60;
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupStatus {
+    pub success: bool,
+    pub last_time: u64,
+    pub last_success_time: u64,
+    pub file: String,
+    pub size: u64,
+    pub cost_ms: u64,
+    pub msg: String,
+}
+
+enum BackupSchedule {
+    Interval(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+///
+/// 按保留文件数与保留天数选出需要删除的备份文件;files为(文件名,修改时间)
+pub fn select_expired_files(
+    mut files: Vec<(String, u64)>,
+    keep_files: usize,
+    keep_days: u64,
+    now: u64,
+) -> Vec<String> {
+    //文件名包含时间,按文件名倒序即从新到旧
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+    files
+        .into_iter()
+        .enumerate()
+        .filter(|(i, (_, modified_time))| {
+            (keep_files > 0 && *i >= keep_files)
+                || (keep_days > 0 && *modified_time + keep_days * DAY_MILLIS < now)
+        })
+        .map(|(_, (name, _))| name)
+        .collect()
+}
+
+fn clear_expired_files(dir: &str, keep_files: usize, keep_days: u64) -> anyhow::Result<()> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with(BACKUP_FILE_PREFIX) || !name.ends_with(BACKUP_FILE_SUFFIX) {
+            continue;
+        }
+        let modified_time = entry
+            .metadata()?
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|v| v.as_millis() as u64)
+            .unwrap_or_default();
+        files.push((name, modified_time));
+    }
+    for name in select_expired_files(files, keep_files, keep_days, now_millis()) {
+        log::info!("remove expired backup file:{}", &name);
+        std::fs::remove_file(Path::new(dir).join(name)).ok();
+    }
+    Ok(())
+}
+
+///
+/// 定时备份,只在leader节点执行;备份文件写入本地目录,可选上传到s3兼容存储
+#[bean(inject)]
+pub struct BackupScheduler {
+    sys_config: Arc<AppSysConfig>,
+    schedule: Option<BackupSchedule>,
+    timezone_offset: FixedOffset,
+    writer_manager: Option<Addr<TransferWriterManager>>,
+    metrics_manager: Option<Addr<MetricsManager>>,
+    raft: Option<Arc<NacosRaft>>,
+    s3_uploader: Option<Arc<S3Uploader>>,
+    status: Option<BackupStatus>,
+}
+
+impl BackupScheduler {
+    pub fn new(sys_config: Arc<AppSysConfig>) -> Self {
+        let schedule = if sys_config.backup_dir.is_empty() {
+            None
+        } else if sys_config.backup_cron.is_empty() {
+            Some(Self::build_interval_schedule(&sys_config))
+        } else {
+            match cron::Schedule::from_str(&sys_config.backup_cron) {
+                Ok(v) => Some(BackupSchedule::Cron(Box::new(v))),
+                Err(err) => {
+                    log::error!(
+                        "backup cron {} is invalid,use interval instead,{}",
+                        &sys_config.backup_cron,
+                        err
+                    );
+                    Some(Self::build_interval_schedule(&sys_config))
+                }
+            }
+        };
+        let timezone_offset =
+            if let Some(offset_value) = sys_config.gmt_fixed_offset_hours.map(|e| e * 3600) {
+                FixedOffset::east_opt(offset_value).unwrap_or(Local::now().offset().fix())
+            } else {
+                Local::now().offset().fix()
+            };
+        let s3_uploader =
+            S3Config::from_sys_config(&sys_config).map(|config| Arc::new(S3Uploader::new(config)));
+        Self {
+            sys_config,
+            schedule,
+            timezone_offset,
+            writer_manager: None,
+            metrics_manager: None,
+            raft: None,
+            s3_uploader,
+            status: None,
+        }
+    }
+
+    fn build_interval_schedule(sys_config: &AppSysConfig) -> BackupSchedule {
+        BackupSchedule::Interval(Duration::from_secs(std::cmp::max(
+            sys_config.backup_interval_second,
+            MIN_INTERVAL_SECOND,
+        )))
+    }
+
+    fn next_delay(&self) -> Option<Duration> {
+        match self.schedule.as_ref()? {
+            BackupSchedule::Interval(v) => Some(*v),
+            BackupSchedule::Cron(schedule) => {
+                let now = Utc::now().with_timezone(&self.timezone_offset);
+                let next = schedule.after(&now).next()?;
+                (next - now).to_std().ok()
+            }
+        }
+    }
+
+    fn schedule_next(&mut self, ctx: &mut Context<Self>) {
+        if let Some(delay) = self.next_delay() {
+            ctx.run_later(delay, |act, ctx| {
+                act.run_backup(ctx);
+            });
+        }
+    }
+
+    fn run_backup(&mut self, ctx: &mut Context<Self>) {
+        let sys_config = self.sys_config.clone();
+        let raft = self.raft.clone();
+        let writer_manager = self.writer_manager.clone();
+        let s3_uploader = self.s3_uploader.clone();
+        let file_name = format!(
+            "{}{}{}",
+            BACKUP_FILE_PREFIX,
+            Utc::now()
+                .with_timezone(&self.timezone_offset)
+                .format("%Y%m%d%H%M%S%3f"),
+            BACKUP_FILE_SUFFIX
+        );
+        async move {
+            Self::do_backup(sys_config, raft, writer_manager, s3_uploader, file_name).await
+        }
+        .into_actor(self)
+        .map(|r: anyhow::Result<Option<BackupStatus>>, act, ctx| {
+            act.update_status(r);
+            act.schedule_next(ctx);
+        })
+        .spawn(ctx);
+    }
+
+    async fn do_backup(
+        sys_config: Arc<AppSysConfig>,
+        raft: Option<Arc<NacosRaft>>,
+        writer_manager: Option<Addr<TransferWriterManager>>,
+        s3_uploader: Option<Arc<S3Uploader>>,
+        file_name: String,
+    ) -> anyhow::Result<Option<BackupStatus>> {
+        let (raft, writer_manager) = match (raft, writer_manager) {
+            (Some(raft), Some(writer_manager)) => (raft, writer_manager),
+            _ => return Err(anyhow::anyhow!("backup scheduler is not inited")),
+        };
+        if raft.current_leader().await != Some(sys_config.raft_node_id) {
+            return Ok(None);
+        }
+        let start = now_millis();
+        let TransferManagerResponse::BackupFile(temp_file) = writer_manager
+            .send(TransferManagerAsyncRequest::Backup(
+                TransferBackupParam::all(),
+            ))
+            .await??;
+        let dir = &sys_config.backup_dir;
+        tokio::fs::create_dir_all(dir).await?;
+        let path = Path::new(dir).join(&file_name);
+        let size = tokio::fs::copy(&temp_file.path, &path).await?;
+        drop(temp_file);
+        //清理过期文件失败不影响本次备份结果
+        if let Err(err) = clear_expired_files(
+            dir,
+            sys_config.backup_keep_files,
+            sys_config.backup_keep_days,
+        ) {
+            log::warn!("clear expired backup files error,{}", err);
+        }
+        if let Some(s3_uploader) = s3_uploader {
+            s3_uploader
+                .upload_file(&file_name, &path)
+                .await
+                .map_err(|e| anyhow::anyhow!("upload backup file to s3 error,{}", e))?;
+        }
+        let now = now_millis();
+        Ok(Some(BackupStatus {
+            success: true,
+            last_time: now,
+            last_success_time: now,
+            file: file_name,
+            size,
+            cost_ms: now - start,
+            msg: String::new(),
+        }))
+    }
+
+    fn update_status(&mut self, r: anyhow::Result<Option<BackupStatus>>) {
+        let status = match r {
+            Ok(Some(v)) => {
+                log::info!("scheduled backup success,file:{},size:{}", &v.file, v.size);
+                v
+            }
+            //非leader节点不备份
+            Ok(None) => return,
+            Err(err) => {
+                log::error!("scheduled backup error,{}", err);
+                BackupStatus {
+                    success: false,
+                    last_time: now_millis(),
+                    last_success_time: self
+                        .status
+                        .as_ref()
+                        .map(|e| e.last_success_time)
+                        .unwrap_or_default(),
+                    msg: err.to_string(),
+                    ..Default::default()
+                }
+            }
+        };
+        if let Some(metrics_manager) = &self.metrics_manager {
+            let count_key = if status.success {
+                MetricsKey::BackupSuccessCount
+            } else {
+                MetricsKey::BackupFailureCount
+            };
+            metrics_manager.do_send(MetricsRequest::BatchRecord(vec![
+                MetricsItem::new(
+                    MetricsKey::BackupLastStatus,
+                    MetricsRecord::Gauge(if status.success { 1f32 } else { 0f32 }),
+                ),
+                MetricsItem::new(count_key, MetricsRecord::CounterInc(1)),
+            ]));
+        }
+        self.status = Some(status);
+    }
+}
+
+impl Actor for BackupScheduler {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        log::info!("BackupScheduler started");
+    }
+}
+
+impl Inject for BackupScheduler {
+    type Context = Context<Self>;
+
+    fn inject(
+        &mut self,
+        factory_data: FactoryData,
+        _factory: BeanFactory,
+        ctx: &mut Self::Context,
+    ) {
+        self.writer_manager = factory_data.get_actor();
+        self.metrics_manager = factory_data.get_actor();
+        self.raft = factory_data.get_bean();
+        if self.schedule.is_some() {
+            log::info!(
+                "backup scheduler enable,dir:{}",
+                &self.sys_config.backup_dir
+            );
+            self.schedule_next(ctx);
+        }
+    }
+}
+
+#[derive(Message, Debug)]
+#[rtype(result = "anyhow::Result<BackupSchedulerResponse>")]
+pub enum BackupSchedulerRequest {
+    QueryStatus,
+}
+
+pub enum BackupSchedulerResponse {
+    Status {
+        enable: bool,
+        status: Option<BackupStatus>,
+    },
+}
+
+impl Handler<BackupSchedulerRequest> for BackupScheduler {
+    type Result = anyhow::Result<BackupSchedulerResponse>;
+
+    fn handle(&mut self, msg: BackupSchedulerRequest, _ctx: &mut Self::Context) -> Self::Result {
+        match msg {
+            BackupSchedulerRequest::QueryStatus => Ok(BackupSchedulerResponse::Status {
+                enable: self.schedule.is_some(),
+                status: self.status.clone(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_expired_files() {
+        let now = 10 * DAY_MILLIS;
+        let files = vec![
+            (
+                "rnacos_backup_20240101000000000.data".to_owned(),
+                DAY_MILLIS,
+            ),
+            (
+                "rnacos_backup_20240103000000000.data".to_owned(),
+                3 * DAY_MILLIS,
+            ),
+            (
+                "rnacos_backup_20240102000000000.data".to_owned(),
+                2 * DAY_MILLIS,
+            ),
+            (
+                "rnacos_backup_20240109000000000.data".to_owned(),
+                9 * DAY_MILLIS,
+            ),
+        ];
+        let mut expired = select_expired_files(files.clone(), 2, 0, now);
+        expired.sort();
+        assert_eq!(
+            expired,
+            vec![
+                "rnacos_backup_20240101000000000.data".to_owned(),
+                "rnacos_backup_20240102000000000.data".to_owned(),
+            ]
+        );
+        let expired = select_expired_files(files.clone(), 0, 8, now);
+        assert_eq!(
+            expired,
+            vec!["rnacos_backup_20240101000000000.data".to_owned()]
+        );
+        assert!(select_expired_files(files, 0, 0, now).is_empty());
+    }
+}
@@ -0,0 +1,385 @@
+use crate::common::byte_utils::id_to_bin;
+use crate::common::constant::{
+    CACHE_TREE_NAME, CONFIG_TREE_NAME, NAMESPACE_TREE_NAME, SEQUENCE_TREE_NAME, SEQ_KEY_CONFIG,
+    USER_TREE_NAME,
+};
+use crate::config::model::ConfigValueDO;
+use crate::raft::filestore::log::{LogRange, SnapshotRange};
+use crate::raft::filestore::model::{
+    RaftIndexDto, SnapshotHeaderDto, SnapshotRecordDto, SNAPSHOT_COMPRESS_NONE,
+    SNAPSHOT_RECORD_CRC_VERSION,
+};
+use crate::raft::filestore::raftindex::RaftIndexInnerManager;
+use crate::raft::filestore::raftlog::LogInnerManager;
+use crate::raft::filestore::raftsnapshot::SnapshotWriter;
+use crate::raft::filestore::StoreUtils;
+use crate::transfer::reader::{reader_transfer_record, TransferFileReader};
+use async_raft_ext::raft::{Entry, MembershipConfig};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 恢复后的镜像与日志位置
+const RESTORE_INDEX: u64 = 1;
+const RESTORE_TERM: u64 = 1;
+const RESTORE_SNAPSHOT_ID: u64 = 1;
+const RESTORE_LOG_ID: u64 = 1;
+
+#[derive(Debug, Default)]
+pub struct RestoreCount {
+    pub config: u64,
+    pub namespace: u64,
+    pub user: u64,
+    pub cache: u64,
+    pub ignore: u64,
+    /// 忽略的表及记录数
+    pub ignore_tables: BTreeMap<String, u64>,
+}
+
+fn get_path(data_dir: &str, name: &str) -> String {
+    Path::new(data_dir)
+        .join(name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+///
+/// 只允许恢复到空目录,避免覆盖已有的raft数据
+fn check_data_dir(data_dir: &str) -> anyhow::Result<()> {
+    let path = Path::new(data_dir);
+    if !path.exists() {
+        std::fs::create_dir_all(path)?;
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(path)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name == "index" || name.starts_with("log_") || name.starts_with("snapshot_") {
+            return Err(anyhow::anyhow!(
+                "the data dir {} already has raft data,file:{}",
+                data_dir,
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+///
+/// 将迁移中间文件离线写入为单节点raft数据(镜像+镜像指针日志+索引),
+/// 节点使用相同的node_id启动后直接从镜像加载数据
+pub async fn data_to_raft(
+    data_file: &str,
+    data_dir: &str,
+    node_id: u64,
+    node_addr: &str,
+) -> anyhow::Result<RestoreCount> {
+    check_data_dir(data_dir)?;
+    let mut file_reader = TransferFileReader::new(data_file).await?;
+    let member = vec![node_id];
+    let mut node_addrs = HashMap::new();
+    node_addrs.insert(node_id, Arc::new(node_addr.to_owned()));
+
+    //1. 写入镜像
+    let header = SnapshotHeaderDto {
+        last_index: RESTORE_INDEX,
+        last_term: RESTORE_TERM,
+        member: member.clone(),
+        member_after_consensus: vec![],
+        node_addrs: node_addrs.clone(),
+        compress_type: SNAPSHOT_COMPRESS_NONE,
+        version: SNAPSHOT_RECORD_CRC_VERSION,
+    };
+    let snapshot_path = get_path(data_dir, &format!("snapshot_{}", RESTORE_SNAPSHOT_ID));
+    let mut writer = SnapshotWriter::init(&snapshot_path, header).await?;
+    let mut count = RestoreCount::default();
+    let mut config_last_id = 0;
+    while let Ok(Some(vec)) = file_reader.read_record_vec().await {
+        let record = reader_transfer_record(&vec, &file_reader.header)?;
+        let (tree, value) = if record.table_name.as_str() == CONFIG_TREE_NAME.as_str() {
+            count.config += 1;
+            //历史记录id重新编号
+            let mut value_do = ConfigValueDO::from_bytes(&record.value)?;
+            for item in &mut value_do.histories {
+                config_last_id += 1;
+                item.id = Some(config_last_id);
+            }
+            (CONFIG_TREE_NAME.clone(), value_do.to_bytes()?)
+        } else if record.table_name.as_str() == NAMESPACE_TREE_NAME.as_str() {
+            count.namespace += 1;
+            (NAMESPACE_TREE_NAME.clone(), record.value.to_vec())
+        } else if record.table_name.as_str() == USER_TREE_NAME.as_str() {
+            count.user += 1;
+            (USER_TREE_NAME.clone(), record.value.to_vec())
+        } else if record.table_name.as_str() == CACHE_TREE_NAME.as_str() {
+            count.cache += 1;
+            (CACHE_TREE_NAME.clone(), record.value.to_vec())
+        } else if record.table_name.as_str() == SEQUENCE_TREE_NAME.as_str() {
+            //配置历史记录id重新编号,序列号按编号结果重新生成
+            continue;
+        } else {
+            //节点加载镜像时不支持的表
+            count.ignore += 1;
+            *count
+                .ignore_tables
+                .entry(record.table_name.as_str().to_owned())
+                .or_default() += 1;
+            continue;
+        };
+        let snapshot_record = SnapshotRecordDto {
+            tree,
+            key: record.key.to_vec(),
+            value,
+            op_type: 0,
+        };
+        writer.write_record(&snapshot_record).await?;
+    }
+    let seq_record = SnapshotRecordDto {
+        tree: SEQUENCE_TREE_NAME.clone(),
+        key: SEQ_KEY_CONFIG.as_bytes().to_vec(),
+        value: id_to_bin(config_last_id),
+        op_type: 0,
+    };
+    writer.write_record(&seq_record).await?;
+    writer.flush().await?;
+
+    //2. 写入镜像指针日志
+    let log_path = get_path(data_dir, &format!("log_{}", RESTORE_LOG_ID));
+    let mut log_manager =
+        LogInnerManager::init(log_path, RESTORE_INDEX, RESTORE_TERM, RESTORE_INDEX).await?;
+    let entry = Entry::new_snapshot_pointer(
+        RESTORE_INDEX,
+        RESTORE_TERM,
+        RESTORE_SNAPSHOT_ID.to_string(),
+        MembershipConfig::new_initial(node_id),
+    );
+    log_manager
+        .write(&StoreUtils::entry_to_record(&entry)?)
+        .await?;
+    log_manager.flush_log().await?;
+
+    //3. 写入索引
+    let mut index_manager = RaftIndexInnerManager::init(&get_path(data_dir, "index")).await?;
+    let raft_index = RaftIndexDto {
+        logs: vec![LogRange {
+            id: RESTORE_LOG_ID,
+            pre_term: RESTORE_TERM,
+            start_index: RESTORE_INDEX,
+            record_count: 0,
+            split_off_index: RESTORE_INDEX,
+            is_close: false,
+            mark_remove: false,
+        }],
+        current_log: RESTORE_LOG_ID,
+        snapshots: vec![SnapshotRange {
+            id: RESTORE_SNAPSHOT_ID,
+            end_index: RESTORE_INDEX,
+        }],
+        last_snapshot: RESTORE_SNAPSHOT_ID,
+        last_snapshot_index: RESTORE_INDEX,
+        last_snapshot_term: RESTORE_TERM,
+        current_term: RESTORE_TERM,
+        voted_for: 0,
+        member,
+        member_after_consensus: vec![],
+        node_addrs,
+    };
+    index_manager.write_index(raft_index).await?;
+    index_manager.write_last_applied_log(RESTORE_INDEX).await?;
+    index_manager.flush().await?;
+    for (table_name, record_count) in &count.ignore_tables {
+        log::warn!(
+            "transfer data to raft ignore unsupported table:{},record count:{}",
+            table_name,
+            record_count
+        );
+    }
+    log::info!(
+        "transfer data to raft finished,config count:{},namespace count:{},user count:{},cache count:{},ignore count:{}",
+        count.config,
+        count.namespace,
+        count.user,
+        count.cache,
+        count.ignore
+    );
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::byte_utils::bin_to_id;
+    use crate::config::model::ConfigHistoryItemDO;
+    use crate::raft::filestore::core::FileStore;
+    use crate::raft::filestore::raftapply::StateApplyManager;
+    use crate::raft::filestore::raftindex::RaftIndexManager;
+    use crate::raft::filestore::raftlog::RaftLogManager;
+    use crate::raft::filestore::raftsnapshot::{RaftSnapshotManager, SnapshotReader};
+    use crate::raft::filestore::verify::read_raft_index;
+    use crate::transfer::model::{TransferHeaderDto, TransferRecordDto};
+    use crate::transfer::writer::TransferWriter;
+    use actix::Actor;
+    use async_raft_ext::RaftStorage;
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "{}_{}_{}",
+            name,
+            std::process::id(),
+            crate::now_millis()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn write_data_file(data_file: &str) {
+        let mut writer = TransferWriter::init(data_file, TransferHeaderDto::new(0))
+            .await
+            .unwrap();
+        for i in 0..2 {
+            let value = ConfigValueDO {
+                content: Some(format!("v{}", i)),
+                histories: vec![
+                    ConfigHistoryItemDO {
+                        id: Some(100),
+                        content: Some("old".to_owned()),
+                        ..Default::default()
+                    },
+                    ConfigHistoryItemDO {
+                        id: Some(200),
+                        content: Some(format!("v{}", i)),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            };
+            let record = TransferRecordDto {
+                table_name: Some(CONFIG_TREE_NAME.clone()),
+                table_id: 0,
+                key: format!("data_{}#DEFAULT_GROUP#", i).into_bytes(),
+                value: value.to_bytes().unwrap(),
+            };
+            writer.write_record(&record).await.unwrap();
+        }
+        let record = TransferRecordDto {
+            table_name: Some(USER_TREE_NAME.clone()),
+            table_id: 0,
+            key: b"admin".to_vec(),
+            value: b"user".to_vec(),
+        };
+        writer.write_record(&record).await.unwrap();
+        //序列号重新生成,不支持的表忽略
+        let record = TransferRecordDto {
+            table_name: Some(SEQUENCE_TREE_NAME.clone()),
+            table_id: 0,
+            key: SEQ_KEY_CONFIG.as_bytes().to_vec(),
+            value: id_to_bin(1000),
+        };
+        writer.write_record(&record).await.unwrap();
+        let record = TransferRecordDto {
+            table_name: Some(Arc::new("T_UNKNOWN".to_owned())),
+            table_id: 0,
+            key: b"k".to_vec(),
+            value: b"v".to_vec(),
+        };
+        writer.write_record(&record).await.unwrap();
+        writer.flush().await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_data_to_raft() {
+        let dir = test_dir("rnacos_data_to_raft_test");
+        let data_file = dir.join("data.bak").to_string_lossy().into_owned();
+        let data_dir = dir.join("db").to_string_lossy().into_owned();
+        write_data_file(&data_file).await;
+
+        let count = data_to_raft(&data_file, &data_dir, 2, "127.0.0.1:9848")
+            .await
+            .unwrap();
+        assert_eq!(count.config, 2);
+        assert_eq!(count.user, 1);
+
+        let (last_applied_log, raft_index) = read_raft_index(&get_path(&data_dir, "index"))
+            .await
+            .unwrap();
+        assert_eq!(last_applied_log, 1);
+        assert_eq!(raft_index.member, vec![2]);
+        assert_eq!(raft_index.snapshots[0].end_index, 1);
+
+        let mut reader = SnapshotReader::init(&get_path(&data_dir, "snapshot_1"))
+            .await
+            .unwrap();
+        assert_eq!(reader.get_header().member, vec![2]);
+        let mut records = vec![];
+        while let Some(record) = reader.read_record().await.unwrap() {
+            records.push(record);
+        }
+        assert_eq!(records.len(), 4);
+        let value_do = ConfigValueDO::from_bytes(&records[1].value).unwrap();
+        assert_eq!(value_do.histories[1].id, Some(4));
+        assert_eq!(records[3].tree.as_str(), SEQUENCE_TREE_NAME.as_str());
+        assert_eq!(bin_to_id(&records[3].value), 4);
+
+        //已有raft数据的目录不允许恢复
+        assert!(data_to_raft(&data_file, &data_dir, 2, "127.0.0.1:9848")
+            .await
+            .is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[actix_rt::test]
+    async fn test_boot_from_data_to_raft() {
+        let dir = test_dir("rnacos_data_to_raft_boot_test");
+        let data_file = dir.join("data.bak").to_string_lossy().into_owned();
+        let data_dir = dir.join("db").to_string_lossy().into_owned();
+        write_data_file(&data_file).await;
+        let count = data_to_raft(&data_file, &data_dir, 2, "127.0.0.1:9848")
+            .await
+            .unwrap();
+        assert_eq!(count.ignore, 1);
+        assert_eq!(count.ignore_tables.get("T_UNKNOWN"), Some(&1));
+
+        //按节点启动方式加载生成的raft目录
+        let base_path = Arc::new(data_dir.clone());
+        let index_manager = RaftIndexManager::new(base_path.clone()).start();
+        let log_manager =
+            RaftLogManager::new(base_path.clone(), Some(index_manager.clone())).start();
+        let snapshot_manager =
+            RaftSnapshotManager::new(base_path, Some(index_manager.clone()), 0).start();
+        let store = FileStore::new(
+            2,
+            index_manager,
+            snapshot_manager,
+            log_manager,
+            StateApplyManager::new().start(),
+        );
+        let state = store.get_initial_state().await.unwrap();
+        assert_eq!(state.last_applied_log, 1);
+        assert!(state.membership.members.contains(&2));
+        let node_addrs = store.get_node_addrs().await.unwrap();
+        assert_eq!(
+            node_addrs.get(&2).map(|e| e.as_str()),
+            Some("127.0.0.1:9848")
+        );
+        let snapshot = store.get_current_snapshot().await.unwrap().unwrap();
+        assert_eq!(snapshot.index, 1);
+        assert!(snapshot.membership.members.contains(&2));
+        //镜像中只包含节点加载时支持的表,序列号为重新编号后的值
+        let mut reader = SnapshotReader::init(&get_path(&data_dir, "snapshot_1"))
+            .await
+            .unwrap();
+        let mut trees = HashMap::new();
+        let mut seq_value = None;
+        while let Some(record) = reader.read_record().await.unwrap() {
+            if record.tree.as_str() == SEQUENCE_TREE_NAME.as_str() {
+                seq_value = Some(bin_to_id(&record.value));
+            }
+            *trees.entry(record.tree.as_str().to_owned()).or_insert(0) += 1;
+        }
+        assert_eq!(trees.get(CONFIG_TREE_NAME.as_str()), Some(&2));
+        assert_eq!(trees.get(USER_TREE_NAME.as_str()), Some(&1));
+        assert_eq!(trees.get(SEQUENCE_TREE_NAME.as_str()), Some(&1));
+        assert_eq!(trees.len(), 3);
+        assert_eq!(seq_value, Some(4));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -6,12 +6,15 @@ use crate::transfer::model::TransferWriterRequest;
 use crate::transfer::writer::TransferWriterActor;
 use actix::Addr;
 
+pub mod backup_scheduler;
+pub mod data_to_raft;
 pub mod data_to_sqlite;
 pub mod model;
 pub mod mysql;
 pub mod mysql_to_data;
 pub mod openapi_to_data;
 pub mod reader;
+pub mod s3;
 pub mod sqlite;
 pub mod sqlite_to_data;
 pub mod writer;
@@ -42,8 +42,8 @@ pub(crate) fn reader_transfer_record<'a>(
         } else if NAMESPACE_TREE_NAME.as_str() == record_do.table_name.as_ref() {
             NAMESPACE_TREE_NAME.clone()
         } else {
-            //ignore
-            EMPTY_ARC_STRING.clone()
+            //未知表保留原表名,由使用方决定是否忽略
+            Arc::new(record_do.table_name.to_string())
         }
     } else {
         header
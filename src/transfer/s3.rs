@@ -0,0 +1,466 @@
+use crate::common::AppSysConfig;
+use chrono::Utc;
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+const SIGN_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+/// 分片上传的分片大小,s3要求除最后一片外不小于5MB
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default)]
+pub struct S3Config {
+    /// 例如: http://127.0.0.1:9000
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub prefix: String,
+}
+
+impl S3Config {
+    pub fn from_sys_config(sys_config: &AppSysConfig) -> Option<Self> {
+        if sys_config.backup_s3_endpoint.is_empty() || sys_config.backup_s3_bucket.is_empty() {
+            return None;
+        }
+        Some(Self {
+            endpoint: sys_config.backup_s3_endpoint.clone(),
+            bucket: sys_config.backup_s3_bucket.clone(),
+            region: sys_config.backup_s3_region.clone(),
+            access_key: sys_config.backup_s3_access_key.clone(),
+            secret_key: sys_config.backup_s3_secret_key.clone(),
+            prefix: sys_config.backup_s3_prefix.clone(),
+        })
+    }
+
+    fn build_object_key(&self, name: &str) -> String {
+        let prefix = self.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}/{}", prefix, name)
+        }
+    }
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(data);
+    hasher.result_str()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut hmac = Hmac::new(Sha256::new(), key);
+    hmac.input(data);
+    hmac.result().code().to_vec()
+}
+
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut s = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                s.push(b as char)
+            }
+            b'/' if !encode_slash => s.push('/'),
+            _ => s.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    s
+}
+
+fn uri_encode_path(path: &str) -> String {
+    uri_encode(path, false)
+}
+
+///
+/// 按签名规范生成排序后的query
+fn build_canonical_query(params: &[(&str, &str)]) -> String {
+    let mut items: Vec<String> = params
+        .iter()
+        .map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true)))
+        .collect();
+    items.sort();
+    items.join("&")
+}
+
+fn xml_tag_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start_tag = format!("<{}>", tag);
+    let end_tag = format!("</{}>", tag);
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = xml[start..].find(&end_tag)? + start;
+    Some(&xml[start..end])
+}
+
+pub(crate) fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let k_region = hmac_sha256(&k_date, region.as_bytes());
+    let k_service = hmac_sha256(&k_region, service.as_bytes());
+    hmac_sha256(&k_service, b"aws4_request")
+}
+
+///
+/// 以path-style方式上传对象到s3兼容存储,使用aws signature v4签名
+pub struct S3Uploader {
+    config: S3Config,
+    client: reqwest::Client,
+    part_size: usize,
+}
+
+impl S3Uploader {
+    pub fn new(config: S3Config) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            part_size: PART_SIZE,
+        }
+    }
+
+    pub async fn put_object(&self, name: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let resp = self
+            .send_request(reqwest::Method::PUT, name, &[], data)
+            .await?;
+        Self::check_response(resp, "put object").await?;
+        Ok(())
+    }
+
+    ///
+    /// 上传本地文件;超过分片大小时使用分片上传,每次只读取一个分片到内存
+    pub async fn upload_file(&self, name: &str, path: &Path) -> anyhow::Result<()> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let size = file.metadata().await?.len();
+        if size <= self.part_size as u64 {
+            let mut data = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut data).await?;
+            return self.put_object(name, data).await;
+        }
+        let resp = self
+            .send_request(reqwest::Method::POST, name, &[("uploads", "")], vec![])
+            .await?;
+        let body = Self::check_response(resp, "create multipart upload").await?;
+        let upload_id = xml_tag_value(&body, "UploadId")
+            .ok_or_else(|| anyhow::anyhow!("s3 create multipart upload error,no UploadId"))?
+            .to_owned();
+        match self.upload_parts(name, &upload_id, &mut file).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                //上传失败时取消分片上传,避免残留分片
+                if let Ok(resp) = self
+                    .send_request(
+                        reqwest::Method::DELETE,
+                        name,
+                        &[("uploadId", &upload_id)],
+                        vec![],
+                    )
+                    .await
+                {
+                    Self::check_response(resp, "abort multipart upload")
+                        .await
+                        .map_err(|e| log::warn!("{}", e))
+                        .ok();
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn upload_parts(
+        &self,
+        name: &str,
+        upload_id: &str,
+        file: &mut tokio::fs::File,
+    ) -> anyhow::Result<()> {
+        let mut etags = vec![];
+        loop {
+            let mut data = Vec::with_capacity(self.part_size);
+            (&mut *file)
+                .take(self.part_size as u64)
+                .read_to_end(&mut data)
+                .await?;
+            if data.is_empty() {
+                break;
+            }
+            let part_number = (etags.len() + 1).to_string();
+            let resp = self
+                .send_request(
+                    reqwest::Method::PUT,
+                    name,
+                    &[("partNumber", &part_number), ("uploadId", upload_id)],
+                    data,
+                )
+                .await?;
+            let etag = resp
+                .headers()
+                .get("etag")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_owned();
+            Self::check_response(resp, "upload part").await?;
+            etags.push(etag);
+        }
+        let mut body = String::from("<CompleteMultipartUpload>");
+        for (i, etag) in etags.iter().enumerate() {
+            body.push_str(&format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                i + 1,
+                etag
+            ));
+        }
+        body.push_str("</CompleteMultipartUpload>");
+        let resp = self
+            .send_request(
+                reqwest::Method::POST,
+                name,
+                &[("uploadId", upload_id)],
+                body.into_bytes(),
+            )
+            .await?;
+        let body = Self::check_response(resp, "complete multipart upload").await?;
+        //完成分片上传时,s3可能返回200但响应体为错误信息
+        if body.contains("<Error>") {
+            return Err(anyhow::anyhow!(
+                "s3 complete multipart upload error,{}",
+                body
+            ));
+        }
+        Ok(())
+    }
+
+    async fn check_response(resp: reqwest::Response, action: &str) -> anyhow::Result<String> {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("s3 {} error,{},{}", action, status, body));
+        }
+        Ok(body)
+    }
+
+    async fn send_request(
+        &self,
+        method: reqwest::Method,
+        name: &str,
+        params: &[(&str, &str)],
+        data: Vec<u8>,
+    ) -> anyhow::Result<reqwest::Response> {
+        let object_key = self.config.build_object_key(name);
+        let query = build_canonical_query(params);
+        let mut url = format!(
+            "{}/{}/{}",
+            self.config.endpoint.trim_end_matches('/'),
+            &self.config.bucket,
+            uri_encode_path(&object_key)
+        );
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query);
+        }
+        let url = reqwest::Url::parse(&url)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_owned(),
+            _ => return Err(anyhow::anyhow!("s3 endpoint is invalid")),
+        };
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = sha256_hex(&data);
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method.as_str(),
+            url.path(),
+            &query,
+            &host,
+            &payload_hash,
+            &amz_date,
+            SIGNED_HEADERS,
+            &payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", &date, &self.config.region);
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            SIGN_ALGORITHM,
+            &amz_date,
+            &scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let key = signing_key(&self.config.secret_key, &date, &self.config.region, "s3");
+        let signature = to_hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            SIGN_ALGORITHM, &self.config.access_key, &scope, SIGNED_HEADERS, signature
+        );
+        let resp = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(data)
+            .send()
+            .await?;
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use std::sync::{Arc, Mutex};
+
+    /// (method,path,query,authorization,body)
+    type RequestLog = Arc<Mutex<Vec<(String, String, String, String, Vec<u8>)>>>;
+
+    async fn stub_s3(
+        req: HttpRequest,
+        body: web::Bytes,
+        log: web::Data<RequestLog>,
+    ) -> HttpResponse {
+        let auth = req
+            .headers()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+        let query = req.query_string().to_owned();
+        log.lock().unwrap().push((
+            req.method().to_string(),
+            req.path().to_owned(),
+            query.clone(),
+            auth,
+            body.to_vec(),
+        ));
+        if query == "uploads=" {
+            HttpResponse::Ok()
+                .body("<InitiateMultipartUploadResult><UploadId>u1</UploadId></InitiateMultipartUploadResult>")
+        } else if query.starts_with("partNumber=") {
+            let part_number = query
+                .trim_start_matches("partNumber=")
+                .split('&')
+                .next()
+                .unwrap_or_default()
+                .to_owned();
+            HttpResponse::Ok()
+                .insert_header(("ETag", format!("\"etag{}\"", part_number)))
+                .finish()
+        } else {
+            HttpResponse::Ok().finish()
+        }
+    }
+
+    async fn start_stub_server(log: RequestLog) -> (actix_web::dev::ServerHandle, S3Uploader) {
+        let data = web::Data::new(log);
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .default_service(web::to(stub_s3))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_rt::spawn(server);
+        let uploader = S3Uploader::new(S3Config {
+            endpoint: format!("http://{}", addr),
+            bucket: "backup".to_owned(),
+            region: "us-east-1".to_owned(),
+            access_key: "ak".to_owned(),
+            secret_key: "sk".to_owned(),
+            prefix: "/rnacos/".to_owned(),
+        });
+        (handle, uploader)
+    }
+
+    #[test]
+    fn test_signing_key() {
+        //aws文档中的签名key示例
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            to_hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_build_canonical_query() {
+        assert_eq!(build_canonical_query(&[("uploads", "")]), "uploads=");
+        assert_eq!(
+            build_canonical_query(&[("uploadId", "a/b+c"), ("partNumber", "1")]),
+            "partNumber=1&uploadId=a%2Fb%2Bc"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_put_object() {
+        let log: RequestLog = Arc::new(Mutex::new(vec![]));
+        let (handle, uploader) = start_stub_server(log.clone()).await;
+        uploader
+            .put_object("rnacos_backup_1.data", vec![1u8; 16])
+            .await
+            .unwrap();
+        handle.stop(false).await;
+        let list = log.lock().unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].0, "PUT");
+        assert_eq!(list[0].1, "/backup/rnacos/rnacos_backup_1.data");
+        assert!(list[0].3.starts_with("AWS4-HMAC-SHA256 Credential=ak/"));
+        assert_eq!(list[0].4.len(), 16);
+    }
+
+    #[actix_rt::test]
+    async fn test_upload_file_multipart() {
+        let path = std::env::temp_dir().join(format!(
+            "rnacos_s3_upload_test_{}_{}.data",
+            std::process::id(),
+            crate::now_millis()
+        ));
+        std::fs::write(&path, vec![2u8; 25]).unwrap();
+        let log: RequestLog = Arc::new(Mutex::new(vec![]));
+        let (handle, mut uploader) = start_stub_server(log.clone()).await;
+        uploader.part_size = 10;
+        let r = uploader.upload_file("rnacos_backup_2.data", &path).await;
+        handle.stop(false).await;
+        std::fs::remove_file(&path).ok();
+        r.unwrap();
+        let list = log.lock().unwrap();
+        //创建分片上传 + 3个分片 + 完成分片上传
+        assert_eq!(list.len(), 5);
+        assert_eq!(
+            (list[0].0.as_str(), list[0].2.as_str()),
+            ("POST", "uploads=")
+        );
+        let part_sizes: Vec<usize> = list[1..4]
+            .iter()
+            .map(|(method, _, query, _, body)| {
+                assert_eq!(method, "PUT");
+                assert!(query.contains("uploadId=u1"));
+                body.len()
+            })
+            .collect();
+        assert_eq!(part_sizes, vec![10, 10, 5]);
+        assert_eq!(
+            (list[4].0.as_str(), list[4].2.as_str()),
+            ("POST", "uploadId=u1")
+        );
+        let complete_body = String::from_utf8(list[4].4.clone()).unwrap();
+        assert!(
+            complete_body.contains("<Part><PartNumber>3</PartNumber><ETag>\"etag3\"</ETag></Part>")
+        );
+    }
+}
@@ -162,6 +162,10 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/manage/cluster",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/cluster/cluster_node_list",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/cluster/cluster_node_list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/cluster/grpc_conn_balance",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/cluster/raft_membership",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/cluster/raft_status",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/connections/list",HTTP_METHOD_GET),
     ]);
 
     static ref M_NAMESPACE_VISITOR: ModuleResource = ModuleResource::new(vec![
@@ -275,6 +279,8 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/service/list",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/instance/list",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/instance/info",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/instance/events",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/push_audit",HTTP_METHOD_GET),
     ]);
 
     static ref M_NAMING_MANAGE: ModuleResource = ModuleResource::new(vec![
@@ -301,9 +307,13 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/service/remove",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/instance/list",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/instance/info",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/instance/events",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/push_audit",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/instance/add",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/instance/update",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/instance/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/instance/drain",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/instance/drain/cancel",HTTP_METHOD_ALL),
     ]);
 
     static ref M_METRICS_VISITOR: ModuleResource = ModuleResource::new(vec![
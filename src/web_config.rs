@@ -5,6 +5,8 @@ use rnacos_web_dist_wrap::get_embedded_file;
 
 use crate::common::AppSysConfig;
 use crate::console::api::{console_api_config_v1, console_api_config_v2};
+use crate::consul::consul_config;
+use crate::eureka::eureka_config;
 use crate::openapi::auth::{login_config, mock_token};
 use crate::openapi::backup::backup_config;
 #[cfg(feature = "debug")]
@@ -89,6 +91,12 @@ async fn disable_no_auth_console_index() -> impl Responder {
 /// 面向SDK的http服务接口
 pub fn app_config(conf_data: AppSysConfig) -> impl FnOnce(&mut ServiceConfig) {
     move |config: &mut ServiceConfig| {
+        if conf_data.consul_enable {
+            consul_config(config);
+        }
+        if conf_data.eureka_enable {
+            eureka_config(config);
+        }
         if !conf_data.enable_no_auth_console || conf_data.openapi_enable_auth {
             backup_config(config);
             config
@@ -0,0 +1,385 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::prelude::*;
+use bean_factory::{bean, Inject};
+
+use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
+use crate::naming::service_index::ServiceQueryParam;
+use crate::xds::model::{XdsSnapshot, CLUSTER_TYPE_URL, ENDPOINT_TYPE_URL};
+use crate::xds::xds_proto::{DiscoveryRequest, DiscoveryResponse};
+
+pub type XdsSenderType = tokio::sync::mpsc::Sender<Result<DiscoveryResponse, tonic::Status>>;
+
+/// 服务变更后延迟合并刷新快照
+const RELOAD_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Default)]
+struct WatchItem {
+    resource_names: HashSet<String>,
+    nonce: String,
+}
+
+struct XdsConn {
+    sender: XdsSenderType,
+    //type_url: WatchItem
+    watches: HashMap<String, WatchItem>,
+}
+
+///
+/// 管理envoy ads连接,按注册中心快照下发CDS/EDS资源
+#[bean(inject)]
+#[derive(Default)]
+pub struct XdsManage {
+    conns: HashMap<Arc<String>, XdsConn>,
+    snapshot: Option<Arc<XdsSnapshot>>,
+    pending_requests: Vec<(Arc<String>, DiscoveryRequest)>,
+    loading: bool,
+    dirty: bool,
+    reload_scheduled: bool,
+    version: u64,
+    nonce: u64,
+    naming_addr: Option<Addr<NamingActor>>,
+}
+
+async fn query_snapshot(
+    naming_addr: Addr<NamingActor>,
+    version: u64,
+) -> anyhow::Result<XdsSnapshot> {
+    let param = ServiceQueryParam {
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let cmd = NamingCmd::QueryInstanceListWithIndex(param);
+    match naming_addr.send(cmd).await?? {
+        NamingResult::InstanceListWithIndex(_, list) => Ok(XdsSnapshot::build(version, list)),
+        _ => Err(anyhow::anyhow!("naming result error")),
+    }
+}
+
+impl XdsManage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_nonce(&mut self) -> String {
+        self.nonce += 1;
+        self.nonce.to_string()
+    }
+
+    fn send_response(
+        &mut self,
+        ctx: &mut Context<Self>,
+        sender: XdsSenderType,
+        response: DiscoveryResponse,
+    ) {
+        async move { sender.send(Ok(response)).await }
+            .into_actor(self)
+            .map(|_, _, _| {})
+            .spawn(ctx);
+    }
+
+    fn load_snapshot(&mut self, ctx: &mut Context<Self>) {
+        if self.loading {
+            self.dirty = true;
+            return;
+        }
+        let naming_addr = if let Some(v) = self.naming_addr.clone() {
+            v
+        } else {
+            return;
+        };
+        self.loading = true;
+        self.version += 1;
+        let version = self.version;
+        query_snapshot(naming_addr, version)
+            .into_actor(self)
+            .map(|res: anyhow::Result<XdsSnapshot>, act, ctx| {
+                act.loading = false;
+                match res {
+                    Ok(snapshot) => act.apply_snapshot(ctx, Arc::new(snapshot)),
+                    Err(e) => log::error!("xds load snapshot error,{}", e),
+                }
+                if act.dirty {
+                    act.dirty = false;
+                    act.load_snapshot(ctx);
+                }
+            })
+            .spawn(ctx);
+    }
+
+    ///
+    /// 快照更新后向已订阅的连接推送变更;CDS推送全量,EDS只推送有变化的cluster
+    fn apply_snapshot(&mut self, ctx: &mut Context<Self>, snapshot: Arc<XdsSnapshot>) {
+        if let Some(old) = self.snapshot.replace(snapshot.clone()) {
+            let cluster_list_changed = snapshot.is_cluster_list_changed(&old);
+            let changed_clusters = snapshot.changed_clusters(&old);
+            let conn_ids: Vec<Arc<String>> = self.conns.keys().cloned().collect();
+            for conn_id in conn_ids {
+                if cluster_list_changed {
+                    self.push_clusters(ctx, &conn_id, &snapshot);
+                }
+                if !changed_clusters.is_empty() {
+                    self.push_endpoints(ctx, &conn_id, &snapshot, &changed_clusters);
+                }
+            }
+        }
+        for (conn_id, req) in std::mem::take(&mut self.pending_requests) {
+            self.handle_request(ctx, conn_id, req);
+        }
+    }
+
+    fn push_clusters(
+        &mut self,
+        ctx: &mut Context<Self>,
+        conn_id: &Arc<String>,
+        snapshot: &XdsSnapshot,
+    ) {
+        let nonce = self.next_nonce();
+        if let Some(conn) = self.conns.get_mut(conn_id) {
+            if let Some(watch) = conn.watches.get_mut(CLUSTER_TYPE_URL) {
+                watch.nonce = nonce.clone();
+                let sender = conn.sender.clone();
+                self.send_response(ctx, sender, snapshot.build_cluster_response(nonce));
+            }
+        }
+    }
+
+    fn push_endpoints(
+        &mut self,
+        ctx: &mut Context<Self>,
+        conn_id: &Arc<String>,
+        snapshot: &XdsSnapshot,
+        changed_clusters: &HashSet<String>,
+    ) {
+        let nonce = self.next_nonce();
+        if let Some(conn) = self.conns.get_mut(conn_id) {
+            if let Some(watch) = conn.watches.get_mut(ENDPOINT_TYPE_URL) {
+                let names: Vec<&String> = if watch.resource_names.is_empty() {
+                    changed_clusters.iter().collect()
+                } else {
+                    changed_clusters
+                        .iter()
+                        .filter(|e| watch.resource_names.contains(*e))
+                        .collect()
+                };
+                if names.is_empty() {
+                    return;
+                }
+                watch.nonce = nonce.clone();
+                let response = snapshot.build_endpoint_response(names.into_iter(), nonce);
+                let sender = conn.sender.clone();
+                self.send_response(ctx, sender, response);
+            }
+        }
+    }
+
+    ///
+    /// 处理envoy订阅请求;对最近一次响应的ACK/NACK且订阅内容不变时不再响应
+    fn handle_request(
+        &mut self,
+        ctx: &mut Context<Self>,
+        conn_id: Arc<String>,
+        req: DiscoveryRequest,
+    ) {
+        let snapshot = if let Some(v) = self.snapshot.clone() {
+            v
+        } else {
+            self.pending_requests.push((conn_id, req));
+            self.load_snapshot(ctx);
+            return;
+        };
+        if let Some(error_detail) = &req.error_detail {
+            log::warn!(
+                "xds nack,conn:{},type_url:{},version:{},error:{}",
+                &conn_id,
+                &req.type_url,
+                &req.version_info,
+                &error_detail.message
+            );
+        }
+        let nonce = self.next_nonce();
+        let conn = if let Some(v) = self.conns.get_mut(&conn_id) {
+            v
+        } else {
+            return;
+        };
+        let resource_names: HashSet<String> = req.resource_names.into_iter().collect();
+        let watch = conn.watches.entry(req.type_url.clone()).or_default();
+        if !req.response_nonce.is_empty()
+            && (req.response_nonce != watch.nonce || watch.resource_names == resource_names)
+        {
+            return;
+        }
+        watch.resource_names = resource_names;
+        watch.nonce = nonce.clone();
+        let response = match req.type_url.as_str() {
+            CLUSTER_TYPE_URL => snapshot.build_cluster_response(nonce),
+            ENDPOINT_TYPE_URL => {
+                if watch.resource_names.is_empty() {
+                    snapshot.build_endpoint_response(snapshot.resources.keys(), nonce)
+                } else {
+                    snapshot.build_endpoint_response(watch.resource_names.iter(), nonce)
+                }
+            }
+            //不支持的资源类型返回空列表,避免envoy一直等待
+            _ => DiscoveryResponse {
+                version_info: snapshot.version.to_string(),
+                type_url: req.type_url.clone(),
+                nonce,
+                ..Default::default()
+            },
+        };
+        let sender = conn.sender.clone();
+        self.send_response(ctx, sender, response);
+    }
+
+    fn service_changed(&mut self, ctx: &mut Context<Self>) {
+        if self.conns.is_empty() {
+            //没有连接时不维护快照,下次请求时重新加载
+            self.snapshot = None;
+            return;
+        }
+        if self.reload_scheduled {
+            return;
+        }
+        self.reload_scheduled = true;
+        ctx.run_later(RELOAD_DELAY, |act, ctx| {
+            act.reload_scheduled = false;
+            act.load_snapshot(ctx);
+        });
+    }
+}
+
+impl Actor for XdsManage {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        log::info!("XdsManage started");
+    }
+}
+
+impl Inject for XdsManage {
+    type Context = Context<Self>;
+
+    fn inject(
+        &mut self,
+        factory_data: bean_factory::FactoryData,
+        _factory: bean_factory::BeanFactory,
+        _ctx: &mut Self::Context,
+    ) {
+        self.naming_addr = factory_data.get_actor();
+        log::info!("XdsManage inject complete");
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "anyhow::Result<XdsManageResult>")]
+pub enum XdsManageCmd {
+    AddConn(Arc<String>, XdsSenderType),
+    Request(Arc<String>, DiscoveryRequest),
+    ConnClose(Arc<String>),
+    ServiceChanged,
+}
+
+pub enum XdsManageResult {
+    None,
+}
+
+impl Handler<XdsManageCmd> for XdsManage {
+    type Result = anyhow::Result<XdsManageResult>;
+
+    fn handle(&mut self, msg: XdsManageCmd, ctx: &mut Context<Self>) -> Self::Result {
+        match msg {
+            XdsManageCmd::AddConn(conn_id, sender) => {
+                log::info!("xds add conn:{}", &conn_id);
+                self.conns.insert(
+                    conn_id,
+                    XdsConn {
+                        sender,
+                        watches: Default::default(),
+                    },
+                );
+            }
+            XdsManageCmd::Request(conn_id, req) => {
+                self.handle_request(ctx, conn_id, req);
+            }
+            XdsManageCmd::ConnClose(conn_id) => {
+                log::info!("xds remove conn:{}", &conn_id);
+                self.conns.remove(&conn_id);
+                self.pending_requests.retain(|(id, _)| id != &conn_id);
+            }
+            XdsManageCmd::ServiceChanged => {
+                self.service_changed(ctx);
+            }
+        }
+        Ok(XdsManageResult::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::naming::model::Instance;
+
+    fn build_instance(port: u32) -> Instance {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.init();
+        instance
+    }
+
+    #[actix_rt::test]
+    async fn test_push_endpoints_on_synced_update() {
+        let xds_ctx = Context::<XdsManage>::new();
+        let xds_addr = xds_ctx.address();
+        let mut naming = NamingActor::new();
+        naming.xds_manage = Some(xds_addr.clone());
+        let instance = build_instance(8080);
+        naming.update_instance(&instance.get_service_key(), instance, None, false);
+        let naming_addr = naming.start();
+        let mut xds = XdsManage::new();
+        xds.naming_addr = Some(naming_addr.clone());
+        xds_ctx.run(xds);
+
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(8);
+        let conn_id = Arc::new("conn_1".to_owned());
+        xds_addr
+            .send(XdsManageCmd::AddConn(conn_id.clone(), sender))
+            .await
+            .unwrap()
+            .unwrap();
+        let req = DiscoveryRequest {
+            type_url: ENDPOINT_TYPE_URL.to_owned(),
+            ..Default::default()
+        };
+        xds_addr
+            .send(XdsManageCmd::Request(conn_id, req))
+            .await
+            .unwrap()
+            .unwrap();
+        let first = tokio::time::timeout(Duration::from_secs(3), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        //其它节点同步过来的实例变更也要推送
+        let mut instance = build_instance(8081);
+        instance.from_cluster = 2;
+        naming_addr
+            .send(NamingCmd::UpdateFromSync(instance, None))
+            .await
+            .unwrap()
+            .unwrap();
+        let second = tokio::time::timeout(Duration::from_secs(3), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_ne!(first.version_info, second.version_info);
+    }
+}
@@ -0,0 +1,6 @@
+//envoy xds(ads) server backed by naming registry
+
+pub mod manage;
+pub mod model;
+pub mod server;
+pub mod xds_proto;
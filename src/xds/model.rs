@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use prost::Message;
+
+use crate::naming::model::{Instance, ServiceKey};
+use crate::xds::xds_proto::{
+    address, cluster, config_source, lb_endpoint, socket_address, Address, AggregatedConfigSource,
+    ApiVersion, Cluster, ClusterLoadAssignment, ConfigSource, DiscoveryResponse, Endpoint,
+    HealthStatus, LbEndpoint, Locality, LocalityLbEndpoints, SocketAddress,
+};
+
+pub const CLUSTER_TYPE_URL: &str = "type.googleapis.com/envoy.config.cluster.v3.Cluster";
+pub const ENDPOINT_TYPE_URL: &str =
+    "type.googleapis.com/envoy.config.endpoint.v3.ClusterLoadAssignment";
+
+/// nacos权重是浮点数,envoy权重是不小于1的整数,按100倍换算
+const WEIGHT_SCALE: f32 = 100f32;
+const CONNECT_TIMEOUT_SECONDS: i64 = 5;
+
+///
+/// envoy cluster名称,格式与dns域名前缀保持一致: service.group.namespace
+pub fn get_cluster_name(key: &ServiceKey) -> String {
+    format!(
+        "{}.{}.{}",
+        &key.service_name, &key.group_name, &key.namespace_id
+    )
+}
+
+pub fn to_any<M: Message>(type_url: &str, msg: &M) -> prost_types::Any {
+    let mut value = Vec::with_capacity(msg.encoded_len());
+    msg.encode(&mut value).ok();
+    prost_types::Any {
+        type_url: type_url.to_owned(),
+        value,
+    }
+}
+
+pub fn build_cluster(cluster_name: &str) -> Cluster {
+    let eds_config = ConfigSource {
+        resource_api_version: ApiVersion::V3 as i32,
+        config_source_specifier: Some(config_source::ConfigSourceSpecifier::Ads(
+            AggregatedConfigSource {},
+        )),
+    };
+    Cluster {
+        name: cluster_name.to_owned(),
+        eds_cluster_config: Some(cluster::EdsClusterConfig {
+            eds_config: Some(eds_config),
+            service_name: cluster_name.to_owned(),
+        }),
+        connect_timeout: Some(prost_types::Duration {
+            seconds: CONNECT_TIMEOUT_SECONDS,
+            nanos: 0,
+        }),
+        lb_policy: cluster::LbPolicy::RoundRobin as i32,
+        cluster_discovery_type: Some(cluster::ClusterDiscoveryType::Type(
+            cluster::DiscoveryType::Eds as i32,
+        )),
+    }
+}
+
+fn build_lb_endpoint(instance: &Instance) -> LbEndpoint {
+    let health_status = if instance.healthy {
+        HealthStatus::Healthy
+    } else {
+        HealthStatus::Unhealthy
+    };
+    let weight = ((instance.weight * WEIGHT_SCALE).round() as u32).max(1);
+    let socket_address = SocketAddress {
+        protocol: socket_address::Protocol::Tcp as i32,
+        address: instance.ip.as_ref().to_owned(),
+        port_specifier: Some(socket_address::PortSpecifier::PortValue(instance.port)),
+    };
+    LbEndpoint {
+        health_status: health_status as i32,
+        load_balancing_weight: Some(weight),
+        host_identifier: Some(lb_endpoint::HostIdentifier::Endpoint(Endpoint {
+            address: Some(Address {
+                address: Some(address::Address::SocketAddress(socket_address)),
+            }),
+            hostname: String::new(),
+        })),
+    }
+}
+
+///
+/// 实例转换为envoy端点;nacos集群名映射为locality的zone,下线或权重为0的实例不下发
+pub fn build_load_assignment(
+    cluster_name: &str,
+    instances: &[Arc<Instance>],
+) -> ClusterLoadAssignment {
+    let mut locality_map: BTreeMap<&str, Vec<LbEndpoint>> = BTreeMap::new();
+    for instance in instances {
+        if !instance.enabled || instance.weight <= 0f32 {
+            continue;
+        }
+        locality_map
+            .entry(instance.cluster_name.as_str())
+            .or_default()
+            .push(build_lb_endpoint(instance));
+    }
+    let endpoints = locality_map
+        .into_iter()
+        .map(|(zone, lb_endpoints)| LocalityLbEndpoints {
+            locality: Some(Locality {
+                zone: zone.to_owned(),
+                ..Default::default()
+            }),
+            lb_endpoints,
+            ..Default::default()
+        })
+        .collect();
+    ClusterLoadAssignment {
+        cluster_name: cluster_name.to_owned(),
+        endpoints,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XdsResource {
+    pub cluster: prost_types::Any,
+    pub load_assignment: prost_types::Any,
+}
+
+///
+/// 注册中心服务在某一时刻转换后的xds资源
+#[derive(Debug, Clone, Default)]
+pub struct XdsSnapshot {
+    pub version: u64,
+    pub resources: BTreeMap<String, XdsResource>,
+}
+
+impl XdsSnapshot {
+    pub fn build(version: u64, instances: Vec<Arc<Instance>>) -> Self {
+        let mut service_map: BTreeMap<String, Vec<Arc<Instance>>> = BTreeMap::new();
+        for instance in instances {
+            let key = ServiceKey::new_by_arc(
+                instance.namespace_id.clone(),
+                instance.group_name.clone(),
+                instance.service_name.clone(),
+            );
+            service_map
+                .entry(get_cluster_name(&key))
+                .or_default()
+                .push(instance);
+        }
+        let resources = service_map
+            .into_iter()
+            .map(|(name, list)| {
+                let resource = XdsResource {
+                    cluster: to_any(CLUSTER_TYPE_URL, &build_cluster(&name)),
+                    load_assignment: to_any(
+                        ENDPOINT_TYPE_URL,
+                        &build_load_assignment(&name, &list),
+                    ),
+                };
+                (name, resource)
+            })
+            .collect();
+        Self { version, resources }
+    }
+
+    ///
+    /// 与旧快照相比端点有变化的cluster,包含新增与删除的cluster
+    pub fn changed_clusters(&self, old: &XdsSnapshot) -> HashSet<String> {
+        let mut set = HashSet::new();
+        for (name, resource) in &self.resources {
+            match old.resources.get(name) {
+                Some(v) if v.load_assignment == resource.load_assignment => {}
+                _ => {
+                    set.insert(name.to_owned());
+                }
+            }
+        }
+        for name in old.resources.keys() {
+            if !self.resources.contains_key(name) {
+                set.insert(name.to_owned());
+            }
+        }
+        set
+    }
+
+    pub fn is_cluster_list_changed(&self, old: &XdsSnapshot) -> bool {
+        self.resources.len() != old.resources.len()
+            || self
+                .resources
+                .keys()
+                .any(|k| !old.resources.contains_key(k))
+    }
+
+    pub fn build_cluster_response(&self, nonce: String) -> DiscoveryResponse {
+        DiscoveryResponse {
+            version_info: self.version.to_string(),
+            resources: self.resources.values().map(|e| e.cluster.clone()).collect(),
+            type_url: CLUSTER_TYPE_URL.to_owned(),
+            nonce,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// 按cluster名称构建端点响应,已删除的cluster下发空端点
+    pub fn build_endpoint_response<'a>(
+        &self,
+        names: impl Iterator<Item = &'a String>,
+        nonce: String,
+    ) -> DiscoveryResponse {
+        let resources = names
+            .map(|name| match self.resources.get(name) {
+                Some(v) => v.load_assignment.clone(),
+                None => to_any(ENDPOINT_TYPE_URL, &build_load_assignment(name, &[])),
+            })
+            .collect();
+        DiscoveryResponse {
+            version_info: self.version.to_string(),
+            resources,
+            type_url: ENDPOINT_TYPE_URL.to_owned(),
+            nonce,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_instance(service: &str, ip: &str, weight: f32, healthy: bool) -> Arc<Instance> {
+        let mut instance = Instance::new(ip.to_owned(), 8080);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.service_name = Arc::new(service.to_owned());
+        instance.cluster_name = "DEFAULT".to_owned();
+        instance.weight = weight;
+        instance.healthy = healthy;
+        Arc::new(instance)
+    }
+
+    #[test]
+    fn test_build_load_assignment() {
+        let list = vec![
+            build_instance("foo", "127.0.0.1", 1.5f32, true),
+            build_instance("foo", "127.0.0.2", 1f32, false),
+            build_instance("foo", "127.0.0.3", 0f32, true),
+        ];
+        let assignment = build_load_assignment("foo.DEFAULT_GROUP.public", &list);
+        assert_eq!(assignment.endpoints.len(), 1);
+        let lb_endpoints = &assignment.endpoints[0].lb_endpoints;
+        assert_eq!(lb_endpoints.len(), 2);
+        assert_eq!(lb_endpoints[0].load_balancing_weight, Some(150));
+        assert_eq!(lb_endpoints[0].health_status, HealthStatus::Healthy as i32);
+        assert_eq!(
+            lb_endpoints[1].health_status,
+            HealthStatus::Unhealthy as i32
+        );
+        let any = to_any(ENDPOINT_TYPE_URL, &assignment);
+        let decoded = ClusterLoadAssignment::decode(any.value.as_slice()).unwrap();
+        assert_eq!(decoded, assignment);
+    }
+
+    #[test]
+    fn test_snapshot_changed_clusters() {
+        let old = XdsSnapshot::build(
+            1,
+            vec![
+                build_instance("foo", "127.0.0.1", 1f32, true),
+                build_instance("bar", "127.0.0.1", 1f32, true),
+            ],
+        );
+        let new = XdsSnapshot::build(
+            2,
+            vec![
+                build_instance("foo", "127.0.0.1", 1f32, false),
+                build_instance("bar", "127.0.0.1", 1f32, true),
+                build_instance("baz", "127.0.0.1", 1f32, true),
+            ],
+        );
+        let changed = new.changed_clusters(&old);
+        assert_eq!(changed.len(), 2);
+        assert!(changed.contains("foo.DEFAULT_GROUP.public"));
+        assert!(changed.contains("baz.DEFAULT_GROUP.public"));
+        assert!(new.is_cluster_list_changed(&old));
+        assert!(!new.is_cluster_list_changed(&new));
+    }
+}
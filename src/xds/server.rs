@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use actix::Addr;
+use tokio_stream::StreamExt;
+use tonic::transport::Server;
+
+use crate::common::AppSysConfig;
+use crate::xds::manage::{XdsManage, XdsManageCmd};
+use crate::xds::xds_proto::aggregated_discovery_service_server::{
+    AggregatedDiscoveryService, AggregatedDiscoveryServiceServer,
+};
+use crate::xds::xds_proto::{DiscoveryRequest, DiscoveryResponse};
+
+pub struct AdsServerImpl {
+    manage: Addr<XdsManage>,
+    conn_seq: AtomicU64,
+}
+
+impl AdsServerImpl {
+    pub fn new(manage: Addr<XdsManage>) -> Self {
+        Self {
+            manage,
+            conn_seq: AtomicU64::new(0),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl AggregatedDiscoveryService for AdsServerImpl {
+    type StreamAggregatedResourcesStream =
+        tokio_stream::wrappers::ReceiverStream<Result<DiscoveryResponse, tonic::Status>>;
+
+    async fn stream_aggregated_resources(
+        &self,
+        request: tonic::Request<tonic::Streaming<DiscoveryRequest>>,
+    ) -> Result<tonic::Response<Self::StreamAggregatedResourcesStream>, tonic::Status> {
+        let seq = self.conn_seq.fetch_add(1, Ordering::Relaxed);
+        let conn_id = match request.remote_addr() {
+            Some(addr) => Arc::new(format!("{}_{}", addr, seq)),
+            None => Arc::new(format!("unknown_{}", seq)),
+        };
+        let mut req_stream = request.into_inner();
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        self.manage
+            .do_send(XdsManageCmd::AddConn(conn_id.clone(), tx));
+        let manage = self.manage.clone();
+        tokio::spawn(async move {
+            while let Some(Ok(req)) = req_stream.next().await {
+                manage.do_send(XdsManageCmd::Request(conn_id.clone(), req));
+            }
+            manage.do_send(XdsManageCmd::ConnClose(conn_id));
+        });
+        Ok(tonic::Response::new(
+            tokio_stream::wrappers::ReceiverStream::new(rx),
+        ))
+    }
+}
+
+pub async fn start_xds_server(
+    manage: Addr<XdsManage>,
+    sys_config: Arc<AppSysConfig>,
+) -> anyhow::Result<()> {
+    let addr = sys_config.get_xds_addr().parse()?;
+    log::info!("xds server addr:{}", &addr);
+    tokio::spawn(async move {
+        if let Err(err) = Server::builder()
+            .add_service(AggregatedDiscoveryServiceServer::new(AdsServerImpl::new(
+                manage,
+            )))
+            .serve(addr)
+            .await
+        {
+            log::error!("xds server error,{}", err);
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,327 @@
+#![allow(non_camel_case_types)]
+// 由 proto/envoy_xds.proto 生成,类型结构与 envoy v3 api 保持一致
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Node {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub cluster: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub user_agent_name: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Status {
+    #[prost(int32, tag = "1")]
+    pub code: i32,
+    #[prost(string, tag = "2")]
+    pub message: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiscoveryRequest {
+    #[prost(string, tag = "1")]
+    pub version_info: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub node: ::core::option::Option<Node>,
+    #[prost(string, repeated, tag = "3")]
+    pub resource_names: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "4")]
+    pub type_url: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub response_nonce: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "6")]
+    pub error_detail: ::core::option::Option<Status>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiscoveryResponse {
+    #[prost(string, tag = "1")]
+    pub version_info: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub resources: ::prost::alloc::vec::Vec<::prost_types::Any>,
+    #[prost(bool, tag = "3")]
+    pub canary: bool,
+    #[prost(string, tag = "4")]
+    pub type_url: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub nonce: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AggregatedConfigSource {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigSource {
+    #[prost(enumeration = "ApiVersion", tag = "6")]
+    pub resource_api_version: i32,
+    #[prost(oneof = "config_source::ConfigSourceSpecifier", tags = "3")]
+    pub config_source_specifier: ::core::option::Option<config_source::ConfigSourceSpecifier>,
+}
+pub mod config_source {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum ConfigSourceSpecifier {
+        #[prost(message, tag = "3")]
+        Ads(super::AggregatedConfigSource),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Cluster {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub eds_cluster_config: ::core::option::Option<cluster::EdsClusterConfig>,
+    #[prost(message, optional, tag = "4")]
+    pub connect_timeout: ::core::option::Option<::prost_types::Duration>,
+    #[prost(enumeration = "cluster::LbPolicy", tag = "6")]
+    pub lb_policy: i32,
+    #[prost(oneof = "cluster::ClusterDiscoveryType", tags = "2")]
+    pub cluster_discovery_type: ::core::option::Option<cluster::ClusterDiscoveryType>,
+}
+pub mod cluster {
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct EdsClusterConfig {
+        #[prost(message, optional, tag = "1")]
+        pub eds_config: ::core::option::Option<super::ConfigSource>,
+        #[prost(string, tag = "2")]
+        pub service_name: ::prost::alloc::string::String,
+    }
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum DiscoveryType {
+        Static = 0,
+        StrictDns = 1,
+        LogicalDns = 2,
+        Eds = 3,
+        OriginalDst = 4,
+    }
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum LbPolicy {
+        RoundRobin = 0,
+        LeastRequest = 1,
+        RingHash = 2,
+        Random = 3,
+    }
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum ClusterDiscoveryType {
+        #[prost(enumeration = "DiscoveryType", tag = "2")]
+        Type(i32),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SocketAddress {
+    #[prost(enumeration = "socket_address::Protocol", tag = "1")]
+    pub protocol: i32,
+    #[prost(string, tag = "2")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(oneof = "socket_address::PortSpecifier", tags = "3")]
+    pub port_specifier: ::core::option::Option<socket_address::PortSpecifier>,
+}
+pub mod socket_address {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Protocol {
+        Tcp = 0,
+        Udp = 1,
+    }
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum PortSpecifier {
+        #[prost(uint32, tag = "3")]
+        PortValue(u32),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Address {
+    #[prost(oneof = "address::Address", tags = "1")]
+    pub address: ::core::option::Option<address::Address>,
+}
+pub mod address {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Address {
+        #[prost(message, tag = "1")]
+        SocketAddress(super::SocketAddress),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Endpoint {
+    #[prost(message, optional, tag = "1")]
+    pub address: ::core::option::Option<Address>,
+    #[prost(string, tag = "3")]
+    pub hostname: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LbEndpoint {
+    #[prost(enumeration = "HealthStatus", tag = "2")]
+    pub health_status: i32,
+    #[prost(message, optional, tag = "4")]
+    pub load_balancing_weight: ::core::option::Option<u32>,
+    #[prost(oneof = "lb_endpoint::HostIdentifier", tags = "1")]
+    pub host_identifier: ::core::option::Option<lb_endpoint::HostIdentifier>,
+}
+pub mod lb_endpoint {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum HostIdentifier {
+        #[prost(message, tag = "1")]
+        Endpoint(super::Endpoint),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Locality {
+    #[prost(string, tag = "1")]
+    pub region: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub zone: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub sub_zone: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LocalityLbEndpoints {
+    #[prost(message, optional, tag = "1")]
+    pub locality: ::core::option::Option<Locality>,
+    #[prost(message, repeated, tag = "2")]
+    pub lb_endpoints: ::prost::alloc::vec::Vec<LbEndpoint>,
+    #[prost(message, optional, tag = "3")]
+    pub load_balancing_weight: ::core::option::Option<u32>,
+    #[prost(uint32, tag = "5")]
+    pub priority: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClusterLoadAssignment {
+    #[prost(string, tag = "1")]
+    pub cluster_name: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub endpoints: ::prost::alloc::vec::Vec<LocalityLbEndpoints>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ApiVersion {
+    Auto = 0,
+    V2 = 1,
+    V3 = 2,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum HealthStatus {
+    Unknown = 0,
+    Healthy = 1,
+    Unhealthy = 2,
+    Draining = 3,
+    Timeout = 4,
+    Degraded = 5,
+}
+#[doc = r" Generated server implementations."]
+pub mod aggregated_discovery_service_server {
+    #![allow(unused_variables, dead_code, missing_docs)]
+    use tonic::codegen::*;
+    #[doc = "Generated trait containing gRPC methods that should be implemented for use with AggregatedDiscoveryServiceServer."]
+    #[async_trait]
+    pub trait AggregatedDiscoveryService: Send + Sync + 'static {
+        #[doc = "Server streaming response type for the StreamAggregatedResources method."]
+        type StreamAggregatedResourcesStream: futures_core::Stream<Item = Result<super::DiscoveryResponse, tonic::Status>>
+            + Send
+            + Sync
+            + 'static;
+        async fn stream_aggregated_resources(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::DiscoveryRequest>>,
+        ) -> Result<tonic::Response<Self::StreamAggregatedResourcesStream>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct AggregatedDiscoveryServiceServer<T: AggregatedDiscoveryService> {
+        inner: _Inner<T>,
+    }
+    struct _Inner<T>(Arc<T>, Option<tonic::Interceptor>);
+    impl<T: AggregatedDiscoveryService> AggregatedDiscoveryServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            let inner = Arc::new(inner);
+            let inner = _Inner(inner, None);
+            Self { inner }
+        }
+        pub fn with_interceptor(inner: T, interceptor: impl Into<tonic::Interceptor>) -> Self {
+            let inner = Arc::new(inner);
+            let inner = _Inner(inner, Some(interceptor.into()));
+            Self { inner }
+        }
+    }
+    impl<T, B> Service<http::Request<B>> for AggregatedDiscoveryServiceServer<T>
+    where
+        T: AggregatedDiscoveryService,
+        B: HttpBody + Send + Sync + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = Never;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/envoy.service.discovery.v3.AggregatedDiscoveryService/StreamAggregatedResources" => {
+                    #[allow(non_camel_case_types)]
+                    struct StreamAggregatedResourcesSvc<T: AggregatedDiscoveryService>(pub Arc<T>);
+                    impl<T: AggregatedDiscoveryService>
+                        tonic::server::StreamingService<super::DiscoveryRequest>
+                        for StreamAggregatedResourcesSvc<T>
+                    {
+                        type Response = super::DiscoveryResponse;
+                        type ResponseStream = T::StreamAggregatedResourcesStream;
+                        type Future =
+                            BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<tonic::Streaming<super::DiscoveryRequest>>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut =
+                                async move { (*inner).stream_aggregated_resources(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let interceptor = inner.1;
+                        let inner = inner.0;
+                        let method = StreamAggregatedResourcesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = if let Some(interceptor) = interceptor {
+                            tonic::server::Grpc::with_interceptor(codec, interceptor)
+                        } else {
+                            tonic::server::Grpc::new(codec)
+                        };
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => Box::pin(async move {
+                    Ok(http::Response::builder()
+                        .status(200)
+                        .header("grpc-status", "12")
+                        .header("content-type", "application/grpc")
+                        .body(tonic::body::BoxBody::empty())
+                        .unwrap())
+                }),
+            }
+        }
+    }
+    impl<T: AggregatedDiscoveryService> Clone for AggregatedDiscoveryServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self { inner }
+        }
+    }
+    impl<T: AggregatedDiscoveryService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(self.0.clone(), self.1.clone())
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: AggregatedDiscoveryService> tonic::transport::NamedService
+        for AggregatedDiscoveryServiceServer<T>
+    {
+        const NAME: &'static str = "envoy.service.discovery.v3.AggregatedDiscoveryService";
+    }
+}